@@ -8,54 +8,633 @@ pub struct Builtin {
     pub name: String,
     /// short description of the builtin
     pub description: String,
-    /// help text for the builtin
+    /// help text for the builtin, shown after the usage generated from its signature
     pub help: String,
+    /// arguments of the builtin
+    pub signature: Signature,
     /// function to execute the builtin
-    pub execute: Box<
-        dyn Fn(
-                // config
-                std::sync::Arc<std::sync::Mutex<crate::config::Config>>,
-                // args
-                std::sync::Arc<std::sync::Mutex<crate::config::Arguments>>,
-                // env
-                std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
-                // aliases
-                std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
-                // vars
-                std::sync::Arc<
-                    std::sync::Mutex<
-                        std::collections::HashMap<String, crate::expressions::Expressions>,
-                    >,
-                >,
-                // history
-                std::sync::Arc<std::sync::Mutex<crate::history::History>>,
-                // available builtin names
-                Vec<String>,
-                // arguments to the builtin
-                Vec<String>,
-                // TODO: consider passing context back into builtins instead of individual components
-                bool, // debug
-            ) + Send
-            + Sync
-            + 'static,
-    >,
+    pub execute: BuiltinFn,
 }
 
+/// function executing a builtin with its context and parsed arguments
+pub type BuiltinFn = Box<
+    dyn Fn(
+            &BuiltinContext<'_>,
+            Args,
+        ) -> Result<crate::expressions::Expressions, crate::errors::TurtleError>
+        + Send
+        + Sync
+        + 'static,
+>;
+
 impl Builtin {
     pub fn _fields() -> Vec<String> {
         vec![
             "name".to_string(),
             "description".to_string(),
             "help".to_string(),
+            "signature".to_string(),
             "execute".to_string(),
         ]
     }
+
+    /// the `--help` of the builtin: its usage, arguments, options and help text
+    pub fn usage(&self) -> String {
+        let mut usage = format!(
+            "Usage: {}\n{}\n{}",
+            self.signature.usage(&self.name),
+            self.description,
+            self.signature.describe()
+        );
+        if !self.help.is_empty() {
+            usage.push('\n');
+            usage.push_str(&self.help);
+        }
+        usage
+    }
 }
 
 impl std::fmt::Debug for Builtin {
     /// format builtin outputs
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TurtleBuiltin")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("help", &self.help)
+            .field("signature", &self.signature)
+            .finish()
+    }
+}
+
+/// the shell state a builtin runs with
+pub struct BuiltinContext<'a> {
+    pub config: std::sync::Arc<std::sync::Mutex<crate::config::Config>>,
+    /// command line arguments of the shell
+    pub args: std::sync::Arc<std::sync::Mutex<crate::config::Arguments>>,
+    pub env: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
+    pub aliases: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
+    pub vars: std::sync::Arc<
+        std::sync::Mutex<std::collections::HashMap<String, crate::expressions::Expressions>>,
+    >,
+    pub history: std::sync::Arc<std::sync::Mutex<crate::history::History>>,
+    /// every builtin, eg: for `help`
+    pub builtins: &'a Builtins,
+    pub debug: bool,
+}
+
+impl BuiltinContext<'_> {
+    /// an interpreter sharing the state of the shell
+    pub fn interpreter(&self) -> crate::lang::Interpreter {
+        crate::lang::Interpreter::new(
+            Some(self.args.clone()),
+            self.env.clone(),
+            self.aliases.clone(),
+            self.vars.clone(),
+            self.builtins.list(),
+            self.debug,
+        )
+    }
+
+    /// an execution context sharing the state of the shell
+    pub fn context(&self) -> crate::context::Context {
+        let mut context = crate::context::Context::new(
+            Some(self.config.clone()),
+            Some(self.args.clone()),
+            self.env.clone(),
+            self.aliases.clone(),
+            self.vars.clone(),
+            self.history.clone(),
+            self.debug,
+        );
+        context.setup();
+        context
+    }
+}
+
+/// the type of an argument or option value
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgType {
+    String,
+    Integer,
+    Number,
+    Path,
+    /// one of the values, which are also its completions
+    Choice(&'static [&'static str]),
+    /// the rest of the line as written, eg: the code of `eval`
+    Code,
+}
+
+impl ArgType {
+    /// the value of an argument, eg: `10` for an integer
+    fn parse(&self, value: &str) -> Result<crate::expressions::Expressions, String> {
+        use crate::expressions::Expressions;
+        match self {
+            ArgType::Integer => crate::numbers::Integer::parse(value)
+                .map(Expressions::Integer)
+                .ok_or(format!("expected an integer, got `{}`", value)),
+            ArgType::Number => value
+                .parse::<f64>()
+                .map(Expressions::Number)
+                .map_err(|_| format!("expected a number, got `{}`", value)),
+            ArgType::Choice(choices) if !choices.contains(&value) => Err(format!(
+                "expected one of {}, got `{}`",
+                choices.join(", "),
+                value
+            )),
+            _ => Ok(Expressions::String(value.to_string())),
+        }
+    }
+
+    /// the placeholder of a value in usages, eg: `<integer>`
+    fn placeholder(&self, name: &str) -> String {
+        match self {
+            ArgType::Integer => "<integer>".to_string(),
+            ArgType::Number => "<number>".to_string(),
+            ArgType::Choice(choices) => choices.join("|"),
+            _ => format!("<{}>", name),
+        }
+    }
+}
+
+/// a positional argument
+#[derive(Debug, Clone, PartialEq)]
+pub struct Positional {
+    pub name: String,
+    pub kind: ArgType,
+    pub description: String,
+    pub required: bool,
+    /// takes every remaining argument, as an array
+    pub rest: bool,
+}
+
+/// an option, or a flag when it takes no value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Opt {
+    /// the long name, eg: `limit` for `--limit`
+    pub name: String,
+    pub short: Option<char>,
+    pub description: String,
+    /// the type of its value, `None` for flags
+    pub kind: Option<ArgType>,
+    /// the value when it isn't given, read like a given value
+    pub default: Option<String>,
+}
+
+impl Opt {
+    /// eg: `-n, --limit <integer>`
+    fn usage(&self) -> String {
+        let short = self.short.map(|c| format!("-{}, ", c)).unwrap_or_default();
+        let value = match &self.kind {
+            Some(kind) => format!(" {}", kind.placeholder(&self.name)),
+            None => String::new(),
+        };
+        format!("{}--{}{}", short, self.name, value)
+    }
+
+    fn matches(&self, arg: &str) -> bool {
+        arg.strip_prefix("--") == Some(self.name.as_str())
+            || self.short.is_some_and(|c| arg == format!("-{}", c))
+    }
+}
+
+/// the arguments a builtin takes, it parses them and generates `--help` and completions
+///
+/// ```text
+/// Signature::new()
+///     .optional("section", ArgType::Choice(SECTIONS), "Section to show")
+///     .option("limit", Some('n'), ArgType::Integer, Some("10"), "Rows per section")
+///     .flag("clear", Some('c'), "Clear the history")
+/// ```
+///
+/// options can come before, after and between positional arguments, `--` ends them.
+/// `-h` and `--help` are added to every signature
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signature {
+    pub positionals: Vec<Positional>,
+    pub options: Vec<Opt>,
+}
+
+/// the parsed arguments of a builtin by name, see `Signature::parse`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    values: std::collections::HashMap<String, crate::expressions::Expressions>,
+}
+
+impl Args {
+    /// the value of an argument or option, defaults included
+    pub fn get(&self, name: &str) -> Option<&crate::expressions::Expressions> {
+        self.values.get(name)
+    }
+
+    /// the value of an argument or option as text
+    pub fn string(&self, name: &str) -> Option<String> {
+        self.get(name).map(crate::format::display)
+    }
+
+    /// the value of an integer argument or option
+    pub fn integer(&self, name: &str) -> Option<i64> {
+        match self.get(name) {
+            Some(crate::expressions::Expressions::Integer(n)) => n.to_i64(),
+            _ => None,
+        }
+    }
+
+    /// the values of a rest argument
+    pub fn strings(&self, name: &str) -> Vec<String> {
+        match self.get(name) {
+            Some(crate::expressions::Expressions::Array(values)) => {
+                values.iter().map(crate::format::display).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// whether a flag is given
+    pub fn flag(&self, name: &str) -> bool {
+        self.get(name) == Some(&crate::expressions::Expressions::Boolean(true))
+    }
+}
+
+impl Signature {
+    pub fn new() -> Self {
+        Signature::default()
+    }
+
+    fn positional(
+        mut self,
+        name: &str,
+        kind: ArgType,
+        description: &str,
+        required: bool,
+        rest: bool,
+    ) -> Self {
+        self.positionals.push(Positional {
+            name: name.to_string(),
+            kind,
+            description: description.to_string(),
+            required,
+            rest,
+        });
+        self
+    }
+
+    /// a positional argument that must be given
+    pub fn required(self, name: &str, kind: ArgType, description: &str) -> Self {
+        self.positional(name, kind, description, true, false)
+    }
+
+    /// a positional argument that can be left out
+    pub fn optional(self, name: &str, kind: ArgType, description: &str) -> Self {
+        self.positional(name, kind, description, false, false)
+    }
+
+    /// the remaining positional arguments, none or more
+    pub fn rest(self, name: &str, kind: ArgType, description: &str) -> Self {
+        self.positional(name, kind, description, false, true)
+    }
+
+    /// the rest of the line as written, it must be given
+    pub fn code(self, name: &str, description: &str) -> Self {
+        self.positional(name, ArgType::Code, description, true, true)
+    }
+
+    /// a flag, eg: `-c, --clear`
+    pub fn flag(mut self, name: &str, short: Option<char>, description: &str) -> Self {
+        self.options.push(Opt {
+            name: name.to_string(),
+            short,
+            description: description.to_string(),
+            kind: None,
+            default: None,
+        });
+        self
+    }
+
+    /// an option with a value, eg: `-n, --limit <integer>`
+    pub fn option(
+        mut self,
+        name: &str,
+        short: Option<char>,
+        kind: ArgType,
+        default: Option<&str>,
+        description: &str,
+    ) -> Self {
+        self.options.push(Opt {
+            name: name.to_string(),
+            short,
+            description: description.to_string(),
+            kind: Some(kind),
+            default: default.map(str::to_string),
+        });
+        self
+    }
+
+    /// parse the arguments of a builtin, `None` when `-h` or `--help` is given
+    pub fn parse(&self, args: &str) -> Result<Option<Args>, String> {
+        use crate::expressions::Expressions;
+        let mut parsed = Args::default();
+        let mut positionals = self.positionals.iter().peekable();
+        let mut rest = Vec::new();
+        let mut options_ended = false;
+        let mut words = crate::utils::split_args_at(args).into_iter();
+        while let Some((offset, word)) = words.next() {
+            // negative numbers are arguments
+            let is_option = !options_ended
+                && word.starts_with('-')
+                && word.len() > 1
+                && word.parse::<f64>().is_err();
+            if let Some(positional) = positionals.peek()
+                && positional.kind == ArgType::Code
+                && !is_option
+            {
+                let code = args[offset..].trim_end().to_string();
+                parsed
+                    .values
+                    .insert(positional.name.clone(), Expressions::String(code));
+                positionals.next();
+                break;
+            }
+            if is_option {
+                if word == "--" {
+                    options_ended = true;
+                    continue;
+                }
+                if word == "-h" || word == "--help" {
+                    return Ok(None);
+                }
+                let (name, inline) = match word.split_once('=') {
+                    Some((name, value)) if name.starts_with("--") => {
+                        (name, Some(value.to_string()))
+                    }
+                    _ => (word.as_str(), None),
+                };
+                if let Some(option) = self.options.iter().find(|option| option.matches(name)) {
+                    let value = match &option.kind {
+                        None if inline.is_some() => {
+                            return Err(format!("--{} doesn't take a value", option.name));
+                        }
+                        None => Expressions::Boolean(true),
+                        Some(kind) => {
+                            let value = inline
+                                .or_else(|| words.next().map(|(_, value)| value))
+                                .ok_or(format!(
+                                    "--{} expects {}",
+                                    option.name,
+                                    kind.placeholder(&option.name)
+                                ))?;
+                            kind.parse(&value)
+                                .map_err(|e| format!("--{}: {}", option.name, e))?
+                        }
+                    };
+                    parsed.values.insert(option.name.clone(), value);
+                    continue;
+                }
+                return Err(format!("unknown option `{}`", word));
+            }
+            let Some(positional) = positionals.peek() else {
+                return Err(format!("unexpected argument `{}`", word));
+            };
+            let value = positional
+                .kind
+                .parse(&word)
+                .map_err(|e| format!("{}: {}", positional.name, e))?;
+            match positional.rest {
+                true => rest.push(value),
+                false => {
+                    parsed.values.insert(positional.name.clone(), value);
+                    positionals.next();
+                }
+            }
+        }
+
+        for positional in positionals {
+            if positional.rest && positional.kind != ArgType::Code {
+                parsed.values.insert(
+                    positional.name.clone(),
+                    Expressions::Array(std::mem::take(&mut rest)),
+                );
+            } else if positional.required {
+                return Err(format!(
+                    "missing {}",
+                    positional.kind.placeholder(&positional.name)
+                ));
+            }
+        }
+        for option in &self.options {
+            if let (Some(kind), Some(default)) = (&option.kind, &option.default)
+                && !parsed.values.contains_key(&option.name)
+            {
+                parsed
+                    .values
+                    .insert(option.name.clone(), kind.parse(default)?);
+            }
+        }
+        Ok(Some(parsed))
+    }
+
+    /// the usage line of a builtin, eg: `stats [section] [-n, --limit <integer>]`
+    pub fn usage(&self, name: &str) -> String {
+        let mut usage = vec![name.to_string()];
+        usage.extend(
+            self.options
+                .iter()
+                .map(|option| format!("[{}]", option.usage())),
+        );
+        usage.extend(self.positionals.iter().map(|positional| {
+            let placeholder = match &positional.kind {
+                ArgType::Choice(_) | ArgType::Code => positional.name.clone(),
+                kind => kind
+                    .placeholder(&positional.name)
+                    .trim_matches(['<', '>'])
+                    .to_string(),
+            };
+            match (positional.required, positional.rest) {
+                (true, true) => format!("<{}...>", placeholder),
+                (true, false) => format!("<{}>", placeholder),
+                (false, true) => format!("[{}...]", placeholder),
+                (false, false) => format!("[{}]", placeholder),
+            }
+        }));
+        usage.join(" ")
+    }
+
+    /// the arguments and options with their descriptions, defaults and choices
+    pub fn describe(&self) -> String {
+        let mut rows: Vec<(String, String)> = Vec::new();
+        let mut lines = Vec::new();
+        for positional in &self.positionals {
+            let description = match &positional.kind {
+                ArgType::Choice(choices) => {
+                    format!("{} ({})", positional.description, choices.join(", "))
+                }
+                _ => positional.description.clone(),
+            };
+            rows.push((positional.name.clone(), description));
+        }
+        let arguments = rows.len();
+        for option in &self.options {
+            let description = match &option.default {
+                Some(default) => format!("{} (default: {})", option.description, default),
+                None => option.description.clone(),
+            };
+            rows.push((option.usage(), description));
+        }
+        rows.push(("-h, --help".to_string(), "Show this help".to_string()));
+
+        let width = rows
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (i, (name, description)) in rows.iter().enumerate() {
+            if i == 0 && arguments > 0 {
+                lines.push("Arguments:".to_string());
+            }
+            if i == arguments {
+                lines.push("Options:".to_string());
+            }
+            lines.push(format!("  {:width$}  {}", name, description, width = width));
+        }
+        lines.join("\n")
+    }
+
+    /// completions of the word being typed, after the arguments before it
+    ///
+    /// option names complete after `-`, and choices complete as arguments and option values
+    pub fn complete(&self, args: &[String], word: &str) -> Vec<String> {
+        let choices = |kind: Option<&ArgType>| match kind {
+            Some(ArgType::Choice(choices)) => choices
+                .iter()
+                .filter(|choice| choice.starts_with(word))
+                .map(|choice| choice.to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        if let Some(option) = args
+            .last()
+            .and_then(|arg| self.options.iter().find(|option| option.matches(arg)))
+            && let Some(kind) = &option.kind
+        {
+            return choices(Some(kind));
+        }
+        if word.starts_with('-') {
+            return self
+                .options
+                .iter()
+                .map(|option| format!("--{}", option.name))
+                .chain(["--help".to_string()])
+                .filter(|name| name.starts_with(word))
+                .collect();
+        }
+
+        // the positional argument being typed, skipping options and their values
+        let mut given = 0;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match self.options.iter().find(|option| option.matches(arg)) {
+                Some(option) if option.kind.is_some() => {
+                    args.next();
+                }
+                Some(_) => {}
+                None => given += 1,
+            }
+        }
+        let positional = self
+            .positionals
+            .get(given)
+            .or_else(|| self.positionals.last().filter(|positional| positional.rest));
+        choices(positional.map(|positional| &positional.kind))
+    }
+}
+
+/// completes builtin names and their arguments in the line editor
+#[derive(Debug, Clone, Default)]
+pub struct Completions {
+    /// the names and signatures of the builtins
+    pub signatures: Vec<(String, Signature)>,
+}
+
+impl Completions {
+    /// the start of the word being typed at `pos` and its completions
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let line = &line[..pos];
+        let start = line
+            .rfind(char::is_whitespace)
+            .map(|i| i + line[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        let word = &line[start..];
+        let mut words = crate::utils::split_args(&line[..start]);
+        if words.is_empty() {
+            let names = self
+                .signatures
+                .iter()
+                .map(|(name, _)| name.clone())
+                .filter(|name| name.starts_with(word))
+                .collect();
+            return (start, names);
+        }
+        let name = words.remove(0);
+        match self.signatures.iter().find(|(builtin, _)| *builtin == name) {
+            Some((_, signature)) => (start, signature.complete(&words, word)),
+            None => (start, Vec::new()),
+        }
+    }
+}
+
+impl rustyline::completion::Completer for Completions {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl rustyline::hint::Hinter for Completions {
+    type Hint = String;
+}
+
+impl rustyline::highlight::Highlighter for Completions {}
+
+impl rustyline::validate::Validator for Completions {}
+
+impl rustyline::Helper for Completions {}
+
+/// pipeline stage builtins
+///
+/// stages transform the value piped into them, eg: `ls | where size > 1000`,
+/// and return the result rather than printing it
+pub struct Stage {
+    /// name of the stage
+    pub name: String,
+    /// short description of the stage
+    pub description: String,
+    /// help text for the stage
+    pub help: String,
+    /// function to execute the stage
+    pub execute: StageFn,
+}
+
+/// function executing a stage with its input value and arguments
+pub type StageFn = Box<
+    dyn Fn(
+            // input value
+            crate::expressions::Expressions,
+            // arguments to the stage
+            &str,
+        ) -> Result<crate::context::EvalResults, String>
+        + Send
+        + Sync
+        + 'static,
+>;
+
+impl std::fmt::Debug for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TurtleStage")
             .field("name", &self.name)
             .field("description", &self.description)
             .field("help", &self.help)
@@ -117,37 +696,157 @@ impl Builtins {
         self.builtins.iter().map(|b| b.name.clone()).collect()
     }
 
+    /// the `--help` of a builtin by name
+    pub fn help(&self, name: &str) -> Option<String> {
+        self.get(name).map(Builtin::usage)
+    }
+
+    /// the completions of builtin names and arguments for the line editor
+    pub fn completions(&self) -> Completions {
+        Completions {
+            signatures: self
+                .builtins
+                .iter()
+                .map(|b| (b.name.clone(), b.signature.clone()))
+                .collect(),
+        }
+    }
+
     /// execute a builtin by name
+    ///
+    /// `-h` and `--help` print the help of the builtin, other arguments are parsed with its
+    /// signature before it runs
     pub fn exec(
         &self,
         name: &str,
-        vars: std::sync::Arc<
-            std::sync::Mutex<std::collections::HashMap<String, crate::expressions::Expressions>>,
-        >,
-
-        config: std::sync::Arc<std::sync::Mutex<crate::config::Config>>,
-        turtle_args: std::sync::Arc<std::sync::Mutex<crate::config::Arguments>>,
-        env: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
-        aliases: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
-        history: std::sync::Arc<std::sync::Mutex<crate::history::History>>,
-        builtin_names: Vec<String>,
-        args: Vec<String>,
-    ) {
-        let debug = self.debug;
-        if let Some(builtin) = self.get(name) {
-            (builtin.execute)(
-                config,
-                turtle_args,
-                env,
-                aliases,
-                vars,
-                history,
-                builtin_names,
-                args,
-                debug,
-            );
-        } else {
-            println!("Builtin command '{}' not found", name);
+        context: &BuiltinContext<'_>,
+        args: &str,
+    ) -> Result<crate::expressions::Expressions, crate::errors::TurtleError> {
+        let Some(builtin) = self.get(name) else {
+            return Err(crate::errors::TurtleError::new(format!(
+                "Builtin command '{}' not found",
+                name
+            )));
+        };
+        match builtin.signature.parse(args) {
+            Ok(Some(args)) => (builtin.execute)(context, args),
+            Ok(None) => {
+                println!("{}", builtin.usage());
+                Ok(crate::expressions::Expressions::Null)
+            }
+            Err(e) => Err(crate::errors::TurtleError::new(format!(
+                "{}: {}, see `{} --help`",
+                name, e, name
+            ))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::Expressions;
+
+    fn signature() -> Signature {
+        Signature::new()
+            .optional(
+                "section",
+                ArgType::Choice(&["commands", "hours"]),
+                "Section to show",
+            )
+            .option(
+                "limit",
+                Some('n'),
+                ArgType::Integer,
+                Some("10"),
+                "Number of rows",
+            )
+            .flag("clear", Some('c'), "Clear the history")
+    }
+
+    #[test]
+    fn test_parse() {
+        let args = signature().parse("-n 3 hours").unwrap().unwrap();
+        assert_eq!(args.integer("limit"), Some(3));
+        assert_eq!(args.string("section"), Some("hours".to_string()));
+        assert!(!args.flag("clear"));
+
+        let args = signature().parse("--clear --limit=-1").unwrap().unwrap();
+        assert_eq!(args.get("section"), None);
+        assert_eq!(args.integer("limit"), Some(-1));
+        assert!(args.flag("clear"));
+        assert_eq!(
+            signature().parse("").unwrap().unwrap().integer("limit"),
+            Some(10)
+        );
+
+        assert_eq!(signature().parse("hours --help"), Ok(None));
+        assert!(signature().parse("days").is_err());
+        assert!(signature().parse("--limit").is_err());
+        assert!(signature().parse("--limit x").is_err());
+        assert!(signature().parse("--verbose").is_err());
+        assert!(signature().parse("hours commands").is_err());
+    }
+
+    #[test]
+    fn test_rest_and_code() {
+        let rest = Signature::new()
+            .required("format", ArgType::String, "The format")
+            .rest("args", ArgType::String, "Values");
+        let args = rest.parse(r#""{} {}" a -2"#).unwrap().unwrap();
+        assert_eq!(args.string("format"), Some("{} {}".to_string()));
+        assert_eq!(args.strings("args"), vec!["a", "-2"]);
+        assert!(rest.parse("").is_err());
+
+        // code is the rest of the line as written, quotes included
+        let code = Signature::new().code("code", "The code");
+        let args = code.parse(r#"let x = "a  b""#).unwrap().unwrap();
+        assert_eq!(
+            args.get("code"),
+            Some(&Expressions::String(r#"let x = "a  b""#.to_string()))
+        );
+        assert_eq!(
+            code.parse("-1 + 2").unwrap().unwrap().string("code"),
+            Some("-1 + 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_help_and_completions() {
+        assert_eq!(
+            signature().usage("stats"),
+            "stats [-n, --limit <integer>] [-c, --clear] [section]"
+        );
+        assert_eq!(
+            signature().describe(),
+            [
+                "Arguments:",
+                "  section                Section to show (commands, hours)",
+                "Options:",
+                "  -n, --limit <integer>  Number of rows (default: 10)",
+                "  -c, --clear            Clear the history",
+                "  -h, --help             Show this help",
+            ]
+            .join("\n")
+        );
+
+        let completions = Completions {
+            signatures: vec![("stats".to_string(), signature())],
+        };
+        assert_eq!(
+            completions.candidates("st", 2),
+            (0, vec!["stats".to_string()])
+        );
+        assert_eq!(
+            completions.candidates("stats h", 7),
+            (6, vec!["hours".to_string()])
+        );
+        assert_eq!(
+            completions.candidates("stats --l", 9),
+            (6, vec!["--limit".to_string()])
+        );
+        // option values aren't arguments
+        assert!(completions.candidates("stats -n ", 9).1.is_empty());
+        assert!(completions.candidates("stats hours ", 12).1.is_empty());
+    }
+}
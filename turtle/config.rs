@@ -25,6 +25,27 @@ pub const DEFAULT_THEME: &str = "monokai";
 /// default debug
 pub const DEFAULT_DEBUG: bool = false;
 
+/// default editor tab width
+pub const DEFAULT_TAB_WIDTH: u8 = 4;
+
+/// largest accepted editor tab width
+pub const MAX_TAB_WIDTH: u8 = 16;
+
+/// supported output formats
+pub const FORMATS: &[&str] = &["table", "text", "json", "jsonl", "yaml"];
+
+/// system wide config file
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/turtle/config.yaml";
+
+/// project config file name, discovered by walking up from the working directory
+pub const PROJECT_CONFIG_FILE: &str = ".turtle.yaml";
+
+/// largest accepted history size
+pub const MAX_HISTORY_SIZE: usize = 1_000_000;
+
+/// keys accepted in config files that are not (yet) part of `Config`
+pub const RESERVED_CONFIG_KEYS: &[&str] = &["debug"];
+
 /// default config
 pub const DEFAULT_CONFIG: &str = r#"
 # Default Turtle configuration file
@@ -191,6 +212,14 @@ impl Default for Environment {
 ///  ll: "ls -la"
 /// history_size: 2000
 /// theme: "monokai"
+/// editor:
+///   mode: emacs
+/// history:
+///   dedupe: true
+///   ignore: ["^ls$"]
+/// execution:
+///   format: json
+///   timeout: 30
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -229,6 +258,30 @@ pub struct Config {
     ///
     /// environment `TURTLE_THEME="monokai"`
     pub theme: Option<String>,
+    /// prompt shown while a multi-line command is being entered
+    ///
+    /// default: "⏭️ "
+    ///
+    /// environment `TURTLE_CONTINUATION_PROMPT="... "`
+    pub continuation_prompt: Option<String>,
+    /// prompt shown when a command can't be interpreted
+    ///
+    /// default: "<<< ❌❌❌ >>>"
+    ///
+    /// environment `TURTLE_ERROR_PROMPT="!! "`
+    pub error_prompt: Option<String>,
+    /// line editor settings
+    ///
+    /// environment `TURTLE_EDITOR_<FIELD>`, eg: `TURTLE_EDITOR_MODE=emacs`
+    pub editor: Option<EditorConfig>,
+    /// command history settings
+    ///
+    /// environment `TURTLE_HISTORY_<FIELD>`, eg: `TURTLE_HISTORY_DEDUPE=true`
+    pub history: Option<HistoryConfig>,
+    /// command execution settings
+    ///
+    /// environment `TURTLE_EXECUTION_<FIELD>`, eg: `TURTLE_EXECUTION_TIMEOUT=30`
+    pub execution: Option<ExecutionConfig>,
 }
 
 impl Default for Config {
@@ -239,6 +292,30 @@ impl Default for Config {
             aliases: None,
             history_size: Some(defaults.history_size),
             theme: Some(defaults.theme),
+            continuation_prompt: Some(defaults.continuation_prompt),
+            error_prompt: Some(defaults.error_prompt),
+            editor: Some(EditorConfig {
+                mode: Some(EditMode::Vi),
+                bell: Some(false),
+                tab_width: Some(DEFAULT_TAB_WIDTH),
+            }),
+            history: Some(HistoryConfig {
+                path: Some(defaults.history_path),
+                size: None,
+                dedupe: Some(false),
+                ignore: None,
+                backend: Some(HistoryBackend::Json),
+                flush_interval: Some(defaults.save_interval),
+            }),
+            execution: Some(ExecutionConfig {
+                format: Some(defaults.format),
+                timeout: None,
+                path: None,
+                env: None,
+                lenient: Some(false),
+                modules: None,
+                errexit: Some(false),
+            }),
         }
     }
 }
@@ -247,8 +324,16 @@ impl std::fmt::Display for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Config prompt: {:?}, aliases: {:?}, history_size: {:?}, theme: {:?} }}",
-            self.prompt, self.aliases, self.history_size, self.theme
+            "Config {{ prompt: {:?}, aliases: {:?}, history_size: {:?}, theme: {:?}, continuation_prompt: {:?}, error_prompt: {:?}, editor: {:?}, history: {:?}, execution: {:?} }}",
+            self.prompt,
+            self.aliases,
+            self.history_size,
+            self.theme,
+            self.continuation_prompt,
+            self.error_prompt,
+            self.editor,
+            self.history,
+            self.execution
         )
     }
 }
@@ -282,100 +367,201 @@ impl Config {
         .or_else(|| Some(Config::default()))
     }
 
-    /// resolve configuration
-    ///
-    /// resolves a Config using the following rules:
-    ///
-    /// 1. start with the provided Config or default Config
-    ///
-    /// 2. override with environment variables if set
+    /// create a Config with every field unset
     ///
-    /// 3. override with command line arguments if set and where applicable
-    ///
-    pub fn resolve(
-        // &self,
-        // config: &Config,
-        yaml: &str,
-        // env: &Environment,
-        args: &Option<crate::config::Arguments>,
-    ) -> Option<Self> {
-        let defaults = Defaults::default();
-        let config = Config::new(yaml, args.clone())?;
-        let env = Environment::new(args.clone().unwrap_or_default());
-        let mut merged = config.clone();
+    /// used as the starting point for configuration layers that only set some fields
+    pub fn empty() -> Self {
+        Config {
+            prompt: None,
+            aliases: None,
+            history_size: None,
+            theme: None,
+            continuation_prompt: None,
+            error_prompt: None,
+            editor: None,
+            history: None,
+            execution: None,
+        }
+    }
+
+    /// line editor settings, falling back to the defaults for unset fields
+    pub fn editor(&self) -> EditorConfig {
+        let mut editor = Config::default().editor.unwrap_or_default();
+        if let Some(configured) = self.editor.clone() {
+            editor.merge(configured);
+        }
+        editor
+    }
 
-        // override with environment variables / args
-        // fall back to defaults if not set
+    /// history settings, falling back to the defaults for unset fields
+    ///
+    /// `history.size` takes precedence over the top level `history_size`
+    pub fn history(&self) -> HistoryConfig {
+        let mut history = Config::default().history.unwrap_or_default();
+        history.size = self.history_size.or(Some(DEFAULT_HISTORY_SIZE));
+        if let Some(configured) = self.history.clone() {
+            history.merge(configured);
+        }
+        history
+    }
 
-        // we should iterate over the list of fields in the Config
+    /// execution settings, falling back to the defaults for unset fields
+    pub fn execution(&self) -> ExecutionConfig {
+        let mut execution = Config::default().execution.unwrap_or_default();
+        if let Some(configured) = self.execution.clone() {
+            execution.merge(configured);
+        }
+        execution
+    }
 
+    /// build a Config from `TURTLE_<FIELD>` environment variables
+    ///
+    /// `TURTLE_ALIASES` is parsed as a JSON object, eg: `TURTLE_ALIASES='{"ll": "ls -la"}'`
+    pub fn from_environment(env: &Environment) -> Self {
+        let mut config = Config::empty();
         for field in Config::fields() {
-            // if the field is provided in env.config, use is to override
-            // TURTLE_<FIELD>
-            let env_key = format!("TURTLE_{}", field.to_uppercase());
-            if let Some(value) = env.get(&env_key) {
-                match field.as_str() {
-                    "prompt" => {
-                        merged.prompt = Some(value.clone());
-                    }
-                    "aliases" => {
-                        if let Ok(aliases_map) =
-                            serde_json::from_str::<std::collections::HashMap<String, String>>(value)
-                        {
-                            merged.aliases = Some(aliases_map);
-                        }
-                    }
-                    "history_size" => {
-                        if let Ok(size) = value.parse::<usize>() {
-                            merged.history_size = Some(size);
-                        }
-                    }
-                    "theme" => {
-                        merged.theme = Some(value.clone());
-                    }
-                    _ => {}
-                }
-            }
-
-            // if the value in merged is None, set to default
+            // Environment strips the TURTLE_ prefix from its keys
+            let value = match env.get(&field.to_uppercase()) {
+                Some(value) => value,
+                None => continue,
+            };
             match field.as_str() {
-                "prompt" => {
-                    if merged.prompt.is_none() {
-                        merged.prompt = Some(defaults.prompt.clone());
-                    }
-                }
+                "prompt" => config.prompt = Some(value.clone()),
                 "aliases" => {
-                    if merged.aliases.is_none() {
-                        merged.aliases = Some(std::collections::HashMap::new());
-                    }
-                }
-                "history_size" => {
-                    if merged.history_size.is_none() {
-                        merged.history_size = Some(defaults.history_size);
+                    match serde_json::from_str::<std::collections::HashMap<String, String>>(value) {
+                        Ok(aliases) => config.aliases = Some(aliases),
+                        Err(e) => {
+                            if env.debug {
+                                println!("❌ ignoring invalid TURTLE_ALIASES: {}", e);
+                            }
+                        }
                     }
                 }
-                "theme" => {
-                    if merged.theme.is_none() {
-                        merged.theme = Some(defaults.theme.clone());
+                "history_size" => match value.parse::<usize>() {
+                    Ok(size) => config.history_size = Some(size),
+                    Err(e) => {
+                        if env.debug {
+                            println!("❌ ignoring invalid TURTLE_HISTORY_SIZE: {}", e);
+                        }
                     }
-                }
+                },
+                "theme" => config.theme = Some(value.clone()),
+                "continuation_prompt" => config.continuation_prompt = Some(value.clone()),
+                "error_prompt" => config.error_prompt = Some(value.clone()),
                 _ => {}
             }
         }
+        config.editor = section_from_environment(env, "editor");
+        config.history = section_from_environment(env, "history");
+        config.execution = section_from_environment(env, "execution");
+        config
+    }
+
+    /// build a Config from command line arguments
+    pub fn from_arguments(args: &crate::config::Arguments) -> Self {
+        let mut config = Config::empty();
+        config.prompt = args.prompt.clone();
+        config.history_size = args.history_size;
+        config.theme = args.theme.clone();
+        if let Some(path) = &args.history_path {
+            config.history = Some(HistoryConfig {
+                path: Some(path.clone()),
+                ..Default::default()
+            });
+        }
+        if let Some(format) = &args.format {
+            config.execution = Some(ExecutionConfig {
+                format: Some(format.clone()),
+                ..Default::default()
+            });
+        }
+        config
+    }
 
-        if let Some(prompt) = env.get("PROMPT") {
-            merged.prompt = Some(prompt.clone());
+    /// merge another Config on top of this one
+    ///
+    /// fields set in `other` replace the fields in `self`, aliases are merged by name
+    ///
+    /// returns the keys that were set by `other`, eg: `["prompt", "aliases.ll"]`
+    pub fn merge(&mut self, other: Config) -> Vec<String> {
+        let mut keys = Vec::new();
+        if let Some(prompt) = other.prompt {
+            self.prompt = Some(prompt);
+            keys.push("prompt".to_string());
         }
-        if let Some(history_size) = env.get("HISTORY_SIZE") {
-            if let Ok(size) = history_size.parse::<usize>() {
-                merged.history_size = Some(size);
+        if let Some(aliases) = other.aliases {
+            let merged = self
+                .aliases
+                .get_or_insert_with(std::collections::HashMap::new);
+            for (name, command) in aliases {
+                keys.push(format!("aliases.{}", name));
+                merged.insert(name, command);
             }
         }
-        if let Some(theme) = env.get("THEME") {
-            merged.theme = Some(theme.clone());
+        if let Some(history_size) = other.history_size {
+            self.history_size = Some(history_size);
+            keys.push("history_size".to_string());
         }
+        if let Some(theme) = other.theme {
+            self.theme = Some(theme);
+            keys.push("theme".to_string());
+        }
+        if let Some(continuation_prompt) = other.continuation_prompt {
+            self.continuation_prompt = Some(continuation_prompt);
+            keys.push("continuation_prompt".to_string());
+        }
+        if let Some(error_prompt) = other.error_prompt {
+            self.error_prompt = Some(error_prompt);
+            keys.push("error_prompt".to_string());
+        }
+        if let Some(editor) = other.editor {
+            let merged = self.editor.get_or_insert_with(EditorConfig::default);
+            keys.extend(
+                merged
+                    .merge(editor)
+                    .into_iter()
+                    .map(|k| format!("editor.{}", k)),
+            );
+        }
+        if let Some(history) = other.history {
+            let merged = self.history.get_or_insert_with(HistoryConfig::default);
+            keys.extend(
+                merged
+                    .merge(history)
+                    .into_iter()
+                    .map(|k| format!("history.{}", k)),
+            );
+        }
+        if let Some(execution) = other.execution {
+            let merged = self.execution.get_or_insert_with(ExecutionConfig::default);
+            keys.extend(
+                merged
+                    .merge(execution)
+                    .into_iter()
+                    .map(|k| format!("execution.{}", k)),
+            );
+        }
+        keys
+    }
 
-        Some(merged)
+    /// resolve configuration
+    ///
+    /// resolves a Config by merging each `ConfigLayer` in order of precedence:
+    ///
+    /// 1. built-in defaults
+    ///
+    /// 2. the system config file, `/etc/turtle/config.yaml`
+    ///
+    /// 3. the user config, `yaml` may be a file path or yaml content
+    ///
+    /// 4. the nearest project `.turtle.yaml`, discovered by walking up from the working directory
+    ///
+    /// 5. `TURTLE_*` environment variables
+    ///
+    /// 6. command line arguments
+    ///
+    pub fn resolve(yaml: &str, args: &Option<crate::config::Arguments>) -> Option<Self> {
+        Some(LayeredConfig::resolve(yaml, args).config)
     }
 
     pub fn as_mutex(&self) -> std::sync::Arc<std::sync::Mutex<Self>> {
@@ -383,21 +569,7 @@ impl Config {
     }
 
     pub fn load(path: &str, args: &crate::config::Arguments) -> Option<Self> {
-        // if the --debug flag is set, print the path being loaded
-
-        let expanded_path = if path.starts_with("~") {
-            if let Some(home) = dirs::home_dir() {
-                let without_tilde = path.trim_start_matches("~");
-                home.join(without_tilde).to_string_lossy().to_string()
-            } else {
-                path.to_string()
-            }
-        } else {
-            path.to_string()
-        };
-
-        // let contents = std::fs::read_to_string(expanded_path).ok()?;
-        // serde_yaml::from_str::<Config>(&contents).ok()
+        let expanded_path = crate::utils::expand_path(path);
 
         let contents = match std::fs::read_to_string(&expanded_path) {
             Ok(c) => c,
@@ -408,11 +580,13 @@ impl Config {
                 return None;
             }
         };
-        let mut config = match serde_yaml::from_str::<Config>(&contents) {
+
+        // invalid config files are reported regardless of --debug, and skipped
+        let config = match Config::check(&contents, Some(&expanded_path)) {
             Ok(c) => c,
-            Err(e) => {
-                if args.debug {
-                    println!("❌ Failed to parse config file at {}: {}", expanded_path, e);
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("❌ {}", error);
                 }
                 return None;
             }
@@ -426,11 +600,11 @@ impl Config {
     }
 
     pub fn loads(yaml_content: &str, args: &crate::config::Arguments) -> Option<Self> {
-        let mut config = match serde_yaml::from_str::<Config>(yaml_content) {
+        let config = match Config::check(yaml_content, None) {
             Ok(c) => c,
-            Err(e) => {
-                if args.debug {
-                    println!("❌ Failed to parse config content: {}", e);
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("❌ {}", error);
                 }
                 return None;
             }
@@ -441,60 +615,98 @@ impl Config {
         Some(config)
     }
 
+    /// watch every config layer file for changes
+    ///
+    /// the parent directories are watched rather than the files themselves, so
+    /// editors that save by writing a temporary file and renaming it over the
+    /// config are picked up. Changed files are validated before every layer is
+    /// resolved again and sent as `ConfigSignal::Reloaded`
     pub fn watch(
         self,
         path: &str,
         args: Option<crate::config::Arguments>,
         config_sender: Option<std::sync::mpsc::Sender<crate::config::ConfigSignal>>,
     ) -> std::io::Result<notify::RecommendedWatcher> {
-        let watch_path = crate::utils::expand_path(path);
+        let user_path = crate::utils::expand_path(path);
         let (tx, rx) = std::sync::mpsc::channel();
-        let mut watcher = notify::recommended_watcher(tx)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-        watcher
-            .watch(
-                std::path::Path::new(&watch_path),
-                notify::RecursiveMode::NonRecursive,
-            )
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        let mut watcher = notify::recommended_watcher(tx).map_err(std::io::Error::other)?;
+
+        let watched: Vec<std::path::PathBuf> = LayeredConfig::paths(&user_path)
+            .into_iter()
+            .map(|p| std::path::absolute(&p).unwrap_or(p))
+            .collect();
+        let mut directories: Vec<std::path::PathBuf> = watched
+            .iter()
+            .filter_map(|p| p.parent().map(|d| d.to_path_buf()))
+            .filter(|d| d.is_dir())
+            .collect();
+        directories.sort();
+        directories.dedup();
+        for directory in &directories {
+            watcher
+                .watch(directory, notify::RecursiveMode::NonRecursive)
+                .map_err(std::io::Error::other)?;
+        }
 
-        let current_config = self.clone();
         std::thread::spawn(move || {
-            for res in rx {
-                match res {
-                    Ok(event) => {
-                        let config_updated = event.kind.is_modify()
-                            && matches!(
-                                event.kind,
-                                notify::EventKind::Modify(notify::event::ModifyKind::Data(_))
-                            );
-
-                        if config_updated {
-                            let new_config = Config::load(
-                                &watch_path,
-                                args.as_ref()
-                                    .unwrap_or(&crate::config::Arguments::default()),
-                            );
-
-                            match new_config {
-                                Some(cfg) => {
-                                    if let Some(sender) = &config_sender {
-                                        let _ =
-                                            sender.send(crate::config::ConfigSignal::Reloaded(cfg));
-                                    }
-                                }
-                                None => {
-                                    if let Some(sender) = &config_sender {
-                                        let _ = sender.send(crate::config::ConfigSignal::Error(
-                                            format!("Failed to reload config from {}", watch_path),
-                                        ));
-                                    }
-                                }
+            let is_change = |event: &notify::Event| {
+                matches!(
+                    event.kind,
+                    notify::EventKind::Create(_)
+                        | notify::EventKind::Remove(_)
+                        | notify::EventKind::Modify(
+                            notify::event::ModifyKind::Data(_)
+                                | notify::event::ModifyKind::Name(_)
+                                | notify::event::ModifyKind::Any
+                        )
+                ) && event.paths.iter().any(|p| watched.contains(p))
+            };
+
+            while let Ok(res) = rx.recv() {
+                let event = match res {
+                    Ok(event) => event,
+                    Err(e) => {
+                        println!("watch error: {:?}", e);
+                        continue;
+                    }
+                };
+                if !is_change(&event) {
+                    continue;
+                }
+
+                // a single save usually produces a burst of events
+                let mut changed = event.paths;
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                for event in rx.try_iter().flatten() {
+                    if is_change(&event) {
+                        changed.extend(event.paths);
+                    }
+                }
+                changed.retain(|p| watched.contains(p));
+                changed.sort();
+                changed.dedup();
+
+                let mut errors = Vec::new();
+                for changed_path in changed.iter().filter(|p| p.is_file()) {
+                    let path = changed_path.to_string_lossy().to_string();
+                    match std::fs::read_to_string(changed_path) {
+                        Ok(contents) => {
+                            if let Err(e) = Config::check(&contents, Some(&path)) {
+                                errors.extend(e.iter().map(|e| e.to_string()));
                             }
                         }
+                        Err(e) => errors.push(format!("{}: {}", path, e)),
                     }
-                    Err(e) => println!("watch error: {:?}", e),
+                }
+
+                let signal = if errors.is_empty() {
+                    let config = LayeredConfig::resolve(&user_path, &args).config;
+                    crate::config::ConfigSignal::Reloaded(config)
+                } else {
+                    crate::config::ConfigSignal::Error(errors.join("; "))
+                };
+                if let Some(sender) = &config_sender {
+                    let _ = sender.send(signal);
                 }
             }
         });
@@ -502,23 +714,503 @@ impl Config {
         Ok(watcher)
     }
 
+    /// list the changes between this config and another
+    ///
+    /// nested keys are joined with `.`, eg: `aliases.ll` or `editor.mode`
+    pub fn diff(&self, other: &Config) -> Vec<ConfigChange> {
+        let before = flatten_config(self);
+        let after = flatten_config(other);
+        let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter(|key| before.get(*key) != after.get(*key))
+            .map(|key| ConfigChange {
+                key: key.clone(),
+                before: before.get(key).cloned(),
+                after: after.get(key).cloned(),
+            })
+            .collect()
+    }
+
+    /// write the config to a yaml file
+    ///
+    /// an existing file is edited in place so comments and key order are kept,
+    /// only the values that differ from the file are changed
     pub fn write(&self, path: &str) -> std::io::Result<()> {
-        let expanded_path = if path.starts_with("~") {
-            if let Some(home) = dirs::home_dir() {
-                let without_tilde = path.trim_start_matches("~");
-                home.join(without_tilde).to_string_lossy().to_string()
-            } else {
-                path.to_string()
+        let expanded_path = crate::utils::expand_path(path);
+        let contents = std::fs::read_to_string(&expanded_path).unwrap_or_default();
+        let current = Config::check(&contents, Some(&expanded_path)).map_err(|errors| {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            std::io::Error::new(std::io::ErrorKind::InvalidData, errors.join("; "))
+        })?;
+
+        let mut edited = Some(contents);
+        for change in current.diff(self) {
+            let keys: Vec<&str> = change.key.split('.').collect();
+            edited = edited.and_then(|contents| match &change.after {
+                Some(value) => set_yaml_value(&contents, &keys, value),
+                None => Some(remove_yaml_value(&contents, &keys)),
+            });
+        }
+
+        // fall back to a plain dump when the file can't be edited in place
+        let yaml = match edited.filter(|yaml| {
+            Config::check(yaml, None)
+                .map(|written| written.diff(self).is_empty())
+                .unwrap_or(false)
+        }) {
+            Some(yaml) => yaml,
+            None => {
+                let mut values = serde_json::to_value(self).map_err(std::io::Error::other)?;
+                strip_nulls(&mut values);
+                serde_yaml::to_string(&values).map_err(std::io::Error::other)?
             }
-        } else {
-            path.to_string()
         };
-        let yaml = serde_yaml::to_string(self).unwrap();
+
+        if let Some(parent) = std::path::Path::new(&expanded_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
         std::fs::write(expanded_path, yaml)
     }
 
-    pub fn validate(&self) -> bool {
-        true
+    /// settable keys, eg: `theme` or `editor.mode`
+    ///
+    /// `aliases.<name>` and `execution.env.<name>` accept any name
+    pub fn keys() -> Vec<String> {
+        let mut keys = Vec::new();
+        for field in Config::fields() {
+            let section = match field.as_str() {
+                "editor" => serde_json::to_value(EditorConfig::default()),
+                "history" => serde_json::to_value(HistoryConfig::default()),
+                "execution" => serde_json::to_value(ExecutionConfig::default()),
+                _ => {
+                    keys.push(field);
+                    continue;
+                }
+            };
+            if let Some(section) = section.ok().as_ref().and_then(|s| s.as_object()) {
+                keys.extend(section.keys().map(|k| format!("{}.{}", field, k)));
+            }
+        }
+        keys.sort();
+        keys
+    }
+
+    /// check that a key can be set, suggesting the closest key otherwise
+    pub fn check_key(key: &str) -> Result<(), ConfigError> {
+        let keys = Config::keys();
+        let free_form = ["aliases.", "execution.env."]
+            .iter()
+            .any(|prefix| key.len() > prefix.len() && key.starts_with(prefix));
+        if keys.iter().any(|k| k == key) || free_form {
+            return Ok(());
+        }
+        let mut message = format!("unknown key `{}`", key);
+        if let Some(suggestion) = crate::utils::closest_match(key, keys.iter().map(|k| k.as_str()))
+        {
+            message.push_str(&format!(", did you mean `{}`?", suggestion));
+        }
+        Err(ConfigError {
+            path: None,
+            line: None,
+            column: None,
+            key: Some(key.to_string()),
+            message,
+        })
+    }
+
+    /// get a value by key, eg: `theme`, `editor.mode` or `aliases.ll`
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let values = serde_json::to_value(self).ok()?;
+        let pointer = format!("/{}", key.replace('.', "/"));
+        values.pointer(&pointer).filter(|v| !v.is_null()).cloned()
+    }
+
+    /// set a value by key
+    ///
+    /// the value is parsed as yaml, so `history_size 500` sets a number and
+    /// `history.ignore ["^ls$"]` a list. Values that don't fit the key's type
+    /// are used as plain strings, eg: `prompt 1000`
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Vec<ConfigError>> {
+        Config::check_key(key).map_err(|e| vec![e])?;
+        let parsed = serde_yaml::from_str::<serde_json::Value>(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        let config = self.with_value(key, Some(parsed)).or_else(|_| {
+            self.with_value(key, Some(serde_json::Value::String(value.to_string())))
+        })?;
+        config.validate()?;
+        *self = config;
+        Ok(())
+    }
+
+    /// unset a value by key, falling back to the default
+    pub fn unset(&mut self, key: &str) -> Result<(), Vec<ConfigError>> {
+        Config::check_key(key).map_err(|e| vec![e])?;
+        *self = self.with_value(key, None)?;
+        Ok(())
+    }
+
+    /// apply a change, eg: one found by `Config::diff`
+    pub fn apply_change(&mut self, change: &ConfigChange) -> Result<(), Vec<ConfigError>> {
+        *self = self.with_value(&change.key, change.after.clone())?;
+        Ok(())
+    }
+
+    /// every set value, keyed by its dotted path, eg: `editor.mode`
+    pub fn values(&self) -> std::collections::BTreeMap<String, serde_json::Value> {
+        flatten_config(self)
+    }
+
+    /// copy of this config with a single value replaced or removed
+    fn with_value(
+        &self,
+        key: &str,
+        value: Option<serde_json::Value>,
+    ) -> Result<Config, Vec<ConfigError>> {
+        let error = |message: String| {
+            vec![ConfigError {
+                path: None,
+                line: None,
+                column: None,
+                key: Some(key.to_string()),
+                message,
+            }]
+        };
+        let mut values = serde_json::to_value(self).map_err(|e| error(e.to_string()))?;
+        let keys: Vec<&str> = key.split('.').collect();
+        let (last, parents) = keys
+            .split_last()
+            .ok_or_else(|| error("empty key".to_string()))?;
+
+        let mut target = &mut values;
+        for parent in parents {
+            if target.get(*parent).is_none_or(|v| v.is_null()) {
+                if value.is_none() {
+                    return Ok(self.clone());
+                }
+                target[*parent] = serde_json::json!({});
+            }
+            target = &mut target[*parent];
+        }
+        let object = target
+            .as_object_mut()
+            .ok_or_else(|| error(format!("`{}` is not a mapping", parents.join("."))))?;
+        match value {
+            Some(value) => {
+                object.insert(last.to_string(), value);
+            }
+            None => {
+                object.remove(*last);
+            }
+        }
+
+        serde_json::from_value::<Config>(values).map_err(|e| error(e.to_string()))
+    }
+
+    /// parse and validate yaml config content
+    ///
+    /// reports unknown keys, values of the wrong type and invalid values,
+    /// with the line of the offending key when it can be found
+    pub fn check(contents: &str, path: Option<&str>) -> Result<Self, Vec<ConfigError>> {
+        let error = |keys: &[&str], message: String| ConfigError {
+            path: path.map(|p| p.to_string()),
+            line: find_key_line(contents, keys),
+            column: None,
+            key: Some(keys.join(".")),
+            message,
+        };
+
+        let value = match serde_yaml::from_str::<serde_yaml::Value>(contents) {
+            Ok(value) => value,
+            Err(e) => {
+                return Err(vec![ConfigError {
+                    path: path.map(|p| p.to_string()),
+                    line: e.location().map(|l| l.line()),
+                    column: e.location().map(|l| l.column()),
+                    key: None,
+                    message: e.to_string(),
+                }]);
+            }
+        };
+
+        let mapping = match value {
+            serde_yaml::Value::Null => return Ok(Config::empty()),
+            serde_yaml::Value::Mapping(mapping) => mapping,
+            _ => {
+                return Err(vec![ConfigError {
+                    path: path.map(|p| p.to_string()),
+                    line: Some(1),
+                    column: None,
+                    key: None,
+                    message: "expected a mapping of settings, eg: `theme: monokai`".to_string(),
+                }]);
+            }
+        };
+
+        let fields = Config::fields();
+        let mut errors = Vec::new();
+        let mut known = serde_yaml::Mapping::new();
+        for (key, value) in mapping {
+            let key = match key.as_str() {
+                Some(key) => key.to_string(),
+                None => {
+                    errors.push(ConfigError {
+                        path: path.map(|p| p.to_string()),
+                        line: None,
+                        column: None,
+                        key: None,
+                        message: format!("keys must be strings, found {:?}", key),
+                    });
+                    continue;
+                }
+            };
+
+            if RESERVED_CONFIG_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+
+            if !fields.contains(&key) {
+                let mut message = format!("unknown key `{}`", key);
+                if let Some(suggestion) =
+                    crate::utils::closest_match(&key, fields.iter().map(|f| f.as_str()))
+                {
+                    message.push_str(&format!(", did you mean `{}`?", suggestion));
+                }
+                errors.push(error(&[&key], message));
+                continue;
+            }
+
+            let expected = match key.as_str() {
+                "prompt" | "theme" | "continuation_prompt" | "error_prompt" => (value.is_string()
+                    || value.is_null())
+                .then_some(())
+                .ok_or("a string"),
+                "history_size" => (value.is_u64() || value.is_null())
+                    .then_some(())
+                    .ok_or("a positive integer"),
+                "aliases" => match &value {
+                    serde_yaml::Value::Null => Ok(()),
+                    serde_yaml::Value::Mapping(aliases) => {
+                        for (name, command) in aliases {
+                            let name = name.as_str().unwrap_or_default();
+                            if !command.is_string() {
+                                errors.push(error(
+                                    &["aliases", name],
+                                    format!(
+                                        "expected a string command, found {}",
+                                        yaml_type(command)
+                                    ),
+                                ));
+                            }
+                        }
+                        Ok(())
+                    }
+                    _ => Err("a mapping of alias names to commands"),
+                },
+                "editor" | "history" | "execution" => {
+                    let section = match key.as_str() {
+                        "editor" => serde_yaml::from_value::<EditorConfig>(value.clone()).err(),
+                        "history" => serde_yaml::from_value::<HistoryConfig>(value.clone()).err(),
+                        _ => serde_yaml::from_value::<ExecutionConfig>(value.clone()).err(),
+                    };
+                    if let Some(e) = section {
+                        errors.push(error(&[&key], e.to_string()));
+                        continue;
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            if let Err(expected) = expected {
+                errors.push(error(
+                    &[&key],
+                    format!("expected {}, found {}", expected, yaml_type(&value)),
+                ));
+                continue;
+            }
+
+            known.insert(serde_yaml::Value::String(key), value);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let config =
+            serde_yaml::from_value::<Config>(serde_yaml::Value::Mapping(known)).map_err(|e| {
+                vec![ConfigError {
+                    path: path.map(|p| p.to_string()),
+                    line: None,
+                    column: None,
+                    key: None,
+                    message: e.to_string(),
+                }]
+            })?;
+
+        config.validate().map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|e| {
+                    let keys: Vec<&str> = e.key.as_deref().unwrap_or_default().split('.').collect();
+                    ConfigError {
+                        path: path.map(|p| p.to_string()),
+                        line: find_key_line(contents, &keys),
+                        ..e
+                    }
+                })
+                .collect::<Vec<ConfigError>>()
+        })?;
+
+        Ok(config)
+    }
+
+    /// validate configured values
+    ///
+    /// checks themes against the bundled themes, prompt template fields against
+    /// `PromptContext` and the history size against `MAX_HISTORY_SIZE`
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let error = |key: &str, message: String| ConfigError {
+            path: None,
+            line: None,
+            column: None,
+            key: Some(key.to_string()),
+            message,
+        };
+        let mut errors = Vec::new();
+
+        if let Some(theme) = &self.theme {
+            let themes = crate::style::ThemeManager::from(crate::style::DEFAULT_THEMES);
+            let mut available: Vec<&str> = themes.list().into_iter().map(|t| t.as_str()).collect();
+            available.sort();
+            if !available.contains(&theme.as_str()) {
+                let mut message = format!("unknown theme `{}`", theme);
+                match crate::utils::closest_match(theme, available.iter().copied()) {
+                    Some(suggestion) => {
+                        message.push_str(&format!(", did you mean `{}`?", suggestion))
+                    }
+                    None => message.push_str(&format!(" (available: {})", available.join(", "))),
+                }
+                errors.push(error("theme", message));
+            }
+        }
+
+        let prompts = [
+            ("prompt", &self.prompt),
+            ("continuation_prompt", &self.continuation_prompt),
+            ("error_prompt", &self.error_prompt),
+        ];
+        for (key, prompt) in prompts {
+            let Some(prompt) = prompt else {
+                continue;
+            };
+            let fields = crate::style::PromptContext::list_fields();
+            for field in template_fields(prompt) {
+                if fields.contains(&field) {
+                    continue;
+                }
+                let mut message = format!("unknown prompt field `{{{}}}`", field);
+                match crate::utils::closest_match(&field, fields.iter().map(|f| f.as_str())) {
+                    Some(suggestion) => {
+                        message.push_str(&format!(", did you mean `{{{}}}`?", suggestion))
+                    }
+                    None => message.push_str(&format!(" (available: {})", fields.join(", "))),
+                }
+                errors.push(error(key, message));
+            }
+        }
+
+        if let Some(history_size) = self.history_size
+            && (history_size == 0 || history_size > MAX_HISTORY_SIZE)
+        {
+            errors.push(error(
+                "history_size",
+                format!(
+                    "history_size must be between 1 and {}, found {}",
+                    MAX_HISTORY_SIZE, history_size
+                ),
+            ));
+        }
+
+        if let Some(tab_width) = self.editor.as_ref().and_then(|e| e.tab_width)
+            && (tab_width == 0 || tab_width > MAX_TAB_WIDTH)
+        {
+            errors.push(error(
+                "editor.tab_width",
+                format!(
+                    "tab_width must be between 1 and {}, found {}",
+                    MAX_TAB_WIDTH, tab_width
+                ),
+            ));
+        }
+
+        if let Some(history) = &self.history {
+            if let Some(size) = history.size
+                && (size == 0 || size > MAX_HISTORY_SIZE)
+            {
+                errors.push(error(
+                    "history.size",
+                    format!(
+                        "size must be between 1 and {}, found {}",
+                        MAX_HISTORY_SIZE, size
+                    ),
+                ));
+            }
+            for pattern in history.ignore.iter().flatten() {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    errors.push(error(
+                        "history.ignore",
+                        format!("invalid pattern `{}`: {}", pattern, e),
+                    ));
+                }
+            }
+            if history.flush_interval == Some(0) {
+                errors.push(error(
+                    "history.flush_interval",
+                    "flush_interval must be at least 1 second".to_string(),
+                ));
+            }
+        }
+
+        if let Some(execution) = &self.execution {
+            if let Some(format) = &execution.format
+                && !FORMATS.contains(&format.as_str())
+            {
+                let mut message = format!("unknown format `{}`", format);
+                match crate::utils::closest_match(format, FORMATS.iter().copied()) {
+                    Some(suggestion) => {
+                        message.push_str(&format!(", did you mean `{}`?", suggestion))
+                    }
+                    None => message.push_str(&format!(" (available: {})", FORMATS.join(", "))),
+                }
+                errors.push(error("execution.format", message));
+            }
+            if execution.timeout == Some(0) {
+                errors.push(error(
+                    "execution.timeout",
+                    "timeout must be at least 1 second".to_string(),
+                ));
+            }
+        }
+
+        if let Some(aliases) = &self.aliases {
+            for (name, command) in aliases {
+                if name.trim().is_empty() || name.contains(char::is_whitespace) {
+                    errors.push(error("aliases", format!("invalid alias name `{}`", name)));
+                } else if command.trim().is_empty() {
+                    errors.push(error(
+                        &format!("aliases.{}", name),
+                        "alias command is empty".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
     pub fn to_json(&self) -> Option<String> {
@@ -533,36 +1225,992 @@ pub enum ConfigSignal {
     Error(String),
 }
 
-/// resolved shell configuration
+/// remove null values from a json value, recursively
+fn strip_nulls(value: &mut serde_json::Value) {
+    if let serde_json::Value::Object(map) = value {
+        map.retain(|_, v| !v.is_null());
+        map.values_mut().for_each(strip_nulls);
+    }
+}
+
+/// apply a new config to the running shell state
 ///
-/// used internally by the shell after merging config file, environment variables, and command line arguments
+/// replaces the shared config, re-derives aliases (keeping aliases defined with
+/// the `alias` builtin), applies the theme and history settings, and returns
+/// what changed
+pub fn apply_config(
+    config: &std::sync::Arc<std::sync::Mutex<Config>>,
+    aliases: &std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
+    history: &std::sync::Arc<std::sync::Mutex<crate::history::History>>,
+    new_config: Config,
+) -> Vec<ConfigChange> {
+    let old_config = config.lock().unwrap().clone();
+    let changes = old_config.diff(&new_config);
+    if changes.is_empty() {
+        return changes;
+    }
+
+    {
+        let mut aliases = aliases.lock().unwrap();
+        let new_aliases = new_config.aliases.clone().unwrap_or_default();
+        for name in old_config.aliases.iter().flat_map(|a| a.keys()) {
+            if !new_aliases.contains_key(name) {
+                aliases.remove(name);
+            }
+        }
+        aliases.extend(new_aliases);
+    }
+
+    if old_config.theme != new_config.theme
+        && crate::style::ThemeManager::applies(
+            new_config
+                .execution()
+                .format
+                .as_deref()
+                .unwrap_or(DEFAULT_FORMAT),
+        )
+    {
+        let theme = new_config
+            .theme
+            .clone()
+            .unwrap_or(DEFAULT_THEME.to_string());
+        crate::style::ThemeManager::from(crate::style::DEFAULT_THEMES)
+            .apply(&mut std::io::stdout(), &theme)
+            .ok();
+    }
+
+    history.lock().unwrap().configure(new_config.history());
+
+    *config.lock().unwrap() = new_config;
+
+    changes
+}
+
+/// a config value that changed on reload
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct ResolvedConfig {
-    // pub debug: bool,
-    pub prompt: String,
-    pub aliases: std::collections::HashMap<String, String>,
-    pub history_size: usize,
-    pub theme: String,
+pub struct ConfigChange {
+    pub key: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
 }
 
-impl std::fmt::Display for ResolvedConfig {
+impl std::fmt::Display for ConfigChange {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "ResolvedConfig {{ prompt: {}, aliases: {:?}, history_size: {}, theme: {} }}",
-            self.prompt, self.aliases, self.history_size, self.theme
-        )
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => write!(f, "{} {} → {}", self.key, before, after),
+            (None, Some(after)) => write!(f, "+{} {}", self.key, after),
+            (Some(_), None) => write!(f, "-{}", self.key),
+            (None, None) => write!(f, "{}", self.key),
+        }
     }
 }
 
-impl From<Config> for ResolvedConfig {
-    fn from(config: Config) -> Self {
-        let defaults = Defaults::default();
-        ResolvedConfig {
-            prompt: config.prompt.unwrap_or(defaults.prompt),
-            aliases: config.aliases.unwrap_or_default(),
-            history_size: config.history_size.unwrap_or(defaults.history_size),
-            theme: config.theme.unwrap_or(defaults.theme),
+/// flatten a config into `key.path` → value pairs, skipping unset values
+fn flatten_config(config: &Config) -> std::collections::BTreeMap<String, serde_json::Value> {
+    fn flatten(
+        prefix: &str,
+        value: serde_json::Value,
+        values: &mut std::collections::BTreeMap<String, serde_json::Value>,
+    ) {
+        match value {
+            serde_json::Value::Null => {}
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    let key = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    flatten(&key, value, values);
+                }
+            }
+            value => {
+                values.insert(prefix.to_string(), value);
+            }
+        }
+    }
+    let mut values = std::collections::BTreeMap::new();
+    flatten(
+        "",
+        serde_json::to_value(config).unwrap_or_default(),
+        &mut values,
+    );
+    values
+}
+
+/// line editor key bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EditMode {
+    Emacs,
+    Vi,
+}
+
+impl From<EditMode> for rustyline::config::EditMode {
+    fn from(mode: EditMode) -> Self {
+        match mode {
+            EditMode::Emacs => rustyline::config::EditMode::Emacs,
+            EditMode::Vi => rustyline::config::EditMode::Vi,
+        }
+    }
+}
+
+/// line editor settings
+///
+/// ```yaml
+/// editor:
+///   mode: emacs
+///   bell: true
+///   tab_width: 2
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditorConfig {
+    /// key bindings, `emacs` or `vi`
+    ///
+    /// default: vi
+    pub mode: Option<EditMode>,
+    /// ring the terminal bell, eg: when completion has no candidates
+    ///
+    /// default: false
+    pub bell: Option<bool>,
+    /// number of columns used to display a tab
+    ///
+    /// default: 4
+    pub tab_width: Option<u8>,
+}
+
+impl EditorConfig {
+    /// merge another EditorConfig on top of this one, returning the keys that were set
+    pub fn merge(&mut self, other: EditorConfig) -> Vec<String> {
+        let mut keys = Vec::new();
+        if other.mode.is_some() {
+            self.mode = other.mode;
+            keys.push("mode".to_string());
+        }
+        if other.bell.is_some() {
+            self.bell = other.bell;
+            keys.push("bell".to_string());
+        }
+        if other.tab_width.is_some() {
+            self.tab_width = other.tab_width;
+            keys.push("tab_width".to_string());
+        }
+        keys
+    }
+}
+
+/// where command history is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryBackend {
+    /// json lines file at `history.path`
+    Json,
+    /// keep history in memory only, nothing is read or written
+    Memory,
+}
+
+/// command history settings
+///
+/// ```yaml
+/// history:
+///   path: ~/.turtle_history.json
+///   size: 5000
+///   dedupe: true
+///   ignore: ["^ls$", "password"]
+///   backend: json
+///   flush_interval: 30
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// history file
+    ///
+    /// default: ~/.turtle_history.json
+    pub path: Option<String>,
+    /// maximum number of commands kept, overrides `history_size`
+    pub size: Option<usize>,
+    /// skip commands identical to the previous command
+    ///
+    /// default: false
+    pub dedupe: Option<bool>,
+    /// regex patterns for commands that are never recorded
+    pub ignore: Option<Vec<String>>,
+    /// storage backend
+    ///
+    /// default: json
+    pub backend: Option<HistoryBackend>,
+    /// seconds between writes to the history file
+    ///
+    /// default: 60
+    pub flush_interval: Option<u64>,
+}
+
+impl HistoryConfig {
+    /// merge another HistoryConfig on top of this one, returning the keys that were set
+    pub fn merge(&mut self, other: HistoryConfig) -> Vec<String> {
+        let mut keys = Vec::new();
+        if other.path.is_some() {
+            self.path = other.path;
+            keys.push("path".to_string());
+        }
+        if other.size.is_some() {
+            self.size = other.size;
+            keys.push("size".to_string());
+        }
+        if other.dedupe.is_some() {
+            self.dedupe = other.dedupe;
+            keys.push("dedupe".to_string());
+        }
+        if other.ignore.is_some() {
+            self.ignore = other.ignore;
+            keys.push("ignore".to_string());
+        }
+        if other.backend.is_some() {
+            self.backend = other.backend;
+            keys.push("backend".to_string());
+        }
+        if other.flush_interval.is_some() {
+            self.flush_interval = other.flush_interval;
+            keys.push("flush_interval".to_string());
+        }
+        keys
+    }
+}
+
+/// command execution settings
+///
+/// ```yaml
+/// execution:
+///   format: json
+///   timeout: 30
+///   path: ["~/.local/bin"]
+///   lenient: false
+///   modules: ["~/.turtle/lib"]
+///   errexit: false
+///   env:
+///     PAGER: cat
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecutionConfig {
+    /// default output format, one of `FORMATS`
+    ///
+    /// default: table
+    pub format: Option<String>,
+    /// seconds before a running command is killed
+    ///
+    /// default: none
+    pub timeout: Option<u64>,
+    /// directories prepended to `PATH` for executed commands
+    pub path: Option<Vec<String>>,
+    /// environment variables set for executed commands
+    pub env: Option<std::collections::HashMap<String, String>>,
+    /// read undefined turtle variables as `null` instead of failing
+    ///
+    /// default: false
+    pub lenient: Option<bool>,
+    /// directories searched for imported turtle files, after those in `TURTLE_PATH`
+    pub modules: Option<Vec<String>>,
+    /// raise an error when a command exits with a non-zero status, like `set -e`, so it can be
+    /// caught with `try`
+    ///
+    /// default: false
+    pub errexit: Option<bool>,
+}
+
+impl ExecutionConfig {
+    /// merge another ExecutionConfig on top of this one, returning the keys that were set
+    ///
+    /// environment variables are merged by name
+    pub fn merge(&mut self, other: ExecutionConfig) -> Vec<String> {
+        let mut keys = Vec::new();
+        if other.format.is_some() {
+            self.format = other.format;
+            keys.push("format".to_string());
+        }
+        if other.timeout.is_some() {
+            self.timeout = other.timeout;
+            keys.push("timeout".to_string());
+        }
+        if other.path.is_some() {
+            self.path = other.path;
+            keys.push("path".to_string());
+        }
+        if other.lenient.is_some() {
+            self.lenient = other.lenient;
+            keys.push("lenient".to_string());
+        }
+        if other.modules.is_some() {
+            self.modules = other.modules;
+            keys.push("modules".to_string());
+        }
+        if other.errexit.is_some() {
+            self.errexit = other.errexit;
+            keys.push("errexit".to_string());
+        }
+        if let Some(env) = other.env {
+            let merged = self.env.get_or_insert_with(std::collections::HashMap::new);
+            for (name, value) in env {
+                keys.push(format!("env.{}", name));
+                merged.insert(name, value);
+            }
+        }
+        keys
+    }
+
+    /// apply `path` and `env` to the environment of an executed command
+    ///
+    /// `path` entries are prepended to `PATH` from `env`, or the shell's own `PATH`
+    pub fn apply(&self, env: &mut std::collections::HashMap<String, String>) {
+        if let Some(vars) = &self.env {
+            env.extend(vars.clone());
+        }
+        if let Some(paths) = &self.path {
+            let mut entries: Vec<String> =
+                paths.iter().map(|p| crate::utils::expand_path(p)).collect();
+            if let Some(path) = env
+                .get("PATH")
+                .cloned()
+                .or_else(|| std::env::var("PATH").ok())
+                .filter(|p| !p.is_empty())
+            {
+                entries.push(path);
+            }
+            env.insert("PATH".to_string(), entries.join(":"));
+        }
+    }
+}
+
+/// build a config section from `TURTLE_<SECTION>_<FIELD>` environment variables
+///
+/// values are parsed as yaml, eg: `TURTLE_HISTORY_IGNORE='["^ls$"]'`
+fn section_from_environment<T>(env: &Environment, section: &str) -> Option<T>
+where
+    T: Default + Serialize + serde::de::DeserializeOwned,
+{
+    let fields = serde_json::to_value(T::default()).ok()?;
+    let mut mapping = serde_yaml::Mapping::new();
+    for field in fields.as_object()?.keys() {
+        let name = format!("{}_{}", section, field).to_uppercase();
+        if let Some(value) = env.get(&name) {
+            let value = serde_yaml::from_str::<serde_yaml::Value>(value)
+                .unwrap_or_else(|_| serde_yaml::Value::String(value.clone()));
+            mapping.insert(serde_yaml::Value::String(field.clone()), value);
+        }
+    }
+    if mapping.is_empty() {
+        return None;
+    }
+    match serde_yaml::from_value::<T>(serde_yaml::Value::Mapping(mapping)) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            if env.debug {
+                println!(
+                    "❌ ignoring invalid TURTLE_{}_* variables: {}",
+                    section.to_uppercase(),
+                    e
+                );
+            }
+            None
+        }
+    }
+}
+
+/// a problem found while validating configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigError {
+    /// file the problem was found in
+    pub path: Option<String>,
+    /// 1-based line number
+    pub line: Option<usize>,
+    /// 1-based column number
+    pub column: Option<usize>,
+    /// offending key, eg: `theme`, `aliases.ll`
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path)?;
+            if let Some(line) = self.line {
+                write!(f, "{}:", line)?;
+                if let Some(column) = self.column {
+                    write!(f, "{}:", column)?;
+                }
+            }
+            write!(f, " ")?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "{}: ", key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// name of a yaml value's type, used in error messages
+fn yaml_type(value: &serde_yaml::Value) -> &'static str {
+    match value {
+        serde_yaml::Value::Null => "null",
+        serde_yaml::Value::Bool(_) => "a boolean",
+        serde_yaml::Value::Number(n) if n.is_f64() => "a float",
+        serde_yaml::Value::Number(n) if n.is_i64() && n.as_i64() < Some(0) => "a negative integer",
+        serde_yaml::Value::Number(_) => "an integer",
+        serde_yaml::Value::String(_) => "a string",
+        serde_yaml::Value::Sequence(_) => "a list",
+        serde_yaml::Value::Mapping(_) => "a mapping",
+        serde_yaml::Value::Tagged(_) => "a tagged value",
+    }
+}
+
+/// find the 1-based line of a (possibly nested) key in yaml content
+///
+/// eg: `["aliases", "ll"]` finds the `ll:` line inside the `aliases:` block
+fn find_key_line(contents: &str, keys: &[&str]) -> Option<usize> {
+    let lines: Vec<&str> = contents.lines().collect();
+    find_key(&lines, keys).map(|(index, _)| index + 1)
+}
+
+/// find the 0-based line index and indentation of a (possibly nested) key
+fn find_key(lines: &[&str], keys: &[&str]) -> Option<(usize, usize)> {
+    let mut start = 0;
+    let mut min_indent = 0;
+    let mut found = None;
+    for (depth, key) in keys.iter().enumerate() {
+        found = None;
+        for (i, line) in lines.iter().enumerate().skip(start) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            if depth > 0 && indent < min_indent {
+                // left the parent block
+                break;
+            }
+            if depth == 0 && indent > 0 {
+                continue;
+            }
+            let name = trimmed.split(':').next().unwrap_or_default().trim();
+            if name.trim_matches(|c| c == '"' || c == '\'') == *key {
+                found = Some((i, indent));
+                min_indent = indent + 1;
+                break;
+            }
+        }
+        start = found?.0 + 1;
+    }
+    found
+}
+
+/// index of the line after the block of the key at `index`
+///
+/// trailing blank lines and comments are left outside the block
+fn block_end(lines: &[&str], index: usize, indent: usize) -> usize {
+    let mut end = index + 1;
+    for (i, line) in lines.iter().enumerate().skip(index + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if line.len() - trimmed.len() <= indent {
+            break;
+        }
+        end = i + 1;
+    }
+    end
+}
+
+/// split a `key: value  # comment` line into the value and the trailing comment
+fn split_comment(value: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in value.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if previous.is_whitespace() => {
+                return (value[..i].trim_end(), Some(&value[i..]));
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    (value.trim_end(), None)
+}
+
+/// render a yaml key, quoting it when needed
+fn yaml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        serde_json::to_string(key).unwrap_or_default()
+    }
+}
+
+/// set a (possibly nested) key in yaml content, keeping comments and ordering
+///
+/// lists and mappings are written as json, which is valid yaml flow syntax. Returns None
+/// when the content can't be edited in place, eg: when a parent is a flow mapping
+fn set_yaml_value(contents: &str, keys: &[&str], value: &serde_json::Value) -> Option<String> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let rendered = match value {
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+            serde_json::to_string(value).ok()?
+        }
+        _ => serde_yaml::to_string(value).ok()?.trim_end().to_string(),
+    };
+    let mut edited: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+
+    if let Some((index, indent)) = find_key(&lines, keys) {
+        let line = lines[index];
+        let (_, rest) = line.split_once(':')?;
+        let (_, comment) = split_comment(rest);
+        let mut replacement = format!(
+            "{}{}: {}",
+            " ".repeat(indent),
+            yaml_key(keys[keys.len() - 1]),
+            rendered
+        );
+        if let Some(comment) = comment {
+            replacement.push_str(&format!(" {}", comment));
+        }
+        let end = block_end(&lines, index, indent);
+        edited.splice(index..end, [replacement]);
+    } else {
+        // find the deepest parent that already exists
+        let mut depth = keys.len() - 1;
+        let mut parent = None;
+        while depth > 0 {
+            if let Some(found) = find_key(&lines, &keys[..depth]) {
+                parent = Some(found);
+                break;
+            }
+            depth -= 1;
+        }
+
+        let (insert_at, indent) = match parent {
+            Some((index, parent_indent)) => {
+                let (_, rest) = lines[index].split_once(':')?;
+                if !split_comment(rest).0.trim().is_empty() {
+                    return None;
+                }
+                let end = block_end(&lines, index, parent_indent);
+                let indent = lines[index + 1..end]
+                    .iter()
+                    .find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+                    .map(|l| l.len() - l.trim_start().len())
+                    .unwrap_or(parent_indent + 2);
+                (end, indent)
+            }
+            None => (edited.len(), 0),
+        };
+
+        let mut inserted = Vec::new();
+        for (offset, key) in keys[depth..].iter().enumerate() {
+            let padding = " ".repeat(indent + offset * 2);
+            if depth + offset == keys.len() - 1 {
+                inserted.push(format!("{}{}: {}", padding, yaml_key(key), rendered));
+            } else {
+                inserted.push(format!("{}{}:", padding, yaml_key(key)));
+            }
+        }
+        edited.splice(insert_at..insert_at, inserted);
+    }
+
+    let mut output = edited.join("\n");
+    output.push('\n');
+    Some(output)
+}
+
+/// remove a (possibly nested) key from yaml content, keeping comments and ordering
+fn remove_yaml_value(contents: &str, keys: &[&str]) -> String {
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut edited: Vec<&str> = lines.clone();
+    if let Some((index, indent)) = find_key(&lines, keys) {
+        let end = block_end(&lines, index, indent);
+        edited.drain(index..end);
+    }
+    let mut output = edited.join("\n");
+    output.push('\n');
+    output
+}
+
+/// list the fields referenced by a tinytemplate template, eg: `{cwd}` or `{ uname }`
+fn template_fields(template: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // escaped character
+            '\\' => {
+                chars.next();
+            }
+            '{' if chars.peek() == Some(&'{') => {
+                // skip {{ block }} expressions
+                while let Some(c) = chars.next() {
+                    if c == '}' && chars.peek() == Some(&'}') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            '{' => {
+                let mut expr = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    expr.push(c);
+                }
+                // strip formatters (`{ value | formatter }`) and nested paths (`{ a.b }`)
+                let path = expr.split('|').next().unwrap_or_default().trim();
+                let field = path.split('.').next().unwrap_or_default();
+                if !field.is_empty() && !field.starts_with('@') {
+                    fields.push(field.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+/// configuration layers, from lowest to highest precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ConfigLayer {
+    /// built-in defaults
+    Default,
+    /// `/etc/turtle/config.yaml`
+    System,
+    /// `$XDG_CONFIG_HOME/turtle/config.yaml`, `TURTLE_CONFIG_PATH` or `--config-path`
+    User,
+    /// the nearest `.turtle.yaml` above the working directory
+    Project,
+    /// `TURTLE_*` environment variables
+    Environment,
+    /// command line arguments
+    Arguments,
+}
+
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ConfigLayer::Default => "default",
+            ConfigLayer::System => "system",
+            ConfigLayer::User => "user",
+            ConfigLayer::Project => "project",
+            ConfigLayer::Environment => "environment",
+            ConfigLayer::Arguments => "arguments",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// where a configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigSource {
+    pub layer: ConfigLayer,
+    /// file the value was read from, if any
+    pub path: Option<String>,
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{} ({})", self.layer, path),
+            None => write!(f, "{}", self.layer),
+        }
+    }
+}
+
+/// resolve the user config file path
+///
+/// `--config-path` takes precedence, then `TURTLE_CONFIG_PATH`, then
+/// `$XDG_CONFIG_HOME/turtle/config.yaml` (`~/.config/turtle/config.yaml` when unset).
+///
+/// falls back to the legacy `~/.turtlerc.yaml` when it exists and the XDG file does not
+pub fn user_config_path(args: &Option<crate::config::Arguments>) -> String {
+    if let Some(path) = args.as_ref().and_then(|a| a.config_path.clone()) {
+        return crate::utils::expand_path(&path);
+    }
+    if let Ok(path) = std::env::var("TURTLE_CONFIG_PATH") {
+        return crate::utils::expand_path(&path);
+    }
+
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    let xdg_path = config_home.join("turtle").join("config.yaml");
+
+    let legacy_path = Defaults::default().config_path;
+    if !xdg_path.exists() && std::path::Path::new(&legacy_path).exists() {
+        return legacy_path;
+    }
+    xdg_path.to_string_lossy().to_string()
+}
+
+/// find the nearest project config file by walking up from `start`
+pub fn find_project_config(start: &std::path::Path) -> Option<std::path::PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|candidate| candidate.is_file())
+}
+
+/// a Config along with the layer each of its values came from
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    /// the merged configuration
+    pub config: Config,
+    /// the source of each key, eg: `prompt`, `aliases.ll`
+    pub sources: std::collections::BTreeMap<String, ConfigSource>,
+    /// layers that contributed to the configuration, in the order they were applied
+    pub layers: Vec<ConfigSource>,
+}
+
+impl LayeredConfig {
+    /// create a LayeredConfig holding only the built-in defaults
+    pub fn new() -> Self {
+        let mut layered = LayeredConfig {
+            config: Config::empty(),
+            sources: std::collections::BTreeMap::new(),
+            layers: Vec::new(),
+        };
+        layered.apply(
+            ConfigSource {
+                layer: ConfigLayer::Default,
+                path: None,
+            },
+            Config::default(),
+        );
+        layered
+    }
+
+    /// merge a layer on top of the current configuration
+    pub fn apply(&mut self, source: ConfigSource, config: Config) {
+        for key in self.config.merge(config) {
+            self.sources.insert(key, source.clone());
+        }
+        self.layers.push(source);
+    }
+
+    /// load a config file layer, skipping files that don't exist
+    fn apply_file(&mut self, layer: ConfigLayer, path: &str, args: &crate::config::Arguments) {
+        if !std::path::Path::new(path).is_file() {
+            if args.debug {
+                println!("⚠️ no {} config found at {}", layer, path);
+            }
+            return;
+        }
+        if let Some(config) = Config::load(path, args) {
+            self.apply(
+                ConfigSource {
+                    layer,
+                    path: Some(path.to_string()),
+                },
+                config,
+            );
+        }
+    }
+
+    /// resolve every configuration layer
+    ///
+    /// `user` is the user config, as a file path or yaml content
+    pub fn resolve(user: &str, args: &Option<crate::config::Arguments>) -> Self {
+        let arguments = args.clone().unwrap_or_default();
+        let mut layered = LayeredConfig::new();
+
+        layered.apply_file(ConfigLayer::System, SYSTEM_CONFIG_PATH, &arguments);
+
+        let user_path = crate::utils::expand_path(user);
+        if std::path::Path::new(&user_path).is_file() {
+            layered.apply_file(ConfigLayer::User, &user_path, &arguments);
+        } else if let Ok(serde_yaml::Value::Mapping(_)) =
+            serde_yaml::from_str::<serde_yaml::Value>(user)
+            && let Some(config) = Config::loads(user, &arguments)
+        {
+            layered.apply(
+                ConfigSource {
+                    layer: ConfigLayer::User,
+                    path: None,
+                },
+                config,
+            );
+        }
+
+        if let Some(path) = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_project_config(&cwd))
+        {
+            layered.apply_file(ConfigLayer::Project, &path.to_string_lossy(), &arguments);
+        }
+
+        let env = Environment::new(arguments.clone());
+        layered.apply(
+            ConfigSource {
+                layer: ConfigLayer::Environment,
+                path: None,
+            },
+            Config::from_environment(&env),
+        );
+
+        layered.apply(
+            ConfigSource {
+                layer: ConfigLayer::Arguments,
+                path: None,
+            },
+            Config::from_arguments(&arguments),
+        );
+
+        if layered.config.aliases.is_none() {
+            layered.config.aliases = Some(std::collections::HashMap::new());
+        }
+
+        layered
+    }
+
+    /// validate every configuration layer without applying them
+    ///
+    /// returns each checked layer along with the problems found in it
+    pub fn check(
+        user: &str,
+        args: &Option<crate::config::Arguments>,
+    ) -> Vec<(ConfigSource, Vec<ConfigError>)> {
+        let arguments = args.clone().unwrap_or_default();
+        let mut files = vec![(ConfigLayer::System, SYSTEM_CONFIG_PATH.to_string())];
+        files.push((ConfigLayer::User, crate::utils::expand_path(user)));
+        if let Some(path) = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_project_config(&cwd))
+        {
+            files.push((ConfigLayer::Project, path.to_string_lossy().to_string()));
+        }
+
+        let mut results = Vec::new();
+        for (layer, path) in files {
+            if !std::path::Path::new(&path).is_file() {
+                continue;
+            }
+            let errors = match std::fs::read_to_string(&path) {
+                Ok(contents) => Config::check(&contents, Some(&path))
+                    .err()
+                    .unwrap_or_default(),
+                Err(e) => vec![ConfigError {
+                    path: Some(path.clone()),
+                    line: None,
+                    column: None,
+                    key: None,
+                    message: e.to_string(),
+                }],
+            };
+            results.push((
+                ConfigSource {
+                    layer,
+                    path: Some(path),
+                },
+                errors,
+            ));
+        }
+
+        let env = Environment::new(arguments.clone());
+        for (layer, config) in [
+            (ConfigLayer::Environment, Config::from_environment(&env)),
+            (ConfigLayer::Arguments, Config::from_arguments(&arguments)),
+        ] {
+            results.push((
+                ConfigSource { layer, path: None },
+                config.validate().err().unwrap_or_default(),
+            ));
+        }
+
+        results
+    }
+
+    /// config files for every file layer, whether or not they exist yet
+    ///
+    /// the project config is only included when one is found
+    pub fn paths(user: &str) -> Vec<std::path::PathBuf> {
+        let mut paths = vec![
+            std::path::PathBuf::from(SYSTEM_CONFIG_PATH),
+            std::path::PathBuf::from(crate::utils::expand_path(user)),
+        ];
+        if let Some(path) = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_project_config(&cwd))
+        {
+            paths.push(path);
+        }
+        paths
+    }
+
+    /// get the source of a configuration key
+    pub fn source(&self, key: &str) -> Option<&ConfigSource> {
+        self.sources.get(key)
+    }
+}
+
+impl Default for LayeredConfig {
+    fn default() -> Self {
+        LayeredConfig::new()
+    }
+}
+
+impl std::fmt::Display for LayeredConfig {
+    /// list each configured value along with the layer it came from
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values = serde_json::to_value(&self.config).map_err(|_| std::fmt::Error)?;
+        for (key, source) in &self.sources {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            let value = values
+                .pointer(&pointer)
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            writeln!(f, "{:<24} {:<32} # {}", key, value, source)?;
+        }
+        Ok(())
+    }
+}
+
+/// resolved shell configuration
+///
+/// used internally by the shell after merging config file, environment variables, and command line arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedConfig {
+    // pub debug: bool,
+    pub prompt: String,
+    pub aliases: std::collections::HashMap<String, String>,
+    pub history_size: usize,
+    pub theme: String,
+}
+
+impl std::fmt::Display for ResolvedConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ResolvedConfig {{ prompt: {}, aliases: {:?}, history_size: {}, theme: {} }}",
+            self.prompt, self.aliases, self.history_size, self.theme
+        )
+    }
+}
+
+impl From<Config> for ResolvedConfig {
+    fn from(config: Config) -> Self {
+        let defaults = Defaults::default();
+        ResolvedConfig {
+            prompt: config.prompt.unwrap_or(defaults.prompt),
+            aliases: config.aliases.unwrap_or_default(),
+            history_size: config.history_size.unwrap_or(defaults.history_size),
+            theme: config.theme.unwrap_or(defaults.theme),
         }
     }
 }
@@ -597,11 +2245,25 @@ pub struct Arguments {
     pub version: bool,
 
     /// configuration file
-    #[arg(long, help = "Config File", default_value = "~/.turtle.yaml")]
+    ///
+    /// defaults to `TURTLE_CONFIG_PATH` or `$XDG_CONFIG_HOME/turtle/config.yaml`
+    #[arg(long, help = "Config File", default_value = None)]
     pub config_path: Option<String>,
 
+    /// override the configured prompt
+    #[arg(long, help = "Prompt Template", default_value = None)]
+    pub prompt: Option<String>,
+
+    /// override the configured theme
+    #[arg(long, help = "Color Theme", default_value = None)]
+    pub theme: Option<String>,
+
+    /// override the configured history size
+    #[arg(long, help = "History Size", default_value = None)]
+    pub history_size: Option<usize>,
+
     /// history file
-    #[arg(long, help = "History File", default_value = "~/.turtle_history.json")]
+    #[arg(long, help = "History File", default_value = None)]
     pub history_path: Option<String>,
 
     /// command to execute in non-interactive mode
@@ -609,7 +2271,7 @@ pub struct Arguments {
     pub command: Option<String>,
 
     /// set output format
-    #[arg(short, long, help = "Output Format", default_value = "table")]
+    #[arg(short, long, help = "Output Format", default_value = None)]
     pub format: Option<String>,
 
     /// skip history loading
@@ -643,12 +2305,33 @@ pub struct Arguments {
     // display-prompt
     #[arg(long, help = "Display Current Prompt", default_value_t = false)]
     pub display_prompt: bool,
+
+    /// subcommand to run instead of starting the shell
+    #[command(subcommand)]
+    pub subcommand: Option<Commands>,
+}
+
+/// turtle subcommands
+#[derive(clap::Subcommand, Clone, Debug, Serialize, Deserialize)]
+pub enum Commands {
+    /// manage configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+}
+
+/// `turtle config` subcommands
+#[derive(clap::Subcommand, Clone, Debug, Serialize, Deserialize)]
+pub enum ConfigCommands {
+    /// validate every configuration layer and report problems
+    Check,
 }
 
 impl Arguments {
     pub fn new() -> Self {
         use clap::Parser;
-        return Arguments::parse();
+        Arguments::parse()
     }
 
     pub fn as_mutex(&self) -> std::sync::Arc<std::sync::Mutex<Self>> {
@@ -675,6 +2358,7 @@ impl Arguments {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -683,4 +2367,404 @@ mod tests {
         let args = Arguments::new();
         assert!(args.validate());
     }
+
+    #[test]
+    fn test_layered_config_precedence() {
+        let mut layered = LayeredConfig::new();
+        assert_eq!(layered.source("theme").unwrap().layer, ConfigLayer::Default);
+
+        let mut user = Config::empty();
+        user.theme = Some("solarized_dark".to_string());
+        user.aliases = Some(std::collections::HashMap::from([
+            ("ll".to_string(), "ls -la".to_string()),
+            ("gs".to_string(), "git status".to_string()),
+        ]));
+        layered.apply(
+            ConfigSource {
+                layer: ConfigLayer::User,
+                path: Some("/home/turtle/.config/turtle/config.yaml".to_string()),
+            },
+            user,
+        );
+
+        let mut project = Config::empty();
+        project.aliases = Some(std::collections::HashMap::from([(
+            "ll".to_string(),
+            "ls -lah".to_string(),
+        )]));
+        layered.apply(
+            ConfigSource {
+                layer: ConfigLayer::Project,
+                path: Some("/src/.turtle.yaml".to_string()),
+            },
+            project,
+        );
+
+        let args = Arguments {
+            theme: Some("monokai".to_string()),
+            ..Arguments::default()
+        };
+        layered.apply(
+            ConfigSource {
+                layer: ConfigLayer::Arguments,
+                path: None,
+            },
+            Config::from_arguments(&args),
+        );
+
+        let aliases = layered.config.aliases.as_ref().unwrap();
+        assert_eq!(aliases.get("ll").unwrap(), "ls -lah");
+        assert_eq!(aliases.get("gs").unwrap(), "git status");
+        assert_eq!(layered.config.theme.as_deref(), Some("monokai"));
+        assert_eq!(layered.config.prompt.as_deref(), Some(DEFAULT_PROMPT));
+
+        assert_eq!(
+            layered.source("theme").unwrap().layer,
+            ConfigLayer::Arguments
+        );
+        assert_eq!(
+            layered.source("aliases.ll").unwrap().layer,
+            ConfigLayer::Project
+        );
+        assert_eq!(
+            layered.source("aliases.gs").unwrap().layer,
+            ConfigLayer::User
+        );
+        assert_eq!(
+            layered.source("prompt").unwrap().layer,
+            ConfigLayer::Default
+        );
+
+        let display = layered.to_string();
+        assert!(display.contains("user (/home/turtle/.config/turtle/config.yaml)"));
+        assert!(display.contains("\"monokai\""));
+    }
+
+    #[test]
+    fn test_config_from_environment() {
+        let env = Environment {
+            debug: false,
+            config: std::collections::HashMap::from([
+                ("PROMPT".to_string(), "$ ".to_string()),
+                ("HISTORY_SIZE".to_string(), "not a number".to_string()),
+                ("ALIASES".to_string(), r#"{"ll": "ls -la"}"#.to_string()),
+            ]),
+            defaults: false,
+        };
+        let config = Config::from_environment(&env);
+        assert_eq!(config.prompt.as_deref(), Some("$ "));
+        assert_eq!(config.history_size, None);
+        assert_eq!(config.theme, None);
+        assert_eq!(config.aliases.unwrap().get("ll").unwrap(), "ls -la");
+    }
+
+    #[test]
+    fn test_config_sections() {
+        let yaml = "history_size: 500\neditor:\n  mode: emacs\nhistory:\n  dedupe: true\n  ignore: [\"^ls$\"]\nexecution:\n  timeout: 30\n  env:\n    PAGER: cat\n";
+        let mut config = Config::default();
+        let keys = config.merge(Config::check(yaml, None).unwrap());
+        assert!(keys.contains(&"editor.mode".to_string()));
+        assert!(keys.contains(&"execution.env.PAGER".to_string()));
+
+        let editor = config.editor();
+        assert_eq!(editor.mode, Some(EditMode::Emacs));
+        assert_eq!(editor.tab_width, Some(DEFAULT_TAB_WIDTH));
+
+        let history = config.history();
+        assert_eq!(history.size, Some(500));
+        assert_eq!(history.dedupe, Some(true));
+        assert_eq!(history.backend, Some(HistoryBackend::Json));
+
+        let execution = config.execution();
+        assert_eq!(execution.format.as_deref(), Some(DEFAULT_FORMAT));
+        assert_eq!(execution.timeout, Some(30));
+
+        let mut env = std::collections::HashMap::from([("PATH".to_string(), "/bin".to_string())]);
+        ExecutionConfig {
+            path: Some(vec!["/opt/turtle/bin".to_string()]),
+            ..execution
+        }
+        .apply(&mut env);
+        assert_eq!(env.get("PATH").unwrap(), "/opt/turtle/bin:/bin");
+        assert_eq!(env.get("PAGER").unwrap(), "cat");
+    }
+
+    #[test]
+    fn test_config_sections_check() {
+        let yaml = "editor:\n  mode: vim\nhistory:\n  dedup: true\nexecution:\n  format: xml\n";
+        let errors = Config::check(yaml, None).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].key.as_deref(), Some("editor"));
+        assert_eq!(errors[0].line, Some(1));
+        assert_eq!(errors[1].key.as_deref(), Some("history"));
+        assert!(errors[1].message.contains("dedup"));
+
+        let errors = Config::check("execution:\n  format: jsn\n", None).unwrap_err();
+        assert_eq!(errors[0].key.as_deref(), Some("execution.format"));
+        assert!(errors[0].message.contains("did you mean `json`"));
+
+        let errors = Config::check("error_prompt: \"{oops} \"\neditor:\n  tab_width: 0\n", None)
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].key.as_deref(), Some("error_prompt"));
+        assert_eq!(errors[1].key.as_deref(), Some("editor.tab_width"));
+        assert_eq!(errors[1].line, Some(3));
+    }
+
+    #[test]
+    fn test_config_sections_from_environment() {
+        let env = Environment {
+            debug: false,
+            config: std::collections::HashMap::from([
+                ("EDITOR_MODE".to_string(), "emacs".to_string()),
+                (
+                    "HISTORY_IGNORE".to_string(),
+                    r#"["^ls$", "secret"]"#.to_string(),
+                ),
+                ("EXECUTION_TIMEOUT".to_string(), "10".to_string()),
+                ("ERROR_PROMPT".to_string(), "!! ".to_string()),
+            ]),
+            defaults: false,
+        };
+        let config = Config::from_environment(&env);
+        assert_eq!(config.editor.unwrap().mode, Some(EditMode::Emacs));
+        assert_eq!(config.history.unwrap().ignore.unwrap().len(), 2);
+        assert_eq!(config.execution.unwrap().timeout, Some(10));
+        assert_eq!(config.error_prompt.as_deref(), Some("!! "));
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let before = Config::default();
+        let mut after = Config {
+            theme: Some("gruvbox-dark".to_string()),
+            aliases: Some(std::collections::HashMap::from([(
+                "ll".to_string(),
+                "ls -la".to_string(),
+            )])),
+            ..Config::default()
+        };
+        after.editor.as_mut().unwrap().mode = Some(EditMode::Emacs);
+        after.history_size = None;
+
+        let changes: Vec<String> = before.diff(&after).iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "+aliases.ll \"ls -la\"",
+                "editor.mode \"vi\" → \"emacs\"",
+                "-history_size",
+                "theme \"monokai\" → \"gruvbox-dark\"",
+            ]
+        );
+        assert!(before.diff(&Config::default()).is_empty());
+    }
+
+    #[test]
+    fn test_config_watch_rename() {
+        let root = std::env::temp_dir().join(format!("turtle-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("config.yaml");
+        std::fs::write(&path, "theme: monokai\n").unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _watcher = Config::default()
+            .watch(&path.to_string_lossy(), None, Some(sender))
+            .unwrap();
+
+        // save the way editors do, by renaming a temporary file over the config
+        let temp = root.join(".config.yaml.swp");
+        std::fs::write(&temp, "theme: gruvbox-dark\n").unwrap();
+        std::fs::rename(&temp, &path).unwrap();
+
+        let timeout = std::time::Duration::from_secs(5);
+        match receiver.recv_timeout(timeout).unwrap() {
+            ConfigSignal::Reloaded(config) => {
+                assert_eq!(config.theme.as_deref(), Some("gruvbox-dark"))
+            }
+            signal => panic!("unexpected signal: {:?}", signal),
+        }
+
+        std::fs::write(&path, "theme: gruvbox-drak\n").unwrap();
+        match receiver.recv_timeout(timeout).unwrap() {
+            ConfigSignal::Error(message) => assert!(message.contains("gruvbox-drak")),
+            signal => panic!("unexpected signal: {:?}", signal),
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_config_set_get_unset() {
+        let mut config = Config::default();
+        config.set("history_size", "500").unwrap();
+        assert_eq!(config.history_size, Some(500));
+        config.set("prompt", "1000").unwrap();
+        assert_eq!(config.get("prompt"), Some(serde_json::json!("1000")));
+        config.set("editor.mode", "emacs").unwrap();
+        assert_eq!(config.editor().mode, Some(EditMode::Emacs));
+        config.set("aliases.ll", "ls -la").unwrap();
+        assert_eq!(config.get("aliases.ll"), Some(serde_json::json!("ls -la")));
+        config.set("history.ignore", r#"["^ls$"]"#).unwrap();
+        assert_eq!(config.history().ignore, Some(vec!["^ls$".to_string()]));
+
+        let errors = config.set("theme", "gruvbox-drak").unwrap_err();
+        assert!(errors[0].message.contains("did you mean `gruvbox-dark`"));
+        let errors = config.set("editor.mdoe", "vi").unwrap_err();
+        assert!(errors[0].message.contains("did you mean `editor.mode`"));
+        assert!(config.set("history_size", "lots").is_err());
+
+        config.unset("aliases.ll").unwrap();
+        assert_eq!(config.get("aliases.ll"), None);
+        config.unset("execution.env.MISSING").unwrap();
+        assert!(Config::keys().contains(&"history.dedupe".to_string()));
+    }
+
+    #[test]
+    fn test_set_yaml_value() {
+        let yaml = "# my config\ntheme: monokai # favourite\n\naliases:\n    ll: ls -la\n\n# history\nhistory_size: 100\n";
+        let edited = set_yaml_value(yaml, &["theme"], &serde_json::json!("gruvbox-dark")).unwrap();
+        assert_eq!(
+            edited,
+            "# my config\ntheme: gruvbox-dark # favourite\n\naliases:\n    ll: ls -la\n\n# history\nhistory_size: 100\n"
+        );
+
+        let edited =
+            set_yaml_value(yaml, &["aliases", "gs"], &serde_json::json!("git status")).unwrap();
+        assert!(edited.contains("    ll: ls -la\n    gs: git status\n\n# history"));
+
+        let edited =
+            set_yaml_value(yaml, &["editor", "mode"], &serde_json::json!("emacs")).unwrap();
+        assert!(edited.ends_with("history_size: 100\neditor:\n  mode: emacs\n"));
+
+        let edited = remove_yaml_value(yaml, &["aliases", "ll"]);
+        assert_eq!(
+            edited,
+            "# my config\ntheme: monokai # favourite\n\naliases:\n\n# history\nhistory_size: 100\n"
+        );
+
+        assert_eq!(
+            set_yaml_value(
+                "aliases: {ll: ls}\n",
+                &["aliases", "gs"],
+                &serde_json::json!("git")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_config_write() {
+        let root = std::env::temp_dir().join(format!("turtle-write-{}", uuid::Uuid::new_v4()));
+        let path = root.join("turtle").join("config.yaml");
+        let path = path.to_string_lossy().to_string();
+
+        let mut config = Config::empty();
+        config.theme = Some("monokai".to_string());
+        config.write(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "theme: monokai\n");
+
+        std::fs::write(
+            &path,
+            "# keep me\ntheme: monokai\nhistory_size: 10 # small\n",
+        )
+        .unwrap();
+        config.history_size = Some(20);
+        config.editor = Some(EditorConfig {
+            mode: Some(EditMode::Emacs),
+            ..Default::default()
+        });
+        config.write(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# keep me\ntheme: monokai\nhistory_size: 20 # small\neditor:\n  mode: emacs\n"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_find_project_config() {
+        let root = std::env::temp_dir().join(format!("turtle-project-{}", uuid::Uuid::new_v4()));
+        let nested = root.join("src").join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_config(&nested), None);
+
+        std::fs::write(root.join(PROJECT_CONFIG_FILE), "theme: monokai\n").unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(root.join(PROJECT_CONFIG_FILE))
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_config_check_valid() {
+        let yaml = "prompt: \"{uname}@{hostname}:{cwd}$ \"\ntheme: monokai\nhistory_size: 100\naliases:\n  ll: ls -la\n";
+        let config = Config::check(yaml, None).unwrap();
+        assert_eq!(config.theme, Some("monokai".to_string()));
+        assert_eq!(config.history_size, Some(100));
+        assert!(Config::check("", None).is_ok());
+    }
+
+    #[test]
+    fn test_config_check_unknown_key() {
+        let yaml = "theme: monokai\nhistroy_size: 100\n";
+        let errors = Config::check(yaml, Some("config.yaml")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(2));
+        assert_eq!(errors[0].key, Some("histroy_size".to_string()));
+        assert_eq!(
+            errors[0].to_string(),
+            "config.yaml:2: histroy_size: unknown key `histroy_size`, did you mean `history_size`?"
+        );
+    }
+
+    #[test]
+    fn test_config_check_types() {
+        let yaml = "history_size: lots\naliases:\n  ll: ls -la\n  gs:\n    - git\n";
+        let errors = Config::check(yaml, None).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].message,
+            "expected a positive integer, found a string"
+        );
+        assert_eq!(errors[0].line, Some(1));
+        assert_eq!(errors[1].key, Some("aliases.gs".to_string()));
+        assert_eq!(errors[1].line, Some(4));
+
+        let errors = Config::check("- theme\n", None).unwrap_err();
+        assert_eq!(errors.len(), 1);
+
+        let errors = Config::check("theme: [monokai\n", None).unwrap_err();
+        assert!(errors[0].line.is_some());
+    }
+
+    #[test]
+    fn test_config_check_values() {
+        let yaml = "# comment\ntheme: monokia\nprompt: \"{uname} {cwdd} {{ if cwd }}x{{ endif }}\"\nhistory_size: 0\n";
+        let errors = Config::check(yaml, None).unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].line, Some(2));
+        assert!(errors[0].message.contains("did you mean `monokai`"));
+        assert_eq!(errors[1].line, Some(3));
+        assert!(errors[1].message.contains("`{cwdd}`, did you mean `{cwd}`"));
+        assert_eq!(errors[2].line, Some(4));
+        assert!(
+            errors[2]
+                .message
+                .starts_with("history_size must be between 1")
+        );
+    }
+
+    #[test]
+    fn test_template_fields() {
+        assert_eq!(
+            template_fields(
+                "{uname}@{ hostname }:{cwd | upper} \\{x} {{ if turtle }}{@index}{{ endif }}"
+            ),
+            vec!["uname", "hostname", "cwd"]
+        );
+    }
 }
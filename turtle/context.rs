@@ -1,141 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-/// Format a floating-point number according to format specifier
-fn format_number(num: f64, spec: &str) -> String {
-    if spec.is_empty() {
-        return format!("{}", num);
-    }
-
-    // Parse format spec
-    let spec = spec.trim_start_matches(':');
-
-    // Check for precision (.N)
-    if let Some(dot_pos) = spec.find('.') {
-        if let Ok(precision) = spec[dot_pos + 1..].parse::<usize>() {
-            return format!("{:.prec$}", num, prec = precision);
-        }
-    }
-
-    // Check for width and alignment
-    if let Some(first_char) = spec.chars().next() {
-        let (align, rest) = match first_char {
-            '<' => ("left", &spec[1..]),
-            '^' => ("center", &spec[1..]),
-            '0' => {
-                // Zero-padded
-                if let Ok(width) = spec[1..].parse::<usize>() {
-                    return format!("{:0width$}", num, width = width);
-                }
-                ("right", spec)
-            }
-            _ => ("right", spec),
-        };
-
-        if let Ok(width) = rest.parse::<usize>() {
-            let s = format!("{}", num);
-            return match align {
-                "left" => format!("{:<width$}", s, width = width),
-                "center" => format!("{:^width$}", s, width = width),
-                _ => format!("{:>width$}", s, width = width),
-            };
-        }
-    }
-
-    format!("{}", num)
-}
-
-/// Format an integer according to format specifier
-fn format_integer(num: i64, spec: &str) -> String {
-    if spec.is_empty() {
-        return format!("{}", num);
-    }
-
-    let spec = spec.trim_start_matches(':');
-
-    // Check for number base
-    match spec.chars().next() {
-        Some('x') => return format!("{:x}", num),
-        Some('X') => return format!("{:X}", num),
-        Some('b') => return format!("{:b}", num),
-        Some('o') => return format!("{:o}", num),
-        Some('?') => {
-            if spec == "#?" {
-                return format!("{:#?}", num);
-            }
-            return format!("{:?}", num);
-        }
-        _ => {}
-    }
-
-    // Check for width and zero-padding
-    if let Some('0') = spec.chars().next() {
-        if let Ok(width) = spec[1..].parse::<usize>() {
-            return format!("{:0width$}", num, width = width);
-        }
-    }
-
-    // Check for alignment and width
-    if let Some(first_char) = spec.chars().next() {
-        let (align, rest) = match first_char {
-            '<' => ("left", &spec[1..]),
-            '^' => ("center", &spec[1..]),
-            _ => ("right", spec),
-        };
-
-        if let Ok(width) = rest.parse::<usize>() {
-            let s = format!("{}", num);
-            return match align {
-                "left" => format!("{:<width$}", s, width = width),
-                "center" => format!("{:^width$}", s, width = width),
-                _ => format!("{:>width$}", s, width = width),
-            };
-        }
-    }
-
-    format!("{}", num)
-}
-
-/// Format a string according to format specifier
-fn format_string(s: &str, spec: &str) -> String {
-    if spec.is_empty() {
-        return s.to_string();
-    }
-
-    let spec = spec.trim_start_matches(':');
-
-    // Check for debug formatting
-    if spec == "?" {
-        return format!("{:?}", s);
-    } else if spec == "#?" {
-        return format!("{:#?}", s);
-    }
-
-    // Check for alignment and width
-    if let Some(first_char) = spec.chars().next() {
-        let (align, rest) = match first_char {
-            '<' => ("left", &spec[1..]),
-            '^' => ("center", &spec[1..]),
-            _ => ("right", spec),
-        };
-
-        if let Ok(width) = rest.parse::<usize>() {
-            return match align {
-                "left" => format!("{:<width$}", s, width = width),
-                "center" => format!("{:^width$}", s, width = width),
-                _ => format!("{:>width$}", s, width = width),
-            };
-        }
-    }
-
-    s.to_string()
-}
-
 /// context
 pub struct Context {
     pub debug: bool,
     pub config: Option<std::sync::Arc<std::sync::Mutex<crate::config::Config>>>,
     pub args: Option<std::sync::Arc<std::sync::Mutex<crate::config::Arguments>>>,
     pub builtins: Option<crate::builtins::Builtins>,
+    pub stages: Vec<crate::builtins::Stage>,
     pub env: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
     pub vars: std::sync::Arc<
         std::sync::Mutex<std::collections::HashMap<String, crate::expressions::Expressions>>,
@@ -145,302 +16,583 @@ pub struct Context {
 
     pub functions: std::collections::HashMap<String, crate::expressions::Expressions>,
     pub code: Vec<crate::expressions::Expressions>,
+
+    /// the error being raised, see `raise`
+    pub error: Option<crate::errors::TurtleError>,
+    /// `try` blocks being evaluated, raised errors are only printed outside of them
+    pub catching: usize,
+
+    /// namespaces of imported modules by file, each module is evaluated once
+    pub modules: std::collections::HashMap<std::path::PathBuf, crate::expressions::Expressions>,
+    /// files being imported, the last one is importing the next, see `eval_import`
+    pub importing: Vec<std::path::PathBuf>,
 }
 
 impl Context {
     /// return available builtins
+    ///
+    /// builtins print their own output and return `null`, or return a value for the shell to
+    /// print, eg: `eval`. errors are raised like the errors of expressions
     fn get_builtins(&self) -> Vec<crate::builtins::Builtin> {
         vec![
             // help
             crate::builtins::Builtin {
                 name: "help".to_string(),
                 description: "Display help information".to_string(),
-                help: "Usage: help".to_string(),
-                execute: Box::new(|_, _, _, _, _, _, builtin_names, _, _| {
+                help: String::new(),
+                signature: crate::builtins::Signature::new().optional(
+                    "builtin",
+                    crate::builtins::ArgType::String,
+                    "Show the help of a builtin",
+                ),
+                execute: Box::new(|context, args| {
+                    if let Some(name) = args.string("builtin") {
+                        let help = context.builtins.help(&name).ok_or_else(|| {
+                            crate::errors::TurtleError::new(format!(
+                                "help: no builtin named `{}`",
+                                name
+                            ))
+                        })?;
+                        println!("{}", help);
+                        return Ok(crate::expressions::Expressions::Null);
+                    }
                     println!("🐢 builtins:");
-                    for name in builtin_names {
-                        println!(" - {}", name);
+                    for builtin in &context.builtins.builtins {
+                        println!(" - {:<10} {}", builtin.name, builtin.description);
                     }
+                    Ok(crate::expressions::Expressions::Null)
                 }),
             },
             // printf
             crate::builtins::Builtin {
                 name: "printf".to_string(),
                 description: "Print formatted output to the console".to_string(),
-                help: r#"Usage: printf <format_string> [args...]
-Format specifiers:
-  {}       - default formatting
-  {:?}     - debug formatting
-  {:#?}    - pretty debug formatting
-  {:x}     - lowercase hex
-  {:X}     - uppercase hex
-  {:b}     - binary
-  {:o}     - octal
-  {:.N}    - precision (N decimal places)
-  {:N}     - minimum width (right-aligned)
-  {:<N}    - left-aligned width
-  {:^N}    - center-aligned width
-  {:0N}    - zero-padded width
+                help: r#"Placeholders: {} (next argument), {0} (by position), {name} (turtle variable)
+Format spec: {:[[fill]align][sign]['#']['0'][width]['.' precision][type]}
+  align    - < left, ^ center, > right
+  sign     - + always write the sign of numbers
+  #        - 0x, 0b and 0o prefixes, pretty debug output
+  0        - zero-pad numbers to the width
+  width    - minimum width, or N$ / name$ to read it from an argument
+  .prec    - decimal places for numbers, most characters for strings, .* reads it from an argument
+  type     - ? debug, x/X hex, b binary, o octal, e/E exponent
+  %...     - strftime for datetimes, eg: {:%Y-%m-%d %H:%M}
 Examples:
   printf "Hello, {}!" "World"
   printf "Number: {:.2}" 3.14159
-  printf "Hex: {:x}" 255
-  printf "Binary: {:b}" 42"#
+  printf "Hex: {:#x}" 255
+  printf "{0:>8} {0:<8}|" turtle
+  printf "{:*^9}" shell
+  printf "Hello, {name}!""#
                     .to_string(),
-                execute: Box::new(|_, _, _, _, _, _, _, args, _| {
-                    if args.is_empty() {
-                        eprintln!("printf: missing format string");
-                        eprintln!("Usage: printf <format_string> [args...]");
-                        return;
-                    }
-
-                    let format_str = &args[0];
-                    let format_args = &args[1..];
-
-                    // Parse format string and find placeholders
-                    let mut result = String::new();
-                    let mut chars = format_str.chars().peekable();
-                    let mut arg_idx = 0;
-
-                    while let Some(c) = chars.next() {
-                        if c == '{' {
-                            if let Some(&next_c) = chars.peek() {
-                                if next_c == '{' {
-                                    // Escaped brace: {{
-                                    result.push('{');
-                                    chars.next();
-                                    continue;
-                                }
-                            }
-
-                            // Parse format specifier
-                            let mut spec = String::new();
-                            while let Some(&c) = chars.peek() {
-                                if c == '}' {
-                                    chars.next();
-                                    break;
-                                }
-                                spec.push(c);
-                                chars.next();
-                            }
-
-                            // Get the argument
-                            if arg_idx >= format_args.len() {
-                                eprintln!("printf: not enough arguments for format string");
-                                return;
-                            }
-
-                            let arg = &format_args[arg_idx];
-                            arg_idx += 1;
-
-                            // Try to parse as number first
-                            let formatted = if let Ok(num) = arg.parse::<f64>() {
-                                format_number(num, &spec)
-                            } else if let Ok(num) = arg.parse::<i64>() {
-                                format_integer(num, &spec)
-                            } else {
-                                format_string(arg, &spec)
-                            };
-
-                            result.push_str(&formatted);
-                        } else if c == '}' {
-                            if let Some(&next_c) = chars.peek() {
-                                if next_c == '}' {
-                                    // Escaped brace: }}
-                                    result.push('}');
-                                    chars.next();
-                                    continue;
-                                }
-                            }
-                            result.push(c);
-                        } else {
-                            result.push(c);
-                        }
-                    }
-
-                    print!("{}\n", result);
+                signature: crate::builtins::Signature::new()
+                    .required(
+                        "format_string",
+                        crate::builtins::ArgType::String,
+                        "The format string",
+                    )
+                    .rest("args", crate::builtins::ArgType::String, "Values to format"),
+                execute: Box::new(|context, args| {
+                    let template = args.string("format_string").unwrap_or_default();
+                    let values: Vec<crate::expressions::Expressions> = args
+                        .strings("args")
+                        .iter()
+                        .map(|value| crate::expressions::Expressions::infer(value))
+                        .collect();
+                    let vars = context.vars.clone();
+                    let named = |name: &str| vars.lock().unwrap().get(name).cloned();
+                    let output = crate::format::format(&template, &values, &named)
+                        .map_err(|e| crate::errors::TurtleError::new(format!("printf: {}", e)))?;
+                    println!("{}", output);
+                    Ok(crate::expressions::Expressions::Null)
                 }),
             },
             // keywords
             crate::builtins::Builtin {
                 name: "keywords".to_string(),
                 description: "Display keywords".to_string(),
-                help: "Usage: keywords".to_string(),
-                execute: Box::new(|_, _, _, _, _, _, builtin_names, _, _| {
+                help: String::new(),
+                signature: crate::builtins::Signature::new(),
+                execute: Box::new(|_, _| {
                     println!("🐢 keywords:");
                     for keyword in crate::lang::KEYWORDS {
                         println!(" - {}", keyword);
                     }
+                    Ok(crate::expressions::Expressions::Null)
                 }),
             },
             // timestamp
             crate::builtins::Builtin {
                 name: "timestamp".to_string(),
-                description: "Convert a timestamp to a date".to_string(),
-                help: "Usage: timestamp <timestamp>".to_string(),
-                execute: Box::new(|_, _, _, _, _, _, builtin_names, args, _| {
-                    if args.is_empty() {
-                        eprintln!("timestamp <timestamp>");
-                        return;
-                    }
-                    let timestamp = &args[0];
-                    let duration =
-                        std::time::Duration::from_secs(timestamp.parse::<u64>().unwrap_or(0));
-                    let datetime = std::time::UNIX_EPOCH + duration;
-                    println!("DateTime: {:?}", datetime);
+                description: "Convert between unix timestamps and dates".to_string(),
+                help: r#"Prints the local date and unix timestamp of a date, or of now when none is given.
+Dates are read like date(), eg: 1735689600, "2025-01-01T00:00Z", "10/Oct/2000:13:55:36 -0700""#
+                    .to_string(),
+                signature: crate::builtins::Signature::new().rest(
+                    "date",
+                    crate::builtins::ArgType::String,
+                    "A unix timestamp or date",
+                ),
+                execute: Box::new(|_, args| {
+                    let text = args.strings("date").join(" ");
+                    let datetime = if text.is_empty() {
+                        crate::dates::now()
+                    } else {
+                        crate::dates::parse(&text).ok_or_else(|| {
+                            crate::errors::TurtleError::new(format!(
+                                "timestamp: can't read `{}` as a date",
+                                text
+                            ))
+                        })?
+                    };
+                    let local = crate::dates::to_timezone(&datetime, "local").unwrap_or(datetime);
+                    println!("{}", crate::dates::display(&local));
+                    println!("{}", datetime.timestamp());
+                    Ok(crate::expressions::Expressions::Null)
                 }),
             },
             // imgcat
             crate::builtins::Builtin {
                 name: "imgcat".to_string(),
                 description: "display images".to_string(),
-                help: "Usage: imgcat <image_path>".to_string(),
-                execute: Box::new(|_, _, _, _, _, _, builtin_names, args, _| {
-                    if args.is_empty() {
-                        eprintln!("imgcat <image_path>");
-                        return;
-                    }
-                    let image_path = &args[0];
+                help: String::new(),
+                signature: crate::builtins::Signature::new().required(
+                    "image_path",
+                    crate::builtins::ArgType::Path,
+                    "The image to display",
+                ),
+                execute: Box::new(|_, _| {
                     // TODO: implement image display logic
+                    Ok(crate::expressions::Expressions::Null)
                 }),
             },
             // ast
             crate::builtins::Builtin {
                 name: "ast".to_string(),
                 description: "Translate a string to Turtle AST".to_string(),
-                help: "Usage: ast <code>".to_string(),
-                execute: Box::new(
-                    |_, turtle_args, env, aliases, vars, _, builtin_names, args, debug| {
-                        let code = args.join(" ");
-                        // Evaluate the code
-                        let mut interpreter = crate::lang::Interpreter::new(
-                            Some(turtle_args.clone()),
-                            env.clone(),
-                            aliases.clone(),
-                            vars.clone(),
-                            builtin_names,
-                            debug,
-                        );
-
-                        let _tokens = interpreter.tokenize(&code.as_str());
+                help: String::new(),
+                signature: crate::builtins::Signature::new().code("code", "The code to parse"),
+                execute: Box::new(|context, args| {
+                    let code = args.string("code").unwrap_or_default();
+                    let mut interpreter = context.interpreter();
+                    interpreter.tokenize(&code);
                         let expr = interpreter.interpret();
                         println!("turtle ast: {:?}", expr);
-                    },
-                ),
+                    Ok(crate::expressions::Expressions::Null)
+                }),
             },
             // tokenize
             crate::builtins::Builtin {
                 name: "tokenize".to_string(),
                 description: "Tokenize a string as Turtle code".to_string(),
-                help: "Usage: tokenize <code>".to_string(),
-                execute: Box::new(
-                    |_, turtle_args, env, aliases, vars, _, builtin_names, args, debug| {
-                        let code = args.join(" ");
-
-                        // Evaluate the code
-                        let mut interpreter = crate::lang::Interpreter::new(
-                            Some(turtle_args.clone()),
-                            env.clone(),
-                            aliases.clone(),
-                            vars.clone(),
-                            builtin_names,
-                            debug,
-                        );
-
-                        let tokens = interpreter.tokenize(&code.as_str());
+                help: String::new(),
+                signature: crate::builtins::Signature::new().code("code", "The code to tokenize"),
+                execute: Box::new(|context, args| {
+                    let code = args.string("code").unwrap_or_default();
+                    let tokens = context.interpreter().tokenize(&code);
                         println!("turtle tokens: {:?}", tokens);
-                    },
-                ),
+                    Ok(crate::expressions::Expressions::Null)
+                }),
             },
             // eval
             crate::builtins::Builtin {
                 name: "eval".to_string(),
                 description: "Evaluate a string as Turtle code".to_string(),
-                help: "Usage: eval <code>".to_string(),
-                execute: Box::new(
-                    |config,
-                     turtle_args,
-                     env,
-                     aliases,
-                     vars,
-                     history,
-                     builtin_names,
-                     args,
-                     debug| {
-                        if args.is_empty() {
-                            eprintln!("eval <code>");
-                            return;
-                        }
-                        let code = args.join(" ");
-                        let mut interpreter = crate::lang::Interpreter::new(
-                            Some(turtle_args.clone()),
-                            env.clone(),
-                            aliases.clone(),
-                            vars.clone(),
-                            builtin_names,
-                            debug,
-                        );
-                        let mut context = crate::context::Context::new(
-                            Some(config.clone()),
-                            Some(turtle_args.clone()),
-                            env.clone(),
-                            aliases.clone(),
-                            vars.clone(),
-                            history.clone(),
-                            debug,
-                        );
-                        context.setup();
-                        let _tokens = interpreter.tokenize(&code.as_str());
+                help: "The value of the code is the value of eval, eg: `let n = eval 1 + 2`"
+                    .to_string(),
+                signature: crate::builtins::Signature::new().code("code", "The code to evaluate"),
+                execute: Box::new(|context, args| {
+                    let code = args.string("code").unwrap_or_default();
+                    let mut interpreter = context.interpreter();
+                    interpreter.tokenize(&code);
                         let expr = interpreter.interpret();
-                        context.eval(expr);
-                    },
-                ),
+                    let mut turtle = context.context();
+                    // errors are raised again by the caller, which prints them
+                    turtle.catching += 1;
+                    let Some(result) = turtle.eval(expr) else {
+                        return Err(turtle.error.take().unwrap_or_else(|| {
+                            crate::errors::TurtleError::new(format!("eval: invalid code `{}`", code))
+                        }));
+                    };
+                    match result.to_expression() {
+                        Some(value) => Ok(value),
+                        None => {
+                            turtle.print(&result, "text");
+                            Ok(crate::expressions::Expressions::Null)
+                        }
+                    }
+                }),
             },
             // history
             crate::builtins::Builtin {
                 name: "history".to_string(),
                 description: "Get and Manage command history".to_string(),
-                help: "Usage: history".to_string(),
-                execute: Box::new(|_, _, _, _, _, history, _, args, _| {
-                    if args.is_empty() {
-                        if let Some(events) = history.lock().unwrap().events.as_ref() {
+                help: String::new(),
+                signature: crate::builtins::Signature::new().flag(
+                    "clear",
+                    Some('c'),
+                    "Clear the command history",
+                ),
+                execute: Box::new(|context, args| {
+                    let mut history = context.history.lock().unwrap();
+                    if args.flag("clear") {
+                        history.events = Some(vec![]);
+                        println!("Command history cleared.");
+                    } else if let Some(events) = history.events.as_ref() {
                             for (i, event) in events.iter().enumerate() {
                                 println!("{}: {:?}", i + 1, event);
                             }
                         } else {
                             println!("No history available.");
                         }
+                    Ok(crate::expressions::Expressions::Null)
+                }),
+            },
+            // stats
+            crate::builtins::Builtin {
+                name: "stats".to_string(),
+                description: "Report command history statistics".to_string(),
+                help: r#"Sections:
+  commands    Most used commands.
+  failures    Commands with the highest failure rate.
+  slowest     Commands with the longest run time.
+  hours       Activity by hour of the day.
+  days        Activity by day of the week."#
+                    .to_string(),
+                signature: crate::builtins::Signature::new()
+                    .optional(
+                        "section",
+                        crate::builtins::ArgType::Choice(crate::history::HistoryStats::SECTIONS),
+                        "Section to report, every section when left out",
+                    )
+                    .option(
+                        "limit",
+                        Some('n'),
+                        crate::builtins::ArgType::Integer,
+                        Some("10"),
+                        "Number of rows per section",
+                    )
+                    .option(
+                        "format",
+                        Some('f'),
+                        crate::builtins::ArgType::Choice(&["table", "text", "json", "yaml"]),
+                        None,
+                        "Output format, the configured format when left out",
+                    ),
+                execute: Box::new(|context, args| {
+                    let section = args.string("section");
+                    let section = section.as_deref();
+                    let limit = args
+                        .integer("limit")
+                        .and_then(|n| usize::try_from(n).ok())
+                        .ok_or_else(|| {
+                            crate::errors::TurtleError::new("stats: --limit expects a count")
+                        })?;
+                    let format = args.string("format").unwrap_or_else(|| {
+                        context
+                            .config
+                            .lock()
+                            .unwrap()
+                            .execution()
+                            .format
+                            .unwrap_or(crate::config::DEFAULT_FORMAT.to_string())
+                    });
+
+                    let stats = context.history.lock().unwrap().stats();
+                    let outputs = stats.outputs(&format, section, limit).ok_or_else(|| {
+                        crate::errors::TurtleError::new(format!(
+                            "stats: unknown section or format (sections: {})",
+                            crate::history::HistoryStats::SECTIONS.join(", ")
+                        ))
+                    })?;
+                    for (name, output) in crate::history::HistoryStats::SECTIONS
+                        .iter()
+                        .filter(|s| section.is_none_or(|section| section == **s))
+                        .zip(outputs.iter())
+                    {
+                        if let crate::expressions::Outputs::Table(_) = output
+                            && section.is_none()
+                        {
+                            println!("🐢 {}:", name);
+                        }
+                        println!("{}", output);
                     }
-                    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-
-                    if arg_refs.contains(&"-h") || arg_refs.contains(&"--help") {
-                        println!("history");
-                        println!("Display command history.");
-                        println!("Options:");
-                        println!("  -c, --clear    Clear the command history.");
-                        return;
-                    }
+                    Ok(crate::expressions::Expressions::Null)
+                }),
+            },
+            // config
+            crate::builtins::Builtin {
+                name: "config".to_string(),
+                description: "Get, set and persist configuration".to_string(),
+                help: r#"Commands:
+  get [key]          Show a value, or every value.
+  set <key> <value>  Set a value for this session.
+  unset <key>        Reset a value to its default.
+  path               Show the path of the user config.
+  edit               Open the user config in $EDITOR and reload.
+  save               Write session changes to the user config."#
+                    .to_string(),
+                signature: crate::builtins::Signature::new()
+                    .optional(
+                        "command",
+                        crate::builtins::ArgType::Choice(&[
+                            "get", "set", "unset", "path", "edit", "save",
+                        ]),
+                        "What to do, get when left out",
+                    )
+                    .optional(
+                        "key",
+                        crate::builtins::ArgType::String,
+                        "A config key, eg: editor.mode",
+                    )
+                    .rest(
+                        "value",
+                        crate::builtins::ArgType::String,
+                        "The value to set, read as YAML",
+                    ),
+                execute: Box::new(|context, args| {
+                    let config = &context.config;
+                    let aliases = &context.aliases;
+                    let history = &context.history;
+                    let command = args.string("command").unwrap_or("get".to_string());
+                    let key = args.string("key");
+                    // a value can have spaces, eg: a YAML list
+                    let value = Some(args.strings("value").join(" ")).filter(|v| !v.is_empty());
+
+                    let turtle_args = Some(context.args.lock().unwrap().clone());
+                    let user_config_path = crate::config::user_config_path(&turtle_args);
+                    let display = |value: &serde_json::Value| match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        value => value.to_string(),
+                    };
+                    let failed = |prefix: &str, errors: Vec<crate::config::ConfigError>| {
+                        let errors: Vec<String> =
+                            errors.iter().map(|e| format!("{}{}", prefix, e)).collect();
+                        crate::errors::TurtleError::new(errors.join("\n"))
+                    };
+
+                    match (command.as_str(), key.as_deref(), value.as_deref()) {
+                        ("get", None, None) => {
+                            for (key, value) in config.lock().unwrap().values() {
+                                println!("{} = {}", key, value);
+                            }
+                        }
+                        ("get", Some(key), None) => {
+                            crate::config::Config::check_key(key)
+                                .map_err(|e| failed("config: ", vec![e]))?;
+                            match config.lock().unwrap().get(key) {
+                                Some(value) => println!("{}", display(&value)),
+                                None => {
+                                    return Err(crate::errors::TurtleError::new(format!(
+                                        "config: {} is not set",
+                                        key
+                                    )));
+                                }
+                            }
+                        }
+                        ("set", Some(key), Some(value)) => {
+                            let mut new_config = config.lock().unwrap().clone();
+                            new_config
+                                .set(key, value)
+                                .map_err(|errors| failed("config: ", errors))?;
+                            let value = new_config.get(key).map(|v| display(&v)).unwrap_or_default();
+                            crate::config::apply_config(config, aliases, history, new_config);
+                            println!("{} = {}", key, value);
+                        }
+                        ("unset", Some(key), None) => {
+                            let mut new_config = config.lock().unwrap().clone();
+                            new_config
+                                .unset(key)
+                                .map_err(|errors| failed("config: ", errors))?;
+                            crate::config::apply_config(config, aliases, history, new_config);
+                            println!("unset {}", key);
+                        }
+                        ("path", None, None) => {
+                            println!("{}", crate::utils::expand_path(&user_config_path));
+                        }
+                        ("edit", None, None) => {
+                            let path = crate::utils::expand_path(&user_config_path);
+                            let editor = std::env::var("VISUAL")
+                                .or_else(|_| std::env::var("EDITOR"))
+                                .unwrap_or("vi".to_string());
+                            let mut editor_args = editor.split_whitespace();
+                            let program = editor_args.next().unwrap_or("vi");
+                            if let Some(parent) = std::path::Path::new(&path).parent() {
+                                std::fs::create_dir_all(parent).ok();
+                            }
+                            let status = std::process::Command::new(program)
+                                .args(editor_args)
+                                .arg(&path)
+                                .status();
+                            if let Err(e) = status {
+                                return Err(crate::errors::TurtleError::new(format!(
+                                    "config: failed to run {}: {}",
+                                    editor, e
+                                )));
+                            }
 
-                    if arg_refs.contains(&"-c") || arg_refs.contains(&"--clear") {
-                        history.lock().unwrap().events = Some(vec![]);
-                        println!("Command history cleared.");
+                            let contents = std::fs::read_to_string(&path).unwrap_or_default();
+                            if let Err(errors) = crate::config::Config::check(&contents, Some(&path)) {
+                                let mut error = failed("❌ ", errors);
+                                error.message.push_str(
+                                    "\nconfig: not reloaded, fix the errors and run `config edit` again",
+                                );
+                                return Err(error);
+                            }
+                            let new_config =
+                                crate::config::LayeredConfig::resolve(&user_config_path, &turtle_args)
+                                    .config;
+                            let changes =
+                                crate::config::apply_config(config, aliases, history, new_config);
+                            if changes.is_empty() {
+                                println!("config: no changes");
+                            } else {
+                                let changes: Vec<String> =
+                                    changes.iter().map(|c| c.to_string()).collect();
+                                println!("🔄 config reloaded: {}", changes.join(", "));
+                            }
+                        }
+                        ("save", None, None) => {
+                            // session changes are whatever differs from the config files
+                            let session = config.lock().unwrap().clone();
+                            let resolved =
+                                crate::config::LayeredConfig::resolve(&user_config_path, &turtle_args)
+                                    .config;
+                            let changes = resolved.diff(&session);
+                            if changes.is_empty() {
+                                println!("config: nothing to save");
+                                return Ok(crate::expressions::Expressions::Null);
+                            }
+
+                            let path = crate::utils::expand_path(&user_config_path);
+                            let contents = std::fs::read_to_string(&path).unwrap_or_default();
+                            let mut user = crate::config::Config::check(&contents, Some(&path))
+                                .map_err(|errors| failed("❌ ", errors))?;
+                            for change in &changes {
+                                user.apply_change(change)
+                                    .map_err(|errors| failed("config: ", errors))?;
+                            }
+                            user.write(&path).map_err(|e| {
+                                crate::errors::TurtleError::new(format!(
+                                    "config: failed to write {}: {}",
+                                    path, e
+                                ))
+                            })?;
+                            println!("config: saved {} change(s) to {}", changes.len(), path);
+                        }
+                        (command, ..) => {
+                            return Err(crate::errors::TurtleError::new(format!(
+                                "config: wrong arguments for {}, see `config --help`",
+                                command
+                            )));
+                        }
+                    }
+                    Ok(crate::expressions::Expressions::Null)
+                }),
+            },
+            // view
+            crate::builtins::Builtin {
+                name: "view".to_string(),
+                description: "Browse output, values or history full screen".to_string(),
+                help: r#"Keys:
+  ↑↓ j k, PgUp PgDn  Scroll rows.
+  ←→ h l             Scroll columns.
+  / n N              Search, next and previous match.
+  c                  Show only the named columns.
+  ⏎ y                Copy the row into the prompt.
+  q Esc              Close the viewer."#
+                    .to_string(),
+                signature: crate::builtins::Signature::new().optional(
+                    "target",
+                    crate::builtins::ArgType::String,
+                    "`history` or a variable, the last command's output when left out",
+                ),
+                execute: Box::new(|context, args| {
+                    let viewer = match args.string("target").as_deref() {
+                        Some("history") => Some(crate::viewer::Viewer::from_history(
+                            &context.history.lock().unwrap(),
+                        )),
+                        Some(name) => match context.vars.lock().unwrap().get(name) {
+                            Some(value) => crate::viewer::Viewer::from_value(name, value),
+                            None => {
+                                return Err(crate::errors::TurtleError::new(format!(
+                                    "view: no variable named `{}`",
+                                    name
+                                )));
+                            }
+                        },
+                        None => {
+                            let history = context.history.lock().unwrap();
+                            let events = history.events.clone().unwrap_or_default();
+                            // the last response and the request it answers
+                            events
+                                .iter()
+                                .rev()
+                                .find_map(|event| match event {
+                                    crate::history::Event::CommandResponse(response) => {
+                                        Some(response)
+                                    }
+                                    _ => None,
+                                })
+                                .map(|response| {
+                                    let title = events
+                                        .iter()
+                                        .find_map(|event| match event {
+                                            crate::history::Event::CommandRequest(request)
+                                                if request.id == response.id =>
+                                            {
+                                                Some(request.line())
+                                            }
+                                            _ => None,
+                                        })
+                                        .unwrap_or("output".to_string());
+                                    crate::viewer::Viewer::from_text(&title, &response.output)
+                                })
+                        }
+                    };
+
+                    let Some(viewer) = viewer else {
+                        return Err(crate::errors::TurtleError::new("view: nothing to show"));
+                    };
+                    let theme = context.config.lock().unwrap().theme.clone().and_then(|name| {
+                        crate::style::ThemeManager::from(crate::style::DEFAULT_THEMES)
+                            .themes
+                            .remove(&name)
+                    });
+                    match viewer.with_theme(theme.as_ref()).run() {
+                        Ok(Some(row)) => crate::terminal::set_prompt_input(row),
+                        Ok(None) => {}
+                        Err(e) => {
+                            return Err(crate::errors::TurtleError::new(format!("view: {}", e)));
+                    }
                     }
+                    Ok(crate::expressions::Expressions::Null)
                 }),
             },
             // noop
             crate::builtins::Builtin {
                 name: "noop".to_string(),
                 description: "No operation builtin".to_string(),
-                help: "Usage: noop".to_string(),
-                execute: Box::new(|_, _, _, _, _, _, _, _, _| ()),
+                help: String::new(),
+                signature: crate::builtins::Signature::new().rest(
+                    "args",
+                    crate::builtins::ArgType::String,
+                    "Ignored",
+                ),
+                execute: Box::new(|_, _| Ok(crate::expressions::Expressions::Null)),
             },
             // exit
             crate::builtins::Builtin {
                 name: "exit".to_string(),
                 description: "Exit the turtle shell".to_string(),
-                help: "Usage: exit".to_string(),
-                execute: Box::new(|_, _, _, _, _, _, _, _, _| {
-                    let _farewell_messages = vec![
+                help: String::new(),
+                signature: crate::builtins::Signature::new().optional(
+                    "code",
+                    crate::builtins::ArgType::Integer,
+                    "The exit status, 0 when left out",
+                ),
+                execute: Box::new(|_, args| {
+                    let _farewell_messages = [
                         "Goodbye!",
                         "See you later!",
                         "Exiting Turtle shell. Bye!",
@@ -448,74 +600,293 @@ Examples:
                         "Adios from Turtle shell!",
                     ];
 
-                    std::process::exit(0);
+                    let code = args.integer("code").unwrap_or(0);
+                    std::process::exit(code.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
                 }),
             },
             // TODO: handle builtins masked by commands that exist
             crate::builtins::Builtin {
                 name: "cd".to_string(),
                 description: "Change the current directory".to_string(),
-                help: "Usage: cd [directory]".to_string(),
-                execute: Box::new(|_, _, _, _, _, _, _, args, _| {
+                help: String::new(),
+                signature: crate::builtins::Signature::new().optional(
+                    "directory",
+                    crate::builtins::ArgType::Path,
+                    "The directory, home when left out",
+                ),
+                execute: Box::new(|_, args| {
                     let home = std::env::var("HOME").unwrap();
-                    let dest = args.get(0).map(|s| s.as_str()).unwrap_or(home.as_str());
+                    let dest = args.string("directory").unwrap_or(home);
 
                     // does the destination exist?
-                    if !std::path::Path::new(dest).exists() {
-                        eprintln!("cd: no such file or directory: {}", dest);
-                        return;
+                    if !std::path::Path::new(&dest).exists() {
+                        return Err(crate::errors::TurtleError::new(format!(
+                            "cd: no such file or directory: {}",
+                            dest
+                        )));
                     }
 
-                    if let Err(e) = std::env::set_current_dir(dest) {
-                        eprintln!("cd: {}: {}", dest, e);
-                    }
+                    std::env::set_current_dir(&dest).map_err(|e| {
+                        crate::errors::TurtleError::new(format!("cd: {}: {}", dest, e))
+                    })?;
+                    Ok(crate::expressions::Expressions::Null)
                 }),
             },
             crate::builtins::Builtin {
                 name: "alias".to_string(),
                 description: "Manage command aliases".to_string(),
-                help: "Usage: alias [name='command']".to_string(),
-                execute: Box::new(|_, _, _, aliases, _, _, _, args, _| {
-                    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+                help: "If no arguments are provided, lists all aliases.".to_string(),
+                signature: crate::builtins::Signature::new().rest(
+                    "assignment",
+                    crate::builtins::ArgType::String,
+                    "An alias, eg: ll='ls -la'",
+                ),
+                execute: Box::new(|context, args| {
+                    let assignment = args.strings("assignment").join(" ");
 
                     // if no args are provided, list all aliases
-                    if args.is_empty() {
-                        let aliases_lock = aliases.lock().unwrap();
+                    if assignment.is_empty() {
+                        let aliases_lock = context.aliases.lock().unwrap();
                         for (name, command) in aliases_lock.iter() {
                             println!("alias {}='{}'", name, command);
                         }
-                        return;
-                    }
-
-                    // user requested args contains '-h' or '--help'
-                    if arg_refs.contains(&"-h") || arg_refs.contains(&"--help") {
-                        println!("alias [name='command']");
-                        println!("Create or display command aliases.");
-                        println!("If no arguments are provided, lists all aliases.");
-                        return;
+                        return Ok(crate::expressions::Expressions::Null);
                     }
 
-                    // backwards compatibility: support single assignment only
-                    if arg_refs.len() == 1 {
-                        let assignment = arg_refs[0];
-                        if let Some(eq_pos) = assignment.find('=') {
-                            let name = &assignment[..eq_pos];
-                            let command = &assignment[eq_pos + 1..].trim_matches('\'');
-                            let mut aliases_lock = aliases.lock().unwrap();
+                    let Some((name, command)) = assignment.split_once('=') else {
+                        return Err(crate::errors::TurtleError::new(
+                            "alias: invalid alias format. Use name='command'",
+                        ));
+                    };
+                    let command = command.trim_matches('\'');
+                    let mut aliases_lock = context.aliases.lock().unwrap();
                             aliases_lock.insert(name.to_string(), command.to_string());
                             println!("alias set: {}='{}'", name, command);
-                        } else {
-                            eprintln!("invalid alias format. Use name='command'");
+                    Ok(crate::expressions::Expressions::Null)
+                }),
+            },
+        ]
+    }
+
+    /// Initializeexecution context
+    /// return available pipeline stages
+    ///
+    /// stages other than `from` parse text input with `from auto` first, so
+    /// they can be applied to command output directly
+    fn get_stages(&self) -> Vec<crate::builtins::Stage> {
+        vec![
+            // from
+            crate::builtins::Stage {
+                name: "from".to_string(),
+                description: "Parse text into values".to_string(),
+                help: "Usage: from [json|yaml|csv|tsv|lines|kv|auto]".to_string(),
+                execute: Box::new(|input, args| match input {
+                    crate::expressions::Expressions::String(text) => {
+                        let format = args.split_whitespace().next().unwrap_or("auto");
+                        crate::expressions::Expressions::parse_as(format, &text)
+                            .map(crate::context::EvalResults::from_value)
+                    }
+                    other => Err(format!("expected text, got {:?}", other)),
+                }),
+            },
+            // where
+            crate::builtins::Stage {
+                name: "where".to_string(),
+                description: "Keep items matching a condition".to_string(),
+                help: "Usage: where <column> <==|!=|>|>=|<|<=|=~|!~> <value>".to_string(),
+                execute: Box::new(|input, args| {
+                    let values = Self::filter(Self::items(input)?, args)?;
+                    Ok(crate::context::EvalResults::ArrayExpressionResult(
+                        crate::context::ArrayEvalResult { value: values },
+                    ))
+                }),
+            },
+            // map
+            crate::builtins::Stage {
+                name: "map".to_string(),
+                description: "Replace each item with one of its columns".to_string(),
+                help: "Usage: map <.column>".to_string(),
+                execute: Box::new(|input, args| {
+                    let path = args.trim();
+                    let values = Self::items(input)?
+                        .iter()
+                        .filter_map(|value| Self::column(value, path).cloned())
+                        .collect();
+                    Ok(crate::context::EvalResults::ArrayExpressionResult(
+                        crate::context::ArrayEvalResult { value: values },
+                    ))
+                }),
+            },
+            // sort-by
+            crate::builtins::Stage {
+                name: "sort-by".to_string(),
+                description: "Sort items, or objects by a column".to_string(),
+                help: "Usage: sort-by [column] [-r|--reverse]".to_string(),
+                execute: Box::new(|input, args| {
+                    let args: Vec<&str> = args.split_whitespace().collect();
+                    let values = Self::sort_by(Self::items(input)?, &args);
+                    Ok(crate::context::EvalResults::ArrayExpressionResult(
+                        crate::context::ArrayEvalResult { value: values },
+                    ))
+                }),
+            },
+            // select
+            crate::builtins::Stage {
+                name: "select".to_string(),
+                description: "Keep only the given columns".to_string(),
+                help: "Usage: select <column..>".to_string(),
+                execute: Box::new(|input, args| {
+                    let columns: Vec<&str> = args.split_whitespace().collect();
+                    if columns.is_empty() {
+                        return Err("expected at least one column".to_string());
+                    }
+                    Ok(match input {
+                        crate::expressions::Expressions::Object(_) => {
+                            crate::context::EvalResults::from_value(Self::select(input, &columns))
+                        }
+                        input => crate::context::EvalResults::ArrayExpressionResult(
+                            crate::context::ArrayEvalResult {
+                                value: Self::items(input)?
+                                    .into_iter()
+                                    .map(|value| Self::select(value, &columns))
+                                    .collect(),
+                            },
+                        ),
+                    })
+                }),
+            },
+            // uniq
+            crate::builtins::Stage {
+                name: "uniq".to_string(),
+                description: "Remove duplicate items, or items with a duplicate column".to_string(),
+                help: "Usage: uniq [column]".to_string(),
+                execute: Box::new(|input, args| {
+                    let path = args.trim();
+                    let mut seen: Vec<crate::expressions::Expressions> = Vec::new();
+                    let mut values = Vec::new();
+                    for value in Self::items(input)? {
+                        let key = Self::column(&value, path).cloned();
+                        if let Some(key) = key
+                            && !seen.contains(&key)
+                        {
+                            seen.push(key);
+                            values.push(value);
+                        }
+                    }
+                    Ok(crate::context::EvalResults::ArrayExpressionResult(
+                        crate::context::ArrayEvalResult { value: values },
+                    ))
+                }),
+            },
+            // group-by
+            crate::builtins::Stage {
+                name: "group-by".to_string(),
+                description: "Group items into an object by a column".to_string(),
+                help: "Usage: group-by <column>".to_string(),
+                execute: Box::new(|input, args| {
+                    let path = args.trim();
+                    if path.is_empty() {
+                        return Err("expected a column".to_string());
+                    }
+                    let mut groups: Vec<(String, crate::expressions::Expressions)> = Vec::new();
+                    for value in Self::items(input)? {
+                        let key = match Self::column(&value, path) {
+                            Some(crate::expressions::Expressions::String(key)) => key.clone(),
+                            Some(key) => key.to_json().to_string(),
+                            None => continue,
+                        };
+                        match groups.iter_mut().find(|(group, _)| *group == key) {
+                            Some((_, crate::expressions::Expressions::Array(items))) => {
+                                items.push(value)
+                            }
+                            _ => groups
+                                .push((key, crate::expressions::Expressions::Array(vec![value]))),
+                        }
+                    }
+                    Ok(crate::context::EvalResults::ObjectExpressionResult(
+                        crate::context::ObjectEvalResult { value: groups },
+                    ))
+                }),
+            },
+            // first
+            crate::builtins::Stage {
+                name: "first".to_string(),
+                description: "Keep the first N items".to_string(),
+                help: "Usage: first [N]".to_string(),
+                execute: Box::new(|input, args| {
+                    let count = Self::count_arg(args)?;
+                    let values = Self::items(input)?.into_iter().take(count).collect();
+                    Ok(crate::context::EvalResults::ArrayExpressionResult(
+                        crate::context::ArrayEvalResult { value: values },
+                    ))
+                }),
+            },
+            // last
+            crate::builtins::Stage {
+                name: "last".to_string(),
+                description: "Keep the last N items".to_string(),
+                help: "Usage: last [N]".to_string(),
+                execute: Box::new(|input, args| {
+                    let count = Self::count_arg(args)?;
+                    let values = Self::items(input)?;
+                    let skip = values.len().saturating_sub(count);
+                    Ok(crate::context::EvalResults::ArrayExpressionResult(
+                        crate::context::ArrayEvalResult {
+                            value: values.into_iter().skip(skip).collect(),
+                        },
+                    ))
+                }),
+            },
+            // count
+            crate::builtins::Stage {
+                name: "count".to_string(),
+                description: "Count items".to_string(),
+                help: "Usage: count".to_string(),
+                execute: Box::new(|input, _| {
+                    Ok(crate::context::EvalResults::IntegerExpressionResult(
+                        crate::context::IntegerEvalResult {
+                            value: crate::numbers::Integer::from(Self::items(input)?.len() as i64),
+                        },
+                    ))
+                }),
+            },
+            // sum
+            crate::builtins::Stage {
+                name: "sum".to_string(),
+                description: "Add up numbers, or a column of numbers".to_string(),
+                help: "Usage: sum [column]".to_string(),
+                execute: Box::new(|input, args| {
+                    let path = args.trim();
+                    // integers stay exact until a float is added
+                    let mut total = crate::expressions::Expressions::Integer(0.into());
+                    for value in Self::items(input)? {
+                        if let Some(value) = Self::column(&value, path)
+                            && let Some(sum) = crate::numbers::binary(&total, "+", value)
+                        {
+                            total = sum?;
                         }
-                        return;
                     }
+                    Ok(crate::context::EvalResults::from_value(total))
                 }),
             },
         ]
     }
 
-    /// Initializeexecution context
+    /// the count for `first` and `last`, defaulting to 1
+    fn count_arg(args: &str) -> Result<usize, String> {
+        match args.split_whitespace().next() {
+            Some(count) => count
+                .parse::<f64>()
+                .ok()
+                .filter(|n| *n >= 0.0)
+                .map(|n| n as usize)
+                .ok_or(format!("expected a count, got `{}`", count)),
+            None => Ok(1),
+        }
+    }
+
     pub fn setup(&mut self) {
+        self.stages = self.get_stages();
         let builtins = self.get_builtins();
         self.builtins = Some(crate::builtins::Builtins {
             env: self.env.clone(),
@@ -572,6 +943,32 @@ Examples:
             }
         }
 
+        // `??` only evaluates its right side when the left side is `null`
+        if op == "??" {
+            return match self.eval(Some(left))? {
+                crate::context::EvalResults::NullExpressionResult => self.eval(Some(right)),
+                value => Some(value),
+            };
+        }
+
+        // `&&` and `||` only evaluate their right side when it decides the result
+        if op == "&&" || op == "||" {
+            let left = self.eval_condition(left)?;
+            let value = match Self::is_true(&left) {
+                Ok(value) if value == (op == "||") => Ok(value),
+                Ok(_) => self
+                    .eval_condition(right)
+                    .map_or(Ok(false), |r| Self::is_true(&r)),
+                Err(e) => Err(e),
+            };
+            return match value {
+                Ok(value) => Some(crate::context::EvalResults::BooleanExpressionResult(
+                    crate::context::BooleanEvalResult { value },
+                )),
+                Err(e) => self.fail(format!("{}: {}", op, e)),
+            };
+        }
+
         // Recursively evaluate left and right, handling nested BinaryOperation
         let left_result = match left {
             crate::expressions::Expressions::BinaryOperation { left, op, right } => {
@@ -597,27 +994,62 @@ Examples:
             }
         }
 
+        // comparisons and regex matches, commands are compared by their output
+        let operand = |result: &crate::context::EvalResults| match result {
+            crate::context::EvalResults::CommandExpressionResult(cmd) => Some(
+                crate::expressions::Expressions::String(cmd.stdout.trim_end().to_string()),
+            ),
+            result => result.to_expression(),
+        };
+        if matches!(op.as_str(), "==" | "!=" | "<" | "<=" | ">" | ">=") {
+            let (Some(left), Some(right)) = (operand(&left_result), operand(&right_result)) else {
+                return self.fail(format!("{}: expected two values", op));
+            };
+            // see `crate::expressions::Expressions::compare` for how values are ordered
+            let ordering = left.compare(&right);
+            let value = match op.as_str() {
+                "==" => ordering.is_eq(),
+                "!=" => ordering.is_ne(),
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            };
+            return Some(crate::context::EvalResults::BooleanExpressionResult(
+                crate::context::BooleanEvalResult { value },
+            ));
+        }
+
+        // integers and floats, see `crate::numbers::binary` for the promotion rules, then
+        // datetimes and durations, see `crate::dates::binary`, and paths joined with `/`
+        if let (Some(left), Some(right)) =
+            (left_result.to_expression(), right_result.to_expression())
+            && let Some(result) = crate::numbers::binary(&left, &op, &right)
+                .or_else(|| crate::dates::binary(&left, &op, &right))
+                .or_else(|| crate::paths::binary(&left, &op, &right))
+        {
+            return match result {
+                Ok(value) => Some(crate::context::EvalResults::from_value(value)),
+                Err(e) => self.fail(e),
+            };
+        }
+
+        if op == "=~" || op == "!~" {
+            let matched = match (operand(&left_result), right_result.to_expression()) {
+                (Some(text), Some(pattern)) => crate::patterns::is_match(&text, &pattern),
+                _ => Err(format!("{}: expected a value and a regex", op)),
+            };
+            return match matched {
+                Ok(matched) => Some(crate::context::EvalResults::BooleanExpressionResult(
+                    crate::context::BooleanEvalResult {
+                        value: matched == (op == "=~"),
+                    },
+                )),
+                Err(e) => self.fail(e),
+            };
+        }
+
         match (left_result, right_result) {
-            (
-                crate::context::EvalResults::NumberExpressionResult(left_num),
-                crate::context::EvalResults::NumberExpressionResult(right_num),
-            ) => {
-                let result = match op.as_str() {
-                    "+" => left_num.value + right_num.value,
-                    "-" => left_num.value - right_num.value,
-                    "*" => left_num.value * right_num.value,
-                    "**" => left_num.value.powf(right_num.value),
-                    "/" => left_num.value / right_num.value,
-                    "%" => left_num.value % right_num.value,
-                    _ => {
-                        eprintln!("unsupported operation: {}", op);
-                        return None;
-                    }
-                };
-                Some(crate::context::EvalResults::NumberExpressionResult(
-                    crate::context::NumberEvalResult { value: result },
-                ))
-            }
             (
                 crate::context::EvalResults::StringExpressionResult(left_str),
                 crate::context::EvalResults::StringExpressionResult(right_str),
@@ -628,80 +1060,1066 @@ Examples:
                         crate::context::StringEvalResult { value: result },
                     ))
                 } else {
-                    eprintln!("unsupported operation for strings: {}", op);
-                    None
+                    self.fail(format!("unsupported operation for strings: {}", op))
                 }
             }
-            _ => {
-                eprintln!(
-                    "Binary operations are only supported for numbers and string concatenation."
-                );
-                None
-            }
+            _ => self
+                .fail("Binary operations are only supported for numbers and string concatenation."),
         }
     }
 
-    /// Evaluate assignment expressions: `<Identifier> = <Expression>`
-    fn eval_assignment(
+    /// whether a result holds, for `if`, `&&` and `||`
+    ///
+    /// `null` is false and commands hold when they exit with `0`, other values must be booleans
+    fn is_true(result: &crate::context::EvalResults) -> Result<bool, String> {
+        match result {
+            crate::context::EvalResults::BooleanExpressionResult(b) => Ok(b.value),
+            crate::context::EvalResults::NullExpressionResult => Ok(false),
+            crate::context::EvalResults::CommandExpressionResult(cmd) => Ok(cmd.code == 0),
+            other => Err(format!("expected a boolean, got {}", other)),
+        }
+    }
+
+    /// raise an error, evaluation stops and `None` is returned up to the nearest `try`
+    ///
+    /// the error is printed when no `try` is catching it, the message is kept without the
+    /// `Error: ` prefix so `catch e { e.message }` is the bare message
+    fn raise(&mut self, error: crate::errors::TurtleError) -> Option<crate::context::EvalResults> {
+        if self.catching == 0 {
+            eprintln!("Error: {}", error);
+        }
+        self.error = Some(error);
+        None
+    }
+
+    /// raise an error with a message, see `raise`
+    fn fail(&mut self, message: impl Into<String>) -> Option<crate::context::EvalResults> {
+        self.raise(crate::errors::TurtleError::new(message))
+    }
+
+    /// Evaluate blocks: `{ <Expression>; ... }`, a block is the value of its last expression
+    ///
+    /// the output of the other commands in the block is printed
+    fn eval_block(
         &mut self,
-        name: String,
-        value: crate::expressions::Expressions,
+        expressions: Vec<crate::expressions::Expressions>,
     ) -> Option<crate::context::EvalResults> {
-        // Check if the variable name conflicts with a builtin
-        if let Some(ref builtins) = self.builtins {
-            if builtins.list().contains(&name) {
-                let warning = format!(
-                    "Warning: Variable '{}' shadows builtin command. Use a different name.",
-                    name
-                );
-                eprintln!("{}", warning);
-                return Some(crate::context::EvalResults::StringExpressionResult(
-                    crate::context::StringEvalResult { value: warning },
-                ));
+        let mut result = crate::context::EvalResults::NullExpressionResult;
+        for expr in expressions {
+            if let crate::context::EvalResults::CommandExpressionResult(_) = result {
+                self.print(&result, "text");
             }
+            result = self.eval(Some(expr))?;
+        }
+        Some(result)
+    }
+
+    /// Evaluate `if` expressions: `if <Expression> { ... } else { ... }`
+    ///
+    /// an `if` without an `else` is `null` when its condition doesn't hold
+    fn eval_if(
+        &mut self,
+        condition: crate::expressions::Expressions,
+        then_branch: crate::expressions::Expressions,
+        else_branch: Option<crate::expressions::Expressions>,
+    ) -> Option<crate::context::EvalResults> {
+        let condition = self.eval_condition(condition)?;
+        match (Self::is_true(&condition), else_branch) {
+            (Ok(true), _) => self.eval(Some(then_branch)),
+            (Ok(false), Some(else_branch)) => self.eval(Some(else_branch)),
+            (Ok(false), None) => Some(crate::context::EvalResults::NullExpressionResult),
+            (Err(e), _) => self.fail(format!("if: {}", e)),
         }
+    }
+
+    /// Evaluate `match` expressions: `match <Expression> { <Pattern> if <guard> => ..., ... }`
+    ///
+    /// a `match` is the value of the first arm whose pattern matches and whose guard holds, or
+    /// `null` when none does. the names a pattern binds are only set in its guard and body
+    fn eval_match(
+        &mut self,
+        value: crate::expressions::Expressions,
+        arms: Vec<crate::destructure::MatchArm>,
+    ) -> Option<crate::context::EvalResults> {
+        let value = self.eval_value(value, "match")?;
+        for arm in arms {
+            let Some(bindings) = arm.pattern.bind(&value) else {
+                continue;
+            };
+            let previous: Vec<(String, Option<crate::expressions::Expressions>)> = {
+                let mut vars = self.vars.lock().unwrap();
+                bindings
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let previous = vars.insert(name.clone(), value);
+                        (name, previous)
+                    })
+                    .collect()
+            };
+            let result = self.eval_arm(arm);
+            let mut vars = self.vars.lock().unwrap();
+            for (name, previous) in previous.into_iter().rev() {
+                match previous {
+                    Some(previous) => vars.insert(name, previous),
+                    None => vars.remove(&name),
+                };
+            }
+            drop(vars);
+            if let Some(result) = result {
+                return result;
+            }
+        }
+        Some(crate::context::EvalResults::NullExpressionResult)
+    }
+
+    /// the value of a matching arm, `None` when its guard doesn't hold
+    fn eval_arm(
+        &mut self,
+        arm: crate::destructure::MatchArm,
+    ) -> Option<Option<crate::context::EvalResults>> {
+        if let Some(guard) = arm.guard {
+            let Some(guard) = self.eval_condition(guard) else {
+                return Some(None);
+            };
+            match Self::is_true(&guard) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(self.fail(format!("match: {}", e))),
+            }
+        }
+        Some(self.eval(Some(arm.body)))
+    }
+
+    /// Evaluate `for` loops: `for <Identifier> in <Expression> { ... }`
+    ///
+    /// arrays are iterated by item, text and command output by line. the loop is an array of
+    /// the values of its body that aren't `null`, the output of commands in the body is printed
+    fn eval_for(
+        &mut self,
+        iterator: String,
+        iterable: crate::expressions::Expressions,
+        body: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        let lines = |text: &str| {
+            text.lines()
+                .map(|line| crate::expressions::Expressions::String(line.to_string()))
+                .collect()
+        };
+        let items: Vec<crate::expressions::Expressions> = match self.eval(Some(iterable))? {
+            crate::context::EvalResults::CommandExpressionResult(cmd) => lines(&cmd.stdout),
+            result => match result.to_expression() {
+                Some(crate::expressions::Expressions::Array(items)) => items,
+                Some(crate::expressions::Expressions::String(text)) => lines(&text),
+                Some(crate::expressions::Expressions::Null) => Vec::new(),
+                _ => {
+                    return self.fail(format!("for: can't iterate over {}", result));
+                }
+            },
+        };
+
+        // the loop variable is only set in the loop, an existing variable is restored after it
+        let previous = self.vars.lock().unwrap().get(&iterator).cloned();
+        let mut values = Vec::new();
+        let mut failed = false;
+        for item in items {
+            self.vars.lock().unwrap().insert(iterator.clone(), item);
+            match self.eval(Some(body.clone())) {
+                Some(result @ crate::context::EvalResults::CommandExpressionResult(_)) => {
+                    self.print(&result, "text")
+                }
+                Some(result) => values.extend(
+                    result
+                        .to_expression()
+                        .filter(|value| *value != crate::expressions::Expressions::Null),
+                ),
+                None => {
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        let mut vars = self.vars.lock().unwrap();
+        match previous {
+            Some(previous) => vars.insert(iterator, previous),
+            None => vars.remove(&iterator),
+        };
+        drop(vars);
+
+        (!failed).then(|| {
+            crate::context::EvalResults::from_value(crate::expressions::Expressions::Array(values))
+        })
+    }
+
+    /// Evaluate `try` expressions: `try { ... } catch <Identifier> { ... } finally { ... }`
+    ///
+    /// a `try` is the value of its body, or of the `catch` branch when the body raises an error.
+    /// the error variable is only set in the `catch` branch. the `finally` branch always runs
+    /// and errors without a `catch` are raised again after it
+    fn eval_try(
+        &mut self,
+        body: crate::expressions::Expressions,
+        error: Option<String>,
+        catch_branch: Option<crate::expressions::Expressions>,
+        finally_branch: Option<crate::expressions::Expressions>,
+    ) -> Option<crate::context::EvalResults> {
+        self.error = None;
+        self.catching += 1;
+        let result = self.eval(Some(body));
+        self.catching -= 1;
+
+        let mut uncaught = None;
+        let result = match result {
+            Some(result) => Some(result),
+            None => {
+                // errors that were only printed are caught without their message
+                let raised = self
+                    .error
+                    .take()
+                    .unwrap_or_else(|| crate::errors::TurtleError::new("evaluation failed"));
+                match (catch_branch, error) {
+                    (Some(catch_branch), Some(name)) => {
+                        let previous = self.vars.lock().unwrap().get(&name).cloned();
+                        self.set_var(name.clone(), crate::expressions::Expressions::Error(raised));
+                        let result = self.eval(Some(catch_branch));
+                        let mut vars = self.vars.lock().unwrap();
+                        match previous {
+                            Some(previous) => vars.insert(name, previous),
+                            None => vars.remove(&name),
+                        };
+                        result
+                    }
+                    (Some(catch_branch), None) => self.eval(Some(catch_branch)),
+                    (None, _) => {
+                        uncaught = Some(raised);
+                        None
+                    }
+                }
+            }
+        };
+
+        if let Some(finally_branch) = finally_branch {
+            self.eval(Some(finally_branch))?;
+        }
+        match uncaught {
+            Some(error) => self.raise(error),
+            None => result,
+        }
+    }
+
+    /// Evaluate `throw <Expression>`, see `crate::errors::TurtleError::from_value` for the
+    /// error of a value, commands are thrown by their output
+    fn eval_throw(
+        &mut self,
+        value: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        let error = match self.eval(Some(value))? {
+            crate::context::EvalResults::CommandExpressionResult(cmd) => {
+                crate::errors::TurtleError::new(cmd.stdout.trim_end())
+            }
+            result => match result.to_expression() {
+                Some(value) => crate::errors::TurtleError::from_value(&value),
+                None => crate::errors::TurtleError::new(result.to_string()),
+            },
+        };
+        self.raise(error)
+    }
+
+    /// Evaluate the condition of an `if`, `&&` or `||`, like `set -e` commands there don't raise
+    /// errors in `errexit` mode
+    fn eval_condition(
+        &mut self,
+        condition: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        match condition {
+            crate::expressions::Expressions::ShellCommand { name, args } => {
+                self.eval_exec_command(&name, &args)
+            }
+            condition => self.eval(Some(condition)),
+        }
+    }
+
+    /// in `errexit` mode a command exiting with a non-zero status raises an error, its output is
+    /// printed first unless a `try` is catching the error
+    fn check_status(
+        &mut self,
+        command: &str,
+        result: crate::context::EvalResults,
+    ) -> Option<crate::context::EvalResults> {
+        match &result {
+            crate::context::EvalResults::CommandExpressionResult(cmd)
+                if cmd.code != 0 && self.execution().errexit.unwrap_or(false) =>
+            {
+                if self.catching == 0 {
+                    self.print(&result, "text");
+                }
+                let error =
+                    crate::errors::TurtleError::from_command(command, cmd.code, &cmd.stderr);
+                self.raise(error)
+            }
+            _ => Some(result),
+        }
+    }
+
+    /// Evaluate imports: `import "<path>" as <Identifier>` and
+    /// `from "<path>" import <Identifier>, ...`
+    ///
+    /// the namespace of the module is set as a variable, or the named entries of it, and is the
+    /// value of the import. see `crate::modules` for how files are found
+    fn eval_import(
+        &mut self,
+        path: &str,
+        names: &[String],
+        alias: Option<&str>,
+    ) -> Option<crate::context::EvalResults> {
+        let dir = match self.importing.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.to_path_buf(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        let turtle_path = self
+            .get_env(crate::modules::SEARCH_PATH)
+            .or_else(|| std::env::var(crate::modules::SEARCH_PATH).ok());
+        let search = crate::modules::search_path(
+            turtle_path.as_deref(),
+            &self.execution().modules.unwrap_or_default(),
+        );
+        let namespace = crate::modules::resolve(path, &dir, &search)
+            .map_err(|e| {
+                // an import that isn't found in a module is reported with the chain to it
+                if self.importing.is_empty() {
+                    return crate::errors::TurtleError::new(e);
+                }
+                let files = self.importing.iter().map(|entry| entry.as_path());
+                crate::errors::TurtleError::new(format!("{}: {}", crate::modules::chain(files), e))
+            })
+            .and_then(|file| match self.modules.get(&file) {
+                Some(namespace) => Ok(namespace.clone()),
+                None => self.eval_module(file),
+            });
+        let namespace = match namespace {
+            Ok(namespace) => namespace,
+            Err(e) => {
+                return self.raise(e);
+            }
+        };
+
+        if names.is_empty() {
+            self.set_var(crate::modules::alias(path, alias), namespace.clone());
+            return Some(crate::context::EvalResults::from_value(namespace));
+        }
+        match crate::modules::select(&namespace, names, path) {
+            Ok(entries) => {
+                for (name, value) in &entries {
+                    self.set_var(name.clone(), value.clone());
+                }
+                Some(crate::context::EvalResults::from_value(
+                    crate::expressions::Expressions::Object(entries),
+                ))
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Evaluate a module file with its own variables and cache its namespace
+    ///
+    /// the output of commands in the module is printed, an error in it is raised once by the
+    /// outermost import with the chain of files it came through, eg:
+    /// `a.tt -> b.tt: Variable 'x' not defined`
+    fn eval_module(
+        &mut self,
+        file: std::path::PathBuf,
+    ) -> Result<crate::expressions::Expressions, crate::errors::TurtleError> {
+        if let Some(e) = crate::modules::cycle(&self.importing, &file) {
+            return Err(crate::errors::TurtleError::new(e));
+        }
+        let code = std::fs::read_to_string(&file).map_err(|e| {
+            crate::errors::TurtleError::new(format!("import: {}: {}", file.display(), e))
+        })?;
+
+        let vars = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let args = self.args.clone().unwrap_or_else(|| {
+            std::sync::Arc::new(std::sync::Mutex::new(crate::config::Arguments::default()))
+        });
+        let mut interpreter = crate::lang::Interpreter::new(
+            Some(args),
+            self.env.clone(),
+            self.aliases.clone(),
+            vars.clone(),
+            self.builtins
+                .as_ref()
+                .map(|builtins| builtins.list())
+                .unwrap_or_default(),
+            self.debug,
+        );
+        interpreter.tokenize(&code);
+        let Some(expressions) = interpreter.interpret_script() else {
+            return Err(crate::errors::TurtleError::new(format!(
+                "import: {}: unmatched `}}`",
+                file.display()
+            )));
+        };
+
+        // the module sees only its own variables while it is evaluated
+        let outer = std::mem::replace(&mut self.vars, vars.clone());
+        self.importing.push(file.clone());
+        self.catching += 1;
+        let mut error = None;
+        for expr in expressions {
+            let nested = matches!(expr, crate::expressions::Expressions::Import { .. });
+            match self.eval(Some(expr)) {
+                Some(result @ crate::context::EvalResults::CommandExpressionResult(_)) => {
+                    self.print(&result, "text")
+                }
+                Some(_) => {}
+                None => {
+                    let mut e = self
+                        .error
+                        .take()
+                        .unwrap_or_else(|| crate::errors::TurtleError::new("failed"));
+                    // errors of nested imports already carry their chain
+                    if !nested {
+                        let files = self.importing.iter().map(|entry| entry.as_path());
+                        e.message = format!("{}: {}", crate::modules::chain(files), e.message);
+                    }
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        self.catching -= 1;
+        self.importing.pop();
+        self.vars = outer;
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        let namespace = crate::modules::namespace(&vars.lock().unwrap());
+        self.modules.insert(file, namespace.clone());
+        Ok(namespace)
+    }
+
+    /// Evaluate unary operations: `-<Expression>`, `~<Expression>` and `!<Expression>`
+    fn eval_unary_operation(
+        &mut self,
+        op: &str,
+        expr: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        let value = self.eval(Some(expr))?.to_expression()?;
+        match (op, &value) {
+            ("!", crate::expressions::Expressions::Boolean(b)) => {
+                Some(crate::context::EvalResults::BooleanExpressionResult(
+                    crate::context::BooleanEvalResult { value: !b },
+                ))
+            }
+            _ => {
+                match crate::numbers::unary(op, &value).or_else(|| crate::dates::unary(op, &value))
+                {
+                    Some(Ok(value)) => Some(crate::context::EvalResults::from_value(value)),
+                    Some(Err(e)) => self.fail(e),
+                    None => self.fail(format!("unsupported operation: {}{:?}", op, value)),
+                }
+            }
+        }
+    }
+
+    /// Evaluate destructuring assignments: `let [a, ..rest] = <Expression>`,
+    /// `let { host, port } = <Expression>`
+    ///
+    /// a value the pattern doesn't match is an error, the value of the assignment is an object
+    /// of the variables it sets, eg: `{a: 1, b: 2}` for `let [a, b] = [1, 2]`
+    fn eval_destructuring(
+        &mut self,
+        pattern: crate::destructure::Pattern,
+        value: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        let value = self.eval_value(value, "let")?;
+        let Some(bindings) = pattern.bind(&value) else {
+            return self.fail(format!(
+                "let: {} doesn't match {}",
+                pattern,
+                crate::format::display(&value)
+            ));
+        };
+        for (name, value) in &bindings {
+            self.set_var(name.clone(), value.clone());
+        }
+        Some(crate::context::EvalResults::from_value(
+            crate::expressions::Expressions::Object(bindings),
+        ))
+    }
+
+    /// evaluate an expression to a value, commands are the value of their output
+    fn eval_value(
+        &mut self,
+        expr: crate::expressions::Expressions,
+        label: &str,
+    ) -> Option<crate::expressions::Expressions> {
+        match self.eval(Some(expr))? {
+            crate::context::EvalResults::CommandExpressionResult(cmd) => Some(
+                crate::expressions::Expressions::String(cmd.stdout.trim_end().to_string()),
+            ),
+            result => match result.to_expression() {
+                Some(value) => Some(value),
+                None => {
+                    self.fail(format!("{}: expected a value, got {}", label, result));
+                    None
+                }
+            },
+        }
+    }
+
+    /// Evaluate assignment expressions: `<Identifier> = <Expression>`
+    fn eval_assignment(
+        &mut self,
+        name: String,
+        value: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        // Check if the variable name conflicts with a builtin
+        if let Some(ref builtins) = self.builtins
+            && builtins.list().contains(&name)
+        {
+            let warning = format!(
+                "Warning: Variable '{}' shadows builtin command. Use a different name.",
+                name
+            );
+            eprintln!("{}", warning);
+            return Some(crate::context::EvalResults::StringExpressionResult(
+                crate::context::StringEvalResult { value: warning },
+            ));
+        }
+
+        // Check if the variable name conflicts with a shell command
+        if crate::utils::is_command(&name) {
+            let warning = format!(
+                "Warning: Variable '{}' shadows shell command. Use a different name.",
+                name
+            );
+            eprintln!("{}", warning);
+            return Some(crate::context::EvalResults::StringExpressionResult(
+                crate::context::StringEvalResult { value: warning },
+            ));
+        }
+
+        let evaluated_value = self.eval(Some(value.clone()))?;
+
+        // store values rather than the expressions producing them, so
+        // commands aren't run again each time the variable is used
+        let value = evaluated_value.to_expression().unwrap_or(value);
+
+        // Store the variable in the context
+        self.vars
+            .lock()
+            .unwrap()
+            .insert(name.clone(), value.clone());
+
+        // Return an AssignmentResult
+        Some(crate::context::EvalResults::AssignmentExpressionResult(
+            crate::context::AssignmentEvalResult { name, value },
+        ))
+    }
+
+    /// Evaluate variable access: ```<Identifier>```
+    fn eval_variable_access(
+        &mut self,
+        name: &str,
+        _value: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        // get the variables values - this is an expression
+        let var = {
+            let vars = self.vars.lock().unwrap();
+            vars.get(name)?.clone()
+        };
+
+        let results = self.eval(Some(var.clone()))?;
+
+        Some(results)
+    }
+
+    /// Evaluate member access: `object.property` or `object[index]`
+    fn eval_member_access(
+        &mut self,
+        object: crate::expressions::Expressions,
+        property: &str,
+    ) -> Option<crate::context::EvalResults> {
+        let value = self
+            .eval(Some(object))?
+            .to_expression()
+            .and_then(|value| Self::member(value, property));
+
+        match value {
+            Some(value) => self.eval(Some(value)),
+            None => self.fail(format!("no member '{}'", property)),
+        }
+    }
+
+    /// the member of an object by key, an array by index, a path or an error, see
+    /// `crate::paths::member` and `crate::errors::TurtleError::member`
+    fn member(
+        value: crate::expressions::Expressions,
+        property: &str,
+    ) -> Option<crate::expressions::Expressions> {
+        match value {
+            crate::expressions::Expressions::Object(entries) => entries
+                .into_iter()
+                .find(|(key, _)| key == property)
+                .map(|(_, value)| value),
+            crate::expressions::Expressions::Array(values) => property
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| values.into_iter().nth(index)),
+            crate::expressions::Expressions::Path { segments } => {
+                crate::paths::member(&segments, property)
+            }
+            crate::expressions::Expressions::Error(error) => error.member(property),
+            _ => None,
+        }
+    }
+
+    /// Evaluate optional member access: ```<object>?.<property>```
+    ///
+    /// `null` objects and missing members evaluate to `null` instead of failing
+    fn eval_optional_member_access(
+        &mut self,
+        object: crate::expressions::Expressions,
+        property: &str,
+    ) -> Option<crate::context::EvalResults> {
+        let value = self
+            .eval(Some(object))?
+            .to_expression()
+            .and_then(|value| Self::member(value, property));
+        self.eval(Some(value.unwrap_or(crate::expressions::Expressions::Null)))
+    }
+
+    /// Evaluate a template string: `` `text ${expr}` ``
+    ///
+    /// each expression is evaluated in this context and written as `{}` shows it,
+    /// commands are replaced by their output
+    fn eval_template(
+        &mut self,
+        parts: Vec<crate::expressions::Expressions>,
+    ) -> Option<crate::context::EvalResults> {
+        let mut value = String::new();
+        for part in parts {
+            match self.eval(Some(part))? {
+                crate::context::EvalResults::CommandExpressionResult(cmd) => {
+                    value.push_str(cmd.stdout.trim_end())
+                }
+                result => match result.to_expression() {
+                    Some(part) => value.push_str(&crate::format::display(&part)),
+                    None => {
+                        return self.fail(format!("template: expected a value, got {}", result));
+                    }
+                },
+            }
+        }
+        Some(crate::context::EvalResults::StringExpressionResult(
+            crate::context::StringEvalResult { value },
+        ))
+    }
+
+    /// evaluate the arguments of a call, commands pass their output
+    fn eval_args(
+        &mut self,
+        func: &str,
+        args: impl IntoIterator<Item = crate::expressions::Expressions>,
+    ) -> Option<Vec<crate::expressions::Expressions>> {
+        let mut values = Vec::new();
+        for arg in args {
+            let value = match self.eval(Some(arg))? {
+                crate::context::EvalResults::CommandExpressionResult(cmd) => {
+                    crate::expressions::Expressions::String(cmd.stdout.trim_end().to_string())
+                }
+                result => match result.to_expression() {
+                    Some(value) => value,
+                    None => {
+                        self.fail(format!("{}: expected a value, got {}", func, result));
+                        return None;
+                    }
+                },
+            };
+            values.push(value);
+        }
+        Some(values)
+    }
+
+    /// call the function a value evaluates to, eg: `handlers[0](event)`
+    fn eval_call(
+        &mut self,
+        callee: crate::expressions::Expressions,
+        args: Vec<crate::expressions::Expressions>,
+    ) -> Option<crate::context::EvalResults> {
+        let function = match self.eval(Some(callee))?.to_expression() {
+            Some(crate::expressions::Expressions::Function(function)) => function,
+            Some(other) => {
+                return self.fail(format!(
+                    "`{}` isn't a function",
+                    crate::format::display(&other)
+                ));
+            }
+            None => return self.fail("only functions can be called"),
+        };
+        let values = self.eval_args("call", args)?;
+        self.call_function(&function, values)
+            .map(crate::context::EvalResults::from_value)
+    }
+
+    /// Evaluate a function call: ```<name>(<args>)```
+    ///
+    /// `format(template, args...)` formats its arguments with `crate::format`,
+    /// `{name}` placeholders read turtle variables, eg: `format("{:>8.2}", 3.14159)`
+    ///
+    /// `is_null(value)` is `true` for `null`, eg: `is_null(pod?.status)`
+    ///
+    /// `matches` and `replace` work with regexes, see `crate::patterns::call`, and `split`
+    /// splits on a regex literal or a string, see `crate::stdlib::call`
+    ///
+    /// `now`, `date`, `duration` and `to_timezone` work with datetimes, and `format` of a datetime
+    /// writes it with strftime, eg: `date(text).format("%Y/%m/%d")`, see `crate::dates::call`
+    ///
+    /// `path`, `ls`, `read`, `write` and `append` work with files, see `crate::paths::call`
+    ///
+    /// `http` makes clients and `get`, `post`, `put` and `delete` send requests with them, eg:
+    /// `http.get(url)`, see `crate::http::call`
+    ///
+    /// `map`, `filter`, `reduce`, `sort`, `any` and `all` call functions on arrays, eg:
+    /// `pods.filter(|p| p.ready)`, see `crate::functions::call`
+    ///
+    /// functions in variables are called by their name and functions in objects as methods,
+    /// eg: `double(2)`, `git.branch()`
+    fn eval_function_call(
+        &mut self,
+        func: &str,
+        args: Vec<crate::expressions::Expressions>,
+    ) -> Option<crate::context::EvalResults> {
+        // `strings.split(...)` calls `split` in the `strings` namespace, unless `strings` is a
+        // variable
+        let namespace = match args.first() {
+            Some(crate::expressions::Expressions::Identifier(name))
+                if crate::stdlib::is_namespace(name) && self.get_var(name).is_none() =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        };
+        let skip = namespace.iter().count();
+        let values = self.eval_args(func, args.into_iter().skip(skip))?;
+
+        if let Some(namespace) = namespace {
+            return match crate::stdlib::call_in(&namespace, func, &values) {
+                Ok(value) => Some(crate::context::EvalResults::from_value(value)),
+                Err(e) => self.fail(e),
+            };
+        }
+        if let Some(crate::expressions::Expressions::Function(function)) = self.get_var(func) {
+            return self
+                .call_function(&function, values)
+                .map(crate::context::EvalResults::from_value);
+        }
+        if let Some((crate::expressions::Expressions::Object(entries), args)) = values.split_first()
+            && let Some((_, crate::expressions::Expressions::Function(function))) =
+                entries.iter().find(|(key, _)| key == func)
+        {
+            let function = function.clone();
+            return self
+                .call_function(&function, args.to_vec())
+                .map(crate::context::EvalResults::from_value);
+        }
+
+        // errors of the functions applied are raised where they happen
+        let mut raised = false;
+        let result = crate::functions::call(func, &values, &mut |function, args| {
+            self.call_function(function, args).ok_or_else(|| {
+                raised = true;
+                String::new()
+            })
+        });
+        match result {
+            Some(Ok(value)) => return Some(crate::context::EvalResults::from_value(value)),
+            Some(Err(_)) if raised => return None,
+            Some(Err(e)) => return self.fail(e),
+            None => {}
+        }
+
+        if let Some(result) = crate::patterns::call(func, &values)
+            .or_else(|| crate::dates::call(func, &values))
+            .or_else(|| crate::paths::call(func, &values))
+            .or_else(|| crate::http::call(func, &values))
+            .or_else(|| crate::stdlib::call(func, &values))
+        {
+            return match result {
+                Ok(value) => Some(crate::context::EvalResults::from_value(value)),
+                Err(e) => self.fail(e),
+            };
+        }
+
+        match func {
+            "is_null" => match values.as_slice() {
+                [value] => Some(crate::context::EvalResults::BooleanExpressionResult(
+                    crate::context::BooleanEvalResult {
+                        value: *value == crate::expressions::Expressions::Null,
+                    },
+                )),
+                _ => self.fail("is_null: expected one value, eg: is_null(x)"),
+            },
+            "format" => {
+                let Some((crate::expressions::Expressions::String(template), values)) =
+                    values.split_first()
+                else {
+                    return self
+                        .fail("format: expected a format string, eg: format(\"{}\", value)");
+                };
+                let vars = self.vars.clone();
+                let named = |name: &str| vars.lock().unwrap().get(name).cloned();
+                match crate::format::format(template, values, &named) {
+                    Ok(value) => Some(crate::context::EvalResults::StringExpressionResult(
+                        crate::context::StringEvalResult { value },
+                    )),
+                    Err(e) => self.fail(format!("format: {}", e)),
+                }
+            }
+            _ => self.fail(format!("unknown function: {}", func)),
+        }
+    }
+
+    /// Evaluate function definitions: `fn <name>(<params>) { ... }`, `|<params>| <Expression>`
+    ///
+    /// a function captures the variables it uses from the scope it is defined in. named
+    /// functions are also assigned to their name, like `let`
+    fn eval_function_definition(
+        &mut self,
+        name: Option<String>,
+        params: Vec<String>,
+        body: crate::expressions::Expressions,
+    ) -> Option<crate::context::EvalResults> {
+        let scope = crate::functions::capture(&body, &self.vars.lock().unwrap());
+        let function = crate::expressions::Expressions::Function(crate::functions::Function {
+            name: name.clone(),
+            params,
+            body: Box::new(body),
+            scope,
+        });
+        match name {
+            Some(name) => {
+                self.set_var(name.clone(), function.clone());
+                Some(crate::context::EvalResults::AssignmentExpressionResult(
+                    crate::context::AssignmentEvalResult {
+                        name,
+                        value: function,
+                    },
+                ))
+            }
+            None => Some(crate::context::EvalResults::from_value(function)),
+        }
+    }
+
+    /// call a function with the values of its arguments
+    ///
+    /// the body sees the variables of its scope, itself by its name and its parameters, one
+    /// argument for each parameter. commands are the value of their output
+    fn call_function(
+        &mut self,
+        function: &crate::functions::Function,
+        args: Vec<crate::expressions::Expressions>,
+    ) -> Option<crate::expressions::Expressions> {
+        let label = function.name.as_deref().unwrap_or("fn");
+        if args.len() != function.params.len() {
+            self.fail(format!(
+                "{}: expected {} argument{}, got {}",
+                function,
+                function.params.len(),
+                if function.params.len() == 1 { "" } else { "s" },
+                args.len()
+            ));
+            return None;
+        }
+        let mut vars: std::collections::HashMap<String, crate::expressions::Expressions> =
+            function.scope.iter().cloned().collect();
+        if let Some(name) = &function.name {
+            vars.insert(
+                name.clone(),
+                crate::expressions::Expressions::Function(function.clone()),
+            );
+        }
+        for (param, arg) in function.params.iter().zip(args) {
+            vars.insert(param.clone(), arg);
+        }
+
+        let outer = std::mem::replace(
+            &mut self.vars,
+            std::sync::Arc::new(std::sync::Mutex::new(vars)),
+        );
+        let result = self.eval(Some((*function.body).clone()));
+        self.vars = outer;
+        match result? {
+            crate::context::EvalResults::CommandExpressionResult(cmd) => Some(
+                crate::expressions::Expressions::String(cmd.stdout.trim_end().to_string()),
+            ),
+            result => match result.to_expression() {
+                Some(value) => Some(value),
+                None => {
+                    self.fail(format!("{}: expected a value, got {}", label, result));
+                    None
+                }
+            },
+        }
+    }
+
+    /// Evaluate a pipeline stage over the value of its input: `<input> | <stage> <args>`
+    ///
+    /// command results are piped as their stdout, see `Context::get_stages`
+    fn eval_pipe(
+        &mut self,
+        input: crate::context::EvalResults,
+        stage: &str,
+        args: &str,
+    ) -> Option<crate::context::EvalResults> {
+        let Some(stage) = self.stages.iter().find(|s| s.name == stage) else {
+            return self.fail(format!("unknown pipeline stage: {}", stage));
+        };
+        let input = match input {
+            crate::context::EvalResults::CommandExpressionResult(cmd) => {
+                crate::expressions::Expressions::String(cmd.stdout)
+            }
+            input => match input.to_expression() {
+                Some(value) => value,
+                None => {
+                    return self.fail(format!("{}: expected a value, got {}", stage.name, input));
+                }
+            },
+        };
+        match (stage.execute)(input, args) {
+            Ok(result) => Some(result),
+            Err(e) => self.fail(format!("{}: {}", stage.name, e)),
+        }
+    }
 
-        // Check if the variable name conflicts with a shell command
-        if crate::utils::is_command(&name) {
-            let warning = format!(
-                "Warning: Variable '{}' shadows shell command. Use a different name.",
-                name
-            );
-            eprintln!("{}", warning);
-            return Some(crate::context::EvalResults::StringExpressionResult(
-                crate::context::StringEvalResult { value: warning },
-            ));
+    /// the items of a piped value, parsing text with `from auto`
+    fn items(
+        value: crate::expressions::Expressions,
+    ) -> Result<Vec<crate::expressions::Expressions>, String> {
+        match value {
+            crate::expressions::Expressions::Array(values) => Ok(values),
+            crate::expressions::Expressions::String(text) => {
+                match crate::expressions::Expressions::parse_as("auto", &text)? {
+                    crate::expressions::Expressions::Array(values) => Ok(values),
+                    value => Ok(vec![value]),
+                }
+            }
+            value => Ok(vec![value]),
         }
+    }
 
-        let _evaluated_value = self.eval(Some(value.clone()))?;
+    /// the value of a column, or a dotted path, eg: `size` or `.metadata.name`
+    ///
+    /// an empty path, or `.`, is the value itself
+    fn column<'a>(
+        value: &'a crate::expressions::Expressions,
+        path: &str,
+    ) -> Option<&'a crate::expressions::Expressions> {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(value, |value, segment| match value {
+                crate::expressions::Expressions::Object(entries) => entries
+                    .iter()
+                    .find(|(key, _)| key == segment)
+                    .map(|(_, value)| value),
+                crate::expressions::Expressions::Array(values) => {
+                    values.get(segment.parse::<usize>().ok()?)
+                }
+                _ => None,
+            })
+    }
 
-        // Store the variable in the context
-        self.vars
-            .lock()
-            .unwrap()
-            .insert(name.clone(), value.clone());
+    /// sort an array of values, or of objects by a column
+    fn sort_by(
+        values: Vec<crate::expressions::Expressions>,
+        args: &[&str],
+    ) -> Vec<crate::expressions::Expressions> {
+        let reverse = args.iter().any(|a| *a == "-r" || *a == "--reverse");
+        let column = args.iter().find(|a| !a.starts_with('-')).unwrap_or(&"");
+        let mut values = values;
+
+        // values missing the column sort last
+        values.sort_by(
+            |a, b| match (Self::column(a, column), Self::column(b, column)) {
+                (Some(a), Some(b)) => a.compare(b),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            },
+        );
+        if reverse {
+            values.reverse();
+        }
+        values
+    }
 
-        // Return an AssignmentResult
-        Some(crate::context::EvalResults::AssignmentExpressionResult(
-            crate::context::AssignmentEvalResult { name, value },
-        ))
+    /// keep the given columns of an object
+    fn select(
+        value: crate::expressions::Expressions,
+        columns: &[&str],
+    ) -> crate::expressions::Expressions {
+        match value {
+            crate::expressions::Expressions::Object(entries) => {
+                crate::expressions::Expressions::Object(
+                    columns
+                        .iter()
+                        .filter_map(|column| entries.iter().find(|(key, _)| key == column).cloned())
+                        .collect(),
+                )
+            }
+            value => value,
+        }
     }
 
-    /// Evaluate variable access: ```<Identifier>```
-    fn eval_variable_access(
-        &mut self,
-        name: &str,
-        value: Box<crate::expressions::Expressions>,
-    ) -> Option<crate::context::EvalResults> {
-        // get the variables values - this is an expression
-        let var = {
-            let vars = self.vars.lock().unwrap();
-            vars.get(name)?.clone()
+    /// keep values where `<column> <op> <value>` holds, eg: `size > 1000`
+    ///
+    /// supports `==`, `!=`, `>`, `>=`, `<` and `<=`, and `=~` and `!~` with a regex,
+    /// eg: `name =~ /\.rs$/`
+    fn filter(
+        values: Vec<crate::expressions::Expressions>,
+        condition: &str,
+    ) -> Result<Vec<crate::expressions::Expressions>, String> {
+        let (column, op, expected) = ["=~", "!~", ">=", "<=", "==", "!=", ">", "<"]
+            .iter()
+            .find_map(|op| {
+                condition
+                    .split_once(op)
+                    .map(|(column, value)| (column.trim(), *op, value.trim()))
+            })
+            .ok_or(format!(
+                "expected `<column> <op> <value>`, got `{}`",
+                condition
+            ))?;
+        let expected = match expected.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(text) => crate::expressions::Expressions::String(text.to_string()),
+            None => crate::patterns::parse_literal(expected)
+                .unwrap_or_else(|| crate::expressions::Expressions::infer(expected)),
+        };
+        let regex = match op {
+            "=~" | "!~" => Some(crate::patterns::regex(&expected)?),
+            _ => None,
         };
 
-        let results = self.eval(Some(var.clone()))?;
-
-        return Some(results);
+        Ok(values
+            .into_iter()
+            .filter(|value| {
+                let Some(actual) = Self::column(value, column) else {
+                    return false;
+                };
+                if let Some(regex) = &regex {
+                    return regex.is_match(&crate::format::display(actual)) == (op == "=~");
+                }
+                let ordering = actual.compare(&expected);
+                match op {
+                    "==" => ordering.is_eq(),
+                    "!=" => ordering.is_ne(),
+                    ">" => ordering.is_gt(),
+                    ">=" => ordering.is_ge(),
+                    "<" => ordering.is_lt(),
+                    _ => ordering.is_le(),
+                }
+            })
+            .collect())
     }
 
     fn _eval_binary_operation_deprecated(
@@ -731,150 +2149,79 @@ Examples:
                         return None;
                     }
                 };
-                return Some(crate::context::EvalResults::NumberExpressionResult(
+                Some(crate::context::EvalResults::NumberExpressionResult(
                     crate::context::NumberEvalResult { value: result },
-                ));
+                ))
             }
 
             // support adding strings for concatenation
             (
                 crate::context::EvalResults::StringExpressionResult(left_str),
                 crate::context::EvalResults::StringExpressionResult(right_str),
-            ) => {
-                if operation == "+" {
-                    let result = format!("{}{}", left_str.value, right_str.value);
-                    return Some(crate::context::EvalResults::StringExpressionResult(
-                        crate::context::StringEvalResult { value: result },
-                    ));
-                } else {
-                    return None;
-                }
-            }
-            _ => {
-                return None;
+            ) if operation == "+" => {
+                let result = format!("{}{}", left_str.value, right_str.value);
+                Some(crate::context::EvalResults::StringExpressionResult(
+                    crate::context::StringEvalResult { value: result },
+                ))
             }
+            _ => None,
         }
     }
 
+    /// Evaluate builtins: `<name> <args>`
+    ///
+    /// the value a builtin returns is its result, `null` when it printed its own output
     fn eval_builtin(&mut self, name: &str, args: &str) -> Option<crate::context::EvalResults> {
-        let env = self.env.clone();
-        let aliases = self.aliases.clone();
-        let vars = self.vars.clone();
-        let history = self.history.clone();
-
-        if let Some(ref builtins) = self.builtins {
-            let builtin_names = builtins.list();
-            let builtin = builtins.get(name)?;
-            let arg_vec: Vec<String> = args.split_whitespace().map(|s| s.to_string()).collect();
-
-            // check debugging config
-            let debug = {
-                if let Some(args) = &self.args {
-                    let args = args.lock().unwrap();
-                    args.is_debugging() || args.should_debug_context()
-                } else {
-                    false
-                }
-            };
-
-            let result = (builtin.execute)(
-                self.config.clone().unwrap(),
-                self.args.clone().unwrap(),
-                env,
-                aliases,
-                vars,
-                history,
-                builtin_names,
-                arg_vec,
-                debug,
-            );
-            return Some(crate::context::EvalResults::BuiltinExpressionResult(
-                crate::context::BuiltinEvalResult {
-                    output: Some(format!("{:?}", result)),
-                },
-            ));
-        }
-        None
-    }
-
-    fn eval_spawn_command(
-        &mut self,
-        command: &str,
-        args: &str,
-    ) -> Option<crate::context::EvalResults> {
-        use std::process::Command;
-        let args_vec: Vec<&str> = args.split_whitespace().collect();
-
-        // construct a command request
-        let id = uuid::Uuid::new_v4().to_string();
-        let command_request = crate::history::CommandRequest {
-            id: id.clone(),
-            command: command.to_string(),
-            args: args_vec.iter().map(|s| s.to_string()).collect(),
-            timestamp: crate::utils::now_unix(),
-            // event: "command_request".to_string(),
+        let builtins = self.builtins.as_ref()?;
+        builtins.get(name)?;
+
+        // check debugging config
+        let debug = {
+            if let Some(args) = &self.args {
+                let args = args.lock().unwrap();
+                args.is_debugging() || args.should_debug_context()
+            } else {
+                false
+            }
         };
 
-        let mut gaurd = self.history.lock().unwrap();
-
-        let events = gaurd.events.as_mut();
-
-        if let Some(events) = events {
-            events.push(crate::history::Event::CommandRequest(command_request));
-        }
-
-        // self.history
-        //     .lock()
-        //     .unwrap()
-        //     .events
-        //     .as_mut()
-        //     .push(crate::history::Event::CommandRequest(command_request));
-
-        let mut child = Command::new(command)
-            .args(&args_vec)
-            .stdin(std::process::Stdio::inherit())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-            .ok()?;
-
-        let mut stdout = String::new();
-        if let Some(mut out) = child.stdout.take() {
-            use std::io::Read;
-            let _ = out.read_to_string(&mut stdout).ok();
-        }
-
-        let mut stderr = String::new();
-        if let Some(mut err) = child.stderr.take() {
-            use std::io::Read;
-            let _ = err.read_to_string(&mut stderr).ok();
-        }
-
-        let code = child.wait().ok()?.code().unwrap_or(-1);
-        let output = stdout;
-        let errors = stderr;
-
-        let command_response = crate::history::CommandResponse {
-            id: id.clone(),
-            status: "completed".to_string(),
-            code,
-            output: output.clone(),
-            errors: errors.clone(),
-            timestamp: crate::utils::now_unix(),
+        let context = crate::builtins::BuiltinContext {
+            config: self.config.clone().unwrap_or_default(),
+            args: self.args.clone().unwrap_or_default(),
+            env: self.env.clone(),
+            aliases: self.aliases.clone(),
+            vars: self.vars.clone(),
+            history: self.history.clone(),
+            builtins,
+            debug,
         };
-
-        let mut gaurd = self.history.lock().unwrap();
-        if let Some(event) = gaurd.events.as_mut() {
-            event.push(crate::history::Event::CommandResponse(command_response));
+        match builtins.exec(name, &context, args) {
+            Ok(crate::expressions::Expressions::Null) => {
+                Some(crate::context::EvalResults::BuiltinExpressionResult(
+                    crate::context::BuiltinEvalResult { output: None },
+                ))
+            }
+            Ok(value) => Some(crate::context::EvalResults::from_value(value)),
+            Err(e) => self.raise(e),
         }
+    }
 
-        Some(crate::context::EvalResults::CommandExpressionResult(
-            crate::context::CommandEvalResult {
-                stdout: output,
-                stderr: errors,
-                code,
-            },
-        ))
+    /// execution settings from the current config
+    fn execution(&self) -> crate::config::ExecutionConfig {
+        self.config
+            .as_ref()
+            .map(|config| config.lock().unwrap().execution())
+            .unwrap_or_else(|| crate::config::Config::default().execution())
+    }
+
+    /// environment variables for executed commands, from `execution.env` and `execution.path`
+    fn execution_env(
+        &self,
+        execution: &crate::config::ExecutionConfig,
+    ) -> std::collections::HashMap<String, String> {
+        let mut env = std::collections::HashMap::new();
+        execution.apply(&mut env);
+        env
     }
 
     fn eval_exec_command(
@@ -898,7 +2245,7 @@ Examples:
         //     history.add(crate::history::Event::CommandRequest(command_request));
         // }
 
-        if let Some(gaurd) = self.history.lock().ok() {
+        if let Ok(gaurd) = self.history.lock() {
             let mut history = gaurd;
 
             history.add(crate::history::Event::CommandRequest(
@@ -910,10 +2257,21 @@ Examples:
         //     events.push(crate::history::Event::CommandRequest(command_request));
         // }
 
+        let execution = self.execution();
+        let started = std::time::Instant::now();
         let exec_result = Command::new(command)
             .args(&args_vec)
+            .envs(self.execution_env(&execution))
             .stdin(std::process::Stdio::inherit())
-            .output();
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .and_then(|child| {
+                crate::utils::wait_with_timeout(
+                    child,
+                    execution.timeout.map(std::time::Duration::from_secs),
+                )
+            });
 
         match exec_result {
             Ok(output) => {
@@ -932,6 +2290,7 @@ Examples:
                     output: stdout.to_string(),
                     errors: stderr.to_string(),
                     timestamp: crate::utils::now_unix(),
+                    duration: Some(started.elapsed().as_millis() as u64),
                 };
 
                 // self.history.add
@@ -939,20 +2298,14 @@ Examples:
                 self.history
                     .lock()
                     .unwrap()
-                    .events
-                    .as_mut()
-                    .unwrap()
-                    .push(crate::history::Event::CommandResponse(command_response));
+                    .add(crate::history::Event::CommandResponse(command_response));
 
                 // write to history
 
                 // returns the command result
                 Some(crate::context::EvalResults::CommandExpressionResult(result))
             }
-            Err(e) => {
-                eprintln!("failed to execute command: {}", e);
-                None
-            }
+            Err(e) => self.fail(format!("failed to execute command: {}", e)),
         }
     }
 
@@ -973,6 +2326,7 @@ Examples:
         }
         Context {
             builtins: None,
+            stages: Vec::new(),
             config,
             args,
             env,
@@ -981,10 +2335,70 @@ Examples:
             history,
             functions: std::collections::HashMap::new(),
             code: Vec::new(),
+            error: None,
+            catching: 0,
+            modules: std::collections::HashMap::new(),
+            importing: Vec::new(),
             debug,
         }
     }
 
+    /// output format from the current config, eg: `table` or `json`
+    pub fn format(&self) -> String {
+        self.execution()
+            .format
+            .unwrap_or(crate::config::DEFAULT_FORMAT.to_string())
+    }
+
+    /// print an evaluated result in the given format
+    ///
+    /// unknown formats fall back to text, and command stderr is written to
+    /// stderr unless the format includes it in the document
+    pub fn print(&self, result: &crate::context::EvalResults, format: &str) {
+        let format = if crate::config::FORMATS.contains(&format) {
+            format
+        } else {
+            eprintln!(
+                "unknown format `{}`, expected one of: {}",
+                format,
+                crate::config::FORMATS.join(", ")
+            );
+            "text"
+        };
+
+        if let Some(crate::expressions::Outputs::Table(table)) =
+            crate::expressions::Outputs::from_result(format, result)
+        {
+            let terminal = crate::terminal::TerminalManager::new();
+            let (cols, _) = terminal.get_size();
+            let theme = self
+                .config
+                .as_ref()
+                .filter(|_| crate::style::ThemeManager::applies(format))
+                .and_then(|config| config.lock().unwrap().theme.clone())
+                .and_then(|name| {
+                    crate::style::ThemeManager::from(crate::style::DEFAULT_THEMES)
+                        .themes
+                        .remove(&name)
+                });
+            terminal.page(&table.render(cols as usize, theme.as_ref()).join("\n"));
+        } else if let Some(output) = crate::expressions::Outputs::from_result(format, result) {
+            let output = output.to_string();
+            if output.ends_with('\n') {
+                print!("{}", output);
+            } else if !output.is_empty() {
+                println!("{}", output);
+            }
+        }
+
+        if let crate::context::EvalResults::CommandExpressionResult(cmd) = result
+            && matches!(format, "table" | "text")
+            && !cmd.stderr.is_empty()
+        {
+            eprint!("{}", cmd.stderr);
+        }
+    }
+
     pub fn eval(
         &mut self,
         expr: Option<crate::expressions::Expressions>,
@@ -1000,24 +2414,51 @@ Examples:
                 self.eval_assignment(name, value.as_ref().clone())
             }
 
+            Some(crate::expressions::Expressions::Destructuring { pattern, value }) => {
+                self.eval_destructuring(pattern, *value)
+            }
+
             // experimental variable access
             Some(crate::expressions::Expressions::TurtleVariable { name, value }) => {
-                self.eval_variable_access(&name, value)
+                self.eval_variable_access(&name, *value)
             }
 
             Some(crate::expressions::Expressions::BinaryOperation { left, op, right }) => {
-                let result = self.eval_binary_operation(*left, op, *right);
-
-                if let Some(crate::context::EvalResults::NumberExpressionResult(n)) = &result {
-                    println!("{}", n.value);
-                }
-                result
+                self.eval_binary_operation(*left, op, *right)
             }
             Some(crate::expressions::Expressions::Number(value)) => {
                 Some(crate::context::EvalResults::NumberExpressionResult(
                     crate::context::NumberEvalResult { value },
                 ))
             }
+            Some(crate::expressions::Expressions::Integer(value)) => {
+                Some(crate::context::EvalResults::IntegerExpressionResult(
+                    crate::context::IntegerEvalResult { value },
+                ))
+            }
+            Some(crate::expressions::Expressions::Null) => {
+                Some(crate::context::EvalResults::NullExpressionResult)
+            }
+            Some(
+                value @ (crate::expressions::Expressions::DateTime(_)
+                | crate::expressions::Expressions::Duration(_)
+                | crate::expressions::Expressions::Path { .. }
+                | crate::expressions::Expressions::HttpClient(_)
+                | crate::expressions::Expressions::Error(_)
+                | crate::expressions::Expressions::Function(_)),
+            ) => Some(crate::context::EvalResults::from_value(value)),
+            Some(crate::expressions::Expressions::RegularExpression { pattern, flags }) => {
+                // report invalid patterns where they are written
+                if let Err(e) = crate::patterns::compile(&pattern, flags.as_deref()) {
+                    return self.fail(e);
+                }
+                Some(crate::context::EvalResults::from_value(
+                    crate::expressions::Expressions::RegularExpression { pattern, flags },
+                ))
+            }
+            Some(crate::expressions::Expressions::UnaryOperation { op, expr }) => {
+                self.eval_unary_operation(&op, *expr)
+            }
             Some(crate::expressions::Expressions::String(value)) => {
                 Some(crate::context::EvalResults::StringExpressionResult(
                     crate::context::StringEvalResult { value },
@@ -1032,22 +2473,8 @@ Examples:
                 let evaluated_values: Vec<crate::expressions::Expressions> = values
                     .into_iter()
                     .filter_map(|v| {
-                        self.eval(Some(v.clone())).and_then(|res| match res {
-                            crate::context::EvalResults::NumberExpressionResult(n) => {
-                                Some(crate::expressions::Expressions::Number(n.value))
-                            }
-                            crate::context::EvalResults::StringExpressionResult(s) => {
-                                Some(crate::expressions::Expressions::String(s.value))
-                            }
-                            crate::context::EvalResults::BooleanExpressionResult(b) => {
-                                Some(crate::expressions::Expressions::Boolean(b.value))
-                            }
-                            // // allow objects
-                            // crate::context::EvalResults::ObjectExpressionResult(o) => {
-                            //     Some(crate::expressions::Expressions::Object(o.value))
-                            // }
-                            _ => None,
-                        })
+                        self.eval(Some(v.clone()))
+                            .and_then(|res| res.to_expression())
                     })
                     .collect();
 
@@ -1084,14 +2511,16 @@ Examples:
                     }
 
                     result
-                } else {
-                    // Variable not found - return an error message instead of None
-                    eprintln!("Error: Variable '{}' not defined", name);
-                    Some(crate::context::EvalResults::StringExpressionResult(
-                        crate::context::StringEvalResult {
-                            value: format!("Error: Variable '{}' not defined", name),
-                        },
+                } else if name == crate::http::CLIENT {
+                    // the default client, eg: `http.get(url)`
+                    Some(crate::context::EvalResults::from_value(
+                        crate::expressions::Expressions::HttpClient(crate::http::Client::default()),
                     ))
+                } else if self.execution().lenient.unwrap_or(false) {
+                    // lenient mode reads undefined variables as `null`
+                    Some(crate::context::EvalResults::NullExpressionResult)
+                } else {
+                    self.fail(format!("Variable '{}' not defined", name))
                 }
 
                 // let vars = self.vars.lock().unwrap();
@@ -1112,22 +2541,95 @@ Examples:
             }
 
             Some(crate::expressions::Expressions::Builtin { name, args }) => {
-                let result = self.eval_builtin(&name, &args);
-                result
+                self.eval_builtin(&name, &args)
             }
 
-            Some(crate::expressions::Expressions::ShellCommand { name, args }) => {
-                let result = self.eval_exec_command(&name, &args);
-                // result is an option, we need to unwrap it to access the code
-                // the result is in the CommandResult variant of ShellResults
-                if let Some(crate::context::EvalResults::CommandExpressionResult(cmd)) = &result {
-                    if cmd.code != 0 {
-                        eprintln!("{}", cmd.stderr);
-                    } else {
-                        print!("{}", cmd.stdout);
+            Some(crate::expressions::Expressions::Object(entries)) => {
+                let mut value = Vec::new();
+                for (key, entry) in entries {
+                    if let Some(entry) = self.eval(Some(entry)).and_then(|res| res.to_expression())
+                    {
+                        value.push((key, entry));
                     }
                 }
-                result
+                Some(crate::context::EvalResults::ObjectExpressionResult(
+                    crate::context::ObjectEvalResult { value },
+                ))
+            }
+
+            Some(crate::expressions::Expressions::Grouping { expr }) => self.eval(Some(*expr)),
+
+            Some(crate::expressions::Expressions::CodeBlock { expressions }) => {
+                self.eval_block(expressions)
+            }
+
+            Some(crate::expressions::Expressions::If {
+                condition,
+                then_branch,
+                else_branch,
+            }) => self.eval_if(*condition, *then_branch, else_branch.map(|e| *e)),
+
+            Some(crate::expressions::Expressions::Match { value, arms }) => {
+                self.eval_match(*value, arms)
+            }
+
+            Some(crate::expressions::Expressions::For {
+                iterator,
+                iterable,
+                body,
+            }) => self.eval_for(iterator, *iterable, *body),
+
+            Some(crate::expressions::Expressions::Try {
+                body,
+                error,
+                catch_branch,
+                finally_branch,
+            }) => self.eval_try(
+                *body,
+                error,
+                catch_branch.map(|e| *e),
+                finally_branch.map(|e| *e),
+            ),
+
+            Some(crate::expressions::Expressions::Throw { value }) => self.eval_throw(*value),
+
+            Some(crate::expressions::Expressions::Import { path, names, alias }) => {
+                self.eval_import(&path, &names, alias.as_deref())
+            }
+
+            Some(crate::expressions::Expressions::MemberAccess { object, property }) => {
+                self.eval_member_access(*object, &property)
+            }
+
+            Some(crate::expressions::Expressions::OptionalMemberAccess { object, property }) => {
+                self.eval_optional_member_access(*object, &property)
+            }
+
+            Some(crate::expressions::Expressions::Pipe { input, stage, args }) => {
+                let input = self.eval(Some(*input))?;
+                self.eval_pipe(input, &stage, &args)
+            }
+
+            Some(crate::expressions::Expressions::TemplateString { parts }) => {
+                self.eval_template(parts)
+            }
+
+            Some(crate::expressions::Expressions::FunctionCall { func, args }) => {
+                self.eval_function_call(&func, args)
+            }
+
+            Some(crate::expressions::Expressions::Call { callee, args }) => {
+                self.eval_call(*callee, args)
+            }
+
+            Some(crate::expressions::Expressions::FunctionDefinition { name, params, body }) => {
+                self.eval_function_definition(name, params, *body)
+            }
+
+            Some(crate::expressions::Expressions::ShellCommand { name, args }) => {
+                // output is printed by the caller, see `Context::print`
+                let result = self.eval_exec_command(&name, &args)?;
+                self.check_status(format!("{} {}", name, args).trim(), result)
             }
             _ => {
                 println!("evaluating expression: {:?}", expr);
@@ -1146,6 +2648,7 @@ impl std::fmt::Debug for Context {
             .field("history", &self.history)
             .field("functions", &self.functions)
             .field("code", &self.code)
+            .field("modules", &self.modules)
             .finish()
     }
 }
@@ -1168,6 +2671,41 @@ pub struct NumberEvalResult {
     pub value: f64,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegerEvalResult {
+    pub value: crate::numbers::Integer,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DateTimeEvalResult {
+    pub value: crate::dates::DateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DurationEvalResult {
+    pub value: crate::dates::Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathEvalResult {
+    pub segments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpClientEvalResult {
+    pub value: crate::http::Client,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorEvalResult {
+    pub value: crate::errors::TurtleError,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionEvalResult {
+    pub value: crate::functions::Function,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StringEvalResult {
     pub value: String,
@@ -1180,7 +2718,7 @@ pub struct BooleanEvalResult {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ObjectEvalResult {
-    pub value: std::collections::HashMap<String, crate::expressions::Expressions>,
+    pub value: Vec<(String, crate::expressions::Expressions)>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -1212,6 +2750,14 @@ pub enum EvalResults {
     CommandExpressionResult(CommandEvalResult),
     BuiltinExpressionResult(BuiltinEvalResult),
     NumberExpressionResult(NumberEvalResult),
+    IntegerExpressionResult(IntegerEvalResult),
+    NullExpressionResult,
+    DateTimeExpressionResult(DateTimeEvalResult),
+    DurationExpressionResult(DurationEvalResult),
+    PathExpressionResult(PathEvalResult),
+    HttpClientExpressionResult(HttpClientEvalResult),
+    ErrorExpressionResult(ErrorEvalResult),
+    FunctionExpressionResult(FunctionEvalResult),
     StringExpressionResult(StringEvalResult),
     BooleanExpressionResult(BooleanEvalResult),
     ObjectExpressionResult(ObjectEvalResult),
@@ -1230,6 +2776,18 @@ impl std::fmt::Display for EvalResults {
                 cmd.code, cmd.stdout, cmd.stderr
             ),
             EvalResults::NumberExpressionResult(num) => write!(f, "{}", num.value),
+            EvalResults::IntegerExpressionResult(int) => write!(f, "{}", int.value),
+            EvalResults::NullExpressionResult => write!(f, "null"),
+            EvalResults::DateTimeExpressionResult(datetime) => {
+                write!(f, "{}", crate::dates::display(&datetime.value))
+            }
+            EvalResults::DurationExpressionResult(duration) => write!(f, "{}", duration.value),
+            EvalResults::PathExpressionResult(path) => {
+                write!(f, "{}", crate::paths::display(&path.segments))
+            }
+            EvalResults::HttpClientExpressionResult(client) => write!(f, "{}", client.value),
+            EvalResults::ErrorExpressionResult(error) => write!(f, "{}", error.value),
+            EvalResults::FunctionExpressionResult(function) => write!(f, "{}", function.value),
             EvalResults::StringExpressionResult(string) => {
                 write!(f, "{}", string.value)
             }
@@ -1271,6 +2829,172 @@ impl std::fmt::Display for EvalResults {
 }
 
 impl EvalResults {
+    /// convert the result to json
+    ///
+    /// command results become `{code, stdout, stderr}` objects
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            EvalResults::CommandExpressionResult(cmd) => serde_json::json!({
+                "code": cmd.code,
+                "stdout": cmd.stdout,
+                "stderr": cmd.stderr,
+            }),
+            EvalResults::BuiltinExpressionResult(builtin) => serde_json::json!(builtin.output),
+            EvalResults::NumberExpressionResult(num) => {
+                crate::expressions::Expressions::Number(num.value).to_json()
+            }
+            EvalResults::IntegerExpressionResult(int) => {
+                crate::expressions::Expressions::Integer(int.value.clone()).to_json()
+            }
+            EvalResults::NullExpressionResult => serde_json::Value::Null,
+            EvalResults::DateTimeExpressionResult(_)
+            | EvalResults::DurationExpressionResult(_)
+            | EvalResults::PathExpressionResult(_)
+            | EvalResults::HttpClientExpressionResult(_)
+            | EvalResults::FunctionExpressionResult(_) => serde_json::json!(self.to_string()),
+            EvalResults::ErrorExpressionResult(error) => error.value.to_json(),
+            EvalResults::StringExpressionResult(string) => serde_json::json!(string.value),
+            EvalResults::BooleanExpressionResult(boolean) => serde_json::json!(boolean.value),
+            EvalResults::ObjectExpressionResult(obj) => serde_json::Value::Object(
+                obj.value
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+            EvalResults::ArrayExpressionResult(arr) => {
+                serde_json::Value::Array(arr.value.iter().map(|v| v.to_json()).collect())
+            }
+            EvalResults::AssignmentExpressionResult(assign) => {
+                serde_json::json!({ assign.name.clone(): assign.value.to_json() })
+            }
+            EvalResults::EnvironmentVariableExpressionResult(env) => serde_json::json!(env.value),
+            EvalResults::TurtleVariableExpressionResult(var) => var.value.to_json(),
+        }
+    }
+
+    /// convert a value result back into an expression
+    ///
+    /// commands are objects of their `code`, `stdout` and `stderr`, the same as their json,
+    /// returns None for results that aren't values, eg: builtins
+    pub fn to_expression(&self) -> Option<crate::expressions::Expressions> {
+        match self {
+            EvalResults::CommandExpressionResult(cmd) => {
+                Some(crate::expressions::Expressions::Object(vec![
+                    (
+                        "code".to_string(),
+                        crate::expressions::Expressions::Integer(i64::from(cmd.code).into()),
+                    ),
+                    (
+                        "stdout".to_string(),
+                        crate::expressions::Expressions::String(cmd.stdout.clone()),
+                    ),
+                    (
+                        "stderr".to_string(),
+                        crate::expressions::Expressions::String(cmd.stderr.clone()),
+                    ),
+                ]))
+            }
+            EvalResults::NumberExpressionResult(num) => {
+                Some(crate::expressions::Expressions::Number(num.value))
+            }
+            EvalResults::IntegerExpressionResult(int) => {
+                Some(crate::expressions::Expressions::Integer(int.value.clone()))
+            }
+            EvalResults::NullExpressionResult => Some(crate::expressions::Expressions::Null),
+            EvalResults::DateTimeExpressionResult(datetime) => {
+                Some(crate::expressions::Expressions::DateTime(datetime.value))
+            }
+            EvalResults::DurationExpressionResult(duration) => {
+                Some(crate::expressions::Expressions::Duration(duration.value))
+            }
+            EvalResults::PathExpressionResult(path) => {
+                Some(crate::expressions::Expressions::Path {
+                    segments: path.segments.clone(),
+                })
+            }
+            EvalResults::HttpClientExpressionResult(client) => Some(
+                crate::expressions::Expressions::HttpClient(client.value.clone()),
+            ),
+            EvalResults::ErrorExpressionResult(error) => {
+                Some(crate::expressions::Expressions::Error(error.value.clone()))
+            }
+            EvalResults::FunctionExpressionResult(function) => Some(
+                crate::expressions::Expressions::Function(function.value.clone()),
+            ),
+            EvalResults::StringExpressionResult(string) => Some(
+                crate::expressions::Expressions::String(string.value.clone()),
+            ),
+            EvalResults::BooleanExpressionResult(boolean) => {
+                Some(crate::expressions::Expressions::Boolean(boolean.value))
+            }
+            EvalResults::ArrayExpressionResult(arr) => {
+                Some(crate::expressions::Expressions::Array(arr.value.clone()))
+            }
+            EvalResults::ObjectExpressionResult(obj) => {
+                Some(crate::expressions::Expressions::Object(obj.value.clone()))
+            }
+            EvalResults::TurtleVariableExpressionResult(var) => Some(var.value.clone()),
+            _ => None,
+        }
+    }
+
+    /// the result of a value expression
+    ///
+    /// non-value expressions are kept as turtle variable results
+    pub fn from_value(value: crate::expressions::Expressions) -> Self {
+        match value {
+            crate::expressions::Expressions::Number(value) => {
+                EvalResults::NumberExpressionResult(NumberEvalResult { value })
+            }
+            crate::expressions::Expressions::Integer(value) => {
+                EvalResults::IntegerExpressionResult(IntegerEvalResult { value })
+            }
+            crate::expressions::Expressions::Null => EvalResults::NullExpressionResult,
+            crate::expressions::Expressions::DateTime(value) => {
+                EvalResults::DateTimeExpressionResult(DateTimeEvalResult { value })
+            }
+            crate::expressions::Expressions::Duration(value) => {
+                EvalResults::DurationExpressionResult(DurationEvalResult { value })
+            }
+            crate::expressions::Expressions::Path { segments } => {
+                EvalResults::PathExpressionResult(PathEvalResult { segments })
+            }
+            crate::expressions::Expressions::HttpClient(value) => {
+                EvalResults::HttpClientExpressionResult(HttpClientEvalResult { value })
+            }
+            crate::expressions::Expressions::Error(value) => {
+                EvalResults::ErrorExpressionResult(ErrorEvalResult { value })
+            }
+            crate::expressions::Expressions::Function(value) => {
+                EvalResults::FunctionExpressionResult(FunctionEvalResult { value })
+            }
+            crate::expressions::Expressions::String(value) => {
+                EvalResults::StringExpressionResult(StringEvalResult { value })
+            }
+            crate::expressions::Expressions::Boolean(value) => {
+                EvalResults::BooleanExpressionResult(BooleanEvalResult { value })
+            }
+            crate::expressions::Expressions::Array(value) => {
+                EvalResults::ArrayExpressionResult(ArrayEvalResult { value })
+            }
+            crate::expressions::Expressions::Object(value) => {
+                EvalResults::ObjectExpressionResult(ObjectEvalResult { value })
+            }
+            value => EvalResults::TurtleVariableExpressionResult(TurtleVariableEvalResult {
+                name: String::new(),
+                value,
+            }),
+        }
+    }
+
+    /// exit code of the result, non-command results succeed
+    pub fn code(&self) -> i32 {
+        match self {
+            EvalResults::CommandExpressionResult(cmd) => cmd.code,
+            _ => 0,
+        }
+    }
+
     pub fn from_shell_command_result(stdout: String, stderr: String, code: i32) -> Self {
         EvalResults::CommandExpressionResult(CommandEvalResult {
             stdout,
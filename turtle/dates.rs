@@ -0,0 +1,553 @@
+/// Datetimes and durations: `now()`, `date()`, duration literals, date arithmetic, timezones and
+/// strftime formatting
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// datetimes keep the offset they were read or converted with, eg: `2025-01-01T09:30:00+02:00`,
+/// times read without an offset are local. durations are written as a number and unit per part,
+/// eg: `1h30m`, `500ms`, `-2d`.
+use crate::expressions::Expressions;
+use serde::{Deserialize, Serialize};
+
+/// a point in time with its offset from UTC
+pub type DateTime = chrono::DateTime<chrono::FixedOffset>;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// duration units and their length in nanoseconds, in the order durations are written
+const UNITS: &[(&str, i128)] = &[
+    ("w", 7 * 86_400 * NANOS_PER_SECOND),
+    ("d", 86_400 * NANOS_PER_SECOND),
+    ("h", 3_600 * NANOS_PER_SECOND),
+    ("m", 60 * NANOS_PER_SECOND),
+    ("s", NANOS_PER_SECOND),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// formats tried by `parse` for datetimes with an offset
+const OFFSET_FORMATS: &[&str] = &[
+    // iso 8601 without seconds, eg: `2025-01-01T09:30Z`
+    "%Y-%m-%dT%H:%M%#z",
+    "%Y-%m-%d %H:%M:%S%.f%#z",
+    "%Y-%m-%d %H:%M:%S%.f %#z",
+    "%Y-%m-%d %H:%M%#z",
+    // common log format, eg: apache and nginx access logs
+    "%d/%b/%Y:%H:%M:%S %z",
+];
+
+/// formats tried by `parse` for local datetimes
+const LOCAL_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+];
+
+/// a length of time, negative when it runs backwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(pub chrono::TimeDelta);
+
+impl Duration {
+    /// read a duration literal, eg: `5m`, `2h30m`, `1_500ms`, `-1d`
+    ///
+    /// units are `w`, `d`, `h`, `m`, `s`, `ms`, `us` and `ns`
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        if text.is_empty() {
+            return None;
+        }
+        let mut total: i128 = 0;
+        let mut chars = text.chars().peekable();
+        while chars.peek().is_some() {
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(|d| d.is_ascii_digit() || *d == '_') {
+                digits.push(d);
+            }
+            let mut unit = String::new();
+            while let Some(u) = chars.next_if(|u| u.is_ascii_alphabetic()) {
+                unit.push(u);
+            }
+            let (_, nanos) = UNITS.iter().find(|(name, _)| *name == unit)?;
+            let count: i128 = digits.replace('_', "").parse().ok()?;
+            total = total.checked_add(count.checked_mul(*nanos)?)?;
+        }
+        Self::from_nanos(if negative { -total } else { total })
+    }
+
+    /// a duration of whole and fractional seconds, eg: `duration(1.5)`
+    pub fn from_seconds(seconds: f64) -> Option<Self> {
+        let nanos = (seconds * NANOS_PER_SECOND as f64).round();
+        nanos
+            .is_finite()
+            .then(|| Self::from_nanos(nanos as i128))
+            .flatten()
+    }
+
+    /// `None` past the range of `chrono::TimeDelta`, about 292 million years
+    pub fn from_nanos(nanos: i128) -> Option<Self> {
+        let seconds = i64::try_from(nanos.div_euclid(NANOS_PER_SECOND)).ok()?;
+        let subsec = nanos.rem_euclid(NANOS_PER_SECOND) as u32;
+        chrono::TimeDelta::new(seconds, subsec).map(Duration)
+    }
+
+    pub fn to_nanos(&self) -> i128 {
+        self.0.num_seconds() as i128 * NANOS_PER_SECOND + self.0.subsec_nanos() as i128
+    }
+}
+
+/// written as its literal, largest unit first and without weeks, eg: `1d2h`, `1m30s`, `0s`
+impl std::fmt::Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let nanos = self.to_nanos();
+        if nanos == 0 {
+            return write!(f, "0s");
+        }
+        if nanos < 0 {
+            write!(f, "-")?;
+        }
+        let mut rest = nanos.unsigned_abs();
+        for (unit, length) in UNITS.iter().skip(1) {
+            let count = rest / *length as u128;
+            if count > 0 {
+                write!(f, "{}{}", count, unit)?;
+                rest %= *length as u128;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Duration {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Duration::parse(&text)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid duration `{}`", text)))
+    }
+}
+
+/// the current local time
+pub fn now() -> DateTime {
+    chrono::Local::now().fixed_offset()
+}
+
+/// read a datetime written in a common format
+///
+/// - rfc 3339 and iso 8601, seconds and offset are optional, eg: `2025-01-01T09:30Z`,
+///   `2025-01-01 09:30:00.250+02:00`, `2025-01-01`
+/// - rfc 2822, eg: email and http headers, `Wed, 01 Jan 2025 09:30:00 +0000`
+/// - common log format, eg: apache and nginx, `01/Jan/2025:09:30:00 +0000`
+/// - syslog, eg: `Jan  1 09:30:00`, in the current year
+/// - unix timestamps in seconds, eg: `1735723800`
+pub fn parse(text: &str) -> Option<DateTime> {
+    let text = text.trim();
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(datetime);
+    }
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc2822(text) {
+        return Some(datetime);
+    }
+    for format in OFFSET_FORMATS {
+        if let Ok(datetime) = chrono::DateTime::parse_from_str(text, format) {
+            return Some(datetime);
+        }
+    }
+    for format in LOCAL_FORMATS {
+        if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(text, format) {
+            return local(naive);
+        }
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return local(date.and_time(chrono::NaiveTime::MIN));
+    }
+    // syslog leaves out the year
+    let year = chrono::Datelike::year(&now());
+    if let Ok(naive) =
+        chrono::NaiveDateTime::parse_from_str(&format!("{} {}", year, text), "%Y %b %e %H:%M:%S")
+    {
+        return local(naive);
+    }
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return from_timestamp(text.parse().ok()?);
+    }
+    None
+}
+
+/// read a datetime with a strftime format, eg: `%d.%m.%Y %H:%M`
+///
+/// formats without an offset are read as local time, formats without a time as midnight
+pub fn parse_with(text: &str, format: &str) -> Result<DateTime, String> {
+    let error = |e: chrono::ParseError| format!("can't read `{}` as `{}`: {}", text, format, e);
+    if let Ok(datetime) = chrono::DateTime::parse_from_str(text, format) {
+        return Ok(datetime);
+    }
+    let naive = match chrono::NaiveDateTime::parse_from_str(text, format) {
+        Ok(naive) => naive,
+        Err(_) => chrono::NaiveDate::parse_from_str(text, format)
+            .map_err(error)?
+            .and_time(chrono::NaiveTime::MIN),
+    };
+    local(naive).ok_or_else(|| format!("`{}` doesn't exist in the local timezone", text))
+}
+
+/// the local datetime of a unix timestamp in seconds
+pub fn from_timestamp(seconds: f64) -> Option<DateTime> {
+    let nanos = Duration::from_seconds(seconds)?;
+    let utc = chrono::DateTime::UNIX_EPOCH.checked_add_signed(nanos.0)?;
+    Some(utc.with_timezone(&chrono::Local).fixed_offset())
+}
+
+/// a naive datetime in the local timezone, the earlier one when a clock change repeats it
+fn local(naive: chrono::NaiveDateTime) -> Option<DateTime> {
+    naive
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .map(|datetime| datetime.fixed_offset())
+}
+
+/// convert a datetime to another timezone
+///
+/// `zone` is `UTC`, `local`, an iana name, eg: `America/New_York`, or an offset, eg: `+05:30`
+pub fn to_timezone(datetime: &DateTime, zone: &str) -> Result<DateTime, String> {
+    match zone {
+        "UTC" | "utc" | "Z" => Ok(datetime.with_timezone(&chrono::Utc).fixed_offset()),
+        "local" | "Local" => Ok(datetime.with_timezone(&chrono::Local).fixed_offset()),
+        _ => {
+            if let Ok(tz) = zone.parse::<chrono_tz::Tz>() {
+                return Ok(datetime.with_timezone(&tz).fixed_offset());
+            }
+            match zone.parse::<chrono::FixedOffset>() {
+                Ok(offset) => Ok(datetime.with_timezone(&offset)),
+                Err(_) => Err(format!(
+                    "unknown timezone `{}`, eg: UTC, local, Europe/Paris or +05:30",
+                    zone
+                )),
+            }
+        }
+    }
+}
+
+/// write a datetime the way `{}` shows it, rfc 3339 with `Z` for UTC
+pub fn display(datetime: &DateTime) -> String {
+    datetime.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+}
+
+/// format a datetime with a strftime pattern, eg: `{:%Y-%m-%d}` in a format string
+///
+/// text is read as a datetime, eg: the arguments of `printf`, see `parse`
+pub fn strftime(value: &Expressions, pattern: &str) -> Result<String, String> {
+    let datetime = match value {
+        Expressions::DateTime(datetime) => Some(*datetime),
+        Expressions::String(text) => parse(text),
+        _ => None,
+    };
+    let Some(datetime) = datetime else {
+        return Err(format!(
+            "`{}` isn't a datetime, `{}` formats datetimes",
+            crate::format::display(value),
+            pattern
+        ));
+    };
+    let items = chrono::format::StrftimeItems::new(pattern)
+        .parse()
+        .map_err(|_| format!("invalid datetime format `{}`", pattern))?;
+    Ok(datetime.format_with_items(items.iter()).to_string())
+}
+
+/// apply a binary operator to datetimes and durations
+///
+/// - datetime `+` or `-` duration is a datetime
+/// - datetime `-` datetime is the duration between them
+/// - durations add and subtract, multiply and divide by numbers and divide into each other
+///
+/// returns `None` when neither side is a datetime or duration
+pub fn binary(
+    left: &Expressions,
+    op: &str,
+    right: &Expressions,
+) -> Option<Result<Expressions, String>> {
+    let overflow = || format!("out of range: {} {} {}", show(left), op, show(right));
+    let result = match (left, op, right) {
+        (Expressions::DateTime(at), "+", Expressions::Duration(d))
+        | (Expressions::Duration(d), "+", Expressions::DateTime(at)) => at
+            .checked_add_signed(d.0)
+            .map(Expressions::DateTime)
+            .ok_or_else(overflow),
+        (Expressions::DateTime(at), "-", Expressions::Duration(d)) => at
+            .checked_sub_signed(d.0)
+            .map(Expressions::DateTime)
+            .ok_or_else(overflow),
+        (Expressions::DateTime(a), "-", Expressions::DateTime(b)) => {
+            Ok(Expressions::Duration(Duration(a.signed_duration_since(b))))
+        }
+        (Expressions::Duration(a), "+" | "-", Expressions::Duration(b)) => {
+            let b = if op == "+" {
+                b.to_nanos()
+            } else {
+                -b.to_nanos()
+            };
+            Duration::from_nanos(a.to_nanos() + b)
+                .map(Expressions::Duration)
+                .ok_or_else(overflow)
+        }
+        (Expressions::Duration(_), "/", Expressions::Duration(b)) if b.to_nanos() == 0 => {
+            Err("division by zero".to_string())
+        }
+        (Expressions::Duration(a), "/", Expressions::Duration(b)) => Ok(Expressions::Number(
+            a.to_nanos() as f64 / b.to_nanos() as f64,
+        )),
+        (Expressions::Duration(d), "*", n) | (n, "*", Expressions::Duration(d))
+            if number(n).is_some() =>
+        {
+            let n = number(n).unwrap_or_default();
+            Duration::from_nanos((d.to_nanos() as f64 * n).round() as i128)
+                .map(Expressions::Duration)
+                .ok_or_else(overflow)
+        }
+        (Expressions::Duration(d), "/", n) if number(n).is_some() => {
+            match number(n).unwrap_or_default() {
+                0.0 => Err("division by zero".to_string()),
+                n => Duration::from_nanos((d.to_nanos() as f64 / n).round() as i128)
+                    .map(Expressions::Duration)
+                    .ok_or_else(overflow),
+            }
+        }
+        (Expressions::DateTime(_) | Expressions::Duration(_), op, _)
+        | (_, op, Expressions::DateTime(_) | Expressions::Duration(_))
+            if matches!(op, "+" | "-" | "*" | "/" | "//" | "%" | "**") =>
+        {
+            Err(format!(
+                "unsupported operation: {} {} {}",
+                show(left),
+                op,
+                show(right)
+            ))
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// negate a duration, eg: `-5m`
+///
+/// returns `None` when the value isn't a duration
+pub fn unary(op: &str, value: &Expressions) -> Option<Result<Expressions, String>> {
+    let Expressions::Duration(d) = value else {
+        return None;
+    };
+    Some(match op {
+        "+" => Ok(value.clone()),
+        "-" => Duration::from_nanos(-d.to_nanos())
+            .map(Expressions::Duration)
+            .ok_or_else(|| format!("duration out of range: -{}", d)),
+        op => Err(format!("unsupported operation for durations: {}", op)),
+    })
+}
+
+/// call a datetime function, `now`, `date`, `duration`, `to_timezone` or `format` of a datetime
+///
+/// returns `None` for other functions, so the caller can try them
+///
+/// - `now()` is the current local time
+/// - `date(text)` reads a datetime, see `parse`, `date(text, format)` reads it with a strftime
+///   format and `date(seconds)` is the local time of a unix timestamp
+/// - `duration(text)` reads a duration literal, `duration(seconds)` is a number of seconds
+/// - `to_timezone(datetime, zone)` converts a datetime, see `to_timezone`
+/// - `format(datetime, pattern)` writes a datetime with strftime, eg: `now().format("%Y/%m/%d")`
+pub fn call(func: &str, args: &[Expressions]) -> Option<Result<Expressions, String>> {
+    let result = match (func, args) {
+        ("now", []) => Ok(Expressions::DateTime(now())),
+        ("date", [Expressions::DateTime(datetime)]) => Ok(Expressions::DateTime(*datetime)),
+        ("date", [Expressions::String(text)]) => parse(text)
+            .map(Expressions::DateTime)
+            .ok_or_else(|| format!("date: can't read `{}` as a datetime", text)),
+        ("date", [Expressions::String(text), Expressions::String(format)]) => {
+            parse_with(text, format)
+                .map(Expressions::DateTime)
+                .map_err(|e| format!("date: {}", e))
+        }
+        ("date", [seconds]) if number(seconds).is_some() => {
+            from_timestamp(number(seconds).unwrap_or_default())
+                .map(Expressions::DateTime)
+                .ok_or_else(|| format!("date: timestamp out of range: {}", show(seconds)))
+        }
+        ("duration", [Expressions::Duration(d)]) => Ok(Expressions::Duration(*d)),
+        ("duration", [Expressions::String(text)]) => Duration::parse(text.trim())
+            .map(Expressions::Duration)
+            .ok_or_else(|| format!("duration: can't read `{}` as a duration, eg: 1h30m", text)),
+        ("duration", [seconds]) if number(seconds).is_some() => {
+            Duration::from_seconds(number(seconds).unwrap_or_default())
+                .map(Expressions::Duration)
+                .ok_or_else(|| format!("duration: out of range: {}", show(seconds)))
+        }
+        ("to_timezone", [Expressions::DateTime(datetime), Expressions::String(zone)]) => {
+            to_timezone(datetime, zone)
+                .map(Expressions::DateTime)
+                .map_err(|e| format!("to_timezone: {}", e))
+        }
+        (
+            "format",
+            [
+                datetime @ Expressions::DateTime(_),
+                Expressions::String(pattern),
+            ],
+        ) => strftime(datetime, pattern)
+            .map(Expressions::String)
+            .map_err(|e| format!("format: {}", e)),
+        ("now", _) => Err("now: expected no arguments".to_string()),
+        ("date", _) => Err("date: expected text, text and a format, or seconds".to_string()),
+        ("duration", _) => Err("duration: expected text or seconds".to_string()),
+        ("to_timezone", _) => Err("to_timezone: expected a datetime and a timezone".to_string()),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// a number as a float, for scaling durations and reading timestamps
+fn number(value: &Expressions) -> Option<f64> {
+    match value {
+        Expressions::Number(n) => Some(*n),
+        Expressions::Integer(n) => Some(n.to_f64()),
+        _ => None,
+    }
+}
+
+fn show(value: &Expressions) -> String {
+    crate::format::display(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime {
+        chrono::DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    fn d(text: &str) -> Duration {
+        Duration::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_duration_literals() {
+        assert_eq!(d("2h30m").0, chrono::TimeDelta::minutes(150));
+        assert_eq!(d("1_500ms").0, chrono::TimeDelta::milliseconds(1500));
+        assert_eq!(d("1w").0, chrono::TimeDelta::days(7));
+        assert_eq!(d("-5m").0, chrono::TimeDelta::minutes(-5));
+        assert_eq!(Duration::parse("5"), None);
+        assert_eq!(Duration::parse("5min"), None);
+        assert_eq!(Duration::parse("h"), None);
+        assert_eq!(d("90m").to_string(), "1h30m");
+        assert_eq!(d("1w1s500ms").to_string(), "7d1s500ms");
+        assert_eq!(d("-1h").to_string(), "-1h");
+        assert_eq!(d("0s").to_string(), "0s");
+        assert_eq!(Duration::from_seconds(1.5), Some(d("1s500ms")));
+    }
+
+    #[test]
+    fn test_parse_datetimes() {
+        let expected = at("2025-01-01T09:30:00Z");
+        for text in [
+            "2025-01-01T09:30Z",
+            "2025-01-01T09:30:00Z",
+            "2025-01-01T11:30:00+02:00",
+            "2025-01-01 09:30:00 +0000",
+            "Wed, 01 Jan 2025 09:30:00 +0000",
+            "01/Jan/2025:04:30:00 -0500",
+            "1735723800",
+        ] {
+            assert_eq!(parse(text), Some(expected), "{}", text);
+        }
+        assert_eq!(
+            parse("2025-01-01T09:30:00.25Z").unwrap().timestamp_millis() % 1000,
+            250
+        );
+        assert!(parse("2025-01-01").is_some());
+        assert!(parse("Jan  1 09:30:00").is_some());
+        assert!(parse("yesterday").is_none());
+        assert_eq!(
+            parse_with("01.01.2025 09:30 +0000", "%d.%m.%Y %H:%M %z"),
+            Ok(expected)
+        );
+        assert!(parse_with("01.01.2025", "%Y").is_err());
+    }
+
+    #[test]
+    fn test_datetime_arithmetic() {
+        let start = Expressions::DateTime(at("2025-01-01T09:30:00Z"));
+        let length = Expressions::Duration(d("2h30m"));
+        let end = Expressions::DateTime(at("2025-01-01T12:00:00Z"));
+        assert_eq!(binary(&start, "+", &length), Some(Ok(end.clone())));
+        assert_eq!(binary(&length, "+", &start), Some(Ok(end.clone())));
+        assert_eq!(binary(&end, "-", &length), Some(Ok(start.clone())));
+        assert_eq!(binary(&end, "-", &start), Some(Ok(length.clone())));
+        assert_eq!(
+            binary(&length, "*", &Expressions::Integer(2.into())),
+            Some(Ok(Expressions::Duration(d("5h"))))
+        );
+        assert_eq!(
+            binary(&length, "/", &Expressions::Duration(d("30m"))),
+            Some(Ok(Expressions::Number(5.0)))
+        );
+        assert!(matches!(binary(&start, "+", &end), Some(Err(_))));
+        assert_eq!(
+            binary(
+                &Expressions::Integer(1.into()),
+                "+",
+                &Expressions::Integer(2.into())
+            ),
+            None
+        );
+        assert_eq!(
+            unary("-", &length),
+            Some(Ok(Expressions::Duration(d("-2h30m"))))
+        );
+    }
+
+    #[test]
+    fn test_timezones_and_strftime() {
+        let datetime = at("2025-07-01T12:00:00Z");
+        assert_eq!(
+            to_timezone(&datetime, "America/New_York").map(|dt| display(&dt)),
+            Ok("2025-07-01T08:00:00-04:00".to_string())
+        );
+        assert_eq!(
+            to_timezone(&datetime, "+05:30").map(|dt| display(&dt)),
+            Ok("2025-07-01T17:30:00+05:30".to_string())
+        );
+        assert!(to_timezone(&datetime, "Mars/Olympus").is_err());
+        assert_eq!(display(&datetime), "2025-07-01T12:00:00Z");
+
+        let value = Expressions::DateTime(datetime);
+        assert_eq!(
+            strftime(&value, "%Y-%m-%d %H:%M"),
+            Ok("2025-07-01 12:00".to_string())
+        );
+        assert!(strftime(&value, "%Q").is_err());
+        assert_eq!(
+            call(
+                "format",
+                &[value.clone(), Expressions::String("%Y/%m/%d".to_string())]
+            ),
+            Some(Ok(Expressions::String("2025/07/01".to_string())))
+        );
+        assert!(strftime(&Expressions::Integer(1.into()), "%Y").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+/// Destructuring: the patterns of `match` arms, eg: `[first, ..rest] => first`, and of `let`,
+/// eg: `let { host, port } = cfg`
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// a pattern either matches a value and binds names to its parts, or doesn't match. setting
+/// the bound variables and evaluating guards and bodies is left to `Context`
+use crate::expressions::Expressions;
+use serde::{Deserialize, Serialize};
+
+/// a pattern, see the module docs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    /// `_` matches any value
+    Wildcard,
+    /// a name matches any value and binds it, eg: `n`
+    Binding(String),
+    /// a literal matches an equal value, numbers compare numerically, eg: `0`, `"Running"`
+    Literal(Box<Expressions>),
+    /// the first of the alternatives that matches, eg: `"x" | "y"`
+    Or(Vec<Pattern>),
+    /// an array of as many items, or at least as many with a rest, eg: `[a, b]`,
+    /// `[first, ..rest]`. the rest is an array of the remaining items, `..` alone ignores them
+    Array {
+        items: Vec<Pattern>,
+        rest: Option<String>,
+    },
+    /// an object with the fields, other fields are ignored. a field without a pattern binds
+    /// its name, eg: `{ name, age }`, `{ status: "Running", name: n }`
+    Object { fields: Vec<(String, Pattern)> },
+}
+
+/// an arm of a `match`: `<pattern> if <guard> => <body>`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expressions>,
+    pub body: Expressions,
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |patterns: &mut dyn Iterator<Item = String>, separator: &str| {
+            patterns.collect::<Vec<_>>().join(separator)
+        };
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Binding(name) => write!(f, "{}", name),
+            Pattern::Literal(value) => write!(f, "{}", value.to_json()),
+            Pattern::Or(alternatives) => write!(
+                f,
+                "{}",
+                join(&mut alternatives.iter().map(|p| p.to_string()), " | ")
+            ),
+            Pattern::Array { items, rest } => {
+                let rest = rest.as_ref().map(|rest| match rest.as_str() {
+                    "_" => "..".to_string(),
+                    rest => format!("..{}", rest),
+                });
+                let items = &mut items.iter().map(|p| p.to_string()).chain(rest);
+                write!(f, "[{}]", join(items, ", "))
+            }
+            Pattern::Object { fields } => {
+                let fields = &mut fields.iter().map(|(key, pattern)| match pattern {
+                    Pattern::Binding(name) if name == key => key.clone(),
+                    pattern => format!("{}: {}", key, pattern),
+                });
+                write!(f, "{{ {} }}", join(fields, ", "))
+            }
+        }
+    }
+}
+
+impl Pattern {
+    /// the variables a value binds when the pattern matches it, in the order of the pattern
+    ///
+    /// `None` when it doesn't match
+    pub fn bind(&self, value: &Expressions) -> Option<Vec<(String, Expressions)>> {
+        let mut bindings = Vec::new();
+        self.bind_into(value, &mut bindings).then_some(bindings)
+    }
+
+    /// see `bind`, bindings of a failed match are left behind
+    fn bind_into(&self, value: &Expressions, bindings: &mut Vec<(String, Expressions)>) -> bool {
+        match (self, value) {
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Binding(name), value) => {
+                bindings.push((name.clone(), value.clone()));
+                true
+            }
+            (Pattern::Literal(literal), value) => match (literal.as_ref(), value) {
+                (
+                    Expressions::Integer(_) | Expressions::Number(_),
+                    Expressions::Integer(_) | Expressions::Number(_),
+                ) => literal.compare(value) == std::cmp::Ordering::Equal,
+                (literal, value) => literal == value,
+            },
+            (Pattern::Or(alternatives), value) => alternatives.iter().any(|alternative| {
+                let bound = bindings.len();
+                let matched = alternative.bind_into(value, bindings);
+                if !matched {
+                    bindings.truncate(bound);
+                }
+                matched
+            }),
+            (Pattern::Array { items, rest }, Expressions::Array(values)) => {
+                let fits = match rest {
+                    Some(_) => values.len() >= items.len(),
+                    None => values.len() == items.len(),
+                };
+                fits && items
+                    .iter()
+                    .zip(values)
+                    .all(|(item, value)| item.bind_into(value, bindings))
+                    && match rest {
+                        Some(rest) if rest != "_" => {
+                            let rest_values = values[items.len()..].to_vec();
+                            bindings.push((rest.clone(), Expressions::Array(rest_values)));
+                            true
+                        }
+                        _ => true,
+                    }
+            }
+            (Pattern::Object { fields }, Expressions::Object(entries)) => {
+                fields.iter().all(|(key, pattern)| {
+                    entries
+                        .iter()
+                        .find(|(name, _)| name == key)
+                        .is_some_and(|(_, value)| pattern.bind_into(value, bindings))
+                })
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn i(value: i64) -> Expressions {
+        Expressions::Integer(value.into())
+    }
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    fn lit(value: Expressions) -> Pattern {
+        Pattern::Literal(Box::new(value))
+    }
+
+    fn bind(name: &str) -> Pattern {
+        Pattern::Binding(name.to_string())
+    }
+
+    #[test]
+    fn test_literals_and_alternatives() {
+        assert_eq!(lit(i(0)).bind(&i(0)), Some(vec![]));
+        assert_eq!(lit(i(1)).bind(&Expressions::Number(1.0)), Some(vec![]));
+        assert_eq!(lit(i(1)).bind(&s("1")), None);
+        let either = Pattern::Or(vec![lit(s("x")), lit(s("y"))]);
+        assert_eq!(either.bind(&s("y")), Some(vec![]));
+        assert_eq!(either.bind(&s("z")), None);
+        assert_eq!(either.to_string(), r#""x" | "y""#);
+        assert_eq!(Pattern::Wildcard.bind(&Expressions::Null), Some(vec![]));
+    }
+
+    #[test]
+    fn test_arrays_and_objects() {
+        let pattern = Pattern::Array {
+            items: vec![bind("first")],
+            rest: Some("rest".to_string()),
+        };
+        assert_eq!(
+            pattern.bind(&Expressions::Array(vec![i(1), i(2), i(3)])),
+            Some(vec![
+                ("first".to_string(), i(1)),
+                ("rest".to_string(), Expressions::Array(vec![i(2), i(3)])),
+            ])
+        );
+        assert_eq!(pattern.bind(&Expressions::Array(vec![])), None);
+        assert_eq!(pattern.to_string(), "[first, ..rest]");
+
+        let pair = Pattern::Array {
+            items: vec![bind("a"), bind("b")],
+            rest: None,
+        };
+        assert_eq!(pair.bind(&Expressions::Array(vec![i(1)])), None);
+        assert_eq!(pair.bind(&s("ab")), None);
+
+        let pattern = Pattern::Object {
+            fields: vec![
+                ("name".to_string(), bind("name")),
+                ("status".to_string(), lit(s("Running"))),
+            ],
+        };
+        let pod = |status: &str| {
+            Expressions::Object(vec![
+                ("name".to_string(), s("api")),
+                ("status".to_string(), s(status)),
+                ("age".to_string(), i(3)),
+            ])
+        };
+        assert_eq!(
+            pattern.bind(&pod("Running")),
+            Some(vec![("name".to_string(), s("api"))])
+        );
+        assert_eq!(pattern.bind(&pod("Pending")), None);
+        assert_eq!(pattern.to_string(), r#"{ name, status: "Running" }"#);
+    }
+}
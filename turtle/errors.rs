@@ -0,0 +1,161 @@
+/// Errors: raised with `throw`, by failed evaluation and, in `errexit` mode, by failing commands,
+/// and caught with `try { ... } catch e { ... }`
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// an error is a value, its members are `message`, and the `code` and `stderr` of a failed
+/// command, which are `null` for other errors
+use crate::expressions::Expressions;
+use serde::{Deserialize, Serialize};
+
+/// a turtle error, see the module docs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurtleError {
+    pub message: String,
+    /// exit status of a failed command
+    pub code: Option<i32>,
+    /// stderr of a failed command
+    pub stderr: Option<String>,
+}
+
+impl std::fmt::Display for TurtleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl TurtleError {
+    /// an error with a message
+    pub fn new(message: impl Into<String>) -> Self {
+        TurtleError {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    /// the error of a command that exited with a non-zero status
+    pub fn from_command(command: &str, code: i32, stderr: &str) -> Self {
+        TurtleError {
+            message: format!("`{}` exited with status {}", command, code),
+            code: Some(code),
+            stderr: Some(stderr.to_string()),
+        }
+    }
+
+    /// the error thrown by `throw value`
+    ///
+    /// errors are rethrown as they are, objects give the `message`, `code` and `stderr` of the
+    /// error and other values are the message, eg: `throw "no pods"`
+    pub fn from_value(value: &Expressions) -> Self {
+        match value {
+            Expressions::Error(error) => error.clone(),
+            Expressions::Object(entries) => {
+                let field = |name: &str| entries.iter().find(|(key, _)| key == name);
+                TurtleError {
+                    message: match field("message") {
+                        Some((_, message)) => crate::format::display(message),
+                        None => crate::format::display(value),
+                    },
+                    code: match field("code") {
+                        Some((_, Expressions::Integer(crate::numbers::Integer::Small(code)))) => {
+                            i32::try_from(*code).ok()
+                        }
+                        _ => None,
+                    },
+                    stderr: match field("stderr") {
+                        Some((_, Expressions::String(stderr))) => Some(stderr.clone()),
+                        _ => None,
+                    },
+                }
+            }
+            value => TurtleError::new(crate::format::display(value)),
+        }
+    }
+
+    /// the member of an error, `None` for unknown members
+    pub fn member(&self, property: &str) -> Option<Expressions> {
+        Some(match property {
+            "message" => Expressions::String(self.message.clone()),
+            "code" => match self.code {
+                Some(code) => Expressions::Integer((code as i64).into()),
+                None => Expressions::Null,
+            },
+            "stderr" => match &self.stderr {
+                Some(stderr) => Expressions::String(stderr.clone()),
+                None => Expressions::Null,
+            },
+            _ => return None,
+        })
+    }
+
+    /// the error as an object of its members
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "message": self.message,
+            "code": self.code,
+            "stderr": self.stderr,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    #[test]
+    fn test_error_values() {
+        let error = TurtleError::from_command("kubectl get pods", 1, "no context\n");
+        assert_eq!(
+            error.member("message"),
+            Some(s("`kubectl get pods` exited with status 1"))
+        );
+        assert_eq!(error.member("code"), Some(Expressions::Integer(1.into())));
+        assert_eq!(error.member("stderr"), Some(s("no context\n")));
+        assert_eq!(error.member("status"), None);
+
+        let error = TurtleError::new("no pods");
+        assert_eq!(error.member("code"), Some(Expressions::Null));
+        assert_eq!(
+            error.to_json(),
+            serde_json::json!({"message": "no pods", "code": null, "stderr": null})
+        );
+    }
+
+    #[test]
+    fn test_thrown_values() {
+        assert_eq!(
+            TurtleError::from_value(&s("no pods")),
+            TurtleError::new("no pods")
+        );
+        assert_eq!(
+            TurtleError::from_value(&Expressions::Integer(42.into())),
+            TurtleError::new("42")
+        );
+        assert_eq!(
+            TurtleError::from_value(&Expressions::Object(vec![
+                ("message".to_string(), s("denied")),
+                ("code".to_string(), Expressions::Integer(403.into())),
+            ])),
+            TurtleError {
+                message: "denied".to_string(),
+                code: Some(403),
+                stderr: None,
+            }
+        );
+        let error = TurtleError::new("again");
+        assert_eq!(
+            TurtleError::from_value(&Expressions::Error(error.clone())),
+            error
+        );
+    }
+}
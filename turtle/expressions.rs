@@ -3,15 +3,27 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub enum Expressions {
-    /// A number. ex: `1`, `2`, `3`, ...
+    /// A floating point number. ex: `1.5`, `2.0`, `1e3`, ...
     Number(f64),
 
+    /// An exact integer. ex: `1`, `0xff`, `1_000_000`, `2 ** 64`, ...
+    Integer(crate::numbers::Integer),
+
     /// A string. eg: `"hello"`, `'world'`, ...
     String(String),
 
     /// A boolean. eg: `True`, `False`
     Boolean(bool),
 
+    /// The absence of a value. eg: `null`
+    Null,
+
+    /// A point in time with its offset from UTC. eg: `now()`, `date("2025-01-01T00:00Z")`
+    DateTime(crate::dates::DateTime),
+
+    /// A length of time. eg: `5m`, `2h30m`, `500ms`
+    Duration(crate::dates::Duration),
+
     /// A list of expressions. eg: `[1, 2, 3]`
     Array(Vec<Expressions>),
 
@@ -25,11 +37,22 @@ pub enum Expressions {
         object: Box<Expressions>,
         property: String,
     },
+    /// An optional object access expression, `null` when the object is `null` or has no such
+    /// property. eg: `obj?.property`
+    OptionalMemberAccess {
+        object: Box<Expressions>,
+        property: String,
+    },
     /// An assignment expression. eg: `let var = value`
     Assignment {
         name: String,
         value: Box<Expressions>,
     },
+    /// A destructuring assignment. eg: `let [a, b] = pair`, `let { host, port } = cfg`
+    Destructuring {
+        pattern: crate::destructure::Pattern,
+        value: Box<Expressions>,
+    },
 
     /// An identifier. eg: `some_var`
     Identifier(String),
@@ -47,6 +70,12 @@ pub enum Expressions {
         then_branch: Box<Expressions>,
         else_branch: Option<Box<Expressions>>,
     },
+    /// Match Control Flow, the value of the first arm that matches - eg:
+    /// `match value { 0 => "none", "x" | "y" => "letter", n if n > 10 => "many", _ => "some" }`
+    Match {
+        value: Box<Expressions>,
+        arms: Vec<crate::destructure::MatchArm>,
+    },
     /// While Loop Control Flow - eg: `while cond { ... }`
     While {
         condition: Box<Expressions>,
@@ -58,6 +87,17 @@ pub enum Expressions {
         iterable: Box<Expressions>,
         body: Box<Expressions>,
     },
+    /// Error handling - eg: `try { ... } catch e { ... } finally { ... }`
+    Try {
+        body: Box<Expressions>,
+        error: Option<String>,
+        catch_branch: Option<Box<Expressions>>,
+        finally_branch: Option<Box<Expressions>>,
+    },
+    /// Raise an error - eg: `throw "no pods"`
+    Throw { value: Box<Expressions> },
+    /// A template string, the parts are evaluated and joined. eg: `` `hello ${name}` ``
+    TemplateString { parts: Vec<Expressions> },
     /// Regular Expression - eg: `/pattern/`
     RegularExpression {
         pattern: String,
@@ -65,17 +105,22 @@ pub enum Expressions {
     },
     /// A loop expression. eg: `loop { ... }`
     Loop { body: Box<Expressions> },
-    /// fn <name>(<params>) { ... }
+    /// A function, named or anonymous, eg: `fn <name>(<params>) { ... }`, `|x| x * 2`
     FunctionDefinition {
-        name: String,
+        name: Option<String>,
         params: Vec<String>,
-        body: Box<Vec<Expressions>>,
+        body: Box<Expressions>,
     },
     /// A call to a user defined function. eg: `func(args, ...)`
     FunctionCall {
         func: String,
         args: Vec<Expressions>,
     },
+    /// A call to the function a value evaluates to, eg: `handlers[0](event)`, `(|x| x * 2)(21)`
+    Call {
+        callee: Box<Expressions>,
+        args: Vec<Expressions>,
+    },
 
     /// An expression grouping
     /// eg: `(expr)`
@@ -93,28 +138,276 @@ pub enum Expressions {
     },
     /// A shell command execution. eg: ls -la, echo "hello", ...
     ShellCommand { name: String, args: String },
-    /// A shell directory path. eg: ./path/to/dir, ../parent/dir, /absolute/path
+    /// A file or directory path, kept as written. eg: `./src/main.rs`, `../dir`, `/etc`, `~/notes`
     Path { segments: Vec<String> },
+    /// An http client, the options of its requests. eg: `http`, `http("https://api", {})`
+    HttpClient(crate::http::Client),
+    /// An error, thrown or caught. eg: `e` in `try { ... } catch e { e.message }`
+    Error(crate::errors::TurtleError),
+    /// A function value, with the variables of the scope it was defined in
+    Function(crate::functions::Function),
+    /// A pipeline stage applied to the value of an expression. eg: `(cat pods.json) | from json`
+    Pipe {
+        input: Box<Expressions>,
+        stage: String,
+        args: String,
+    },
+    /// An import of a turtle file, as a namespace or of some of its names.
+    /// eg: `import "lib/git.tt" as git`, `from "lib/k8s.tt" import ctx, ns`
+    Import {
+        path: String,
+        names: Vec<String>,
+        alias: Option<String>,
+    },
+}
+
+/// formats understood by `from`, see `Expressions::parse_as`
+pub static PARSE_FORMATS: &[&str] = &["json", "yaml", "csv", "tsv", "lines", "kv"];
+
+impl Expressions {
+    /// convert a value expression to json
+    ///
+    /// non-value expressions are converted to their debug representation
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            // whole numbers are written without a fraction, eg: `80` rather than `80.0`
+            Expressions::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                serde_json::Value::from(*n as i64)
+            }
+            Expressions::Number(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Expressions::Integer(crate::numbers::Integer::Small(n)) => serde_json::Value::from(*n),
+            // json numbers are read back as floats past 64 bits, so big integers are written as
+            // strings to keep their digits
+            Expressions::Integer(n) => match num_traits::ToPrimitive::to_u64(&n.to_big()) {
+                Some(n) => serde_json::Value::from(n),
+                None => serde_json::Value::String(n.to_string()),
+            },
+            Expressions::String(s) => serde_json::Value::String(s.clone()),
+            Expressions::Boolean(b) => serde_json::Value::Bool(*b),
+            Expressions::Null => serde_json::Value::Null,
+            Expressions::RegularExpression { .. }
+            | Expressions::DateTime(_)
+            | Expressions::Duration(_)
+            | Expressions::Path { .. }
+            | Expressions::HttpClient(_)
+            | Expressions::Function(_) => serde_json::Value::String(crate::format::display(self)),
+            Expressions::Error(error) => error.to_json(),
+            Expressions::Array(values) => {
+                serde_json::Value::Array(values.iter().map(|v| v.to_json()).collect())
+            }
+            Expressions::Object(entries) => serde_json::Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+            expression => serde_json::Value::String(format!("{:?}", expression)),
+        }
+    }
+
+    /// convert json to a value expression
+    pub fn from_json(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Expressions::Null,
+            serde_json::Value::Bool(b) => Expressions::Boolean(*b),
+            serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(n), _) => Expressions::Integer(crate::numbers::Integer::from(n)),
+                (None, Some(n)) => {
+                    Expressions::Integer(crate::numbers::Integer::from(num_bigint::BigInt::from(n)))
+                }
+                _ => Expressions::Number(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(s) => Expressions::String(s.clone()),
+            serde_json::Value::Array(values) => {
+                Expressions::Array(values.iter().map(Expressions::from_json).collect())
+            }
+            serde_json::Value::Object(entries) => Expressions::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Expressions::from_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// a value from a single field of text, eg: a csv cell
+    ///
+    /// integers, floats and `true`/`false` are converted, everything else stays a string
+    pub fn infer(field: &str) -> Self {
+        let trimmed = field.trim();
+        let digits = trimmed.strip_prefix('-').unwrap_or(trimmed);
+        if !digits.is_empty()
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && let Some(n) = crate::numbers::Integer::parse(trimmed)
+        {
+            return Expressions::Integer(n);
+        }
+        if let Ok(n) = trimmed.parse::<f64>()
+            && !trimmed.is_empty()
+        {
+            return Expressions::Number(n);
+        }
+        match trimmed {
+            "true" | "True" => Expressions::Boolean(true),
+            "false" | "False" => Expressions::Boolean(false),
+            _ => Expressions::String(field.to_string()),
+        }
+    }
+
+    /// parse structured text into turtle values
+    ///
+    /// - `json` and `yaml` documents become arrays, objects and scalars
+    /// - `csv` and `tsv` become an array with one object per row, keyed by the header
+    /// - `lines` becomes an array of strings
+    /// - `kv` reads `key=value` or `key: value` lines into an object
+    ///
+    /// `auto` picks the format with `Expressions::detect_format`
+    pub fn parse_as(format: &str, data: &str) -> Result<Self, String> {
+        let format = match format {
+            "auto" => Expressions::detect_format(data),
+            format => format,
+        };
+        match format {
+            "json" => serde_json::from_str::<serde_json::Value>(data)
+                .map(|value| Expressions::from_json(&value))
+                .map_err(|e| format!("invalid json: {}", e)),
+            "yaml" => serde_yaml::from_str::<serde_json::Value>(data)
+                .map(|value| Expressions::from_json(&value))
+                .map_err(|e| format!("invalid yaml: {}", e)),
+            "csv" | "tsv" => {
+                let mut rdr = csv::ReaderBuilder::new()
+                    .delimiter(if format == "tsv" { b'\t' } else { b',' })
+                    .trim(csv::Trim::All)
+                    .from_reader(data.as_bytes());
+                let headers: Vec<String> = rdr
+                    .headers()
+                    .map_err(|e| format!("invalid {}: {}", format, e))?
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect();
+                let mut rows = Vec::new();
+                for record in rdr.records() {
+                    let record = record.map_err(|e| format!("invalid {}: {}", format, e))?;
+                    rows.push(Expressions::Object(
+                        headers
+                            .iter()
+                            .cloned()
+                            .zip(record.iter().map(Expressions::infer))
+                            .collect(),
+                    ));
+                }
+                Ok(Expressions::Array(rows))
+            }
+            "lines" => Ok(Expressions::Array(
+                data.lines()
+                    .map(|line| Expressions::String(line.to_string()))
+                    .collect(),
+            )),
+            "kv" => {
+                let mut entries = Vec::new();
+                for (number, line) in data.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let (key, value) = line
+                        .split_once('=')
+                        .or_else(|| line.split_once(':'))
+                        .ok_or(format!("invalid kv on line {}: {}", number + 1, line))?;
+                    let value = value.trim().trim_matches('"');
+                    entries.push((key.trim().to_string(), Expressions::infer(value)));
+                }
+                Ok(Expressions::Object(entries))
+            }
+            format => Err(format!(
+                "unknown format `{}`, expected one of: auto, {}",
+                format,
+                PARSE_FORMATS.join(", ")
+            )),
+        }
+    }
+
+    /// order two values, eg: for `sort-by`
+    ///
+    /// integers and floats compare numerically, datetimes and durations chronologically, `null`
+    /// comes first, other values by their text
+    pub fn compare(&self, other: &Expressions) -> std::cmp::Ordering {
+        match (self, other) {
+            (Expressions::Null, Expressions::Null) => std::cmp::Ordering::Equal,
+            (Expressions::Null, _) => std::cmp::Ordering::Less,
+            (_, Expressions::Null) => std::cmp::Ordering::Greater,
+            (Expressions::Number(a), Expressions::Number(b)) => a.total_cmp(b),
+            (Expressions::Integer(a), Expressions::Integer(b)) => a.compare(b),
+            (Expressions::Integer(a), Expressions::Number(b)) => a.to_f64().total_cmp(b),
+            (Expressions::Number(a), Expressions::Integer(b)) => a.total_cmp(&b.to_f64()),
+            (Expressions::Boolean(a), Expressions::Boolean(b)) => a.cmp(b),
+            (Expressions::String(a), Expressions::String(b)) => a.cmp(b),
+            (Expressions::DateTime(a), Expressions::DateTime(b)) => a.cmp(b),
+            (Expressions::Duration(a), Expressions::Duration(b)) => a.cmp(b),
+            (a, b) => a.to_json().to_string().cmp(&b.to_json().to_string()),
+        }
+    }
+
+    /// guess the format of structured text
+    ///
+    /// tries json, tsv, csv, kv and yaml in order, falling back to `lines`
+    pub fn detect_format(data: &str) -> &'static str {
+        let trimmed = data.trim();
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        {
+            return "json";
+        }
+
+        let lines: Vec<&str> = trimmed.lines().filter(|l| !l.trim().is_empty()).collect();
+        let columns = |delimiter: char| {
+            let counts: Vec<usize> = lines.iter().map(|l| l.matches(delimiter).count()).collect();
+            lines.len() > 1 && counts[0] > 0 && counts.iter().all(|c| *c == counts[0])
+        };
+        if columns('\t') {
+            return "tsv";
+        }
+        if columns(',') && OutputCsv::parse(trimmed).is_some() {
+            return "csv";
+        }
+        if !lines.is_empty()
+            && lines.iter().all(|l| {
+                let l = l.trim();
+                l.starts_with('#')
+                    || l.split_once('=')
+                        .is_some_and(|(k, _)| !k.is_empty() && !k.trim().contains(' '))
+            })
+        {
+            return "kv";
+        }
+        if matches!(
+            serde_yaml::from_str::<serde_yaml::Value>(trimmed),
+            Ok(serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_))
+        ) {
+            return "yaml";
+        }
+        "lines"
+    }
 }
 
 impl std::fmt::Display for Outputs {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Outputs::Table(table) => {
-                let mut output = String::new();
-                output.push_str(&table.headers.join("\t"));
-                output.push('\n');
-                for row in &table.data {
-                    output.push_str(&row.join("\t"));
-                    output.push('\n');
-                }
-                write!(f, "{}", output)
+                let lines = table.render(usize::MAX, None);
+                writeln!(f, "{}", lines.join("\n"))
             }
             Outputs::Json(json) => {
                 let json_string =
                     serde_json::to_string_pretty(&json.data).map_err(|_| std::fmt::Error)?;
                 write!(f, "{}", json_string)
             }
+            Outputs::JsonLines(json) => {
+                let json_string = serde_json::to_string(&json.data).map_err(|_| std::fmt::Error)?;
+                write!(f, "{}", json_string)
+            }
             Outputs::Yaml(yaml) => {
                 let yaml_string = serde_yaml::to_string(&yaml.data).map_err(|_| std::fmt::Error)?;
                 write!(f, "{}", yaml_string)
@@ -133,6 +426,8 @@ impl std::fmt::Display for Outputs {
 pub enum Outputs {
     Table(OutputCsv),
     Json(OutputJson),
+    /// a single line of json, eg: for `--format jsonl`
+    JsonLines(OutputJson),
     Yaml(OutputYaml),
     Text(OutputText),
     Ast(OutAst),
@@ -140,36 +435,108 @@ pub enum Outputs {
 
 /// shell output formats
 impl Outputs {
+    /// format an evaluated result
+    ///
+    /// `table` and `text` render command output as text, or as a table when it
+    /// is csv, while `json`, `jsonl` and `yaml` render structured documents.
+    /// Returns None for results with nothing to show, eg: builtins print
+    /// their own output and assignments are silent
+    pub fn from_result(format: &str, result: &crate::context::EvalResults) -> Option<Self> {
+        use crate::context::EvalResults;
+        // builtins that print as they run, eg: `printf`, have nothing left to output
+        if let EvalResults::BuiltinExpressionResult(builtin) = result
+            && builtin.output.is_none()
+        {
+            return None;
+        }
+        if matches!(
+            result,
+            EvalResults::BuiltinExpressionResult(_) | EvalResults::AssignmentExpressionResult(_)
+        ) && !matches!(format, "json" | "jsonl" | "yaml")
+        {
+            return None;
+        }
+
+        let data = result.to_json();
+        match format {
+            "json" => Some(Outputs::Json(OutputJson { data })),
+            "jsonl" => Some(Outputs::JsonLines(OutputJson { data })),
+            "yaml" => Some(Outputs::Yaml(OutputYaml {
+                data: serde_yaml::to_value(&data).ok()?,
+            })),
+            "table" => match result {
+                EvalResults::CommandExpressionResult(cmd) => match OutputCsv::parse(&cmd.stdout) {
+                    Some(table) => Some(Outputs::Table(table)),
+                    None => Some(Outputs::Text(OutputText {
+                        data: cmd.stdout.clone(),
+                    })),
+                },
+                _ => match OutputCsv::from_json(&data) {
+                    Some(table) => Some(Outputs::Table(table)),
+                    None => Some(Outputs::Text(OutputText {
+                        data: result.to_string(),
+                    })),
+                },
+            },
+            _ => match result {
+                EvalResults::CommandExpressionResult(cmd) => Some(Outputs::Text(OutputText {
+                    data: cmd.stdout.clone(),
+                })),
+                _ => Some(Outputs::Text(OutputText {
+                    data: result.to_string(),
+                })),
+            },
+        }
+    }
+
+    /// split a trailing `| as <format>` off a command line
+    ///
+    /// eg: `ls -la | as json` returns `("ls -la", Some("json"))`, pipes in quotes are text,
+    /// eg: `echo "a | as json"`
+    pub fn split_format(input: &str) -> (&str, Option<&str>) {
+        let mut quote = None;
+        let mut escaped = false;
+        let mut pipe = None;
+        for (i, c) in input.char_indices() {
+            match (quote, c) {
+                _ if escaped => escaped = false,
+                (_, '\\') => escaped = true,
+                (Some(q), c) if c == q => quote = None,
+                (None, '"' | '\'' | '`') => quote = Some(c),
+                (None, '|') => pipe = Some(i),
+                _ => {}
+            }
+        }
+        if let Some((command, suffix)) = pipe.map(|i| (&input[..i], &input[i + 1..])) {
+            let mut words = suffix.split_whitespace();
+            if let (Some("as"), Some(format), None) = (words.next(), words.next(), words.next()) {
+                return (command.trim_end(), Some(format));
+            }
+        }
+        (input, None)
+    }
+
     pub fn from_command_response(
         option: &str,
         response: crate::history::CommandResponse,
     ) -> Option<Self> {
         match option {
-            "table" => {
-                let mut rdr = csv::Reader::from_reader(response.output.as_bytes());
-                let headers = rdr
-                    .headers()
-                    .unwrap()
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect();
-                let mut rows = Vec::new();
-                for result in rdr.records() {
-                    let record = result.unwrap();
-                    let row = record.iter().map(|s| s.to_string()).collect();
-                    rows.push(row);
-                }
-                Some(Outputs::Table(OutputCsv {
-                    headers,
-                    data: rows,
-                }))
-            }
+            // fall back to plain text when the output isn't csv
+            "table" => Some(match OutputCsv::parse(&response.output) {
+                Some(table) => Outputs::Table(table),
+                None => Outputs::Text(OutputText {
+                    data: response.output,
+                }),
+            }),
             "json" => {
                 // use serde to serialize the response object to json
                 let json_data = serde_json::to_string(&response).ok()?;
                 let json_data: serde_json::Value = serde_json::from_str(&json_data).ok()?;
                 Some(Outputs::Json(OutputJson { data: json_data }))
             }
+            "jsonl" => Some(Outputs::JsonLines(OutputJson {
+                data: serde_json::to_value(&response).ok()?,
+            })),
             "yaml" => {
                 let yaml_data = serde_yaml::to_string(&response).ok()?;
                 let yaml_data: serde_yaml::Value = serde_yaml::from_str(&yaml_data).ok()?;
@@ -191,42 +558,34 @@ impl Outputs {
             Expressions::Number(n) => Some(Outputs::Text(OutputText {
                 data: n.to_string(),
             })),
+            Expressions::Integer(n) => Some(Outputs::Text(OutputText {
+                data: n.to_string(),
+            })),
             Expressions::Boolean(b) => Some(Outputs::Text(OutputText {
                 data: b.to_string(),
             })),
+            Expressions::Null => Some(Outputs::Text(OutputText {
+                data: "null".to_string(),
+            })),
             _ => None,
         }
     }
 
     pub fn from_str(option: &str, data: String) -> Option<Self> {
         match option {
-            "table" => {
-                // parse CSV data
-                let mut rdr = csv::Reader::from_reader(data.as_bytes());
-                let headers = rdr
-                    .headers()
-                    .unwrap()
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect();
-                let mut rows = Vec::new();
-                for result in rdr.records() {
-                    let record = result.unwrap();
-                    let row = record.iter().map(|s| s.to_string()).collect();
-                    rows.push(row);
-                }
-                Some(Outputs::Table(OutputCsv {
-                    headers,
-                    data: rows,
-                }))
-            }
+            // parse CSV data, falling back to plain text
+            "table" => Some(match OutputCsv::parse(&data) {
+                Some(table) => Outputs::Table(table),
+                None => Outputs::Text(OutputText { data }),
+            }),
             "json" => {
-                println!("Parsing JSON data...");
-                println!("Option: {}", option);
-                println!("Data: {}", data);
                 let json_data: serde_json::Value = serde_json::from_str(&data).ok()?;
                 Some(Outputs::Json(OutputJson { data: json_data }))
             }
+            "jsonl" => {
+                let json_data: serde_json::Value = serde_json::from_str(&data).ok()?;
+                Some(Outputs::JsonLines(OutputJson { data: json_data }))
+            }
             "yaml" => {
                 let yaml_data: serde_yaml::Value = serde_yaml::from_str(&data).ok()?;
                 Some(Outputs::Yaml(OutputYaml { data: yaml_data }))
@@ -245,6 +604,185 @@ pub struct OutputCsv {
     pub data: Vec<Vec<String>>,
 }
 
+impl OutputCsv {
+    /// parse csv with a header row
+    ///
+    /// returns None unless there are at least two columns and every row has
+    /// as many fields as the header, so plain text isn't mistaken for csv
+    pub fn parse(data: &str) -> Option<Self> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .flexible(false)
+            .from_reader(data.as_bytes());
+        let headers: Vec<String> = rdr.headers().ok()?.iter().map(|s| s.to_string()).collect();
+        if headers.len() < 2 {
+            return None;
+        }
+        let mut rows = Vec::new();
+        for result in rdr.records() {
+            let row = result.ok()?.iter().map(|s| s.to_string()).collect();
+            rows.push(row);
+        }
+        Some(OutputCsv {
+            headers,
+            data: rows,
+        })
+    }
+
+    /// build a table from json
+    ///
+    /// arrays of objects become one row per object, objects become key/value
+    /// rows and arrays of values a single `value` column
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let cell = |value: &serde_json::Value| match value {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Null => String::new(),
+            value => value.to_string(),
+        };
+        match value {
+            serde_json::Value::Array(items) if items.iter().all(|i| i.is_object()) => {
+                let mut headers: Vec<String> = Vec::new();
+                for item in items.iter().filter_map(|i| i.as_object()) {
+                    for key in item.keys() {
+                        if !headers.contains(key) {
+                            headers.push(key.clone());
+                        }
+                    }
+                }
+                let data = items
+                    .iter()
+                    .map(|item| {
+                        headers
+                            .iter()
+                            .map(|h| item.get(h).map(cell).unwrap_or_default())
+                            .collect()
+                    })
+                    .collect();
+                Some(OutputCsv { headers, data })
+            }
+            serde_json::Value::Array(items) => Some(OutputCsv {
+                headers: vec!["value".to_string()],
+                data: items.iter().map(|i| vec![cell(i)]).collect(),
+            }),
+            serde_json::Value::Object(entries) => Some(OutputCsv {
+                headers: vec!["key".to_string(), "value".to_string()],
+                data: entries
+                    .iter()
+                    .map(|(key, value)| vec![key.clone(), cell(value)])
+                    .collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// render an aligned table that fits in `width` columns
+    ///
+    /// columns are sized to their widest cell, then the widest column is
+    /// narrowed until the table fits, truncating its cells with `…`. Numeric
+    /// columns are right aligned, and the header is coloured from `theme`
+    pub fn render(&self, width: usize, theme: Option<&crate::style::Theme>) -> Vec<String> {
+        const SEPARATOR: &str = "  ";
+        const MIN_WIDTH: usize = 3;
+
+        let clean = |cell: &str| cell.replace(['\n', '\r'], " ").replace('\t', " ");
+        let headers: Vec<String> = self.headers.iter().map(|h| clean(h)).collect();
+        let rows: Vec<Vec<String>> = self
+            .data
+            .iter()
+            .map(|row| row.iter().map(|cell| clean(cell)).collect())
+            .collect();
+
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+        for row in &rows {
+            for (i, cell) in row.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+        let numeric: Vec<bool> = (0..headers.len())
+            .map(|i| {
+                rows.iter()
+                    .filter_map(|row| row.get(i))
+                    .filter(|cell| !cell.is_empty())
+                    .all(|cell| cell.trim().parse::<f64>().is_ok())
+                    && rows
+                        .iter()
+                        .any(|row| row.get(i).is_some_and(|c| !c.is_empty()))
+            })
+            .collect();
+
+        let total = |widths: &[usize]| {
+            widths.iter().sum::<usize>() + SEPARATOR.len() * widths.len().saturating_sub(1)
+        };
+        while total(&widths) > width {
+            let (widest, _) = widths
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, w)| **w)
+                .unwrap_or((0, &0));
+            if widths.get(widest).is_none_or(|w| *w <= MIN_WIDTH) {
+                break;
+            }
+            widths[widest] -= 1;
+        }
+
+        let fit = |cell: &str, width: usize, right: bool| {
+            let cell = if cell.chars().count() > width {
+                let mut cell: String = cell.chars().take(width.saturating_sub(1)).collect();
+                cell.push('…');
+                cell
+            } else {
+                cell.to_string()
+            };
+            if right {
+                format!("{:>width$}", cell, width = width)
+            } else {
+                format!("{:<width$}", cell, width = width)
+            }
+        };
+        let line = |cells: &[String]| {
+            widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    fit(
+                        cells.get(i).map(|c| c.as_str()).unwrap_or(""),
+                        *width,
+                        numeric[i],
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(SEPARATOR)
+                .trim_end()
+                .to_string()
+        };
+
+        let header = line(&headers);
+        let header = match theme {
+            Some(theme) => {
+                use crossterm::style::Stylize;
+                format!(
+                    "{}",
+                    header
+                        .with(theme.foreground)
+                        .on(theme.selection)
+                        .attribute(crossterm::style::Attribute::Bold)
+                )
+            }
+            None => header,
+        };
+
+        let mut lines = vec![
+            header,
+            widths
+                .iter()
+                .map(|w| "─".repeat(*w))
+                .collect::<Vec<String>>()
+                .join(SEPARATOR),
+        ];
+        lines.extend(rows.iter().map(|row| line(row)));
+        lines
+    }
+}
+
 /// YAML compatible output
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OutputYaml {
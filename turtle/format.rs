@@ -0,0 +1,579 @@
+/// printf style formatting of turtle values, shared by `printf`, `format()` and f-strings
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// placeholders follow the rust format grammar:
+///
+/// ```text
+/// placeholder := '{' [argument] [':' spec] '}'
+/// argument    := integer | identifier
+/// spec        := [[fill] align] [sign] ['#'] ['0'] [width] ['.' precision] [type] | strftime
+/// align       := '<' | '^' | '>'
+/// sign        := '+' | '-'
+/// width       := count
+/// precision   := count | '*'
+/// count       := integer | integer '$' | identifier '$'
+/// type        := '' | '?' | 'x' | 'X' | 'b' | 'o' | 'e' | 'E'
+/// ```
+///
+/// `{{` and `}}` write literal braces. a spec starting with `%` formats a datetime with strftime,
+/// eg: `{when:%Y-%m-%d %H:%M}`, see `crate::dates::strftime`.
+use crate::expressions::Expressions;
+
+/// an argument referenced by a placeholder, width or precision
+#[derive(Debug, Clone, PartialEq)]
+enum Argument {
+    /// the next positional argument, eg: `{}`
+    Next,
+    /// a positional argument, eg: `{0}`
+    Index(usize),
+    /// a named argument, eg: `{name}`
+    Name(String),
+}
+
+/// a width or precision
+#[derive(Debug, Clone, PartialEq)]
+enum Count {
+    /// a literal count, eg: `{:8}`
+    Value(usize),
+    /// a count taken from an argument, eg: `{:1$}`, `{:width$}` or `{:.*}`
+    Argument(Argument),
+}
+
+/// alignment within the field width
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// how a value is written
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Display,
+    Debug,
+    LowerHex,
+    UpperHex,
+    Binary,
+    Octal,
+    LowerExp,
+    UpperExp,
+}
+
+/// a parsed format spec, everything after the `:` in a placeholder
+#[derive(Debug, Clone, PartialEq)]
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    plus: bool,
+    alternate: bool,
+    zero: bool,
+    width: Option<Count>,
+    precision: Option<Count>,
+    kind: Kind,
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Spec {
+            fill: ' ',
+            align: None,
+            plus: false,
+            alternate: false,
+            zero: false,
+            width: None,
+            precision: None,
+            kind: Kind::Display,
+        }
+    }
+}
+
+/// format `template` with turtle values
+///
+/// - `args` are the positional arguments, used by `{}` in order or by index with `{0}`
+/// - `named` resolves `{name}` placeholders, eg: from turtle variables
+///
+/// ```text
+/// format("{:>8.2}", [3.14159])     => "    3.14"
+/// format("{0:#x} {0:#b}", [5])     => "0x5 0b101"
+/// format("{name:*^9}", name=turtle) => "*turtle**"
+/// ```
+pub fn format(
+    template: &str,
+    args: &[Expressions],
+    named: &dyn Fn(&str) -> Option<Expressions>,
+) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = template.chars().peekable();
+    let mut next = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '}' => return Err("unmatched `}` in format string".to_string()),
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    return Err(format!("unterminated placeholder `{{{}`", placeholder));
+                }
+
+                let (argument, spec) = match placeholder.split_once(':') {
+                    Some((argument, spec)) => (argument, spec),
+                    None => (placeholder.as_str(), ""),
+                };
+                let argument = parse_argument(argument.trim())?;
+                let strftime = spec.starts_with('%').then_some(spec);
+                let spec = match strftime {
+                    Some(_) => Spec::default(),
+                    None => parse_spec(spec)?,
+                };
+
+                let mut resolve = |argument: &Argument| -> Result<Expressions, String> {
+                    match argument {
+                        Argument::Next => {
+                            let value = args.get(next).cloned().ok_or_else(|| {
+                                format!("missing argument {} for format string", next)
+                            });
+                            next += 1;
+                            value
+                        }
+                        Argument::Index(index) => args
+                            .get(*index)
+                            .cloned()
+                            .ok_or_else(|| format!("missing argument {} for format string", index)),
+                        Argument::Name(name) => {
+                            named(name).ok_or_else(|| format!("unknown argument `{}`", name))
+                        }
+                    }
+                };
+                let mut count = |count: &Option<Count>| -> Result<Option<usize>, String> {
+                    match count {
+                        None => Ok(None),
+                        Some(Count::Value(n)) => Ok(Some(*n)),
+                        Some(Count::Argument(argument)) => match resolve(argument)? {
+                            Expressions::Integer(crate::numbers::Integer::Small(n)) if n >= 0 => {
+                                Ok(Some(n as usize))
+                            }
+                            Expressions::Number(n) if n >= 0.0 && n.fract() == 0.0 => {
+                                Ok(Some(n as usize))
+                            }
+                            other => Err(format!(
+                                "width and precision must be whole numbers, got {}",
+                                display(&other)
+                            )),
+                        },
+                    }
+                };
+
+                // rust reads `.*` precision before the value it applies to
+                let precision = count(&spec.precision)?;
+                let width = count(&spec.width)?;
+                let value = resolve(&argument)?;
+                match strftime {
+                    Some(pattern) => output.push_str(&crate::dates::strftime(&value, pattern)?),
+                    None => output.push_str(&write(&value, &spec, width, precision)?),
+                }
+            }
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+/// write a value the way `{}` shows it
+///
+/// whole floats are written without a fraction and arrays and objects as json
+pub fn display(value: &Expressions) -> String {
+    match value {
+        Expressions::String(s) => s.clone(),
+        Expressions::Number(n) => n.to_string(),
+        Expressions::Integer(n) => n.to_string(),
+        Expressions::Boolean(b) => b.to_string(),
+        Expressions::Null => "null".to_string(),
+        Expressions::DateTime(datetime) => crate::dates::display(datetime),
+        Expressions::Duration(duration) => duration.to_string(),
+        Expressions::Path { segments } => crate::paths::display(segments),
+        Expressions::HttpClient(client) => client.to_string(),
+        Expressions::Error(error) => error.to_string(),
+        Expressions::Function(function) => function.to_string(),
+        Expressions::RegularExpression { pattern, flags } => {
+            format!("/{}/{}", pattern, flags.as_deref().unwrap_or_default())
+        }
+        Expressions::Array(_) | Expressions::Object(_) => value.to_json().to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn parse_argument(argument: &str) -> Result<Argument, String> {
+    if argument.is_empty() {
+        Ok(Argument::Next)
+    } else if let Ok(index) = argument.parse::<usize>() {
+        Ok(Argument::Index(index))
+    } else if is_identifier(argument) {
+        Ok(Argument::Name(argument.to_string()))
+    } else {
+        Err(format!("invalid argument `{}` in format string", argument))
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    }
+}
+
+/// parse a count at the start of `rest`: `8`, `1$` or `width$`
+fn parse_count(rest: &[char], at: &mut usize) -> Option<Count> {
+    let start = *at;
+    let mut end = start;
+    while end < rest.len() && (rest[end].is_alphanumeric() || rest[end] == '_') {
+        end += 1;
+    }
+    let word: String = rest[start..end].iter().collect();
+    if word.is_empty() {
+        return None;
+    }
+
+    if rest.get(end) == Some(&'$') {
+        let argument = parse_argument(&word).ok()?;
+        *at = end + 1;
+        return Some(Count::Argument(argument));
+    }
+
+    // a plain count is only the leading digits, the rest may be the type, eg: `8x`
+    let digits: String = word.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let value = digits.parse::<usize>().ok()?;
+    *at = start + digits.len();
+    Some(Count::Value(value))
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+    let chars: Vec<char> = spec.chars().collect();
+    let mut parsed = Spec::default();
+    let mut at = 0;
+
+    // [[fill] align]
+    if let Some(align) = chars.get(1).and_then(|c| parse_align(*c)) {
+        parsed.fill = chars[0];
+        parsed.align = Some(align);
+        at = 2;
+    } else if let Some(align) = chars.first().and_then(|c| parse_align(*c)) {
+        parsed.align = Some(align);
+        at = 1;
+    }
+
+    // [sign] ['#'] ['0']
+    match chars.get(at) {
+        Some('+') => {
+            parsed.plus = true;
+            at += 1;
+        }
+        Some('-') => at += 1,
+        _ => {}
+    }
+    if chars.get(at) == Some(&'#') {
+        parsed.alternate = true;
+        at += 1;
+    }
+    if chars.get(at) == Some(&'0') && chars.get(at + 1) != Some(&'$') {
+        parsed.zero = true;
+        at += 1;
+    }
+
+    // [width] ['.' precision]
+    parsed.width = parse_count(&chars, &mut at);
+    if chars.get(at) == Some(&'.') {
+        at += 1;
+        if chars.get(at) == Some(&'*') {
+            at += 1;
+            parsed.precision = Some(Count::Argument(Argument::Next));
+        } else {
+            parsed.precision = Some(
+                parse_count(&chars, &mut at)
+                    .ok_or_else(|| format!("missing precision in `{}`", spec))?,
+            );
+        }
+    }
+
+    // [type]
+    let kind: String = chars[at..].iter().collect();
+    parsed.kind = match kind.as_str() {
+        "" => Kind::Display,
+        "?" => Kind::Debug,
+        "x" => Kind::LowerHex,
+        "X" => Kind::UpperHex,
+        "b" => Kind::Binary,
+        "o" => Kind::Octal,
+        "e" => Kind::LowerExp,
+        "E" => Kind::UpperExp,
+        other => return Err(format!("unknown format type `{}` in `{}`", other, spec)),
+    };
+
+    Ok(parsed)
+}
+
+/// write one value with its spec, `width` and `precision` already resolved
+fn write(
+    value: &Expressions,
+    spec: &Spec,
+    width: Option<usize>,
+    precision: Option<usize>,
+) -> Result<String, String> {
+    // integers are written as floats for exponents and precision, eg: `{:.2}` of 3 is `3.00`
+    let float = match value {
+        Expressions::Number(n) => Some(*n),
+        Expressions::Integer(n) => Some(n.to_f64()),
+        _ => None,
+    };
+
+    // numbers are split into sign, prefix and digits so `0` padding goes between them
+    let (sign, prefix, body) = match (spec.kind, value, float) {
+        (Kind::LowerHex | Kind::UpperHex | Kind::Binary | Kind::Octal, _, _) => {
+            let Expressions::Integer(n) = value else {
+                return Err(format!(
+                    "`{}` can't be written in hex, binary or octal, it isn't an integer",
+                    display(value)
+                ));
+            };
+            let (prefix, digits) = match spec.kind {
+                Kind::LowerHex => ("0x", n.to_radix(16, false)),
+                Kind::UpperHex => ("0x", n.to_radix(16, true)),
+                Kind::Binary => ("0b", n.to_radix(2, false)),
+                _ => ("0o", n.to_radix(8, false)),
+            };
+            (
+                sign(n.is_negative(), spec.plus),
+                if spec.alternate { prefix } else { "" },
+                digits,
+            )
+        }
+        (Kind::LowerExp | Kind::UpperExp, _, Some(n)) => {
+            let digits = match (spec.kind, precision) {
+                (Kind::LowerExp, Some(p)) => format!("{:.*e}", p, n.abs()),
+                (Kind::LowerExp, None) => format!("{:e}", n.abs()),
+                (_, Some(p)) => format!("{:.*E}", p, n.abs()),
+                (_, None) => format!("{:E}", n.abs()),
+            };
+            (sign(n.is_sign_negative(), spec.plus), "", digits)
+        }
+        (Kind::LowerExp | Kind::UpperExp, other, None) => {
+            return Err(format!(
+                "`{}` can't be written as an exponent, it isn't a number",
+                display(other)
+            ));
+        }
+        (_, Expressions::Integer(n), _) if precision.is_none() => {
+            let digits = n.to_string();
+            let digits = digits.trim_start_matches('-').to_string();
+            (sign(n.is_negative(), spec.plus), "", digits)
+        }
+        (_, _, Some(n)) => {
+            let digits = match precision {
+                Some(p) => format!("{:.*}", p, n.abs()),
+                None => n.abs().to_string(),
+            };
+            (
+                sign(n.is_sign_negative() && n != 0.0, spec.plus),
+                "",
+                digits,
+            )
+        }
+        (Kind::Debug, Expressions::String(s), _) => {
+            ("", "", truncate(&format!("{:?}", s), precision))
+        }
+        (Kind::Debug, Expressions::Array(_) | Expressions::Object(_), _) if spec.alternate => (
+            "",
+            "",
+            serde_json::to_string_pretty(&value.to_json()).unwrap_or_default(),
+        ),
+        (_, other, _) => ("", "", truncate(&display(other), precision)),
+    };
+
+    let numeric = float.is_some();
+    let length = sign.chars().count() + prefix.len() + body.chars().count();
+    let width = width.unwrap_or(0);
+
+    // zero padding goes after the sign and prefix and ignores the fill and alignment
+    if spec.zero && numeric && length < width {
+        let zeros = "0".repeat(width - length);
+        return Ok(format!("{}{}{}{}", sign, prefix, zeros, body));
+    }
+
+    let text = format!("{}{}{}", sign, prefix, body);
+    if length >= width {
+        return Ok(text);
+    }
+
+    // numbers are right aligned by default, everything else left aligned
+    let align = spec
+        .align
+        .unwrap_or(if numeric { Align::Right } else { Align::Left });
+    let padding = width - length;
+    let (before, after) = match align {
+        Align::Left => (0, padding),
+        Align::Center => (padding / 2, padding - padding / 2),
+        Align::Right => (padding, 0),
+    };
+    let fill = |n: usize| spec.fill.to_string().repeat(n);
+    Ok(format!("{}{}{}", fill(before), text, fill(after)))
+}
+
+fn sign(negative: bool, plus: bool) -> &'static str {
+    if negative {
+        "-"
+    } else if plus {
+        "+"
+    } else {
+        ""
+    }
+}
+
+/// precision on a string is the most characters written
+fn truncate(text: &str, precision: Option<usize>) -> String {
+    match precision {
+        Some(p) => text.chars().take(p).collect(),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn none(_: &str) -> Option<Expressions> {
+        None
+    }
+
+    fn fmt(template: &str, args: &[Expressions]) -> String {
+        format(template, args, &none).unwrap()
+    }
+
+    fn n(value: f64) -> Expressions {
+        Expressions::Number(value)
+    }
+
+    fn i(value: i64) -> Expressions {
+        Expressions::Integer(crate::numbers::Integer::Small(value))
+    }
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    #[test]
+    fn test_format_positional_and_named() {
+        assert_eq!(fmt("{} and {}", &[s("a"), s("b")]), "a and b");
+        assert_eq!(fmt("{1} {0} {1}", &[s("a"), s("b")]), "b a b");
+        assert_eq!(fmt("{{{}}}", &[n(1.0)]), "{1}");
+
+        let named = |name: &str| (name == "name").then(|| s("turtle"));
+        assert_eq!(
+            format("hello {name:>8}!", &[], &named).unwrap(),
+            "hello   turtle!"
+        );
+        assert!(format("{missing}", &[], &named).is_err());
+        assert!(format("{}", &[], &named).is_err());
+        assert!(format("{", &[], &named).is_err());
+    }
+
+    #[test]
+    fn test_format_integers() {
+        assert_eq!(fmt("{:x}", &[i(255)]), "ff");
+        assert_eq!(fmt("{:#X}", &[i(255)]), "0xFF");
+        assert_eq!(fmt("{:#010b}", &[i(5)]), "0b00000101");
+        assert_eq!(fmt("{:o}", &[i(8)]), "10");
+        assert_eq!(fmt("{:x}", &[i(-255)]), "-ff");
+        assert_eq!(fmt("{:+05}", &[i(42)]), "+0042");
+        assert_eq!(fmt("{:.2}", &[i(3)]), "3.00");
+        let big = crate::numbers::Integer::parse("0x1_0000_0000_0000_0000").unwrap();
+        assert_eq!(
+            fmt("{0} {0:#x}", &[Expressions::Integer(big)]),
+            "18446744073709551616 0x10000000000000000"
+        );
+        assert!(format("{:x}", &[n(255.0)], &none).is_err());
+    }
+
+    #[test]
+    fn test_format_floats() {
+        assert_eq!(fmt("{:.2}", &[n(2.34567)]), "2.35");
+        assert_eq!(fmt("{:+.1}", &[n(2.0)]), "+2.0");
+        assert_eq!(fmt("{:08.3}", &[n(-12.3456)]), "-012.346");
+        assert_eq!(fmt("{:e}", &[n(1234.5)]), "1.2345e3");
+        assert_eq!(fmt("{:.2E}", &[n(1234.5)]), "1.23E3");
+        assert_eq!(fmt("{:.*}", &[n(1.0), n(2.55)]), "2.5");
+        assert_eq!(fmt("{:1$}|", &[n(7.5), i(6)]), "   7.5|");
+    }
+
+    #[test]
+    fn test_format_fill_and_align() {
+        assert_eq!(fmt("{:<6}|", &[s("ab")]), "ab    |");
+        assert_eq!(fmt("{:6}|", &[s("ab")]), "ab    |");
+        assert_eq!(fmt("{:6}|", &[n(12.0)]), "    12|");
+        assert_eq!(fmt("{:*^7}", &[s("abc")]), "**abc**");
+        assert_eq!(fmt("{:->10.3}", &[s("turtles")]), "-------tur");
+        assert_eq!(fmt("{:?}", &[s("a\"b")]), "\"a\\\"b\"");
+        assert_eq!(
+            fmt("{}", &[Expressions::Array(vec![n(1.0), s("a")])]),
+            "[1,\"a\"]"
+        );
+        assert!(format("{:q}", &[n(1.0)], &none).is_err());
+    }
+
+    #[test]
+    fn test_format_datetimes() {
+        let when = Expressions::DateTime(crate::dates::parse("2025-03-04T05:06:07Z").unwrap());
+        assert_eq!(
+            fmt("{}", std::slice::from_ref(&when)),
+            "2025-03-04T05:06:07Z"
+        );
+        assert_eq!(
+            fmt("{:%Y-%m-%d %H:%M}", std::slice::from_ref(&when)),
+            "2025-03-04 05:06"
+        );
+        assert_eq!(fmt("{0:%H:%M:%S}", &[when]), "05:06:07");
+        assert_eq!(
+            fmt(
+                "{:>6}",
+                &[Expressions::Duration(
+                    crate::dates::Duration::parse("90s").unwrap()
+                )]
+            ),
+            " 1m30s"
+        );
+        assert!(format("{:%Y}", &[n(1.0)], &none).is_err());
+        // text is read as a datetime, eg: `printf "{:%Y}" 2025-01-01`
+        assert_eq!(fmt("{:%Y}", &[s("2025-01-01")]), "2025");
+    }
+}
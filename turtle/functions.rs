@@ -0,0 +1,385 @@
+/// Functions: closures like `|x| x * 2`, `fn(x) { ... }` and `fn name(x) { ... }`, and the
+/// higher-order `map`, `filter`, `reduce`, `sort`, `any` and `all`
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// functions are values, they can be stored in variables and objects and passed to other
+/// functions. a function captures the variables of the scope it is defined in, calling it is
+/// left to `Context`, which evaluates its body
+use crate::expressions::Expressions;
+use serde::{Deserialize, Serialize};
+
+/// a function value, see the module docs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Function {
+    /// the name of a `fn name(...)` definition, it can call itself by it
+    pub name: Option<String>,
+    pub params: Vec<String>,
+    pub body: Box<Expressions>,
+    /// variables of the scope the function was defined in, sorted by name
+    pub scope: Vec<(String, Expressions)>,
+}
+
+impl std::fmt::Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "fn {}({})",
+            self.name.as_deref().unwrap_or_default(),
+            self.params.join(", ")
+        )
+    }
+}
+
+/// calls a function with its arguments, see `call`
+pub type Apply<'a> = dyn FnMut(&Function, Vec<Expressions>) -> Result<Expressions, String> + 'a;
+
+/// the variables a function body captures from the scope it is defined in, sorted by name
+///
+/// names are looked up in every string of the body, so names in format strings are captured
+/// too, eg: `f"{greeting}"`. capturing a few unused variables is harmless
+pub fn capture(
+    body: &Expressions,
+    vars: &std::collections::HashMap<String, Expressions>,
+) -> Vec<(String, Expressions)> {
+    fn words(value: &serde_json::Value, found: &mut std::collections::HashSet<String>) {
+        match value {
+            serde_json::Value::String(text) => found.extend(
+                text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_string),
+            ),
+            serde_json::Value::Array(values) => values.iter().for_each(|value| words(value, found)),
+            serde_json::Value::Object(entries) => entries.iter().for_each(|(key, value)| {
+                found.insert(key.clone());
+                words(value, found)
+            }),
+            _ => {}
+        }
+    }
+    let mut found = std::collections::HashSet::new();
+    if let Ok(body) = serde_json::to_value(body) {
+        words(&body, &mut found);
+    }
+    let mut scope: Vec<(String, Expressions)> = vars
+        .iter()
+        .filter(|(name, _)| found.contains(name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    scope.sort_by(|(a, _), (b, _)| a.cmp(b));
+    scope
+}
+
+/// call a higher-order function, `map`, `filter`, `reduce`, `sort`, `any` or `all`
+///
+/// `apply` calls a function with its arguments. returns `None` for other functions, so the
+/// caller can try them
+///
+/// - `map(items, f)` is the value of `f(item)` for each item
+/// - `filter(items, f)` keeps the items `f(item)` holds for, `null` doesn't hold
+/// - `reduce(items, f, initial)` folds the items with `f(acc, item)`, starting from the first
+///   item without an initial value, and is `null` for no items
+/// - `sort(items)` sorts values like the comparisons do, `sort(items, by)` sorts by the value
+///   of `by(item)`, eg: `sort(pods, |p| p.age)`, or with a comparator of two items, negative
+///   when the first goes first, eg: `sort(xs, |a, b| b - a)`. sorting is stable
+/// - `any(items, f)` and `all(items, f)` stop at the first item that decides them
+pub fn call(
+    func: &str,
+    args: &[Expressions],
+    apply: &mut Apply,
+) -> Option<Result<Expressions, String>> {
+    if !matches!(func, "map" | "filter" | "reduce" | "sort" | "any" | "all") {
+        return None;
+    }
+    let result = match (func, args) {
+        ("sort", [Expressions::Array(items)]) => {
+            let mut items = items.clone();
+            items.sort_by(|a, b| a.compare(b));
+            Ok(Expressions::Array(items))
+        }
+        (
+            func,
+            [
+                Expressions::Array(items),
+                Expressions::Function(f),
+                rest @ ..,
+            ],
+        ) if rest.is_empty() || (func == "reduce" && rest.len() == 1) => {
+            higher_order(func, items, f, rest.first(), apply)
+        }
+        ("sort", _) => Err("sort: expected an array and optionally a function".to_string()),
+        ("reduce", _) => {
+            Err("reduce: expected an array, a function and optionally a value".to_string())
+        }
+        (func, _) => Err(format!(
+            "{}: expected an array and a function, eg: {}(xs, |x| x)",
+            func, func
+        )),
+    };
+    Some(result)
+}
+
+/// see `call`
+fn higher_order(
+    func: &str,
+    items: &[Expressions],
+    f: &Function,
+    initial: Option<&Expressions>,
+    apply: &mut Apply,
+) -> Result<Expressions, String> {
+    let mut holds = |item: &Expressions| match apply(f, vec![item.clone()])? {
+        Expressions::Boolean(value) => Ok(value),
+        Expressions::Null => Ok(false),
+        other => Err(format!(
+            "{}: expected a boolean, got {}",
+            func,
+            crate::format::display(&other)
+        )),
+    };
+    Ok(match func {
+        "map" => Expressions::Array(
+            items
+                .iter()
+                .map(|item| apply(f, vec![item.clone()]))
+                .collect::<Result<_, _>>()?,
+        ),
+        "filter" => {
+            let mut kept = Vec::new();
+            for item in items {
+                if holds(item)? {
+                    kept.push(item.clone());
+                }
+            }
+            Expressions::Array(kept)
+        }
+        "any" => {
+            for item in items {
+                if holds(item)? {
+                    return Ok(Expressions::Boolean(true));
+                }
+            }
+            Expressions::Boolean(false)
+        }
+        "all" => {
+            for item in items {
+                if !holds(item)? {
+                    return Ok(Expressions::Boolean(false));
+                }
+            }
+            Expressions::Boolean(true)
+        }
+        "reduce" => {
+            let mut items = items.iter().cloned();
+            let Some(mut acc) = initial.cloned().or_else(|| items.next()) else {
+                return Ok(Expressions::Null);
+            };
+            for item in items {
+                acc = apply(f, vec![acc, item])?;
+            }
+            acc
+        }
+        _ if f.params.len() == 2 => {
+            let mut items = items.to_vec();
+            let mut error = None;
+            items.sort_by(|a, b| {
+                if error.is_some() {
+                    return std::cmp::Ordering::Equal;
+                }
+                match apply(f, vec![a.clone(), b.clone()]) {
+                    Ok(order @ (Expressions::Integer(_) | Expressions::Number(_))) => {
+                        order.compare(&Expressions::Integer(0.into()))
+                    }
+                    Ok(other) => {
+                        error = Some(format!(
+                            "sort: expected a number from the comparator, got {}",
+                            crate::format::display(&other)
+                        ));
+                        std::cmp::Ordering::Equal
+                    }
+                    Err(e) => {
+                        error = Some(e);
+                        std::cmp::Ordering::Equal
+                    }
+                }
+            });
+            match error {
+                Some(e) => return Err(e),
+                None => Expressions::Array(items),
+            }
+        }
+        _ if f.params.len() != 1 => {
+            return Err(format!(
+                "sort expects a 1-argument key function or a 2-argument comparator, got {}",
+                f
+            ));
+        }
+        _ => {
+            let keys = items
+                .iter()
+                .map(|item| apply(f, vec![item.clone()]))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut keyed: Vec<(Expressions, Expressions)> =
+                keys.into_iter().zip(items.iter().cloned()).collect();
+            keyed.sort_by(|(a, _), (b, _)| a.compare(b));
+            Expressions::Array(keyed.into_iter().map(|(_, item)| item).collect())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn i(value: i64) -> Expressions {
+        Expressions::Integer(value.into())
+    }
+
+    fn function(params: &[&str]) -> Function {
+        Function {
+            name: None,
+            params: params.iter().map(|param| param.to_string()).collect(),
+            body: Box::new(Expressions::Null),
+            scope: Vec::new(),
+        }
+    }
+
+    /// applies functions of one parameter as `x * 2` and of two as `a + b`, on integers
+    fn apply(_: &Function, args: Vec<Expressions>) -> Result<Expressions, String> {
+        let value = |arg: &Expressions| match arg {
+            Expressions::Integer(crate::numbers::Integer::Small(n)) => Ok(*n),
+            other => Err(format!("not an integer: {:?}", other)),
+        };
+        match args.as_slice() {
+            [x] => Ok(i(value(x)? * 2)),
+            [a, b] => Ok(i(value(a)? + value(b)?)),
+            _ => Err("unexpected arguments".to_string()),
+        }
+    }
+
+    fn call_with(func: &str, args: &[Expressions]) -> Option<Result<Expressions, String>> {
+        call(func, args, &mut apply)
+    }
+
+    #[test]
+    fn test_map_reduce_and_sort() {
+        let items = Expressions::Array(vec![i(3), i(1), i(2)]);
+        let double = Expressions::Function(function(&["x"]));
+        let add = Expressions::Function(function(&["a", "b"]));
+
+        assert_eq!(
+            call_with("map", &[items.clone(), double.clone()]),
+            Some(Ok(Expressions::Array(vec![i(6), i(2), i(4)])))
+        );
+        assert_eq!(
+            call_with("reduce", &[items.clone(), add.clone()]),
+            Some(Ok(i(6)))
+        );
+        assert_eq!(
+            call_with("reduce", &[items.clone(), add.clone(), i(10)]),
+            Some(Ok(i(16)))
+        );
+        assert_eq!(
+            call_with("reduce", &[Expressions::Array(vec![]), add]),
+            Some(Ok(Expressions::Null))
+        );
+        assert_eq!(
+            call_with("sort", std::slice::from_ref(&items)),
+            Some(Ok(Expressions::Array(vec![i(1), i(2), i(3)])))
+        );
+        assert_eq!(
+            call_with("sort", &[items.clone(), double]),
+            Some(Ok(Expressions::Array(vec![i(1), i(2), i(3)])))
+        );
+        assert!(matches!(call_with("map", &[items]), Some(Err(_))));
+        assert_eq!(call_with("len", &[]), None);
+    }
+
+    #[test]
+    fn test_sort_comparators() {
+        let items = Expressions::Array(vec![i(3), i(1), i(2)]);
+        let descending = Expressions::Function(function(&["a", "b"]));
+        // `|a, b| b - a`
+        let mut compare = |_: &Function, args: Vec<Expressions>| match args.as_slice() {
+            [
+                Expressions::Integer(crate::numbers::Integer::Small(a)),
+                Expressions::Integer(crate::numbers::Integer::Small(b)),
+            ] => Ok(i(b - a)),
+            _ => Ok(Expressions::Null),
+        };
+
+        assert_eq!(
+            call("sort", &[items.clone(), descending], &mut compare),
+            Some(Ok(Expressions::Array(vec![i(3), i(2), i(1)])))
+        );
+        // comparators are two items to a number, other functions are an error
+        let three = Expressions::Function(function(&["a", "b", "c"]));
+        assert_eq!(
+            call_with("sort", &[items, three]),
+            Some(Err(
+                "sort expects a 1-argument key function or a 2-argument comparator, got fn (a, b, c)"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_filter_any_and_all() {
+        let items = Expressions::Array(vec![i(1), i(2)]);
+        let f = Expressions::Function(function(&["x"]));
+        let mut even =
+            |_: &Function, args: Vec<Expressions>| Ok(Expressions::Boolean(args[0] == i(2)));
+
+        assert_eq!(
+            call("filter", &[items.clone(), f.clone()], &mut even),
+            Some(Ok(Expressions::Array(vec![i(2)])))
+        );
+        assert_eq!(
+            call("any", &[items.clone(), f.clone()], &mut even),
+            Some(Ok(Expressions::Boolean(true)))
+        );
+        assert_eq!(
+            call("all", &[items.clone(), f.clone()], &mut even),
+            Some(Ok(Expressions::Boolean(false)))
+        );
+        // other values than booleans and `null` are an error
+        assert!(matches!(call_with("filter", &[items, f]), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_capture() {
+        let body = Expressions::BinaryOperation {
+            left: Box::new(Expressions::Identifier("factor".to_string())),
+            op: "*".to_string(),
+            right: Box::new(Expressions::FunctionCall {
+                func: "format".to_string(),
+                args: vec![Expressions::String("{unit}s".to_string())],
+            }),
+        };
+        let vars = std::collections::HashMap::from([
+            ("unit".to_string(), Expressions::String("pod".to_string())),
+            ("factor".to_string(), i(2)),
+            ("unused".to_string(), Expressions::Null),
+        ]);
+        assert_eq!(
+            capture(&body, &vars),
+            vec![
+                ("factor".to_string(), i(2)),
+                ("unit".to_string(), Expressions::String("pod".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let mut f = function(&["a", "b"]);
+        assert_eq!(f.to_string(), "fn (a, b)");
+        f.name = Some("add".to_string());
+        assert_eq!(f.to_string(), "fn add(a, b)");
+    }
+}
@@ -7,6 +7,8 @@ pub struct History {
     pub interval: Option<u64>,
     pub path: Option<String>,
     pub events: Option<Vec<Event>>,
+    /// size, dedupe, ignore and backend settings
+    pub config: Option<crate::config::HistoryConfig>,
 }
 
 impl History {
@@ -14,25 +16,75 @@ impl History {
     pub fn new(path: Option<String>, interval: Option<u64>, debug: bool) -> Self {
         if let Some(p) = &path {
             let expanded_path = crate::utils::expand_path(p);
-            return History {
+            History {
                 debug,
                 path: Some(expanded_path),
                 events: Some(Vec::new()),
                 interval,
-            };
+                config: None,
+            }
         } else {
             let defaults = crate::config::Defaults::default();
-            return History {
+            History {
                 debug,
                 path: Some(crate::utils::expand_path(&defaults.history_path)),
                 events: Some(Vec::new()),
                 interval,
-            };
+                config: None,
+            }
+        }
+    }
+
+    /// apply history settings
+    ///
+    /// the path and flush interval take effect the next time history is started
+    pub fn configure(&mut self, config: crate::config::HistoryConfig) {
+        if let Some(path) = &config.path {
+            self.path = Some(crate::utils::expand_path(path));
+        }
+        if config.flush_interval.is_some() {
+            self.interval = config.flush_interval;
+        }
+        self.config = Some(config);
+        if let Some(events) = self.events.as_mut() {
+            Self::truncate(events, self.config.as_ref().and_then(|c| c.size));
         }
     }
 
+    /// whether history is kept in memory only
+    fn in_memory(&self) -> bool {
+        self.config.as_ref().and_then(|c| c.backend) == Some(crate::config::HistoryBackend::Memory)
+    }
+
+    /// drop the oldest commands, along with their responses, until at most `size` remain
+    fn truncate(events: &mut Vec<Event>, size: Option<usize>) {
+        let Some(size) = size else {
+            return;
+        };
+        let requests = events
+            .iter()
+            .filter(|e| matches!(e, Event::CommandRequest(_)))
+            .count();
+        if requests <= size {
+            return;
+        }
+        let dropped: std::collections::HashSet<String> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::CommandRequest(request) => Some(request.id.clone()),
+                _ => None,
+            })
+            .take(requests - size)
+            .collect();
+        events.retain(|e| !dropped.contains(e.id()));
+    }
+
     /// loads history from file
     pub fn load(&mut self) -> Option<Vec<crate::history::Event>> {
+        if self.in_memory() {
+            return None;
+        }
+
         if self.path.is_none() {
             println!("❌ history path is not set.");
             return None;
@@ -66,22 +118,59 @@ impl History {
             self.events = Some(Vec::new());
         }
         // TODO: we should be using the default value here
-        self.events = self.load();
+        self.events = self.load().or_else(|| Some(Vec::new()));
     }
 
     /// add an event to history
+    ///
+    /// commands matching `ignore` patterns, or repeating the previous command when
+    /// `dedupe` is enabled, are skipped along with their responses
     pub fn add(&mut self, event: Event) {
-        if let Some(events) = &mut self.events {
-            events.push(event);
+        let config = self.config.clone().unwrap_or_default();
+        let Some(events) = &mut self.events else {
+            return;
+        };
+        match &event {
+            Event::CommandRequest(request) => {
+                let line = request.line();
+                let ignored = config.ignore.iter().flatten().any(|pattern| {
+                    regex::Regex::new(pattern)
+                        .map(|re| re.is_match(&line))
+                        .unwrap_or(false)
+                });
+                if ignored {
+                    return;
+                }
+                let previous = events.iter().rev().find_map(|e| match e {
+                    Event::CommandRequest(previous) => Some(previous),
+                    _ => None,
+                });
+                if config.dedupe == Some(true) && previous.is_some_and(|p| p.line() == line) {
+                    return;
+                }
+            }
+            Event::CommandResponse(response) => {
+                let requested = events.iter().rev().any(
+                    |e| matches!(e, Event::CommandRequest(request) if request.id == response.id),
+                );
+                if !requested {
+                    return;
+                }
+            }
         }
+        events.push(event);
+        Self::truncate(events, config.size);
     }
 
     /// flush events to file periodically
     pub fn flush(&self) -> std::io::Result<()> {
         use std::io::Write;
+        if self.in_memory() {
+            return Ok(());
+        }
         let duration = std::time::Duration::from_secs(self.interval.unwrap_or(60));
         let path = self.path.clone().unwrap();
-        let events = self.events.clone().unwrap_or(Vec::new());
+        let events = self.events.clone().unwrap_or_default();
         let debug = self.debug;
 
         std::thread::spawn(move || {
@@ -109,6 +198,9 @@ impl History {
     /// serialize and save history to file
     pub fn save(&self) -> std::io::Result<()> {
         use std::io::Write;
+        if self.in_memory() {
+            return Ok(());
+        }
         let expanded_path = crate::utils::expand_path(self.path.as_ref().unwrap());
         let mut file = std::fs::OpenOptions::new()
             .create(true)
@@ -136,16 +228,314 @@ impl History {
             }
         }
     }
+
+    /// compute usage statistics from the recorded events
+    pub fn stats(&self) -> HistoryStats {
+        HistoryStats::from_events(self.events.as_deref().unwrap_or(&[]))
+    }
 }
 
-/// outputs for expression results
-#[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(tag = "event")]
-pub enum OutputResults {
-    #[serde(rename = "command_response")]
-    CommandResponse(CommandResponse),
-    #[serde(rename = "turtle_expression")]
-    TurtleExpression(crate::expressions::Expressions),
+/// usage statistics for a single command
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CommandStats {
+    pub command: String,
+    /// number of times the command was requested
+    pub count: usize,
+    /// number of responses with a non-zero exit code
+    pub failures: usize,
+    /// number of requests with a response, the runs that have an exit code and a duration
+    pub completed: usize,
+    /// total milliseconds spent across all completed runs
+    pub total_duration: u64,
+    /// longest single run in milliseconds
+    pub max_duration: u64,
+}
+
+impl CommandStats {
+    /// fraction of the completed runs that exited with a non-zero code
+    pub fn failure_rate(&self) -> f64 {
+        if self.completed == 0 {
+            return 0.0;
+        }
+        self.failures as f64 / self.completed as f64
+    }
+
+    /// average run time in seconds of the completed runs
+    pub fn average_duration(&self) -> f64 {
+        if self.completed == 0 {
+            return 0.0;
+        }
+        self.total_duration as f64 / self.completed as f64 / 1000.0
+    }
+
+    /// longest single run in seconds
+    pub fn longest_duration(&self) -> f64 {
+        self.max_duration as f64 / 1000.0
+    }
+}
+
+/// aggregated history statistics
+///
+/// built by pairing each `CommandRequest` with the `CommandResponse` sharing its id
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HistoryStats {
+    /// per command statistics, ordered by command name
+    pub commands: Vec<CommandStats>,
+    /// number of requests per hour of the day (local time, 0-23)
+    pub by_hour: std::collections::BTreeMap<u32, usize>,
+    /// number of requests per day of the week (local time, Mon-Sun)
+    pub by_day: std::collections::BTreeMap<u32, usize>,
+}
+
+impl HistoryStats {
+    /// available report sections
+    pub const SECTIONS: &'static [&'static str] =
+        &["commands", "failures", "slowest", "hours", "days"];
+
+    /// aggregate statistics from a list of history events
+    pub fn from_events(events: &[Event]) -> Self {
+        use chrono::{Datelike, Timelike};
+
+        let mut requests: std::collections::HashMap<&str, &CommandRequest> =
+            std::collections::HashMap::new();
+        let mut commands: std::collections::BTreeMap<String, CommandStats> =
+            std::collections::BTreeMap::new();
+        let mut stats = HistoryStats::default();
+
+        for event in events {
+            match event {
+                Event::CommandRequest(req) => {
+                    requests.insert(req.id.as_str(), req);
+                    let entry =
+                        commands
+                            .entry(req.command.clone())
+                            .or_insert_with(|| CommandStats {
+                                command: req.command.clone(),
+                                ..CommandStats::default()
+                            });
+                    entry.count += 1;
+
+                    if let Some(utc) = chrono::DateTime::from_timestamp(req.timestamp as i64, 0) {
+                        let local = utc.with_timezone(&chrono::Local);
+                        *stats.by_hour.entry(local.hour()).or_insert(0) += 1;
+                        *stats
+                            .by_day
+                            .entry(local.weekday().num_days_from_monday())
+                            .or_insert(0) += 1;
+                    }
+                }
+                Event::CommandResponse(res) => {
+                    // responses without a matching request can't be attributed to a command
+                    let Some(req) = requests.get(res.id.as_str()) else {
+                        continue;
+                    };
+                    if let Some(entry) = commands.get_mut(&req.command) {
+                        if res.code != 0 {
+                            entry.failures += 1;
+                        }
+                        let duration = res
+                            .duration
+                            .unwrap_or_else(|| res.timestamp.saturating_sub(req.timestamp) * 1000);
+                        entry.completed += 1;
+                        entry.total_duration += duration;
+                        entry.max_duration = entry.max_duration.max(duration);
+                    }
+                }
+            }
+        }
+
+        stats.commands = commands.into_values().collect();
+        stats
+    }
+
+    /// commands ordered by how often they were run
+    pub fn most_used(&self, limit: usize) -> Vec<&CommandStats> {
+        let mut commands: Vec<&CommandStats> = self.commands.iter().collect();
+        commands.sort_by(|a, b| b.count.cmp(&a.count).then(a.command.cmp(&b.command)));
+        commands.into_iter().take(limit).collect()
+    }
+
+    /// commands that failed at least once, ordered by failure rate
+    pub fn most_failing(&self, limit: usize) -> Vec<&CommandStats> {
+        let mut commands: Vec<&CommandStats> =
+            self.commands.iter().filter(|c| c.failures > 0).collect();
+        commands.sort_by(|a, b| {
+            b.failure_rate()
+                .total_cmp(&a.failure_rate())
+                .then(b.failures.cmp(&a.failures))
+                .then(a.command.cmp(&b.command))
+        });
+        commands.into_iter().take(limit).collect()
+    }
+
+    /// commands ordered by their longest run
+    pub fn slowest(&self, limit: usize) -> Vec<&CommandStats> {
+        let mut commands: Vec<&CommandStats> = self.commands.iter().collect();
+        commands.sort_by(|a, b| {
+            b.max_duration
+                .cmp(&a.max_duration)
+                .then(a.command.cmp(&b.command))
+        });
+        commands.into_iter().take(limit).collect()
+    }
+
+    /// the headers and rows of a single report section, counts and durations are numbers
+    ///
+    /// durations are in seconds and failure rates are fractions of the completed runs
+    pub fn rows(
+        &self,
+        section: &str,
+        limit: usize,
+    ) -> Option<(Vec<&'static str>, Vec<Vec<serde_json::Value>>)> {
+        use serde_json::json;
+
+        let rows = match section {
+            "commands" => (
+                vec!["command", "count", "failures", "avg_duration"],
+                self.most_used(limit)
+                    .iter()
+                    .map(|c| {
+                        vec![
+                            json!(c.command),
+                            json!(c.count),
+                            json!(c.failures),
+                            json!(c.average_duration()),
+                        ]
+                    })
+                    .collect(),
+            ),
+            "failures" => (
+                vec!["command", "failures", "completed", "failure_rate"],
+                self.most_failing(limit)
+                    .iter()
+                    .map(|c| {
+                        vec![
+                            json!(c.command),
+                            json!(c.failures),
+                            json!(c.completed),
+                            json!(c.failure_rate()),
+                        ]
+                    })
+                    .collect(),
+            ),
+            "slowest" => (
+                vec!["command", "max_duration", "avg_duration", "count"],
+                self.slowest(limit)
+                    .iter()
+                    .map(|c| {
+                        vec![
+                            json!(c.command),
+                            json!(c.longest_duration()),
+                            json!(c.average_duration()),
+                            json!(c.count),
+                        ]
+                    })
+                    .collect(),
+            ),
+            "hours" => (
+                vec!["hour", "count"],
+                self.by_hour
+                    .iter()
+                    .map(|(hour, count)| vec![json!(format!("{:02}:00", hour)), json!(count)])
+                    .collect(),
+            ),
+            "days" => (
+                vec!["day", "count"],
+                self.by_day
+                    .iter()
+                    .map(|(day, count)| {
+                        let name = chrono::Weekday::try_from(*day as u8)
+                            .map(|d| d.to_string())
+                            .unwrap_or_else(|_| day.to_string());
+                        vec![json!(name), json!(count)]
+                    })
+                    .collect(),
+            ),
+            _ => return None,
+        };
+        Some(rows)
+    }
+
+    /// build a table for a single report section
+    ///
+    /// durations are shown with two decimals and failure rates as percentages
+    pub fn table(&self, section: &str, limit: usize) -> Option<crate::expressions::OutputCsv> {
+        let (headers, rows) = self.rows(section, limit)?;
+        let data = rows
+            .iter()
+            .map(|row| {
+                headers
+                    .iter()
+                    .zip(row)
+                    .map(|(header, value)| match (value, value.as_f64()) {
+                        (serde_json::Value::String(text), _) => text.clone(),
+                        (_, Some(rate)) if *header == "failure_rate" => {
+                            format!("{:.1}%", rate * 100.0)
+                        }
+                        (_, Some(seconds)) if header.ends_with("_duration") => {
+                            format!("{:.2}", seconds)
+                        }
+                        (value, _) => value.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Some(crate::expressions::OutputCsv {
+            headers: headers.into_iter().map(String::from).collect(),
+            data,
+        })
+    }
+
+    /// render one section, or all sections when `section` is None, in the given output format
+    ///
+    /// tables render one `Outputs::Table` per section, json and yaml render a single document
+    pub fn outputs(
+        &self,
+        format: &str,
+        section: Option<&str>,
+        limit: usize,
+    ) -> Option<Vec<crate::expressions::Outputs>> {
+        let sections: Vec<&str> = match section {
+            Some(s) if Self::SECTIONS.contains(&s) => vec![s],
+            Some(_) => return None,
+            None => Self::SECTIONS.to_vec(),
+        };
+
+        match format {
+            "table" | "text" => sections
+                .iter()
+                .map(|s| self.table(s, limit).map(crate::expressions::Outputs::Table))
+                .collect(),
+            "json" | "yaml" => {
+                let mut document = serde_json::Map::new();
+                for s in &sections {
+                    let (headers, rows) = self.rows(s, limit)?;
+                    let rows = rows
+                        .into_iter()
+                        .map(|row| {
+                            serde_json::Value::Object(
+                                headers.iter().map(|h| h.to_string()).zip(row).collect(),
+                            )
+                        })
+                        .collect();
+                    document.insert(s.to_string(), serde_json::Value::Array(rows));
+                }
+                let data = serde_json::Value::Object(document);
+                if format == "json" {
+                    Some(vec![crate::expressions::Outputs::Json(
+                        crate::expressions::OutputJson { data },
+                    )])
+                } else {
+                    let data = serde_yaml::to_value(&data).ok()?;
+                    Some(vec![crate::expressions::Outputs::Yaml(
+                        crate::expressions::OutputYaml { data },
+                    )])
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
 /// a command request to the shell
@@ -158,6 +548,16 @@ pub struct CommandRequest {
     // pub event: String,
 }
 
+impl CommandRequest {
+    /// the command line, eg: `ls -la`
+    pub fn line(&self) -> String {
+        std::iter::once(self.command.as_str())
+            .chain(self.args.iter().map(|a| a.as_str()))
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
 /// a command response from the shell
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CommandResponse {
@@ -167,6 +567,10 @@ pub struct CommandResponse {
     pub output: String,
     pub errors: String,
     pub timestamp: u64,
+    /// run time in milliseconds, responses recorded without it fall back to the seconds
+    /// between the request and response timestamps
+    #[serde(default)]
+    pub duration: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -177,6 +581,16 @@ pub enum Event {
     CommandResponse(CommandResponse),
 }
 
+impl Event {
+    /// id shared by a request and its response
+    pub fn id(&self) -> &str {
+        match self {
+            Event::CommandRequest(request) => &request.id,
+            Event::CommandResponse(response) => &response.id,
+        }
+    }
+}
+
 impl std::fmt::Display for Event {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -229,19 +643,18 @@ impl Event {
             Event::CommandRequest(req) => {
                 let mut wtr = csv::Writer::from_writer(vec![]);
                 wtr.serialize(req).unwrap();
-                let data = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
-                data
+                String::from_utf8(wtr.into_inner().unwrap()).unwrap()
             }
             Event::CommandResponse(res) => {
                 let mut wtr = csv::Writer::from_writer(vec![]);
                 wtr.serialize(res).unwrap();
-                let data = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
-                data
+                String::from_utf8(wtr.into_inner().unwrap()).unwrap()
             }
         }
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -279,6 +692,7 @@ mod tests {
             output: "Hello, World!".to_string(),
             errors: "".to_string(),
             timestamp: 1625247601,
+            duration: Some(250),
         };
 
         let event = Event::CommandResponse(cmd_res.clone());
@@ -293,8 +707,170 @@ mod tests {
                 assert_eq!(res.output, cmd_res.output);
                 assert_eq!(res.errors, cmd_res.errors);
                 assert_eq!(res.timestamp, cmd_res.timestamp);
+                assert_eq!(res.duration, cmd_res.duration);
             }
             _ => panic!("Deserialized event is not a CommandResponse"),
         }
     }
+
+    fn request(id: &str, command: &str, timestamp: u64) -> Event {
+        Event::CommandRequest(CommandRequest {
+            id: id.to_string(),
+            command: command.to_string(),
+            args: vec![],
+            timestamp,
+        })
+    }
+
+    fn response(id: &str, code: i32, timestamp: u64) -> Event {
+        Event::CommandResponse(CommandResponse {
+            id: id.to_string(),
+            status: "completed".to_string(),
+            code,
+            output: "".to_string(),
+            errors: "".to_string(),
+            timestamp,
+            duration: None,
+        })
+    }
+
+    #[test]
+    fn test_history_add_settings() {
+        let mut history = History::new(
+            Some("/tmp/turtle_history_test.json".to_string()),
+            None,
+            false,
+        );
+        history.configure(crate::config::HistoryConfig {
+            size: Some(2),
+            dedupe: Some(true),
+            ignore: Some(vec!["^secret".to_string()]),
+            backend: Some(crate::config::HistoryBackend::Memory),
+            ..Default::default()
+        });
+        assert!(history.load().is_none());
+
+        history.add(request("1", "ls", 1));
+        history.add(response("1", 0, 2));
+        // repeated command
+        history.add(request("2", "ls", 3));
+        history.add(response("2", 0, 4));
+        // ignored command
+        history.add(request("3", "secret", 5));
+        history.add(response("3", 0, 6));
+        history.add(request("4", "pwd", 7));
+        history.add(request("5", "whoami", 8));
+
+        let ids: Vec<&str> = history
+            .events
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|e| e.id())
+            .collect();
+        assert_eq!(ids, vec!["4", "5"]);
+    }
+
+    #[test]
+    fn test_history_stats() {
+        let events = vec![
+            request("1", "ls", 1625247600),
+            response("1", 0, 1625247604),
+            request("2", "git", 1625247610),
+            response("2", 1, 1625247615),
+            request("3", "git", 1625247620),
+            response("3", 0, 1625247622),
+            request("4", "git", 1625247630),
+            response("4", 0, 1625247630),
+            // still running, without a duration
+            request("5", "ls", 1625247640),
+        ];
+        let stats = HistoryStats::from_events(&events);
+
+        let most_used = stats.most_used(1);
+        assert_eq!(most_used.len(), 1);
+        assert_eq!(most_used[0].command, "git");
+        assert_eq!(most_used[0].count, 3);
+        assert_eq!(most_used[0].failures, 1);
+        assert_eq!(most_used[0].max_duration, 5000);
+        assert!((most_used[0].average_duration() - 7.0 / 3.0).abs() < 1e-9);
+
+        let failing = stats.most_failing(10);
+        assert_eq!(failing.len(), 1);
+        assert!((failing[0].failure_rate() - 1.0 / 3.0).abs() < f64::EPSILON);
+
+        assert_eq!(stats.slowest(10)[0].command, "git");
+        assert_eq!(stats.by_hour.values().sum::<usize>(), 5);
+        assert_eq!(stats.by_day.values().sum::<usize>(), 5);
+
+        // the run of `ls` without a response doesn't count towards its average or failure rate
+        let ls = stats.commands.iter().find(|c| c.command == "ls").unwrap();
+        assert_eq!((ls.count, ls.completed), (2, 1));
+        assert!((ls.average_duration() - 4.0).abs() < f64::EPSILON);
+        assert_eq!(ls.failure_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_history_stats_durations() {
+        let timed = |id: &str, code: i32, duration: u64| {
+            Event::CommandResponse(CommandResponse {
+                id: id.to_string(),
+                status: "completed".to_string(),
+                code,
+                output: "".to_string(),
+                errors: "".to_string(),
+                timestamp: 1625247600,
+                duration: Some(duration),
+            })
+        };
+        let events = vec![
+            request("1", "ls", 1625247600),
+            timed("1", 0, 120),
+            request("2", "ls", 1625247600),
+            timed("2", 1, 380),
+            // still running
+            request("3", "ls", 1625247600),
+        ];
+        let stats = HistoryStats::from_events(&events);
+        let ls = &stats.commands[0];
+
+        assert_eq!(ls.max_duration, 380);
+        assert!((ls.average_duration() - 0.25).abs() < f64::EPSILON);
+        assert!((ls.longest_duration() - 0.38).abs() < f64::EPSILON);
+        assert!((ls.failure_rate() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_history_stats_outputs() {
+        let events = vec![request("1", "ls", 1625247600), response("1", 2, 1625247601)];
+        let stats = HistoryStats::from_events(&events);
+
+        let tables = stats.outputs("table", None, 10).unwrap();
+        assert_eq!(tables.len(), HistoryStats::SECTIONS.len());
+
+        match &stats.outputs("json", Some("failures"), 10).unwrap()[0] {
+            crate::expressions::Outputs::Json(json) => {
+                assert_eq!(json.data["failures"][0]["command"], "ls");
+                assert_eq!(json.data["failures"][0]["failure_rate"], 1.0);
+                assert_eq!(json.data["failures"][0]["completed"], 1);
+            }
+            _ => panic!("expected json output"),
+        }
+
+        match &stats.outputs("json", Some("slowest"), 10).unwrap()[0] {
+            crate::expressions::Outputs::Json(json) => {
+                assert_eq!(json.data["slowest"][0]["max_duration"], 1.0);
+                assert_eq!(json.data["slowest"][0]["count"], 1);
+            }
+            _ => panic!("expected json output"),
+        }
+
+        let table = stats.table("failures", 10).unwrap();
+        assert_eq!(table.data[0], vec!["ls", "1", "1", "100.0%"]);
+        let table = stats.table("slowest", 10).unwrap();
+        assert_eq!(table.data[0], vec!["ls", "1.00", "1.00", "1"]);
+
+        assert!(stats.outputs("table", Some("unknown"), 10).is_none());
+        assert!(stats.outputs("xml", None, 10).is_none());
+    }
 }
@@ -0,0 +1,412 @@
+/// HTTP requests: the `http` client, `http.get`, `http.post`, `http.put` and `http.delete`
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// requests are sent with `reqwest` on the tokio runtime of the shell and awaited, so scripts
+/// read like blocking code, eg: `http.get(url).body.items`. responses are objects with the
+/// `status`, `headers` and `body` of the response, a status of 4xx or 5xx isn't an error.
+use crate::expressions::Expressions;
+use serde::{Deserialize, Serialize};
+
+/// the name of the default client, eg: `http.get(url)`, unless a variable is named `http`
+pub const CLIENT: &str = "http";
+
+/// request methods, each is called on a client, eg: `http.post(url, {body: data})`
+pub const METHODS: &[&str] = &["get", "post", "put", "delete"];
+
+/// how long a request may take when no `timeout` is given
+const DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// the wait before the first retry, it doubles with each retry
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// an http client, the options its requests start from
+///
+/// `http` is the client without options, `http(base, options)` makes one, eg:
+/// `http("https://api.internal/v1", {headers: {authorization: token}, retries: 2})`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Client {
+    /// prefix of relative urls, eg: `https://api.internal/v1`
+    pub base: Option<String>,
+    /// headers sent with each request
+    pub headers: Vec<(String, String)>,
+    /// how long a request may take, 30s when not set
+    pub timeout: Option<crate::dates::Duration>,
+    /// how many times a request is retried after a connection error, a timeout, a 429 or a 5xx
+    pub retries: u32,
+}
+
+impl std::fmt::Display for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.base {
+            Some(base) => write!(f, "http({})", base),
+            None => write!(f, "http"),
+        }
+    }
+}
+
+impl Client {
+    /// the url of a request, relative urls are joined to the base, eg: `/pods`
+    fn url(&self, url: &str) -> String {
+        match &self.base {
+            Some(base) if !url.contains("://") => format!(
+                "{}/{}",
+                base.trim_end_matches('/'),
+                url.trim_start_matches('/')
+            ),
+            _ => url.to_string(),
+        }
+    }
+}
+
+/// the options of a request, the client is updated with its `headers`, `timeout` and `retries`
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    client: Client,
+    /// strings are sent as they are, arrays and objects as json, other values as `{}` shows them
+    body: Option<Expressions>,
+    /// a path the body is streamed to, rather than read into the response
+    output: Option<Expressions>,
+}
+
+/// read request options, eg: `{headers: {accept: "text/plain"}, body: data, timeout: 5s}`
+///
+/// - `headers` is an object, they replace the headers of the client with the same name
+/// - `body` is the body of the request
+/// - `timeout` is a duration or a number of seconds
+/// - `retries` is how many times to retry, see `Client::retries`
+/// - `output` is a path to stream the body of the response to, the `body` of the response is
+///   the path
+fn options(client: &Client, options: Option<&Expressions>) -> Result<Options, String> {
+    let entries = match options {
+        None => &[][..],
+        Some(Expressions::Object(entries)) => entries.as_slice(),
+        Some(other) => {
+            return Err(format!(
+                "expected options like `{{headers, body, timeout}}`, got {}",
+                crate::format::display(other)
+            ));
+        }
+    };
+
+    let mut options = Options {
+        client: client.clone(),
+        ..Default::default()
+    };
+    for (key, value) in entries {
+        match (key.as_str(), value) {
+            ("headers", Expressions::Object(headers)) => {
+                for (name, value) in headers {
+                    let headers = &mut options.client.headers;
+                    headers.retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+                    headers.push((name.clone(), crate::format::display(value)));
+                }
+            }
+            ("body", body) => options.body = Some(body.clone()),
+            ("timeout", Expressions::Duration(timeout)) if timeout.to_nanos() > 0 => {
+                options.client.timeout = Some(*timeout)
+            }
+            ("timeout", Expressions::Number(seconds)) if *seconds > 0.0 => {
+                options.client.timeout = crate::dates::Duration::from_seconds(*seconds)
+            }
+            ("timeout", Expressions::Integer(seconds)) if !seconds.is_negative() => {
+                options.client.timeout = crate::dates::Duration::from_seconds(seconds.to_f64())
+            }
+            ("retries", Expressions::Integer(retries)) => {
+                options.client.retries = retries
+                    .to_i64()
+                    .and_then(|retries| u32::try_from(retries).ok())
+                    .ok_or(format!(
+                        "retries must be a positive integer, got {}",
+                        retries
+                    ))?
+            }
+            ("output", output) => {
+                crate::paths::to_path(output)?;
+                options.output = Some(output.clone())
+            }
+            ("headers", _) => return Err("headers must be an object".to_string()),
+            ("timeout", _) => return Err("timeout must be a positive duration, eg: 5s".to_string()),
+            ("retries", _) => return Err("retries must be a positive integer".to_string()),
+            (key, _) => {
+                return Err(format!(
+                    "unknown option `{}`, expected headers, body, timeout, retries or output",
+                    key
+                ));
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// the value of a response body, json when the content type says so, text otherwise
+fn body_value(content_type: Option<&str>, text: String) -> Expressions {
+    let json = content_type.is_some_and(|content_type| content_type.contains("json"));
+    if json && text.trim().is_empty() {
+        return Expressions::Null;
+    }
+    match json.then(|| serde_json::from_str::<serde_json::Value>(&text)) {
+        Some(Ok(value)) => Expressions::from_json(&value),
+        _ => Expressions::String(text),
+    }
+}
+
+/// call an http function, `http` or a request method on a client
+///
+/// returns `None` for other functions, so the caller can try them
+///
+/// - `http(base)` and `http(base, options)` are clients, see `Client`
+/// - `get(client, url, options)`, `post`, `put` and `delete` send a request, usually called as
+///   methods, eg: `http.get(url)`, see `options` for the options
+pub fn call(func: &str, args: &[Expressions]) -> Option<Result<Expressions, String>> {
+    let result = match (func, args) {
+        (CLIENT, [Expressions::String(base), rest @ ..]) if rest.len() <= 1 => {
+            options(&Client::default(), rest.first()).and_then(|options| {
+                if options.body.is_some() || options.output.is_some() {
+                    return Err("http: `body` and `output` are options of requests".to_string());
+                }
+                Ok(Expressions::HttpClient(Client {
+                    base: Some(base.clone()),
+                    ..options.client
+                }))
+            })
+        }
+        (CLIENT, _) => Err("http: expected a base url and options, eg: http(url, {})".to_string()),
+        (
+            method,
+            [
+                Expressions::HttpClient(client),
+                Expressions::String(url),
+                rest @ ..,
+            ],
+        ) if METHODS.contains(&method) && rest.len() <= 1 => options(client, rest.first())
+            .and_then(|options| block_on(request(method, client.url(url), options)))
+            .map_err(|e| format!("{}: {}", method, e)),
+        (method, [Expressions::HttpClient(_), ..]) if METHODS.contains(&method) => Err(format!(
+            "{}: expected a url and options, eg: http.{}(url, {{timeout: 5s}})",
+            method, method
+        )),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// wait for a request from the synchronous evaluator
+///
+/// the shell runs on a multi threaded runtime, where the request is awaited in place. elsewhere,
+/// eg: in tests, it is awaited on a runtime of its own
+fn block_on<F>(future: F) -> Result<Expressions, String>
+where
+    F: std::future::Future<Output = Result<Expressions, String>> + Send,
+{
+    if let Ok(handle) = tokio::runtime::Handle::try_current()
+        && handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread
+    {
+        return tokio::task::block_in_place(|| handle.block_on(future));
+    }
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(|e| format!("can't start a runtime: {}", e))?
+                    .block_on(future)
+            })
+            .join()
+            .unwrap_or_else(|_| Err("the request panicked".to_string()))
+    })
+}
+
+/// send a request, retrying it as the options allow, and read its response
+async fn request(method: &str, url: String, options: Options) -> Result<Expressions, String> {
+    let method =
+        reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|e| e.to_string())?;
+    let client = reqwest::Client::builder()
+        .timeout(
+            options
+                .client
+                .timeout
+                .and_then(|timeout| timeout.0.to_std().ok())
+                .unwrap_or(DEFAULT_TIMEOUT),
+        )
+        .build()
+        .map_err(|e| e.to_string())?;
+    let body = options.body.as_ref().map(|body| match body {
+        Expressions::String(text) => (text.clone(), None),
+        Expressions::Array(_) | Expressions::Object(_) => {
+            (body.to_json().to_string(), Some("application/json"))
+        }
+        other => (crate::format::display(other), None),
+    });
+
+    let mut attempt = 0;
+    let mut response = loop {
+        let mut request = client.request(method.clone(), &url);
+        for (name, value) in &options.client.headers {
+            request = request.header(name, value);
+        }
+        if let Some((body, content_type)) = &body {
+            let typed = options
+                .client
+                .headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+            if let Some(content_type) = content_type
+                && !typed
+            {
+                request = request.header(reqwest::header::CONTENT_TYPE, *content_type);
+            }
+            request = request.body(body.clone());
+        }
+
+        let response = request.send().await;
+        let retry = match &response {
+            Ok(response) => {
+                response.status().is_server_error()
+                    || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(e) => e.is_connect() || e.is_timeout(),
+        };
+        if !retry || attempt >= options.client.retries {
+            break response.map_err(|e| format!("{}: {}", url, e))?;
+        }
+        tokio::time::sleep(RETRY_DELAY * 2u32.saturating_pow(attempt)).await;
+        attempt += 1;
+    };
+
+    let status = Expressions::Integer(i64::from(response.status().as_u16()).into());
+    let mut headers: Vec<(String, Expressions)> = Vec::new();
+    for name in response.headers().keys() {
+        let values: Vec<String> = response
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+            .collect();
+        headers.push((name.to_string(), Expressions::String(values.join(", "))));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let body = match &options.output {
+        Some(output) => {
+            use tokio::io::AsyncWriteExt;
+            let path = crate::paths::to_path(output)?;
+            let mut file = tokio::fs::File::create(&path)
+                .await
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+                file.write_all(&chunk)
+                    .await
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+            file.flush()
+                .await
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            crate::paths::from_text(&crate::format::display(output))
+        }
+        None => body_value(
+            content_type.as_deref(),
+            response.text().await.map_err(|e| e.to_string())?,
+        ),
+    };
+
+    Ok(Expressions::Object(vec![
+        ("status".to_string(), status),
+        ("headers".to_string(), Expressions::Object(headers)),
+        ("body".to_string(), body),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    fn object(entries: &[(&str, Expressions)]) -> Expressions {
+        Expressions::Object(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_request_options() {
+        let Some(Ok(Expressions::HttpClient(client))) = call(
+            CLIENT,
+            &[
+                s("https://api.internal/v1/"),
+                object(&[
+                    ("headers", object(&[("Accept", s("text/plain"))])),
+                    ("retries", Expressions::Integer(2.into())),
+                ]),
+            ],
+        ) else {
+            panic!("expected a client");
+        };
+        assert_eq!(client.url("/pods"), "https://api.internal/v1/pods");
+        assert_eq!(client.url("http://other/x"), "http://other/x");
+        assert_eq!(client.retries, 2);
+
+        let options = options(
+            &client,
+            Some(&object(&[
+                ("headers", object(&[("accept", s("application/json"))])),
+                ("timeout", Expressions::Number(1.5)),
+                ("body", s("hi")),
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            options.client.headers,
+            vec![("accept".to_string(), "application/json".to_string())]
+        );
+        assert_eq!(options.client.timeout.unwrap().to_string(), "1s500ms");
+        assert_eq!(options.body, Some(s("hi")));
+
+        let invalid = |entries: &[(&str, Expressions)]| {
+            super::options(&Client::default(), Some(&object(entries))).is_err()
+        };
+        assert!(invalid(&[("timeout", Expressions::Integer((-1).into()))]));
+        assert!(invalid(&[("retries", s("3"))]));
+        assert!(invalid(&[("header", object(&[]))]));
+        assert!(matches!(
+            call(CLIENT, &[s("https://x"), object(&[("body", s("hi"))])]),
+            Some(Err(_))
+        ));
+        assert_eq!(call("get", &[s("https://x")]), None);
+    }
+
+    #[test]
+    fn test_body_value() {
+        assert_eq!(
+            body_value(Some("application/json; charset=utf-8"), "[1]".to_string()),
+            Expressions::Array(vec![Expressions::Integer(1.into())])
+        );
+        assert_eq!(
+            body_value(Some("application/problem+json"), "".to_string()),
+            Expressions::Null
+        );
+        assert_eq!(body_value(Some("text/plain"), "[1]".to_string()), s("[1]"));
+        assert_eq!(
+            body_value(Some("application/json"), "{".to_string()),
+            s("{")
+        );
+        assert_eq!(body_value(None, "ok".to_string()), s("ok"));
+    }
+}
@@ -8,10 +8,10 @@
 ///
 /// See LICENSE for details.
 ///
-
 /// Turtle language keywords
 pub static KEYWORDS: &[&str] = &[
     "New", "If", "Elseif", "Else", "While", "For", "Break", "Fn", "Return", "Let", "Set", "Null",
+    "Try", "Catch", "Finally", "Throw", "Match",
 ];
 
 /// Abstract Syntax Tree
@@ -39,13 +39,26 @@ struct AbstractSyntaxTree {
 
     /// current position in tokens
     pos: usize,
+
+    /// how many `{ ... }` blocks the parser is in, commands and builtins end at a newline or
+    /// the closing `}` of a block
+    blocks: usize,
 }
 
 impl AbstractSyntaxTree {
     fn get_operator_precedence(&self, op: &str) -> u8 {
         match op {
-            "*" | "/" | "%" => 2,
-            "+" | "-" => 1,
+            "**" => 11,
+            "*" | "/" | "//" | "%" => 10,
+            "+" | "-" => 9,
+            "<<" | ">>" => 8,
+            "&" => 7,
+            "^" => 6,
+            "|" => 5,
+            "==" | "!=" | "<" | "<=" | ">" | ">=" | "=~" | "!~" => 4,
+            "&&" => 3,
+            "||" => 2,
+            "??" => 1,
             _ => 0,
         }
     }
@@ -64,6 +77,7 @@ impl AbstractSyntaxTree {
         AbstractSyntaxTree {
             parsed: tokens,
             pos: 0,
+            blocks: 0,
             builtins,
             env,
             aliases,
@@ -141,10 +155,48 @@ impl AbstractSyntaxTree {
         }
     }
 
+    /// skip spaces and tabs, newlines end statements so they are kept
+    fn skip_spaces(&mut self) {
+        while matches!(
+            self.peek(),
+            crate::tokens::Token::Space | crate::tokens::Token::Tab
+        ) {
+            self.next();
+        }
+    }
+
+    /// whether the current token ends the statement of a block, a newline or the closing `}`
+    fn at_block_end(&self) -> bool {
+        self.blocks > 0
+            && matches!(
+                self.peek(),
+                crate::tokens::Token::Newline | crate::tokens::Token::BraceClose
+            )
+    }
+
     /// parse literal values (Numbers, Strings, Booleans)
     fn parse_literal(&mut self) -> Option<crate::expressions::Expressions> {
         match self.next() {
-            crate::tokens::Token::Number(n) => Some(crate::expressions::Expressions::Number(*n)),
+            crate::tokens::Token::Number(n, _) => Some(crate::expressions::Expressions::Number(*n)),
+            crate::tokens::Token::Integer(n, _) => {
+                Some(crate::expressions::Expressions::Integer(n.clone()))
+            }
+            crate::tokens::Token::Null => Some(crate::expressions::Expressions::Null),
+            crate::tokens::Token::Duration(text) => {
+                crate::dates::Duration::parse(text).map(crate::expressions::Expressions::Duration)
+            }
+            crate::tokens::Token::ShellDirectory { segments } => {
+                Some(crate::expressions::Expressions::Path {
+                    segments: segments.clone(),
+                })
+            }
+            crate::tokens::Token::ShellFile { path, .. } => Some(crate::paths::from_text(path)),
+            crate::tokens::Token::RegularExpression { pattern, flags } => {
+                Some(crate::expressions::Expressions::RegularExpression {
+                    pattern: pattern.clone(),
+                    flags: flags.clone(),
+                })
+            }
             crate::tokens::Token::String(s) => {
                 Some(crate::expressions::Expressions::String(s.clone()))
             }
@@ -152,90 +204,220 @@ impl AbstractSyntaxTree {
                 println!("Parsing boolean literal: {}", b);
                 Some(crate::expressions::Expressions::Boolean(*b))
             }
+            crate::tokens::Token::TemplateString(parts) => {
+                let parts = parts.clone();
+                self.parse_template(parts)
+            }
+            // f-strings are formatted with the turtle variables, see `crate::format`
+            crate::tokens::Token::FormatString(s) => {
+                let s = s.clone();
+                self.parse_format_string(&s)
+            }
             _ => None,
         }
     }
 
+    /// parse an f-string into a call of `format`
+    ///
+    /// names are looked up as turtle variables, other expressions before the spec are passed
+    /// as arguments, eg: `f"{1 + 2:>5}"` is `format("{0:>5}", 1 + 2)`
+    fn parse_format_string(&self, raw: &str) -> Option<crate::expressions::Expressions> {
+        let mut template = String::new();
+        let mut args = vec![];
+        let mut chars = raw.chars().peekable();
+        while let Some(c) = chars.next() {
+            template.push(c);
+            if c != '{' {
+                continue;
+            }
+            if chars.peek() == Some(&'{') {
+                template.extend(chars.next());
+                continue;
+            }
+
+            // the placeholder ends at a `}` outside of brackets, its spec at a `:` outside of them
+            let mut placeholder = String::new();
+            let mut spec = None;
+            let mut depth = 0;
+            let mut closed = false;
+            for d in chars.by_ref() {
+                match d {
+                    '(' | '[' | '{' => depth += 1,
+                    '}' if depth == 0 => {
+                        closed = true;
+                        break;
+                    }
+                    ')' | ']' | '}' => depth -= 1,
+                    ':' if depth == 0 && spec.is_none() => spec = Some(placeholder.len()),
+                    _ => {}
+                }
+                placeholder.push(d);
+            }
+            let (argument, spec) = placeholder.split_at(spec.unwrap_or(placeholder.len()));
+            let name = argument.trim();
+            if name.is_empty()
+                || name.chars().all(|c| c.is_ascii_digit())
+                || name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            {
+                template.push_str(&placeholder);
+            } else {
+                let mut interpreter = Interpreter::new(
+                    self.args.clone(),
+                    self.env.clone(),
+                    self.aliases.clone(),
+                    self.vars.clone(),
+                    self.builtins.clone(),
+                    false,
+                );
+                let mut tokens = interpreter.tokenize_primitives(name);
+                tokens.retain(|t| *t != crate::tokens::Token::Eof);
+                let mut parser = AbstractSyntaxTree::new(
+                    tokens,
+                    self.builtins.clone(),
+                    self.env.clone(),
+                    self.aliases.clone(),
+                    self.vars.clone(),
+                    self.args.clone(),
+                );
+                template.push_str(&format!("{}{}", args.len(), spec));
+                args.push(parser.parse_expr()?);
+            }
+            if closed {
+                template.push('}');
+            }
+        }
+
+        let mut call_args = vec![crate::expressions::Expressions::String(template)];
+        call_args.extend(args);
+        Some(crate::expressions::Expressions::FunctionCall {
+            func: "format".to_string(),
+            args: call_args,
+        })
+    }
+
+    /// parse the parts of a template string, each `${expr}` is parsed on its own
+    fn parse_template(
+        &mut self,
+        parts: Vec<crate::tokens::Token>,
+    ) -> Option<crate::expressions::Expressions> {
+        let mut expressions = Vec::new();
+        for part in parts {
+            match part {
+                crate::tokens::Token::String(s) => {
+                    expressions.push(crate::expressions::Expressions::String(s))
+                }
+                crate::tokens::Token::CodeBlock(tokens) => {
+                    let mut parser = AbstractSyntaxTree::new(
+                        tokens,
+                        self.builtins.clone(),
+                        self.env.clone(),
+                        self.aliases.clone(),
+                        self.vars.clone(),
+                        self.args.clone(),
+                    );
+                    expressions.push(parser.parse_expr()?);
+                }
+                _ => return None,
+            }
+        }
+        Some(crate::expressions::Expressions::TemplateString { parts: expressions })
+    }
+
     /// parse unary expressions
     ///
-    /// ```
+    /// ```text
     /// -5
     /// !true
     /// ~false
     /// ``````
     fn parse_unary(&mut self) -> Option<crate::expressions::Expressions> {
-        // let args = self.args.as_ref().map(|a| a.lock().unwrap()).unwrap();
         self.skip_whitespace();
 
-        // match self.peek() {}
-        //
-        match self.peek() {
-            crate::Token::AdditionOperator => {
-                self.next(); // consume operator
-                self.skip_whitespace();
+        let op = match self.peek() {
+            crate::tokens::Token::AdditionOperator => "+",
+            crate::tokens::Token::SubtractionOperator => "-",
+            crate::tokens::Token::Operator(op) if op == "-" => "-",
+            crate::tokens::Token::NotOperator => "!",
+            crate::tokens::Token::BitwiseNotOperator => "~",
+            _ => return None,
+        };
+        self.next(); // consume operator
+        self.skip_whitespace();
 
-                if let Some(expr) = self.parse_expr() {
-                    return Some(crate::expressions::Expressions::UnaryOperation {
-                        op: "+".to_string(),
-                        expr: Box::new(expr),
-                    });
-                } else {
+        // unary operators bind tighter than any binary operator, eg: `-2 * 3`
+        let expr = self.parse_primary()?;
+        Some(crate::expressions::Expressions::UnaryOperation {
+            op: op.to_string(),
+            expr: Box::new(expr),
+        })
+    }
+
+    /// the binary operator at the current position and its precedence, if any
+    ///
+    /// `|` pipes into a stage when a stage name follows it, eg: `x | count`,
+    /// and is bitwise or otherwise, eg: `x | 0b100`
+    fn binary_operator(&self) -> Option<(&'static str, u8)> {
+        let op = match self.parsed.get(self.pos)? {
+            crate::tokens::Token::Operator(op) if op == "-" => "-",
+            crate::tokens::Token::PipeOperator => {
+                let stage = self.parsed.iter().skip(self.pos + 1).find(|token| {
+                    !matches!(
+                        token,
+                        crate::tokens::Token::Space
+                            | crate::tokens::Token::Tab
+                            | crate::tokens::Token::Newline
+                    )
+                });
+                if let Some(crate::tokens::Token::Identifier(_)) = stage {
                     return None;
                 }
+                "|"
             }
-            _ => {}
+            token => Self::symbol_text(token)?,
+        };
+        match self.get_operator_precedence(op) {
+            0 => None,
+            precedence => Some((op, precedence)),
         }
-        // if let crate::tokens::Token::Operator(op) = self.peek() {
-        //     if op == "-" || op == "!" || op == "~" {
-        //         let op = op.clone();
-
-        //         self.next(); // consume operator
-        //         self.skip_whitespace();
+    }
 
-        //         if let Some(expr) = self.parse_expr() {
-        //             return Some(crate::expressions::Expressions::UnaryOperation {
-        //                 op,
-        //                 expr: Box::new(expr),
-        //             });
-        //         }
-        //         if let crate::tokens::Token::SubtractionOperator = self.peek() {
-        //             self.next(); // consume operator
-        //             self.skip_whitespace();
-        //             if let Some(expr) = self.parse_expr() {
-        //                 return Some(crate::expressions::Expressions::UnaryOperation {
-        //                     op: "-".to_string(),
-        //                     expr: Box::new(expr),
-        //                 });
-        //             } else {
-        //                 return None;
-        //             }
-        //         } else if let crate::tokens::Token::NotOperator = self.peek() {
-        //             self.next(); // consume operator
-        //             self.skip_whitespace();
-        //             if let Some(expr) = self.parse_expr() {
-        //                 return Some(crate::expressions::Expressions::UnaryOperation {
-        //                     op: "!".to_string(),
-        //                     expr: Box::new(expr),
-        //                 });
-        //             } else {
-        //                 return None;
-        //             }
-        //         } else if let crate::tokens::Token::BitwiseNotOperator = self.peek() {
-        //             self.next(); // consume operator
-        //             self.skip_whitespace();
-        //             if let Some(expr) = self.parse_expr() {
-        //                 return Some(crate::expressions::Expressions::UnaryOperation {
-        //                     op: "~".to_string(),
-        //                     expr: Box::new(expr),
-        //                 });
-        //             } else {
-        //                 return None;
-        //             }
-        //         }
-        //         None
-        //     }
-        // }
-        None
+    /// the text of an operator or punctuation token, eg: `==` for `EqualOperator`
+    fn symbol_text(token: &crate::tokens::Token) -> Option<&'static str> {
+        let text = match token {
+            crate::tokens::Token::ExponentiationOperator => "**",
+            crate::tokens::Token::AdditionOperator => "+",
+            crate::tokens::Token::SubtractionOperator => "-",
+            crate::tokens::Token::MultiplicationOperator => "*",
+            crate::tokens::Token::DivisionOperator => "/",
+            crate::tokens::Token::FloorDivisionOperator => "//",
+            crate::tokens::Token::ModulusOperator => "%",
+            crate::tokens::Token::BitwiseAndOperator => "&",
+            crate::tokens::Token::BitwiseXorOperator => "^",
+            crate::tokens::Token::BitwiseNotOperator => "~",
+            crate::tokens::Token::ShiftLeftOperator => "<<",
+            crate::tokens::Token::ShiftRightOperator => ">>",
+            crate::tokens::Token::AssignmentOperator => "=",
+            crate::tokens::Token::EqualOperator => "==",
+            crate::tokens::Token::NotEqualOperator => "!=",
+            crate::tokens::Token::RegexMatchOperator => "=~",
+            crate::tokens::Token::RegexNotMatchOperator => "!~",
+            crate::tokens::Token::LessThanOperator => "<",
+            crate::tokens::Token::GreaterThanOperator => ">",
+            crate::tokens::Token::LessThanOrEqualOperator => "<=",
+            crate::tokens::Token::GreaterThanOrEqualOperator => ">=",
+            crate::tokens::Token::LogicalAndOperator => "&&",
+            crate::tokens::Token::LogicalOrOperator => "||",
+            crate::tokens::Token::PipeOperator => "|",
+            crate::tokens::Token::OptionalChainOperator => "?.",
+            crate::tokens::Token::NullCoalescingOperator => "??",
+            crate::tokens::Token::NotOperator => "!",
+            crate::tokens::Token::DollarOperator => "$",
+            crate::tokens::Token::Arrow => "->",
+            crate::tokens::Token::FatArrow => "=>",
+            crate::tokens::Token::Colon => ":",
+            _ => return None,
+        };
+        Some(text)
     }
 
     /// parse binary expressions with operator precedence
@@ -245,47 +427,11 @@ impl AbstractSyntaxTree {
         mut left: crate::expressions::Expressions,
     ) -> crate::expressions::Expressions {
         loop {
-            let (op_str, precedence) = match self.peek() {
-                crate::tokens::Token::ExponentiationOperator => {
-                    ("**", self.get_operator_precedence("**"))
-                }
-                crate::tokens::Token::AdditionOperator => ("+", self.get_operator_precedence("+")),
-                crate::tokens::Token::SubtractionOperator => {
-                    ("-", self.get_operator_precedence("-"))
-                }
-                crate::tokens::Token::MultiplicationOperator => {
-                    ("*", self.get_operator_precedence("*"))
-                }
-                crate::tokens::Token::DivisionOperator => ("/", self.get_operator_precedence("/")),
-                crate::tokens::Token::ModulusOperator => ("%", self.get_operator_precedence("%")),
-                crate::tokens::Token::EqualOperator => ("==", self.get_operator_precedence("==")),
-                crate::tokens::Token::NotEqualOperator => {
-                    ("!=", self.get_operator_precedence("!="))
-                }
-                crate::tokens::Token::LessThanOperator => ("<", self.get_operator_precedence("<")),
-                crate::tokens::Token::GreaterThanOperator => {
-                    (">", self.get_operator_precedence(">"))
-                }
-                crate::tokens::Token::LessThanOrEqualOperator => {
-                    ("<=", self.get_operator_precedence("<="))
-                }
-                crate::tokens::Token::GreaterThanOrEqualOperator => {
-                    (">=", self.get_operator_precedence(">="))
-                }
-                crate::tokens::Token::LogicalAndOperator => {
-                    ("&&", self.get_operator_precedence("&&"))
-                }
-                crate::tokens::Token::LogicalOrOperator => {
-                    ("||", self.get_operator_precedence("||"))
-                }
-                crate::tokens::Token::NotOperator => ("!", self.get_operator_precedence("!")),
-                _ => ("", 0),
+            let (op_str, precedence) = match self.binary_operator() {
+                Some((op, precedence)) if precedence >= min_prec => (op, precedence),
+                _ => break,
             };
 
-            if precedence == 0 || precedence < min_prec {
-                break;
-            }
-
             self.next(); // consume operator
             self.skip_whitespace();
             let mut right = match self.parse_primary() {
@@ -294,29 +440,18 @@ impl AbstractSyntaxTree {
             };
 
             self.skip_whitespace();
-            let next_prec = match self.peek() {
-                crate::tokens::Token::ExponentiationOperator => self.get_operator_precedence("**"),
-                crate::tokens::Token::AdditionOperator => self.get_operator_precedence("+"),
-                crate::tokens::Token::SubtractionOperator => self.get_operator_precedence("-"),
-                crate::tokens::Token::MultiplicationOperator => self.get_operator_precedence("*"),
-                crate::tokens::Token::DivisionOperator => self.get_operator_precedence("/"),
-                crate::tokens::Token::ModulusOperator => self.get_operator_precedence("%"),
-                crate::tokens::Token::EqualOperator => self.get_operator_precedence("=="),
-                crate::tokens::Token::NotEqualOperator => self.get_operator_precedence("!="),
-                crate::tokens::Token::LessThanOperator => self.get_operator_precedence("<"),
-                crate::tokens::Token::GreaterThanOperator => self.get_operator_precedence(">"),
-                crate::tokens::Token::LessThanOrEqualOperator => self.get_operator_precedence("<="),
-                crate::tokens::Token::GreaterThanOrEqualOperator => {
-                    self.get_operator_precedence(">=")
-                }
-                crate::tokens::Token::LogicalAndOperator => self.get_operator_precedence("&&"),
-                crate::tokens::Token::LogicalOrOperator => self.get_operator_precedence("||"),
-                crate::tokens::Token::NotOperator => self.get_operator_precedence("!"),
-                _ => 0,
-            };
-
-            if precedence < next_prec {
-                right = self.parse_binary_with_precedence(precedence + 1, right);
+            let next_prec = self
+                .binary_operator()
+                .map_or(0, |(_, precedence)| precedence);
+
+            // `**` is right associative, eg: `2 ** 3 ** 2` is `2 ** 9`
+            if precedence < next_prec || (op_str == "**" && next_prec == precedence) {
+                let min_prec = if op_str == "**" {
+                    precedence
+                } else {
+                    precedence + 1
+                };
+                right = self.parse_binary_with_precedence(min_prec, right);
             }
 
             left = crate::expressions::Expressions::BinaryOperation {
@@ -328,248 +463,429 @@ impl AbstractSyntaxTree {
         left
     }
 
-    ///parse function definitions
+    /// parse function definitions and anonymous functions
+    /// ```text
+    /// fn greet(name) { print(name) }
+    /// fn(x) { x * 2 }
+    /// |x| x * 2
+    /// || now()
     /// ```
-    /// code my_function(arg) { print(arg); })
-    /// ``````
     fn parse_function_def(&mut self) -> Option<crate::expressions::Expressions> {
-        self.skip_whitespace();
-        if let crate::tokens::Token::Keyword(k) = self.peek() {
-            if k == "fn" {
-                self.next(); // consume 'fn'
-                self.skip_whitespace();
-                let func_name = if let crate::tokens::Token::Identifier(name) = self.peek() {
-                    let name = name.clone();
-                    self.next(); // consume function name
-                    self.skip_whitespace();
-                    name
-                } else {
-                    return None; // expected function name
+        let start = self.pos;
+        let function = self.parse_function_parts();
+        if function.is_none() {
+            self.pos = start;
+        }
+        function
+    }
+
+    /// see `parse_function_def`
+    fn parse_function_parts(&mut self) -> Option<crate::expressions::Expressions> {
+        let (name, params) = match self.next().clone() {
+            crate::tokens::Token::Keyword(k) if k == "Fn" => {
+                self.skip_spaces();
+                let name = match self.peek() {
+                    crate::tokens::Token::Identifier(name) => {
+                        let name = name.clone();
+                        self.next(); // consume function name
+                        self.skip_spaces();
+                        Some(name)
+                    }
+                    _ => None,
                 };
+                if !matches!(self.next(), crate::tokens::Token::ParenOpen) {
+                    return None; // expected '('
+                }
+                (name, self.parse_params(crate::tokens::Token::ParenClose)?)
+            }
+            crate::tokens::Token::PipeOperator => {
+                (None, self.parse_params(crate::tokens::Token::PipeOperator)?)
+            }
+            crate::tokens::Token::LogicalOrOperator => (None, Vec::new()),
+            _ => return None,
+        };
+        self.skip_spaces();
+        // a block is the body, objects are returned in parens, eg: `|p| ({ name: p })`
+        let body = if matches!(self.peek(), crate::tokens::Token::BraceOpen) {
+            self.parse_block()?
+        } else {
+            self.parse_expr()?
+        };
+        Some(crate::expressions::Expressions::FunctionDefinition {
+            name,
+            params,
+            body: Box::new(body),
+        })
+    }
 
-                // parse parameters
-                match self.peek() {
-                    crate::tokens::Token::SubtractionOperator
-                    | crate::tokens::Token::NotOperator
-                    | crate::tokens::Token::BitwiseNotOperator => {
-                        let op = match self.peek() {
-                            crate::tokens::Token::SubtractionOperator => "-",
-                            crate::tokens::Token::NotOperator => "!",
-                            crate::tokens::Token::BitwiseNotOperator => "~",
-                            _ => unreachable!(),
-                        }
-                        .to_string();
-                        let mut params = Vec::new();
-                        while !matches!(
-                            self.peek(),
-                            crate::tokens::Token::ParenClose | crate::tokens::Token::Eof
-                        ) {
-                            if let crate::tokens::Token::Identifier(param) = self.peek() {
-                                params.push(param.clone());
-                                self.next(); // consume parameter
-                                self.skip_whitespace();
-                            }
-                            if let crate::tokens::Token::Comma = self.peek() {
-                                self.next(); // consume ','
-                                self.skip_whitespace();
-                            } else {
-                                break;
-                            }
-                        }
-                        if let crate::tokens::Token::ParenClose = self.peek() {
-                            self.next(); // consume ')'
-                            self.skip_whitespace();
-                        } else {
-                            return None; // expected ')'
-                        }
+    /// parse parameters separated by commas, up to and including the closing token
+    fn parse_params(&mut self, close: crate::tokens::Token) -> Option<Vec<String>> {
+        let mut params = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.next().clone() {
+                token if token == close && params.is_empty() => return Some(params),
+                crate::tokens::Token::Identifier(param) => params.push(param),
+                _ => return None, // expected a parameter
+            }
+            self.skip_spaces();
+            match self.next().clone() {
+                crate::tokens::Token::Comma => {}
+                token if token == close => return Some(params),
+                _ => return None, // expected ',' or the closing token
+            }
+        }
+    }
 
-                        // parse function body
-                        if let crate::tokens::Token::BraceOpen = self.peek() {
-                            self.next(); // consume '{'
-                            let mut body = Vec::new();
-                            while !matches!(
-                                self.peek(),
-                                crate::tokens::Token::BraceClose | crate::tokens::Token::Eof
-                            ) {
-                                if let Some(expr) = self.parse_expr() {
-                                    body.push(expr);
-                                } else {
-                                    // skip unknown tokens (?)
-                                    self.next(); // skip unknown tokens
-                                    self.skip_whitespace();
-                                }
-                            }
-                            if let crate::tokens::Token::BraceClose = self.peek() {
-                                self.next(); // consume '}'
-                                self.skip_whitespace();
-                                return Some(crate::expressions::Expressions::FunctionDefinition {
-                                    name: func_name,
-                                    params,
-                                    body: Box::new(body),
-                                });
-                            } else {
-                                return None; // expected '}'
-                            }
+    /// parse function calls
+    /// ```text
+    /// my_function(...)
+    /// object.method(...)
+    /// handlers[0](...)
+    /// ```
+    fn parse_function_call(
+        &mut self,
+        expr: crate::expressions::Expressions,
+    ) -> Option<crate::expressions::Expressions> {
+        // `items[0](...)` calls the item, where `items.first(...)` is a method call
+        let indexed = matches!(
+            self.pos.checked_sub(1).and_then(|pos| self.parsed.get(pos)),
+            Some(crate::tokens::Token::BracketClose)
+        );
+        if let crate::tokens::Token::ParenOpen = self.peek() {
+            self.next(); // consume '('
+            let mut args = Vec::new();
+            while !matches!(
+                self.peek(),
+                crate::tokens::Token::ParenClose | crate::tokens::Token::Eof
+            ) {
+                if let Some(arg) = self.parse_expr() {
+                    args.push(arg);
+                }
+                if let crate::tokens::Token::Comma = self.peek() {
+                    self.next(); // consume ','
+                } else {
+                    break;
+                }
+            }
+            if let crate::tokens::Token::ParenClose = self.peek() {
+                self.next(); // consume ')'
+                // methods are called with their object first, eg: `file.read()` is `read(file)`
+                return Some(match expr {
+                    crate::expressions::Expressions::Identifier(func) => {
+                        crate::expressions::Expressions::FunctionCall { func, args }
+                    }
+                    crate::expressions::Expressions::MemberAccess { .. }
+                    | crate::expressions::Expressions::Grouping { .. }
+                    | crate::expressions::Expressions::FunctionCall { .. }
+                    | crate::expressions::Expressions::Call { .. }
+                        if indexed
+                            || !matches!(
+                                expr,
+                                crate::expressions::Expressions::MemberAccess { .. }
+                            ) =>
+                    {
+                        crate::expressions::Expressions::Call {
+                            callee: Box::new(expr),
+                            args,
+                        }
+                    }
+                    crate::expressions::Expressions::MemberAccess { object, property } => {
+                        crate::expressions::Expressions::FunctionCall {
+                            func: property,
+                            args: std::iter::once(*object).chain(args).collect(),
                         }
                     }
+                    _ => return None,
+                });
+            }
+        }
+        None
+    }
+
+    /// parse member access
+    /// ```text
+    /// object.property
+    /// ```
+    fn parse_member_access(
+        &mut self,
+        expr: crate::expressions::Expressions,
+    ) -> Option<crate::expressions::Expressions> {
+        // dotted paths are tokenized as a single identifier, eg: `.metadata.name`
+        if let crate::tokens::Token::Identifier(path) = self.peek()
+            && path.starts_with('.')
+        {
+            let path = path.clone();
+            self.next(); // consume path
+            return Some(Self::member_chain(expr, &path));
+        }
+        // index access, eg: `items[0]` or `labels["app"]`
+        if let crate::tokens::Token::BracketOpen = self.peek() {
+            let start_pos = self.pos;
+            self.next(); // consume '['
+            let property = match self.next() {
+                crate::tokens::Token::Number(n, _) => n.to_string(),
+                crate::tokens::Token::Integer(n, _) => n.to_string(),
+                crate::tokens::Token::String(s) => s.clone(),
+                _ => {
+                    self.pos = start_pos;
+                    return None;
                 }
+            };
+            if let crate::tokens::Token::BracketClose = self.peek() {
+                self.next(); // consume ']'
+                return Some(crate::expressions::Expressions::MemberAccess {
+                    object: Box::new(expr),
+                    property,
+                });
+            }
+            self.pos = start_pos;
+            return None;
+        }
+        // optional member access, eg: `pod?.status.phase` is `null` when `pod` or
+        // `status` is `null` or missing
+        if let crate::tokens::Token::OptionalChainOperator = self.peek() {
+            let start_pos = self.pos;
+            self.next(); // consume '?.'
+            if let crate::tokens::Token::Identifier(path) = self.peek() {
+                let path = path.clone();
+                self.next(); // consume path
+                return Some(path.split('.').filter(|segment| !segment.is_empty()).fold(
+                    expr,
+                    |object, property| crate::expressions::Expressions::OptionalMemberAccess {
+                        object: Box::new(object),
+                        property: property.to_string(),
+                    },
+                ));
+            }
+            self.pos = start_pos;
+            return None;
+        }
+        if let crate::tokens::Token::ShellDot = self.peek() {
+            self.next(); // consume '.'
+            if let crate::tokens::Token::Identifier(property) = self.peek() {
+                let property = property.clone();
+                self.next(); // consume property identifier
+                return Some(crate::expressions::Expressions::MemberAccess {
+                    object: Box::new(expr),
+                    property,
+                });
             }
-            None
         }
+        None
+    }
 
-        /// parse function calls
-        /// ```
-        /// my_function(...)
-        /// ```
-        fn parse_function_call(
-            &mut self,
-            expr: crate::expressions::Expressions,
-        ) -> Option<crate::expressions::Expressions> {
-            if let crate::tokens::Token::ParenOpen = self.peek() {
-                self.next(); // consume '('
-                let mut args = Vec::new();
-                while !matches!(
-                    self.peek(),
-                    crate::tokens::Token::ParenClose | crate::tokens::Token::Eof
-                ) {
-                    if let Some(arg) = self.parse_expr() {
-                        args.push(arg);
-                    }
-                    if let crate::tokens::Token::Comma = self.peek() {
-                        self.next(); // consume ','
-                    } else {
-                        break;
-                    }
+    /// build member access for each segment of a dotted path
+    /// ```text
+    /// pods.items.length
+    /// ```
+    fn member_chain(
+        expr: crate::expressions::Expressions,
+        path: &str,
+    ) -> crate::expressions::Expressions {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .fold(expr, |object, property| {
+                crate::expressions::Expressions::MemberAccess {
+                    object: Box::new(object),
+                    property: property.to_string(),
                 }
+            })
+    }
+
+    /// parse grouped expressions
+    /// ```text
+    /// (1 + 2)
+    /// (kubectl get pods -o json)
+    /// ```
+    fn parse_grouping(&mut self) -> Option<crate::expressions::Expressions> {
+        let start_pos = self.pos;
+        if let crate::tokens::Token::ParenOpen = self.peek() {
+            self.next(); // consume '('
+            self.skip_whitespace();
+            if let Some(expr) = self.parse_expr() {
+                self.skip_whitespace();
                 if let crate::tokens::Token::ParenClose = self.peek() {
                     self.next(); // consume ')'
-                    // Use expr as the function (can be Identifier or MemberAccess)
-                    return Some(crate::expressions::Expressions::FunctionCall {
-                        func: match expr {
-                            crate::expressions::Expressions::Identifier(ref name) => name.clone(),
-                            crate::expressions::Expressions::MemberAccess { .. } => {
-                                format!("{:?}", expr)
-                            } // Or handle as needed
-                            _ => return None,
-                        },
-                        args,
-                    });
-                }
-            }
-            None
-        }
-
-        /// parse member access
-        /// ```
-        /// object.property
-        /// ```
-        fn parse_member_access(
-            &mut self,
-            expr: crate::expressions::Expressions,
-        ) -> Option<crate::expressions::Expressions> {
-            if let crate::tokens::Token::ShellDot = self.peek() {
-                self.next(); // consume '.'
-                if let crate::tokens::Token::Identifier(property) = self.peek() {
-                    let property = property.clone();
-                    self.next(); // consume property identifier
-                    return Some(crate::expressions::Expressions::MemberAccess {
-                        object: Box::new(expr),
-                        property,
+                    return Some(crate::expressions::Expressions::Grouping {
+                        expr: Box::new(expr),
                     });
                 }
             }
-            None
         }
+        self.pos = start_pos;
+        None
+    }
 
-        /// parse arrays
-        /// ```
-        /// [1, 2, 3]
-        /// ```
-        fn parse_literal_array(&mut self) -> Option<crate::expressions::Expressions> {
-            if let crate::tokens::Token::BracketOpen = self.peek() {
-                self.next(); // consume '['
-                self.skip_whitespace(); // skip whitespace after opening bracket
-                let mut elements = Vec::new();
-                while !matches!(
-                    self.peek(),
-                    crate::tokens::Token::BracketClose | crate::tokens::Token::Eof
-                ) {
-                    if let Some(expr) = self.parse_expr() {
-                        elements.push(expr);
-                    }
+    /// parse pipeline stages applied to an expression
+    /// ```text
+    /// (cat pods.json) | from json
+    /// ```
+    fn parse_pipe(
+        &mut self,
+        mut expr: crate::expressions::Expressions,
+    ) -> crate::expressions::Expressions {
+        loop {
+            self.skip_whitespace();
+            if !matches!(self.peek(), crate::tokens::Token::PipeOperator) {
+                return expr;
+            }
+            self.next(); // consume '|'
+            self.skip_whitespace();
 
-                    self.skip_whitespace(); // skip whitespace before comma or closing bracket
-                    if let crate::tokens::Token::Comma = self.peek() {
-                        self.next(); // consume ','
-                        self.skip_whitespace(); // skip whitespace after comma
-                    } else {
-                        break;
+            let mut stage = match self.peek() {
+                crate::tokens::Token::Identifier(stage) => stage.clone(),
+                _ => return expr,
+            };
+            self.next(); // consume stage name
+            // hyphenated names are split by the tokenizer, eg: `group-by`
+            while let (
+                crate::tokens::Token::Operator(op),
+                Some(crate::tokens::Token::Identifier(part)),
+            ) = (self.peek(), self.parsed.get(self.pos + 1))
+                && op == "-"
+            {
+                stage = format!("{}-{}", stage, part);
+                self.next(); // consume '-'
+                self.next(); // consume the rest of the name
+            }
+
+            let mut args = String::new();
+            while !matches!(
+                self.peek(),
+                crate::tokens::Token::Eof
+                    | crate::tokens::Token::Semicolon
+                    | crate::tokens::Token::PipeOperator
+                    | crate::tokens::Token::ParenClose
+            ) {
+                match self.next() {
+                    crate::tokens::Token::Space
+                    | crate::tokens::Token::Tab
+                    | crate::tokens::Token::Newline => args.push(' '),
+                    crate::tokens::Token::String(s) => args.push_str(&format!("\"{}\"", s)),
+                    crate::tokens::Token::Number(_, lexeme)
+                    | crate::tokens::Token::Integer(_, lexeme) => args.push_str(lexeme),
+                    crate::tokens::Token::Boolean(b) => args.push_str(&b.to_string()),
+                    crate::tokens::Token::Null => args.push_str("null"),
+                    crate::tokens::Token::Duration(text) => args.push_str(text),
+                    crate::tokens::Token::ShellDirectory { segments } => {
+                        args.push_str(&crate::paths::display(segments))
                     }
+                    crate::tokens::Token::ShellFile { path, .. } => args.push_str(path),
+                    crate::tokens::Token::Identifier(id) => args.push_str(id),
+                    crate::tokens::Token::Keyword(k) => args.push_str(&Self::keyword_text(k)),
+                    crate::tokens::Token::ShellDot => args.push('.'),
+                    crate::tokens::Token::Comma => args.push(','),
+                    crate::tokens::Token::Operator(op) => args.push_str(op),
+                    crate::tokens::Token::EqualOperator => args.push_str("=="),
+                    crate::tokens::Token::NotEqualOperator => args.push_str("!="),
+                    crate::tokens::Token::RegexMatchOperator => args.push_str("=~"),
+                    crate::tokens::Token::RegexNotMatchOperator => args.push_str("!~"),
+                    crate::tokens::Token::RegularExpression { pattern, flags } => args.push_str(
+                        &format!("/{}/{}", pattern, flags.as_deref().unwrap_or_default()),
+                    ),
+                    crate::tokens::Token::GreaterThanOperator => args.push('>'),
+                    crate::tokens::Token::GreaterThanOrEqualOperator => args.push_str(">="),
+                    crate::tokens::Token::LessThanOperator => args.push('<'),
+                    crate::tokens::Token::LessThanOrEqualOperator => args.push_str("<="),
+                    crate::tokens::Token::SubtractionOperator => args.push('-'),
+                    _ => {}
+                }
+            }
+
+            expr = crate::expressions::Expressions::Pipe {
+                input: Box::new(expr),
+                stage,
+                args: args.trim().to_string(),
+            };
+        }
+    }
+
+    /// parse arrays
+    /// ```text
+    /// [1, 2, 3]
+    /// ```
+    fn parse_literal_array(&mut self) -> Option<crate::expressions::Expressions> {
+        if let crate::tokens::Token::BracketOpen = self.peek() {
+            self.next(); // consume '['
+            self.skip_whitespace(); // skip whitespace after opening bracket
+            let mut elements = Vec::new();
+            while !matches!(
+                self.peek(),
+                crate::tokens::Token::BracketClose | crate::tokens::Token::Eof
+            ) {
+                if let Some(expr) = self.parse_expr() {
+                    elements.push(expr);
                 }
 
-                if let crate::tokens::Token::BracketClose = self.peek() {
-                    self.next(); // consume ']'
-                    return Some(crate::expressions::Expressions::Array(elements));
+                self.skip_whitespace(); // skip whitespace before comma or closing bracket
+                if let crate::tokens::Token::Comma = self.peek() {
+                    self.next(); // consume ','
+                    self.skip_whitespace(); // skip whitespace after comma
                 } else {
-                    return None; // expected ']'
+                    break;
                 }
             }
-            None
+
+            if let crate::tokens::Token::BracketClose = self.peek() {
+                self.next(); // consume ']'
+                return Some(crate::expressions::Expressions::Array(elements));
+            } else {
+                return None; // expected ']'
+            }
         }
+        None
+    }
 
-        /// parse new array constructor
-        /// ```
-        /// new Array()
-        /// new Array([1, 2, 3])
-        /// ```
-        fn parse_new_array(&mut self) -> Option<crate::expressions::Expressions> {
-            // Save position for backtracking
-            let start_pos = self.pos;
+    /// parse new array constructor
+    /// ```text
+    /// new Array()
+    /// new Array([1, 2, 3])
+    /// ```
+    fn parse_new_array(&mut self) -> Option<crate::expressions::Expressions> {
+        // Save position for backtracking
+        let start_pos = self.pos;
+
+        // Check for 'New' keyword
+        if let crate::tokens::Token::Keyword(k) = self.peek() {
+            if k != "New" {
+                return None;
+            }
+            self.next(); // consume 'New'
+            self.skip_whitespace();
 
-            // Check for 'New' keyword
-            if let crate::tokens::Token::Keyword(k) = self.peek() {
-                if k != "New" {
+            // Check for 'Array' identifier
+            if let crate::tokens::Token::Identifier(name) = self.peek() {
+                if name != "Array" {
+                    self.pos = start_pos; // restore position
                     return None;
                 }
-                self.next(); // consume 'New'
+                self.next(); // consume 'Array'
                 self.skip_whitespace();
 
-                // Check for 'Array' identifier
-                if let crate::tokens::Token::Identifier(name) = self.peek() {
-                    if name != "Array" {
-                        self.pos = start_pos; // restore position
-                        return None;
-                    }
-                    self.next(); // consume 'Array'
+                // Check for '('
+                if let crate::tokens::Token::ParenOpen = self.peek() {
+                    self.next(); // consume '('
                     self.skip_whitespace();
 
-                    // Check for '('
-                    if let crate::tokens::Token::ParenOpen = self.peek() {
-                        self.next(); // consume '('
+                    // Check for optional array literal argument
+                    if let crate::tokens::Token::BracketOpen = self.peek() {
+                        // Parse the array literal
+                        let array_expr = self.parse_literal_array()?;
                         self.skip_whitespace();
 
-                        // Check for optional array literal argument
-                        if let crate::tokens::Token::BracketOpen = self.peek() {
-                            // Parse the array literal
-                            let array_expr = self.parse_literal_array()?;
-                            self.skip_whitespace();
-
-                            // Check for ')'
-                            if let crate::tokens::Token::ParenClose = self.peek() {
-                                self.next(); // consume ')'
-                                return Some(array_expr);
-                            } else {
-                                self.pos = start_pos; // restore position
-                                return None;
-                            }
-                        } else if let crate::tokens::Token::ParenClose = self.peek() {
-                            // Empty constructor: new Array()
+                        // Check for ')'
+                        if let crate::tokens::Token::ParenClose = self.peek() {
                             self.next(); // consume ')'
-                            return Some(crate::expressions::Expressions::Array(Vec::new()));
+                            return Some(array_expr);
                         } else {
                             self.pos = start_pos; // restore position
                             return None;
                         }
+                    } else if let crate::tokens::Token::ParenClose = self.peek() {
+                        // Empty constructor: new Array()
+                        self.next(); // consume ')'
+                        return Some(crate::expressions::Expressions::Array(Vec::new()));
                     } else {
                         self.pos = start_pos; // restore position
                         return None;
@@ -578,63 +894,63 @@ impl AbstractSyntaxTree {
                     self.pos = start_pos; // restore position
                     return None;
                 }
+            } else {
+                self.pos = start_pos; // restore position
+                return None;
             }
-            None
         }
+        None
+    }
 
-        /// parse new object constructor
-        /// ```
-        /// new Object()
-        /// new Object({foo: 1, bar: 2})
-        /// ```
-        fn parse_new_object(&mut self) -> Option<crate::expressions::Expressions> {
-            // Save position for backtracking
-            let start_pos = self.pos;
+    /// parse new object constructor
+    /// ```text
+    /// new Object()
+    /// new Object({foo: 1, bar: 2})
+    /// ```
+    fn parse_new_object(&mut self) -> Option<crate::expressions::Expressions> {
+        // Save position for backtracking
+        let start_pos = self.pos;
+
+        // Check for 'New' keyword
+        if let crate::tokens::Token::Keyword(k) = self.peek() {
+            if k != "New" {
+                return None;
+            }
+            self.next(); // consume 'New'
+            self.skip_whitespace();
 
-            // Check for 'New' keyword
-            if let crate::tokens::Token::Keyword(k) = self.peek() {
-                if k != "New" {
+            // Check for 'Object' identifier
+            if let crate::tokens::Token::Identifier(name) = self.peek() {
+                if name != "Object" {
+                    self.pos = start_pos; // restore position
                     return None;
                 }
-                self.next(); // consume 'New'
+                self.next(); // consume 'Object'
                 self.skip_whitespace();
 
-                // Check for 'Object' identifier
-                if let crate::tokens::Token::Identifier(name) = self.peek() {
-                    if name != "Object" {
-                        self.pos = start_pos; // restore position
-                        return None;
-                    }
-                    self.next(); // consume 'Object'
+                // Check for '('
+                if let crate::tokens::Token::ParenOpen = self.peek() {
+                    self.next(); // consume '('
                     self.skip_whitespace();
 
-                    // Check for '('
-                    if let crate::tokens::Token::ParenOpen = self.peek() {
-                        self.next(); // consume '('
+                    // Check for optional object literal argument
+                    if let crate::tokens::Token::BraceOpen = self.peek() {
+                        // Parse the object literal
+                        let object_expr = self.parse_object()?;
                         self.skip_whitespace();
 
-                        // Check for optional object literal argument
-                        if let crate::tokens::Token::BraceOpen = self.peek() {
-                            // Parse the object literal
-                            let object_expr = self.parse_object()?;
-                            self.skip_whitespace();
-
-                            // Check for ')'
-                            if let crate::tokens::Token::ParenClose = self.peek() {
-                                self.next(); // consume ')'
-                                return Some(object_expr);
-                            } else {
-                                self.pos = start_pos; // restore position
-                                return None;
-                            }
-                        } else if let crate::tokens::Token::ParenClose = self.peek() {
-                            // Empty constructor: new Object()
+                        // Check for ')'
+                        if let crate::tokens::Token::ParenClose = self.peek() {
                             self.next(); // consume ')'
-                            return Some(crate::expressions::Expressions::Object(Vec::new()));
+                            return Some(object_expr);
                         } else {
                             self.pos = start_pos; // restore position
                             return None;
                         }
+                    } else if let crate::tokens::Token::ParenClose = self.peek() {
+                        // Empty constructor: new Object()
+                        self.next(); // consume ')'
+                        return Some(crate::expressions::Expressions::Object(Vec::new()));
                     } else {
                         self.pos = start_pos; // restore position
                         return None;
@@ -643,116 +959,648 @@ impl AbstractSyntaxTree {
                     self.pos = start_pos; // restore position
                     return None;
                 }
+            } else {
+                self.pos = start_pos; // restore position
+                return None;
             }
-            None
         }
+        None
+    }
 
-        /// parse objects
-        /// ```
-        /// {
-        ///     key1: value1,
-        ///     key2: value2
-        /// }
-        /// ```
-        fn parse_object(&mut self) -> Option<crate::expressions::Expressions> {
-            if let crate::tokens::Token::BraceOpen = self.peek() {
-                self.next(); // consume '{'
-                self.skip_whitespace(); // skip whitespace after opening brace
-                let mut properties = Vec::new();
-                while !matches!(
-                    self.peek(),
-                    crate::tokens::Token::BraceClose | crate::tokens::Token::Eof
-                ) {
-                    // Get the key as a cloned value
-                    let key_token = self.next().clone();
-                    let key = if let crate::tokens::Token::Identifier(ref k) = key_token {
+    /// parse objects
+    /// ```text
+    /// {
+    ///     key1: value1,
+    ///     key2: value2
+    /// }
+    /// ```
+    fn parse_object(&mut self) -> Option<crate::expressions::Expressions> {
+        if let crate::tokens::Token::BraceOpen = self.peek() {
+            self.next(); // consume '{'
+            self.skip_whitespace(); // skip whitespace after opening brace
+            let mut properties = Vec::new();
+            while !matches!(
+                self.peek(),
+                crate::tokens::Token::BraceClose | crate::tokens::Token::Eof
+            ) {
+                // keys are names or strings, eg: `{name: 1}` or `{"x-token": 1}`
+                let key = match self.next() {
+                    crate::tokens::Token::Identifier(k) | crate::tokens::Token::String(k) => {
                         k.clone()
-                    } else {
-                        return None; // expected identifier key
-                    };
-
-                    self.skip_whitespace(); // skip whitespace before colon
-                    if let crate::tokens::Token::Colon = self.peek() {
-                        self.next(); // consume ':'
-                        self.skip_whitespace(); // skip whitespace after colon
-                        if let Some(value) = self.parse_expr() {
-                            properties.push((key, value));
-                        } else {
-                            return None; // expected value expression
-                        }
-                    } else {
-                        return None; // expected ':'
                     }
+                    _ => return None, // expected key
+                };
 
-                    self.skip_whitespace(); // skip whitespace before comma or closing brace
-                    if let crate::tokens::Token::Comma = self.peek() {
-                        self.next(); // consume ','
-                        self.skip_whitespace(); // skip whitespace after comma
+                self.skip_whitespace(); // skip whitespace before colon
+                if let crate::tokens::Token::Colon = self.peek() {
+                    self.next(); // consume ':'
+                    self.skip_whitespace(); // skip whitespace after colon
+                    if let Some(value) = self.parse_expr() {
+                        properties.push((key, value));
                     } else {
-                        break;
+                        return None; // expected value expression
                     }
-                }
-                if let crate::tokens::Token::BraceClose = self.peek() {
-                    self.next(); // consume '}'
-                    return Some(crate::expressions::Expressions::Object(properties));
                 } else {
-                    return None; // expected '}'
+                    return None; // expected ':'
                 }
+
+                self.skip_whitespace(); // skip whitespace before comma or closing brace
+                if let crate::tokens::Token::Comma = self.peek() {
+                    self.next(); // consume ','
+                    self.skip_whitespace(); // skip whitespace after comma
+                } else {
+                    break;
+                }
+            }
+            if let crate::tokens::Token::BraceClose = self.peek() {
+                self.next(); // consume '}'
+                return Some(crate::expressions::Expressions::Object(properties));
+            } else {
+                return None; // expected '}'
             }
-            None
         }
+        None
+    }
 
-        /// parse assignment expressions
-        /// ```
-        /// let s = "hello";
-        ///
-        /// let n = 5;
-        ///
-        /// let f = fn(arg) { print(arg) };
-        /// ```
-        fn parse_assignment(&mut self) -> Option<crate::expressions::Expressions> {
-            self.skip_whitespace();
-            // handle assignments prefixed with the let keyword
-            if let crate::tokens::Token::Keyword(k) = self.peek() {
-                if k == "Let" {
-                    self.next(); // consume 'let'
-                    self.skip_whitespace();
-                    if let crate::tokens::Token::Identifier(name) = self.peek() {
-                        let name = name.clone();
-                        self.next(); // consume identifier
+    /// parse control flow, `if` with optional `else` branches, `for` loops, `try` and `throw`
+    /// ```text
+    /// if f.ext == "rs" { f.name } else if f.is_dir { "dir" } else { null }
+    ///
+    /// for f in ls("./src") { f.size }
+    ///
+    /// try { kubectl get pods } catch e { e.stderr } finally { rm -f pods.lock }
+    ///
+    /// throw "no pods"
+    /// ```
+    fn parse_control_flow(&mut self) -> Option<crate::expressions::Expressions> {
+        self.skip_whitespace();
+        let start_pos = self.pos;
+        let keyword = match self.peek() {
+            crate::tokens::Token::Keyword(k)
+                if matches!(k.as_str(), "If" | "For" | "Try" | "Throw" | "Match") =>
+            {
+                k.clone()
+            }
+            _ => return None,
+        };
+        self.next(); // consume keyword
+        self.skip_whitespace();
+
+        let expr = match keyword.as_str() {
+            "If" => self.parse_if(),
+            "For" => self.parse_for(),
+            "Try" => self.parse_try(),
+            "Match" => self.parse_match(),
+            _ => self
+                .parse_expr()
+                .map(|value| crate::expressions::Expressions::Throw {
+                    value: Box::new(value),
+                }),
+        };
+        if expr.is_none() {
+            self.pos = start_pos;
+        }
+        expr
+    }
+
+    /// parse the rest of an `if`, after the keyword
+    fn parse_if(&mut self) -> Option<crate::expressions::Expressions> {
+        let condition = self.parse_head()?;
+        let then_branch = self.parse_block()?;
+
+        let start_pos = self.pos;
+        self.skip_whitespace();
+        let else_branch = match self.peek() {
+            crate::tokens::Token::Keyword(k) if k == "Else" => {
+                self.next(); // consume 'else'
+                self.skip_whitespace();
+                match self.peek() {
+                    crate::tokens::Token::Keyword(k) if k == "If" => {
+                        self.next(); // consume 'if'
                         self.skip_whitespace();
+                        self.parse_if()?
+                    }
+                    _ => self.parse_block()?,
+                }
+            }
+            crate::tokens::Token::Keyword(k) if k == "Elseif" => {
+                self.next(); // consume 'elseif'
+                self.skip_whitespace();
+                self.parse_if()?
+            }
+            _ => {
+                self.pos = start_pos;
+                return Some(crate::expressions::Expressions::If {
+                    condition: Box::new(condition),
+                    then_branch: Box::new(then_branch),
+                    else_branch: None,
+                });
+            }
+        };
+        Some(crate::expressions::Expressions::If {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Some(Box::new(else_branch)),
+        })
+    }
 
-                        if let crate::tokens::Token::EqualOperator = self.peek() {
-                            if op == "=" {
-                                self.next(); // consume '='
-                                self.skip_whitespace();
-                                if let Some(value) = self.parse_expr() {
-                                    return Some(crate::expressions::Expressions::Assignment {
-                                        name,
-                                        value: Box::new(value),
-                                    });
-                                } else {
-                                    return None; // expected value expression
+    /// parse the rest of a `for` loop, after the keyword
+    fn parse_for(&mut self) -> Option<crate::expressions::Expressions> {
+        let iterator = match self.peek() {
+            crate::tokens::Token::Identifier(name) => name.clone(),
+            _ => return None, // expected loop variable
+        };
+        self.next(); // consume loop variable
+        self.skip_whitespace();
+        match self.peek() {
+            crate::tokens::Token::Identifier(k) if k == "in" => self.next(),
+            _ => return None, // expected 'in'
+        };
+        self.skip_whitespace();
+        let iterable = self.parse_head()?;
+        let body = self.parse_block()?;
+        Some(crate::expressions::Expressions::For {
+            iterator,
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+        })
+    }
+
+    /// parse the rest of a `try`, after the keyword, it needs a `catch` or a `finally`
+    fn parse_try(&mut self) -> Option<crate::expressions::Expressions> {
+        let body = self.parse_block()?;
+
+        let mut start_pos = self.pos;
+        self.skip_whitespace();
+        let (error, catch_branch) = match self.peek() {
+            crate::tokens::Token::Keyword(k) if k == "Catch" => {
+                self.next(); // consume 'catch'
+                self.skip_whitespace();
+                let error = match self.peek() {
+                    crate::tokens::Token::Identifier(name) => {
+                        let name = name.clone();
+                        self.next(); // consume error variable
+                        Some(name)
+                    }
+                    _ => None,
+                };
+                let catch_branch = self.parse_block()?;
+                start_pos = self.pos;
+                self.skip_whitespace();
+                (error, Some(Box::new(catch_branch)))
+            }
+            _ => (None, None),
+        };
+
+        let finally_branch = match self.peek() {
+            crate::tokens::Token::Keyword(k) if k == "Finally" => {
+                self.next(); // consume 'finally'
+                Some(Box::new(self.parse_block()?))
+            }
+            _ => {
+                self.pos = start_pos;
+                None
+            }
+        };
+        if catch_branch.is_none() && finally_branch.is_none() {
+            return None; // expected 'catch' or 'finally'
+        }
+        Some(crate::expressions::Expressions::Try {
+            body: Box::new(body),
+            error,
+            catch_branch,
+            finally_branch,
+        })
+    }
+
+    /// parse the rest of a `match`, after the keyword
+    ///
+    /// arms are separated by commas or newlines, a command in an arm ends at the newline
+    /// ```text
+    /// match pod { { status: "Running" } => "up", _ => "down" }
+    /// ```
+    fn parse_match(&mut self) -> Option<crate::expressions::Expressions> {
+        let value = self.parse_head()?;
+        self.next(); // consume '{'
+        self.blocks += 1;
+        let arms = self.parse_match_arms();
+        self.blocks -= 1;
+        Some(crate::expressions::Expressions::Match {
+            value: Box::new(value),
+            arms: arms?,
+        })
+    }
+
+    /// parse the arms of a `match` up to and including the closing `}`
+    fn parse_match_arms(&mut self) -> Option<Vec<crate::destructure::MatchArm>> {
+        let mut arms = Vec::new();
+        loop {
+            while matches!(
+                self.peek(),
+                crate::tokens::Token::Space
+                    | crate::tokens::Token::Tab
+                    | crate::tokens::Token::Newline
+                    | crate::tokens::Token::Comma
+            ) {
+                self.next();
+            }
+            if matches!(self.peek(), crate::tokens::Token::BraceClose) {
+                self.next(); // consume '}'
+                return Some(arms);
+            }
+
+            let pattern = self.parse_pattern()?;
+            self.skip_spaces();
+            let guard = match self.peek() {
+                crate::tokens::Token::Keyword(k) if k == "If" => {
+                    self.next(); // consume 'if'
+                    Some(self.parse_expr()?)
+                }
+                _ => None,
+            };
+            self.skip_spaces();
+            if !matches!(self.next(), crate::tokens::Token::FatArrow) {
+                return None; // expected '=>'
+            }
+            self.skip_spaces();
+            // a block is the body, objects are returned in parens, like function bodies
+            let body = if matches!(self.peek(), crate::tokens::Token::BraceOpen) {
+                self.parse_block()?
+            } else {
+                self.parse_expr()?
+            };
+            arms.push(crate::destructure::MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+
+            self.skip_spaces();
+            if !matches!(
+                self.peek(),
+                crate::tokens::Token::Comma
+                    | crate::tokens::Token::Newline
+                    | crate::tokens::Token::BraceClose
+            ) {
+                return None; // expected ',' or the end of the arm
+            }
+        }
+    }
+
+    /// parse a pattern, alternatives are separated by `|`
+    /// ```text
+    /// "x" | "y"
+    /// [first, ..rest]
+    /// { name, age: a }
+    /// ```
+    fn parse_pattern(&mut self) -> Option<crate::destructure::Pattern> {
+        let mut alternatives = vec![self.parse_pattern_item()?];
+        loop {
+            let start_pos = self.pos;
+            self.skip_spaces();
+            if !matches!(self.peek(), crate::tokens::Token::PipeOperator) {
+                self.pos = start_pos;
+                break;
+            }
+            self.next(); // consume '|'
+            self.skip_spaces();
+            alternatives.push(self.parse_pattern_item()?);
+        }
+        Some(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => crate::destructure::Pattern::Or(alternatives),
+        })
+    }
+
+    /// parse a pattern without alternatives, see `parse_pattern`
+    fn parse_pattern_item(&mut self) -> Option<crate::destructure::Pattern> {
+        Some(match self.peek().clone() {
+            crate::tokens::Token::Identifier(name) if name == "_" => {
+                self.next(); // consume '_'
+                crate::destructure::Pattern::Wildcard
+            }
+            crate::tokens::Token::Identifier(name) if !name.contains('.') => {
+                self.next(); // consume name
+                crate::destructure::Pattern::Binding(name)
+            }
+            crate::tokens::Token::Number(..)
+            | crate::tokens::Token::Integer(..)
+            | crate::tokens::Token::String(_)
+            | crate::tokens::Token::Boolean(_)
+            | crate::tokens::Token::Null => {
+                crate::destructure::Pattern::Literal(Box::new(self.parse_literal()?))
+            }
+            // negative numbers, eg: `-1`
+            crate::tokens::Token::SubtractionOperator => {
+                self.next(); // consume '-'
+                if !matches!(
+                    self.peek(),
+                    crate::tokens::Token::Number(..) | crate::tokens::Token::Integer(..)
+                ) {
+                    return None; // expected a number
+                }
+                let value = self.parse_literal()?;
+                crate::destructure::Pattern::Literal(Box::new(
+                    crate::numbers::unary("-", &value)?.ok()?,
+                ))
+            }
+            crate::tokens::Token::BracketOpen => {
+                self.next(); // consume '['
+                let mut items = Vec::new();
+                let mut rest = None;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek().clone() {
+                        crate::tokens::Token::BracketClose => {}
+                        crate::tokens::Token::ShellDoubleDot => {
+                            self.next(); // consume '..'
+                            rest = Some(match self.peek().clone() {
+                                crate::tokens::Token::Identifier(name) => {
+                                    self.next(); // consume rest name
+                                    name
                                 }
-                            } else {
-                                return None;
-                            }
+                                _ => "_".to_string(),
+                            });
+                            self.skip_whitespace();
                         }
+                        _ => items.push(self.parse_pattern()?),
+                    }
+                    self.skip_whitespace();
+                    match self.next() {
+                        crate::tokens::Token::BracketClose => break,
+                        crate::tokens::Token::Comma if rest.is_none() => {}
+                        _ => return None, // expected ',' or ']', the rest comes last
+                    }
+                }
+                crate::destructure::Pattern::Array { items, rest }
+            }
+            crate::tokens::Token::BraceOpen => {
+                self.next(); // consume '{'
+                let mut fields = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    let key = match self.next().clone() {
+                        crate::tokens::Token::BraceClose => break,
+                        crate::tokens::Token::Identifier(key)
+                        | crate::tokens::Token::String(key) => key,
+                        _ => return None, // expected a field
+                    };
+                    self.skip_whitespace();
+                    let pattern = if matches!(self.peek(), crate::tokens::Token::Colon) {
+                        self.next(); // consume ':'
+                        self.skip_whitespace();
+                        self.parse_pattern()?
+                    } else {
+                        crate::destructure::Pattern::Binding(key.clone())
+                    };
+                    fields.push((key, pattern));
+                    self.skip_whitespace();
+                    match self.next() {
+                        crate::tokens::Token::BraceClose => break,
+                        crate::tokens::Token::Comma => {}
+                        _ => return None, // expected ',' or '}'
                     }
-                } else {
-                    return None;
                 }
+                crate::destructure::Pattern::Object { fields }
             }
+            _ => return None,
+        })
+    }
 
-            // handle assignments without the let keyword
-            if let crate::tokens::Token::Identifier(name) = self.peek() {
-                let start_pos = self.pos; // Save position for backtracking
-                let name = name.clone();
-                self.next(); // consume identifier
+    /// parse the condition of an `if` or the iterable of a `for`, the tokens up to the `{`
+    /// of the block are parsed on their own so commands stop there, eg: `if test -f x { }`
+    ///
+    /// a `{` where an operand is expected starts an object rather than the block, eg:
+    /// `match {name: "a"} { .. }`
+    fn parse_head(&mut self) -> Option<crate::expressions::Expressions> {
+        let mut depth = 0;
+        let mut operand = true;
+        let mut end = self.pos;
+        while let Some(token) = self.parsed.get(end) {
+            match token {
+                crate::tokens::Token::BraceOpen if depth == 0 && !operand => break,
+                crate::tokens::Token::ParenOpen
+                | crate::tokens::Token::BracketOpen
+                | crate::tokens::Token::BraceOpen => depth += 1,
+                crate::tokens::Token::ParenClose
+                | crate::tokens::Token::BracketClose
+                | crate::tokens::Token::BraceClose => depth -= 1,
+                _ => {}
+            }
+            if !matches!(
+                token,
+                crate::tokens::Token::Space
+                    | crate::tokens::Token::Tab
+                    | crate::tokens::Token::Newline
+            ) {
+                operand = matches!(
+                    token,
+                    crate::tokens::Token::ParenOpen
+                        | crate::tokens::Token::BracketOpen
+                        | crate::tokens::Token::BraceOpen
+                        | crate::tokens::Token::Comma
+                ) || Self::symbol_text(token).is_some();
+            }
+            end += 1;
+        }
+        if end >= self.parsed.len() {
+            return None; // expected '{'
+        }
+
+        let mut parser = AbstractSyntaxTree::new(
+            self.parsed[self.pos..end].to_vec(),
+            self.builtins.clone(),
+            self.env.clone(),
+            self.aliases.clone(),
+            self.vars.clone(),
+            self.args.clone(),
+        );
+        let expr = parser.parse_expr()?;
+        self.pos = end;
+        Some(expr)
+    }
+
+    /// parse a block of statements, separated by newlines or `;`
+    /// ```text
+    /// { let name = f.name; name }
+    /// ```
+    fn parse_block(&mut self) -> Option<crate::expressions::Expressions> {
+        self.skip_whitespace();
+        if !matches!(self.peek(), crate::tokens::Token::BraceOpen) {
+            return None; // expected '{'
+        }
+        self.next(); // consume '{'
+
+        let expressions = self.parse_statements();
+        if !matches!(self.next(), crate::tokens::Token::BraceClose) {
+            return None; // expected '}'
+        }
+        Some(crate::expressions::Expressions::CodeBlock { expressions })
+    }
+
+    /// parse statements separated by newlines or `;`, up to a closing `}` or the end of input
+    ///
+    /// unknown tokens are skipped, the `}` is left for the caller
+    fn parse_statements(&mut self) -> Vec<crate::expressions::Expressions> {
+        self.blocks += 1;
+        let mut expressions = Vec::new();
+        loop {
+            while matches!(
+                self.peek(),
+                crate::tokens::Token::Space
+                    | crate::tokens::Token::Tab
+                    | crate::tokens::Token::Newline
+                    | crate::tokens::Token::Semicolon
+            ) {
+                self.next();
+            }
+            if matches!(
+                self.peek(),
+                crate::tokens::Token::BraceClose | crate::tokens::Token::Eof
+            ) {
+                break;
+            }
+            match self.parse_expr() {
+                Some(expr) => expressions.push(expr),
+                None => {
+                    self.next(); // skip unknown tokens
+                }
+            }
+        }
+        self.blocks -= 1;
+        expressions
+    }
+
+    /// parse an import of a turtle file, as a namespace or of some of its names
+    /// ```text
+    /// import "lib/git.tt" as git
+    ///
+    /// from "lib/k8s.tt" import ctx, ns
+    /// ```
+    /// the path must be a string, so the `from` builtin and `import` commands still work
+    fn parse_import(&mut self) -> Option<crate::expressions::Expressions> {
+        self.skip_whitespace();
+        let start_pos = self.pos;
+        let from = match self.peek() {
+            crate::tokens::Token::Identifier(k) if k == "import" => false,
+            crate::tokens::Token::Identifier(k) if k == "from" => true,
+            _ => return None,
+        };
+        self.next(); // consume 'import' or 'from'
+        self.skip_whitespace();
+        let path = match self.peek() {
+            crate::tokens::Token::String(path) => path.clone(),
+            _ => {
+                self.pos = start_pos;
+                return None;
+            }
+        };
+        self.next(); // consume path
+
+        let import = if from {
+            self.parse_import_names(path)
+        } else {
+            self.parse_import_alias(path)
+        };
+        if import.is_none() {
+            self.pos = start_pos;
+        }
+        import
+    }
+
+    /// parse the optional `as name` of an `import`, after the path
+    fn parse_import_alias(&mut self, path: String) -> Option<crate::expressions::Expressions> {
+        let start_pos = self.pos;
+        self.skip_spaces();
+        let alias = match self.peek() {
+            crate::tokens::Token::Identifier(k) if k == "as" => {
+                self.next(); // consume 'as'
+                self.skip_spaces();
+                match self.next() {
+                    crate::tokens::Token::Identifier(name) => Some(name.clone()),
+                    _ => return None, // expected a name
+                }
+            }
+            _ => {
+                self.pos = start_pos;
+                None
+            }
+        };
+        Some(crate::expressions::Expressions::Import {
+            path,
+            names: Vec::new(),
+            alias,
+        })
+    }
+
+    /// parse the `import a, b` names of a `from`, after the path
+    fn parse_import_names(&mut self, path: String) -> Option<crate::expressions::Expressions> {
+        self.skip_spaces();
+        match self.next() {
+            crate::tokens::Token::Identifier(k) if k == "import" => {}
+            _ => return None, // expected 'import'
+        }
+        let mut names = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.next() {
+                crate::tokens::Token::Identifier(name) => names.push(name.clone()),
+                _ => return None, // expected a name
+            }
+            let start_pos = self.pos;
+            self.skip_spaces();
+            if matches!(self.peek(), crate::tokens::Token::Comma) {
+                self.next(); // consume ','
+            } else {
+                self.pos = start_pos;
+                break;
+            }
+        }
+        Some(crate::expressions::Expressions::Import {
+            path,
+            names,
+            alias: None,
+        })
+    }
+
+    /// parse assignment expressions
+    /// ```text
+    /// let s = "hello";
+    ///
+    /// let n = 5;
+    ///
+    /// let f = fn(arg) { print(arg) };
+    /// ```
+    fn parse_assignment(&mut self) -> Option<crate::expressions::Expressions> {
+        self.skip_whitespace();
+        // handle assignments prefixed with the let keyword
+        if let crate::tokens::Token::Keyword(k) = self.peek() {
+            if k == "Let" {
+                self.next(); // consume 'let'
                 self.skip_whitespace();
+                // destructuring, eg: `let [a, b] = pair`
+                if matches!(
+                    self.peek(),
+                    crate::tokens::Token::BracketOpen | crate::tokens::Token::BraceOpen
+                ) {
+                    let pattern = self.parse_pattern()?;
+                    self.skip_whitespace();
+                    if !matches!(self.next(), crate::tokens::Token::AssignmentOperator) {
+                        return None; // expected '='
+                    }
+                    self.skip_whitespace();
+                    let value = self.parse_expr()?;
+                    return Some(crate::expressions::Expressions::Destructuring {
+                        pattern,
+                        value: Box::new(value),
+                    });
+                }
+                if let crate::tokens::Token::Identifier(name) = self.peek() {
+                    let name = name.clone();
+                    self.next(); // consume identifier
+                    self.skip_whitespace();
 
-                if let crate::tokens::Token::Operator(op) = self.peek() {
-                    if op == "=" {
+                    if let crate::tokens::Token::AssignmentOperator = self.peek() {
                         self.next(); // consume '='
                         self.skip_whitespace();
                         if let Some(value) = self.parse_expr() {
@@ -763,395 +1611,529 @@ impl AbstractSyntaxTree {
                         } else {
                             return None; // expected value expression
                         }
-                    } else {
-                        // Not an assignment operator, restore position
-                        self.pos = start_pos;
-                        return None;
                     }
-                } else {
-                    // No operator after identifier, restore position
-                    self.pos = start_pos;
                 }
+            } else {
+                return None;
             }
+        }
 
-            // parse reassignments without the let keyword
-            // if let crate::tokens::Token::Identifier(name) = self.peek() {
-            // }
-
-            None
-        }
-
-        /// parse primitive expressions
-        ///
-        /// 1
-        /// "hello"
-        /// [1, 2, 3]
-        fn parse_primary(&mut self) -> Option<crate::expressions::Expressions> {
-            // Check for Array.new() constructor syntax first
-            if let Some(array_expr) = self.parse_new_array() {
-                return Some(array_expr);
-            }
-
-            // Check for Object.new() constructor syntax
-            if let Some(object_expr) = self.parse_new_object() {
-                return Some(object_expr);
-            }
-
-            // Parse the initial literal, identifier, array, or object
-            let mut expr = match self.peek() {
-                // literals
-                crate::tokens::Token::Number(_)
-                | crate::tokens::Token::String(_)
-                | crate::tokens::Token::Boolean(_) => self.parse_literal(),
-                // arrays & objects
-                // crate::tokens::Token::ShellDot => self.parse_new_array(),
-                crate::tokens::Token::BracketOpen => self.parse_literal_array(),
-                // adds parsing
-                crate::tokens::Token::BraceOpen => self.parse_object(),
-                // identifiers
-                crate::tokens::Token::Identifier(name) => {
-                    let ident = name.clone();
-                    self.next(); // consume identifier
-                    Some(crate::expressions::Expressions::Identifier(ident))
-                }
-                _ => None,
-            }?;
+        // handle assignments without the let keyword
+        if let crate::tokens::Token::Identifier(name) = self.peek() {
+            let start_pos = self.pos; // Save position for backtracking
+            let name = name.clone();
+            self.next(); // consume identifier
+            self.skip_whitespace();
 
-            // Chain member access and function calls modularly
-            loop {
-                // Try member access
-                if let Some(member_expr) = self.parse_member_access(expr.clone()) {
-                    expr = member_expr;
-                    continue;
-                }
-                // Try function call
-                if let Some(call_expr) = self.parse_function_call(expr.clone()) {
-                    expr = call_expr;
-                    continue;
+            if let crate::tokens::Token::AssignmentOperator = self.peek() {
+                self.next(); // consume '='
+                self.skip_whitespace();
+                if let Some(value) = self.parse_expr() {
+                    return Some(crate::expressions::Expressions::Assignment {
+                        name,
+                        value: Box::new(value),
+                    });
+                } else {
+                    return None; // expected value expression
                 }
-                break;
+            } else {
+                // No operator after identifier, restore position
+                self.pos = start_pos;
             }
+        }
 
-            Some(expr)
+        // parse reassignments without the let keyword
+        // if let crate::tokens::Token::Identifier(name) = self.peek() {
+        // }
+
+        None
+    }
+
+    /// parse primitive expressions
+    ///
+    /// 1
+    /// "hello"
+    /// [1, 2, 3]
+    fn parse_primary(&mut self) -> Option<crate::expressions::Expressions> {
+        // Check for Array.new() constructor syntax first
+        if let Some(array_expr) = self.parse_new_array() {
+            return Some(array_expr);
         }
 
-        fn parse_environment_variable(&mut self) -> Option<crate::expressions::Expressions> {
-            if let crate::tokens::Token::Operator(op) = self.peek() {
-                if op == "$" {
-                    self.next(); // consume '$'
-                    if let crate::tokens::Token::Identifier(name) = self.peek() {
-                        let name = name.clone();
-                        self.next(); // consume identifier
-                        return Some(crate::expressions::Expressions::EnvironmentVariable { name });
-                    }
+        // Check for Object.new() constructor syntax
+        if let Some(object_expr) = self.parse_new_object() {
+            return Some(object_expr);
+        }
+
+        // Parse the initial literal, identifier, array, or object
+        let mut expr = match self.peek() {
+            // literals
+            crate::tokens::Token::Number(..)
+            | crate::tokens::Token::Integer(..)
+            | crate::tokens::Token::String(_)
+            | crate::tokens::Token::FormatString(_)
+            | crate::tokens::Token::TemplateString(_)
+            | crate::tokens::Token::Boolean(_)
+            | crate::tokens::Token::Null
+            | crate::tokens::Token::Duration(_)
+            | crate::tokens::Token::ShellDirectory { .. }
+            | crate::tokens::Token::ShellFile { .. }
+            | crate::tokens::Token::RegularExpression { .. } => self.parse_literal(),
+            // unary operators, eg: `-5`, `~0xff`
+            crate::tokens::Token::SubtractionOperator
+            | crate::tokens::Token::NotOperator
+            | crate::tokens::Token::BitwiseNotOperator => self.parse_unary(),
+            crate::tokens::Token::Operator(op) if op == "-" => self.parse_unary(),
+            // arrays & objects
+            // crate::tokens::Token::ShellDot => self.parse_new_array(),
+            crate::tokens::Token::BracketOpen => self.parse_literal_array(),
+            // adds parsing
+            crate::tokens::Token::BraceOpen => self.parse_object(),
+            // groupings
+            crate::tokens::Token::ParenOpen => self.parse_grouping(),
+            // functions, eg: `fn(x) { x * 2 }`, `|x| x * 2`
+            crate::tokens::Token::Keyword(k) if k == "Fn" => self.parse_function_def(),
+            crate::tokens::Token::PipeOperator | crate::tokens::Token::LogicalOrOperator => {
+                self.parse_function_def()
+            }
+            // identifiers, with dotted member access, eg: `pods.items`
+            crate::tokens::Token::Identifier(name) => {
+                let ident = name.clone();
+                self.next(); // consume identifier
+                match ident.split_once('.') {
+                    Some((name, path)) if !name.is_empty() => Some(Self::member_chain(
+                        crate::expressions::Expressions::Identifier(name.to_string()),
+                        path,
+                    )),
+                    _ => Some(crate::expressions::Expressions::Identifier(ident)),
                 }
             }
-            None
+            _ => None,
+        }?;
+
+        // Chain member access and function calls modularly
+        loop {
+            // Try member access
+            if let Some(member_expr) = self.parse_member_access(expr.clone()) {
+                expr = member_expr;
+                continue;
+            }
+            // Try function call
+            if let Some(call_expr) = self.parse_function_call(expr.clone()) {
+                expr = call_expr;
+                continue;
+            }
+            break;
         }
 
-        // TODO: the
-        fn parse_builtin(&mut self) -> Option<crate::expressions::Expressions> {
-            if let crate::tokens::Token::Identifier(cmd) = self.peek() {
-                let cmd = cmd.clone();
+        Some(expr)
+    }
 
-                if !self.builtins.contains(&cmd) {
-                    return None;
-                }
-                self.next(); // consume builtin identifier
+    /// the source text of a keyword, keywords are tokenized with their first letter upper
+    /// cased, eg: `set` is `Set`
+    fn keyword_text(keyword: &str) -> String {
+        keyword[..1].to_ascii_lowercase() + &keyword[1..]
+    }
 
-                let mut input_args = String::new();
-                // if let Some(args) = &self.args {
-                //     let args = args.lock().unwrap();
-                //     if args.debug {
-                //         println!("parse_builtin: found builtin '{}'", cmd);
-                //     }
-                // }
-                // if let Some(args) = &self.args {
-                //     let args = args.lock().unwrap();
-                //     if args.debug {
-                //         println!("parse_builtin: collecting args for builtin '{}'", cmd);
-                //     }
-                // }
-                while !matches!(
-                    self.peek(),
-                    crate::tokens::Token::Eof | crate::tokens::Token::Semicolon
-                ) {
-                    // if let Some(args) = &self.args {
-                    //     let args = args.lock().unwrap();
-                    //     if args.debug {
-                    //         println!("parse_builtin: current token: {:?}", self.peek());
-                    //     }
-                    // }
-                    match self.peek() {
-                        crate::tokens::Token::Space
-                        | crate::tokens::Token::Tab
-                        | crate::tokens::Token::Newline => {
-                            input_args.push(' ');
-                            self.next(); // consume whitespace
-                        }
-                        crate::tokens::Token::String(s) => {
-                            input_args.push_str(&format!("\"{}\"", s));
-                            self.next(); // consume string
-                        }
-                        crate::tokens::Token::Number(n) => {
-                            input_args.push_str(&n.to_string());
-                            self.next(); // consume number
-                        }
-                        crate::tokens::Token::Identifier(id) => {
-                            input_args.push_str(id);
-                            self.next(); // consume identifier
-                        }
-                        crate::tokens::Token::Operator(op) => {
-                            input_args.push_str(op);
-                            self.next(); // consume operator
-                        }
-                        crate::tokens::Token::BracketOpen => {
-                            input_args.push('[');
-                            self.next(); // consume bracket
-                        }
-                        crate::tokens::Token::BracketClose => {
-                            input_args.push(']');
-                            self.next(); // consume bracket
-                        }
-                        crate::tokens::Token::BraceOpen => {
-                            input_args.push('{');
-                            self.next(); // consume brace
-                        }
-                        crate::tokens::Token::BraceClose => {
-                            input_args.push('}');
-                            self.next(); // consume brace
-                        }
-                        crate::tokens::Token::Comma => {
-                            input_args.push(',');
-                            self.next(); // consume comma
-                        }
-                        crate::tokens::Token::Colon => {
-                            input_args.push(':');
-                            self.next(); // consume colon
-                        }
-                        _ => {
-                            self.next(); // consume unknown token
-                        }
-                    }
-                }
+    // TODO: the
+    fn parse_builtin(&mut self) -> Option<crate::expressions::Expressions> {
+        if let crate::tokens::Token::Identifier(cmd) = self.peek() {
+            let cmd = cmd.clone();
 
-                if let crate::tokens::Token::Semicolon = self.peek() {
-                    self.next(); // consume ';'
-                }
+            if !self.builtins.contains(&cmd) {
+                return None;
+            }
+            self.next(); // consume builtin identifier
+
+            let mut input_args = String::new();
+            // if let Some(args) = &self.args {
+            //     let args = args.lock().unwrap();
+            //     if args.debug {
+            //         println!("parse_builtin: found builtin '{}'", cmd);
+            //     }
+            // }
+            // if let Some(args) = &self.args {
+            //     let args = args.lock().unwrap();
+            //     if args.debug {
+            //         println!("parse_builtin: collecting args for builtin '{}'", cmd);
+            //     }
+            // }
+            while !matches!(
+                self.peek(),
+                crate::tokens::Token::Eof
+                    | crate::tokens::Token::Semicolon
+                    | crate::tokens::Token::PipeOperator
+            ) && !self.at_block_end()
+            {
                 // if let Some(args) = &self.args {
                 //     let args = args.lock().unwrap();
                 //     if args.debug {
-                //         println!("parse_builtin: final args string: '{}'", input_args);
+                //         println!("parse_builtin: current token: {:?}", self.peek());
                 //     }
                 // }
-                return Some(crate::expressions::Expressions::Builtin {
-                    name: cmd,
-                    args: input_args.trim().to_string(),
-                });
-            }
-            None
-        }
-
-        // fn parse_variable
-        fn parse_command(&mut self) -> Option<crate::expressions::Expressions> {
-            if let crate::tokens::Token::Identifier(cmd) = self.peek() {
-                let cmd = cmd.clone();
-                if !crate::utils::is_command(&cmd) {
-                    return None;
-                }
-                self.next(); // consume command identifier
-
-                let mut args = String::new();
-                while !matches!(
-                    self.peek(),
-                    crate::tokens::Token::Eof | crate::tokens::Token::Semicolon
-                ) {
-                    match self.peek() {
-                        crate::tokens::Token::Space
-                        | crate::tokens::Token::Tab
-                        | crate::tokens::Token::Newline => {
-                            args.push(' ');
-                            self.next(); // consume whitespace
-                        }
-                        crate::tokens::Token::String(s) => {
-                            args.push_str(&format!("\"{}\"", s));
-                            self.next(); // consume string
-                        }
-                        crate::tokens::Token::Number(n) => {
-                            args.push_str(&n.to_string());
-                            self.next(); // consume number
-                        }
-                        crate::tokens::Token::Identifier(id) => {
-                            args.push_str(id);
-                            self.next(); // consume identifier
-                        }
-                        crate::tokens::Token::Operator(op) => {
-                            args.push_str(op);
-                            self.next(); // consume operator
-                        }
-                        &crate::tokens::Token::ShellDot => {
-                            args.push('.');
-                            self.next(); // consume dot
-                        }
-                        &crate::tokens::Token::ShellDoubleDot => {
-                            args.push_str("..");
-                            self.next(); // consume double dot
-                        }
-                        &crate::tokens::Token::BracketOpen => {
-                            args.push('[');
-                            self.next(); // consume '['
-                        }
-                        &crate::tokens::Token::BracketClose => {
-                            args.push(']');
-                            self.next(); // consume ']'
-                        }
-                        &crate::tokens::Token::ParenOpen => {
-                            args.push('(');
-                            self.next(); // consume '('
-                        }
-                        &crate::tokens::Token::ParenClose => {
-                            args.push(')');
-                            self.next(); // consume ')'
-                        }
-                        &crate::tokens::Token::BraceOpen => {
-                            args.push('{');
-                            self.next(); // consume '{'
-                        }
-                        &crate::tokens::Token::BraceClose => {
-                            args.push('}');
-                            self.next(); // consume '}'
-                        }
-                        &crate::tokens::Token::Comma => {
-                            args.push(',');
-                            self.next(); // consume ','
-                        }
-                        _ => {
-                            self.next(); // consume unknown token
-                        }
+                match self.peek() {
+                    crate::tokens::Token::Space
+                    | crate::tokens::Token::Tab
+                    | crate::tokens::Token::Newline => {
+                        input_args.push(' ');
+                        self.next(); // consume whitespace
+                    }
+                    crate::tokens::Token::String(s) => {
+                        input_args.push_str(&format!("\"{}\"", s));
+                        self.next(); // consume string
+                    }
+                    crate::tokens::Token::FormatString(s) => {
+                        input_args.push_str(&format!("f\"{}\"", s));
+                        self.next(); // consume format string
+                    }
+                    crate::tokens::Token::Number(_, lexeme)
+                    | crate::tokens::Token::Integer(_, lexeme) => {
+                        input_args.push_str(lexeme);
+                        self.next(); // consume number
+                    }
+                    crate::tokens::Token::Duration(text) => {
+                        input_args.push_str(text);
+                        self.next(); // consume duration
+                    }
+                    crate::tokens::Token::ShellDirectory { segments } => {
+                        input_args.push_str(&crate::paths::display(segments));
+                        self.next(); // consume path
+                    }
+                    crate::tokens::Token::ShellFile { path, .. } => {
+                        input_args.push_str(path);
+                        self.next(); // consume path
+                    }
+                    crate::tokens::Token::Null => {
+                        input_args.push_str("null");
+                        self.next(); // consume null
+                    }
+                    crate::tokens::Token::Boolean(b) => {
+                        input_args.push_str(&b.to_string());
+                        self.next(); // consume boolean
+                    }
+                    crate::tokens::Token::Keyword(k) => {
+                        input_args.push_str(&Self::keyword_text(k));
+                        self.next(); // consume keyword
+                    }
+                    crate::tokens::Token::Identifier(id) => {
+                        input_args.push_str(id);
+                        self.next(); // consume identifier
+                    }
+                    crate::tokens::Token::Operator(op) => {
+                        input_args.push_str(op);
+                        self.next(); // consume operator
+                    }
+                    crate::tokens::Token::BracketOpen => {
+                        input_args.push('[');
+                        self.next(); // consume bracket
+                    }
+                    crate::tokens::Token::BracketClose => {
+                        input_args.push(']');
+                        self.next(); // consume bracket
+                    }
+                    crate::tokens::Token::BraceOpen => {
+                        input_args.push('{');
+                        self.next(); // consume brace
+                    }
+                    crate::tokens::Token::BraceClose => {
+                        input_args.push('}');
+                        self.next(); // consume brace
+                    }
+                    crate::tokens::Token::Comma => {
+                        input_args.push(',');
+                        self.next(); // consume comma
+                    }
+                    crate::tokens::Token::Colon => {
+                        input_args.push(':');
+                        self.next(); // consume colon
+                    }
+                    crate::tokens::Token::AssignmentOperator => {
+                        input_args.push('=');
+                        self.next(); // consume '='
+                    }
+                    _ => {
+                        self.next(); // consume unknown token
                     }
                 }
-
-                if let crate::tokens::Token::Semicolon = self.peek() {
-                    self.next(); // consume ';'
-                }
-                return Some(crate::expressions::Expressions::ShellCommand {
-                    name: cmd,
-                    args: args.trim().to_string(),
-                });
             }
-            None
-        }
 
-        /// implements parsing rules to build TurtleExpression AST
-        pub fn parse_expr(&mut self) -> Option<crate::expressions::Expressions> {
+            if let crate::tokens::Token::Semicolon = self.peek() {
+                self.next(); // consume ';'
+            }
             // if let Some(args) = &self.args {
             //     let args = args.lock().unwrap();
             //     if args.debug {
-            //         println!(
-            //             "Parsing expression at token position {}: {:?}",
-            //             self.pos,
-            //             self.peek()
-            //         );
+            //         println!("parse_builtin: final args string: '{}'", input_args);
             //     }
             // }
+            return Some(crate::expressions::Expressions::Builtin {
+                name: cmd,
+                args: input_args.trim().to_string(),
+            });
+        }
+        None
+    }
 
-            // parse  built-in functions
-            if let Some(builtin) = self.parse_builtin() {
-                return Some(builtin);
+    // fn parse_variable
+    fn parse_command(&mut self) -> Option<crate::expressions::Expressions> {
+        if let crate::tokens::Token::Identifier(cmd) = self.peek() {
+            let cmd = cmd.clone();
+            // `name(` is a function call, eg: `split(line, /,/)` rather than `split`
+            if !crate::utils::is_command(&cmd)
+                || matches!(
+                    self.parsed.get(self.pos + 1),
+                    Some(crate::tokens::Token::ParenOpen)
+                )
+            {
+                return None;
             }
-            // parse shell commands
-            if let Some(command) = self.parse_command() {
-                return Some(command);
+            self.next(); // consume command identifier
+
+            let mut args = String::new();
+            // unmatched closing parens end a grouped command, eg: `(ls -la)`
+            let mut depth = 0;
+            while !matches!(
+                self.peek(),
+                crate::tokens::Token::Eof
+                    | crate::tokens::Token::Semicolon
+                    | crate::tokens::Token::PipeOperator
+            ) && !self.at_block_end()
+            {
+                match self.peek() {
+                    crate::tokens::Token::Space
+                    | crate::tokens::Token::Tab
+                    | crate::tokens::Token::Newline => {
+                        args.push(' ');
+                        self.next(); // consume whitespace
+                    }
+                    crate::tokens::Token::String(s) => {
+                        args.push_str(&format!("\"{}\"", s));
+                        self.next(); // consume string
+                    }
+                    crate::tokens::Token::FormatString(s) => {
+                        args.push_str(&format!("f\"{}\"", s));
+                        self.next(); // consume format string
+                    }
+                    // numbers as they were written, eg: `chmod 0755`
+                    crate::tokens::Token::Number(_, lexeme)
+                    | crate::tokens::Token::Integer(_, lexeme) => {
+                        args.push_str(lexeme);
+                        self.next(); // consume number
+                    }
+                    crate::tokens::Token::Duration(text) => {
+                        args.push_str(text);
+                        self.next(); // consume duration
+                    }
+                    crate::tokens::Token::ShellDirectory { segments } => {
+                        args.push_str(&crate::paths::display(segments));
+                        self.next(); // consume path
+                    }
+                    crate::tokens::Token::ShellFile { path, .. } => {
+                        args.push_str(path);
+                        self.next(); // consume path
+                    }
+                    crate::tokens::Token::Null => {
+                        args.push_str("null");
+                        self.next(); // consume null
+                    }
+                    crate::tokens::Token::Identifier(id) => {
+                        args.push_str(id);
+                        self.next(); // consume identifier
+                    }
+                    // keywords are words like any other in commands, eg: `echo try`
+                    crate::tokens::Token::Keyword(k) => {
+                        args.push_str(&Self::keyword_text(k));
+                        self.next(); // consume keyword
+                    }
+                    crate::tokens::Token::Operator(op) => {
+                        args.push_str(op);
+                        self.next(); // consume operator
+                    }
+                    &crate::tokens::Token::ShellDot => {
+                        args.push('.');
+                        self.next(); // consume dot
+                    }
+                    &crate::tokens::Token::ShellDoubleDot => {
+                        args.push_str("..");
+                        self.next(); // consume double dot
+                    }
+                    &crate::tokens::Token::BracketOpen => {
+                        args.push('[');
+                        self.next(); // consume '['
+                    }
+                    &crate::tokens::Token::BracketClose => {
+                        args.push(']');
+                        self.next(); // consume ']'
+                    }
+                    &crate::tokens::Token::ParenOpen => {
+                        depth += 1;
+                        args.push('(');
+                        self.next(); // consume '('
+                    }
+                    &crate::tokens::Token::ParenClose => {
+                        if depth == 0 {
+                            break;
+                        }
+                        depth -= 1;
+                        args.push(')');
+                        self.next(); // consume ')'
+                    }
+                    &crate::tokens::Token::BraceOpen => {
+                        args.push('{');
+                        self.next(); // consume '{'
+                    }
+                    &crate::tokens::Token::BraceClose => {
+                        args.push('}');
+                        self.next(); // consume '}'
+                    }
+                    &crate::tokens::Token::Comma => {
+                        args.push(',');
+                        self.next(); // consume ','
+                    }
+                    crate::tokens::Token::Boolean(b) => {
+                        args.push_str(&b.to_string());
+                        self.next(); // consume boolean
+                    }
+                    crate::tokens::Token::RegularExpression { pattern, flags } => {
+                        args.push_str(&format!(
+                            "/{}/{}",
+                            pattern,
+                            flags.as_deref().unwrap_or_default()
+                        ));
+                        self.next(); // consume regular expression
+                    }
+                    // operators are text like any other in commands, eg: `echo $HOME`, `a/b`
+                    token => {
+                        if let Some(text) = Self::symbol_text(token) {
+                            args.push_str(text);
+                        }
+                        self.next(); // consume token
+                    }
+                }
             }
 
-            // parse assignments
-            if let Some(assignment) = self.parse_assignment() {
-                return Some(assignment);
+            if let crate::tokens::Token::Semicolon = self.peek() {
+                self.next(); // consume ';'
             }
+            return Some(crate::expressions::Expressions::ShellCommand {
+                name: cmd,
+                args: args.trim().to_string(),
+            });
+        }
+        None
+    }
 
-            // // parse variable access - experimental
-            // if let Some(var_expr) = self.parse_variable() {
-            //     return Some(var_expr);
-            // }
+    /// implements parsing rules to build TurtleExpression AST
+    pub fn parse_expr(&mut self) -> Option<crate::expressions::Expressions> {
+        // if let Some(args) = &self.args {
+        //     let args = args.lock().unwrap();
+        //     if args.debug {
+        //         println!(
+        //             "Parsing expression at token position {}: {:?}",
+        //             self.pos,
+        //             self.peek()
+        //         );
+        //     }
+        // }
 
-            // parse environment variables
-            // if let Some(env_var) = self.parse_environment_variable() {
-            //     return Some(env_var);
-            // }
+        // parse control flow, `if` and `for`
+        if let Some(control_flow) = self.parse_control_flow() {
+            return Some(control_flow);
+        }
 
-            let mut expr = self.parse_primary();
+        // parse imports, `import` and `from`
+        if let Some(import) = self.parse_import() {
+            return Some(import);
+        }
 
-            if let Some(func_def) = self.parse_function_def() {
-                return Some(func_def);
-            }
+        // parse  built-in functions
+        if let Some(builtin) = self.parse_builtin() {
+            return Some(builtin);
+        }
+        // parse shell commands
+        if let Some(command) = self.parse_command() {
+            return Some(self.parse_pipe(command));
+        }
 
-            loop {
-                if let Some(member_access) = self.parse_member_access(expr.clone()?) {
-                    expr = Some(member_access);
-                    continue;
-                }
-                if let Some(func_call) = self.parse_function_call(expr.clone().unwrap()) {
-                    expr = Some(func_call);
-                    continue;
-                }
-                break;
-            }
+        // parse assignments
+        if let Some(assignment) = self.parse_assignment() {
+            return Some(assignment);
+        }
 
-            // if let Some(args) = &self.args {
-            //     let args = args.lock().unwrap();
-            //     if args.debug {
-            //         println!(
-            //             "🔍 After primary parse - expr: {:?}, current token: {:?}",
-            //             expr,
-            //             self.peek()
-            //         );
-            //     }
-            // }
+        // // parse variable access - experimental
+        // if let Some(var_expr) = self.parse_variable() {
+        //     return Some(var_expr);
+        // }
 
-            // parse unary operations
-            // if expr.is_none() {
-            //     if let Some(args) = &self.args {
-            //         let args = args.lock().unwrap();
-            //         if args.debug {
-            //             println!("🔍 Expr is None, attempting to parse unary operation");
-            //         }
-            //     }
-            // } else {
-            //     if let Some(args) = &self.args {
-            //         let args = args.lock().unwrap();
-            //         if args.debug {
-            //             println!(
-            //                 "🔍 Expr is Some({:?}), skipping unary operation parsing",
-            //                 expr
-            //             );
-            //         }
-            //     }
-            // }
+        // parse environment variables
+        // if let Some(env_var) = self.parse_environment_variable() {
+        //     return Some(env_var);
+        // }
 
-            // disable unary parsing for now
+        let mut expr = self.parse_primary();
 
-            // if let Some(unary) = self.parse_unary() {
-            //     println!("🔍 Parsed unary operation: {:?}", unary);
-            //     return Some(unary);
-            // }
+        loop {
+            if let Some(member_access) = self.parse_member_access(expr.clone()?) {
+                expr = Some(member_access);
+                continue;
+            }
+            if let Some(func_call) = self.parse_function_call(expr.clone().unwrap()) {
+                expr = Some(func_call);
+                continue;
+            }
+            break;
+        }
 
-            // if expr.is_none() {}
+        // if let Some(args) = &self.args {
+        //     let args = args.lock().unwrap();
+        //     if args.debug {
+        //         println!(
+        //             "🔍 After primary parse - expr: {:?}, current token: {:?}",
+        //             expr,
+        //             self.peek()
+        //         );
+        //     }
+        // }
 
-            // skip whitespace before checking for binary operations
-            self.skip_whitespace();
+        // parse unary operations
+        // if expr.is_none() {
+        //     if let Some(args) = &self.args {
+        //         let args = args.lock().unwrap();
+        //         if args.debug {
+        //             println!("🔍 Expr is None, attempting to parse unary operation");
+        //         }
+        //     }
+        // } else {
+        //     if let Some(args) = &self.args {
+        //         let args = args.lock().unwrap();
+        //         if args.debug {
+        //             println!(
+        //                 "🔍 Expr is Some({:?}), skipping unary operation parsing",
+        //                 expr
+        //             );
+        //         }
+        //     }
+        // }
 
-            // parse binary operations (chained)
-            while let Some(crate::tokens::Token::Operator(_)) = self.peek().clone().into() {
-                if let Some(left) = expr {
-                    // expr = self.parse_binary(left);
-                    expr = Some(self.parse_binary_with_precedence(1, left));
-                } else {
-                    break;
-                }
-            }
+        // disable unary parsing for now
 
-            expr
+        // if let Some(unary) = self.parse_unary() {
+        //     println!("🔍 Parsed unary operation: {:?}", unary);
+        //     return Some(unary);
+        // }
+
+        // if expr.is_none() {}
+
+        // skip whitespace before checking for binary operations
+        self.skip_whitespace();
+
+        // parse binary operations (chained)
+        if let Some(left) = expr {
+            expr = Some(self.parse_binary_with_precedence(1, left));
         }
+
+        // parse pipeline stages
+        expr.map(|expr| self.parse_pipe(expr))
     }
 }
 
@@ -1262,6 +2244,14 @@ impl Interpreter {
                     tokens.push(crate::tokens::Token::Comma);
                     chars.next();
                 }
+                // relative path literals, eg: `./src/main.rs`, `../dir`
+                '.' if matches!(
+                    (chars.clone().nth(1), chars.clone().nth(2)),
+                    (Some('/'), _) | (Some('.'), Some('/'))
+                ) =>
+                {
+                    tokens.push(Self::read_path(&mut chars));
+                }
                 // dots for relative paths and identifiers
                 '.' => {
                     // let's handle the case of a double dot '..' for relative paths
@@ -1315,33 +2305,92 @@ impl Interpreter {
                     }
                     // chars.next();
                 }
-                // string literals
+                // string literals, with escapes, eg: `"tab\tseparated"`
                 '"' => {
                     chars.next(); // skip opening quote
-                    let mut s = String::new();
-                    while let Some(&d) = chars.peek() {
-                        if d == '"' {
-                            chars.next();
-                            break;
-                        } else {
-                            s.push(d);
-                            chars.next();
-                        }
+                    let mut lookahead = chars.clone();
+                    if lookahead.next() == Some('"') && lookahead.next() == Some('"') {
+                        // triple quoted multiline strings, eg: `"""\n  text\n  """`
+                        chars.next();
+                        chars.next();
+                        let raw = Self::read_quoted(&mut chars, "\"\"\"");
+                        tokens.push(crate::tokens::Token::String(Self::unescape(&Self::dedent(
+                            &raw,
+                        ))));
+                    } else {
+                        let raw = Self::read_quoted(&mut chars, "\"");
+                        tokens.push(crate::tokens::Token::String(Self::unescape(&raw)));
                     }
-                    tokens.push(crate::tokens::Token::String(s));
                 }
-                // number literals
+                // template strings, eg: `hello ${name}`
+                '`' => {
+                    chars.next(); // skip opening backtick
+                    let raw = Self::read_quoted(&mut chars, "`");
+                    let parts = self.tokenize_template(&raw);
+                    tokens.push(crate::tokens::Token::TemplateString(parts));
+                }
+                // number literals, integers, eg: `42`, `0xff`, `1_000`, and floats, eg: `2.5`, `1e-3`
                 '0'..='9' => {
                     let mut num = String::new();
+                    let mut float = false;
+                    let prefixed = c == '0'
+                        && matches!(
+                            chars.clone().nth(1),
+                            Some('x' | 'X' | 'o' | 'O' | 'b' | 'B')
+                        );
+                    if prefixed {
+                        num.extend(chars.by_ref().take(2));
+                    }
                     while let Some(&d) = chars.peek() {
-                        if d.is_ascii_digit() || d == '.' {
+                        let next = chars.clone().nth(1);
+                        if d.is_ascii_digit() || d == '_' || (prefixed && d.is_ascii_hexdigit()) {
+                            num.push(d);
+                        } else if prefixed {
+                            break;
+                        } else if d == '.' && !float && next.is_some_and(|n| n.is_ascii_digit()) {
+                            float = true;
+                            num.push(d);
+                        } else if (d == 'e' || d == 'E')
+                            && !num.contains(['e', 'E'])
+                            && next.is_some_and(|n| n.is_ascii_digit() || n == '-' || n == '+')
+                        {
+                            float = true;
                             num.push(d);
                             chars.next();
+                            if let Some(sign) = chars.next_if(|n| *n == '-' || *n == '+') {
+                                num.push(sign);
+                            }
+                            continue;
                         } else {
-                            break; // stop parsing number at any non-digit/dot character
+                            break; // stop parsing number at any other character
+                        }
+                        chars.next();
+                    }
+                    // duration literals, a unit after each number, eg: `5m`, `2h30m`, `500ms`
+                    if !float && !prefixed && chars.peek().is_some_and(|u| u.is_ascii_alphabetic())
+                    {
+                        let mut literal = num.clone();
+                        let mut rest = chars.clone();
+                        while let Some(d) = rest.next_if(|d| d.is_alphanumeric() || *d == '_') {
+                            literal.push(d);
+                        }
+                        if crate::dates::Duration::parse(&literal).is_some() {
+                            chars = rest;
+                            tokens.push(crate::tokens::Token::Duration(literal));
+                            continue;
+                        }
+                    }
+                    if float {
+                        match num.replace('_', "").parse() {
+                            Ok(n) => tokens.push(crate::tokens::Token::Number(n, num)),
+                            Err(_) => tokens.push(crate::tokens::Token::Identifier(num)),
+                        }
+                    } else {
+                        match crate::numbers::Integer::parse(&num) {
+                            Some(n) => tokens.push(crate::tokens::Token::Integer(n, num)),
+                            None => tokens.push(crate::tokens::Token::Identifier(num)),
                         }
                     }
-                    tokens.push(crate::tokens::Token::Number(num.parse().unwrap()))
                 }
 
                 // TODO: handle env vars a little better
@@ -1362,8 +2411,50 @@ impl Interpreter {
                         }
                     }
 
+                    // raw string literals, eg: `r"C:\path"` or `r#"say "hi""#`
+                    if identifier == "r" {
+                        let mut lookahead = chars.clone();
+                        let mut hashes = 0;
+                        while lookahead.peek() == Some(&'#') {
+                            lookahead.next();
+                            hashes += 1;
+                        }
+                        if lookahead.next() == Some('"') {
+                            chars = lookahead;
+                            let end = format!("\"{}", "#".repeat(hashes));
+                            let mut s = String::new();
+                            while chars.peek().is_some() {
+                                if chars.clone().take(end.len()).eq(end.chars()) {
+                                    chars.nth(end.len() - 1);
+                                    break;
+                                }
+                                s.extend(chars.next());
+                            }
+                            tokens.push(crate::tokens::Token::String(s));
+                            continue;
+                        }
+                    }
+
+                    // format string literals, eg: `f"hello {name}"`
+                    if identifier == "f" && chars.peek() == Some(&'"') {
+                        chars.next(); // skip opening quote
+                        let mut s = String::new();
+                        for d in chars.by_ref() {
+                            if d == '"' {
+                                break;
+                            }
+                            s.push(d);
+                        }
+                        tokens.push(crate::tokens::Token::FormatString(s));
+                        continue;
+                    }
+
                     // check for boolean literals
                     let canonical = identifier[..1].to_ascii_uppercase() + &identifier[1..];
+                    if canonical == "Null" {
+                        tokens.push(crate::tokens::Token::Null);
+                        continue;
+                    }
                     if KEYWORDS.contains(&canonical.as_str()) {
                         tokens.push(crate::tokens::Token::Keyword(canonical));
                         continue;
@@ -1376,18 +2467,49 @@ impl Interpreter {
                     }
                 }
                 // operators
-                _ if "+-*/=<>&|!^".contains(c) => {
+                _ if "+-*/%=<>&|!^".contains(c) => {
+                    // regex literals, eg: `/^v\d+/i`
+                    if c == '/' {
+                        let consumed =
+                            input.len() - chars.clone().map(char::len_utf8).sum::<usize>();
+                        let before = input[..consumed].trim_end().chars().last();
+                        if let Some(regex) = Self::read_regex(&mut chars, before) {
+                            tokens.push(regex);
+                            continue;
+                        }
+                        // absolute path literals, eg: `/etc/hosts`, a name follows the `/`
+                        // directly and nothing comes right before it, so `a / b` and `a/b` divide
+                        let previous = input[..consumed].chars().last();
+                        if previous.is_none_or(|p| p.is_whitespace() || "([{,=:;".contains(p))
+                            && chars
+                                .clone()
+                                .nth(1)
+                                .is_some_and(|n| n.is_alphabetic() || "_.~".contains(n))
+                        {
+                            tokens.push(Self::read_path(&mut chars));
+                            continue;
+                        }
+                    }
                     let mut op = String::new();
                     while let Some(&d) = chars.peek() {
-                        if "+-*/=<>&|!^".contains(d) {
+                        if "+-*/%=<>&|!^".contains(d) {
                             op.push(d);
                             chars.next();
                         } else {
                             break;
                         }
                     }
+                    // regex match operators, `=~` and `!~`
+                    if (op == "=" || op == "!") && chars.peek() == Some(&'~') {
+                        op.push('~');
+                        chars.next();
+                    }
                     let operation = op.clone();
-                    if &operation == "==" {
+                    if &operation == "=" {
+                        tokens.push(crate::tokens::Token::AssignmentOperator);
+                    } else if &operation == "=>" {
+                        tokens.push(crate::tokens::Token::FatArrow);
+                    } else if &operation == "==" {
                         tokens.push(crate::tokens::Token::EqualOperator);
                     } else if &operation == "!=" {
                         tokens.push(crate::tokens::Token::NotEqualOperator);
@@ -1411,14 +2533,59 @@ impl Interpreter {
                         tokens.push(crate::tokens::Token::LogicalAndOperator);
                     } else if &operation == "||" {
                         tokens.push(crate::tokens::Token::LogicalOrOperator);
+                    } else if &operation == "|" {
+                        tokens.push(crate::tokens::Token::PipeOperator);
                     } else if &operation == "!" {
                         tokens.push(crate::tokens::Token::NotOperator);
                     } else if &operation == "%" {
                         tokens.push(crate::tokens::Token::ModulusOperator);
-                    } else if &operation == "^" {
+                    } else if &operation == "=~" {
+                        tokens.push(crate::tokens::Token::RegexMatchOperator);
+                    } else if &operation == "!~" {
+                        tokens.push(crate::tokens::Token::RegexNotMatchOperator);
+                    } else if &operation == "**" {
                         tokens.push(crate::tokens::Token::ExponentiationOperator);
+                    } else if &operation == "//" {
+                        tokens.push(crate::tokens::Token::FloorDivisionOperator);
+                    } else if &operation == "&" {
+                        tokens.push(crate::tokens::Token::BitwiseAndOperator);
+                    } else if &operation == "^" {
+                        tokens.push(crate::tokens::Token::BitwiseXorOperator);
+                    } else if &operation == "<<" {
+                        tokens.push(crate::tokens::Token::ShiftLeftOperator);
+                    } else if &operation == ">>" {
+                        tokens.push(crate::tokens::Token::ShiftRightOperator);
+                    } else {
+                        tokens.push(crate::tokens::Token::Operator(operation));
+                    }
+                }
+                // home path literals, eg: `~/notes.md`
+                '~' if chars.clone().nth(1) == Some('/') => {
+                    tokens.push(Self::read_path(&mut chars));
+                }
+                '~' => {
+                    tokens.push(crate::tokens::Token::BitwiseNotOperator);
+                    chars.next();
+                }
+                // optional chaining, eg: `pod?.status`, and null coalescing, eg: `name ?? "none"`
+                '?' => {
+                    chars.next();
+                    match chars.peek() {
+                        Some('.') => {
+                            chars.next();
+                            tokens.push(crate::tokens::Token::OptionalChainOperator);
+                        }
+                        Some('?') => {
+                            chars.next();
+                            tokens.push(crate::tokens::Token::NullCoalescingOperator);
+                        }
+                        _ => {}
                     }
                 }
+                '$' => {
+                    tokens.push(crate::tokens::Token::DollarOperator);
+                    chars.next();
+                }
                 // unrecognized characters
                 _ => {
                     chars.next();
@@ -1437,6 +2604,202 @@ impl Interpreter {
         tokens
     }
 
+    /// read a quoted literal up to the unescaped `end` quote, consuming the quote
+    ///
+    /// escapes are kept as written, see `Interpreter::unescape`
+    fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, end: &str) -> String {
+        let mut raw = String::new();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                raw.push(c);
+                raw.extend(chars.next());
+            } else if end.starts_with(c)
+                && chars.clone().take(end.len() - 1).eq(end.chars().skip(1))
+            {
+                for _ in 1..end.len() {
+                    chars.next();
+                }
+                break;
+            } else {
+                raw.push(c);
+            }
+        }
+        raw
+    }
+
+    /// read a regex literal from its opening `/`, eg: `/^v\d+/i`
+    ///
+    /// a `/` only opens a regex where a value can start, so `a / b` stays a division. the
+    /// literal has to close on the same line and can't run into a word, so paths like
+    /// `/usr/bin` aren't read as regexes. `\/` writes a literal `/`
+    fn read_regex(
+        chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+        before: Option<char>,
+    ) -> Option<crate::tokens::Token> {
+        if before.is_some_and(|c| c.is_alphanumeric() || "_.)]}\"'`".contains(c)) {
+            return None;
+        }
+        let mut lookahead = chars.clone();
+        lookahead.next(); // skip opening '/'
+        let mut pattern = String::new();
+        loop {
+            match lookahead.next()? {
+                '\n' => return None,
+                '\\' if lookahead.peek() == Some(&'/') => {
+                    pattern.push('/');
+                    lookahead.next();
+                }
+                '\\' => {
+                    pattern.push('\\');
+                    pattern.extend(lookahead.next());
+                }
+                '/' => break,
+                c => pattern.push(c),
+            }
+        }
+        let mut flags = String::new();
+        while let Some(&flag) = lookahead.peek()
+            && crate::patterns::FLAGS.contains(flag)
+        {
+            flags.push(flag);
+            lookahead.next();
+        }
+        if pattern.is_empty()
+            || lookahead
+                .peek()
+                .is_some_and(|c| c.is_alphanumeric() || "_./".contains(*c))
+        {
+            return None;
+        }
+        *chars = lookahead;
+        Some(crate::tokens::Token::RegularExpression {
+            pattern,
+            flags: (!flags.is_empty()).then_some(flags),
+        })
+    }
+
+    /// read a path literal, eg: `./src/main.rs`, `../dir`, `~/notes.md` or `/etc/hosts`
+    ///
+    /// paths ending in a name with an extension are files, other paths directories
+    fn read_path(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> crate::tokens::Token {
+        let mut path = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || "_-./~@+%".contains(*c)) {
+            path.push(c);
+        }
+        let name = path.rsplit('/').next().unwrap_or_default().to_string();
+        match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => {
+                crate::tokens::Token::ShellFile {
+                    extension: Some(extension.to_string()),
+                    name,
+                    path,
+                }
+            }
+            _ => crate::tokens::Token::ShellDirectory {
+                segments: path.split('/').map(str::to_string).collect(),
+            },
+        }
+    }
+
+    /// replace escape sequences: `\n`, `\t`, `\r`, `\0`, `\\`, `\"`, `\'`, `` \` ``, `\$` and `\u{1F422}`
+    ///
+    /// unknown escapes are kept as written, eg: `\d` in a regex
+    fn unescape(raw: &str) -> String {
+        let mut s = String::new();
+        let mut chars = raw.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                s.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('r') => s.push('\r'),
+                Some('0') => s.push('\0'),
+                Some(c @ ('\\' | '"' | '\'' | '`' | '$')) => s.push(c),
+                Some('u') if chars.peek() == Some(&'{') => {
+                    let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                    match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                        Some(c) => s.push(c),
+                        None => s.push_str(&format!("\\u{{{}}}", code)),
+                    }
+                }
+                Some(c) => {
+                    s.push('\\');
+                    s.push(c);
+                }
+                None => s.push('\\'),
+            }
+        }
+        s
+    }
+
+    /// strip the common indentation from a multiline string
+    ///
+    /// a newline straight after the opening quotes and the whitespace before the closing
+    /// quotes are dropped, so the string can be indented with the surrounding code
+    fn dedent(raw: &str) -> String {
+        let raw = raw.strip_prefix('\n').unwrap_or(raw);
+        let raw = match raw.rfind('\n') {
+            Some(i) if raw[i + 1..].trim().is_empty() => &raw[..i],
+            _ => raw,
+        };
+        let indent = raw
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        raw.lines()
+            .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    /// split a template string into text and `${expr}` parts
+    ///
+    /// text becomes `Token::String` and each expression is tokenized into a `Token::CodeBlock`
+    fn tokenize_template(&mut self, raw: &str) -> Vec<crate::tokens::Token> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = raw.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    text.push(c);
+                    text.extend(chars.next());
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next(); // skip '{'
+                    let mut expr = String::new();
+                    let mut depth = 0;
+                    for d in chars.by_ref() {
+                        match d {
+                            '{' => depth += 1,
+                            '}' if depth == 0 => break,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                        expr.push(d);
+                    }
+                    if !text.is_empty() {
+                        parts.push(crate::tokens::Token::String(Self::unescape(&text)));
+                        text.clear();
+                    }
+                    let mut tokens = self.tokenize_primitives(expr.trim());
+                    tokens.retain(|t| *t != crate::tokens::Token::Eof);
+                    parts.push(crate::tokens::Token::CodeBlock(tokens));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(crate::tokens::Token::String(Self::unescape(&text)));
+        }
+        parts
+    }
+
     /// Tokenize shell commands and args
     #[deprecated]
     pub fn tokenize_shell_commands(
@@ -1509,11 +2872,19 @@ impl Interpreter {
                             }
 
                             // Handle numbers
-                            crate::tokens::Token::Number(n) => {
+                            crate::tokens::Token::Number(_, lexeme)
+                            | crate::tokens::Token::Integer(_, lexeme) => {
+                                if !args.is_empty() {
+                                    args.push(' ');
+                                }
+                                args.push_str(lexeme);
+                                iter.next();
+                            }
+                            crate::tokens::Token::Duration(text) => {
                                 if !args.is_empty() {
                                     args.push(' ');
                                 }
-                                args.push_str(&n.to_string());
+                                args.push_str(text);
                                 iter.next();
                             }
 
@@ -1562,7 +2933,7 @@ impl Interpreter {
 
         while let Some(token) = iter.next() {
             match &token {
-                crate::tokens::Token::Builtin { name, args } => {
+                crate::tokens::Token::Builtin { name, .. } => {
                     let mut args = Vec::new();
                     while let Some(next_token) = iter.peek() {
                         match next_token {
@@ -1572,31 +2943,31 @@ impl Interpreter {
                             crate::tokens::Token::Operator(op) if op == "-" => {
                                 iter.next(); // consume first '-'
                                 // Handles long arg and their values
-                                if let Some(crate::tokens::Token::Operator(op2)) = iter.peek() {
-                                    if op2 == "-" {
-                                        iter.next(); // consume second '-'
-                                        if let Some(crate::tokens::Token::Identifier(name)) =
-                                            iter.peek()
-                                        {
-                                            let name = name.clone();
-                                            iter.next(); // consume builtin name
-                                            let mut values = Vec::new();
-                                            // Optionally, collect values after long arg
-                                            while let Some(val_token) = iter.peek() {
-                                                match val_token {
-                                                    crate::tokens::Token::String(_)
-                                                    | crate::tokens::Token::Identifier(_) => {
-                                                        values.push(iter.next().unwrap());
-                                                    }
-                                                    _ => break,
+                                if let Some(crate::tokens::Token::Operator(op2)) = iter.peek()
+                                    && op2 == "-"
+                                {
+                                    iter.next(); // consume second '-'
+                                    if let Some(crate::tokens::Token::Identifier(name)) =
+                                        iter.peek()
+                                    {
+                                        let name = name.clone();
+                                        iter.next(); // consume builtin name
+                                        let mut values = Vec::new();
+                                        // Optionally, collect values after long arg
+                                        while let Some(val_token) = iter.peek() {
+                                            match val_token {
+                                                crate::tokens::Token::String(_)
+                                                | crate::tokens::Token::Identifier(_) => {
+                                                    values.push(iter.next().unwrap());
                                                 }
+                                                _ => break,
                                             }
-                                            args.push(crate::tokens::Token::ShellLongArg {
-                                                name,
-                                                values,
-                                            });
-                                            continue;
                                         }
+                                        args.push(crate::tokens::Token::ShellLongArg {
+                                            name,
+                                            values,
+                                        });
+                                        continue;
                                     }
                                 }
                                 // Handles short arg and their values
@@ -1611,10 +2982,9 @@ impl Interpreter {
                                             | crate::tokens::Token::Identifier(_) => {
                                                 if let crate::tokens::Token::Operator(op) =
                                                     val_token
+                                                    && op == "-"
                                                 {
-                                                    if op == "-" {
-                                                        break;
-                                                    }
+                                                    break;
                                                 }
                                                 values.push(iter.next().unwrap());
                                             }
@@ -1647,8 +3017,13 @@ impl Interpreter {
                                             path.push('/');
                                             iter.next(); // consume '/'
                                         }
-                                        crate::tokens::Token::Number(n) => {
-                                            path.push_str(&n.to_string());
+                                        crate::tokens::Token::Number(_, lexeme)
+                                        | crate::tokens::Token::Integer(_, lexeme) => {
+                                            path.push_str(lexeme);
+                                            iter.next(); // consume segment
+                                        }
+                                        crate::tokens::Token::Duration(text) => {
+                                            path.push_str(text);
                                             iter.next(); // consume segment
                                         }
                                         crate::tokens::Token::String(s) => {
@@ -1673,36 +3048,31 @@ impl Interpreter {
                             crate::tokens::Token::Operator(op) if op == "." => {
                                 let mut path = String::from(".");
                                 iter.next(); // consume '.'
-                                if let Some(crate::tokens::Token::Operator(op2)) = iter.peek() {
-                                    if op2 == "/" {
-                                        path.push('/');
-                                        iter.next(); // consume '/'
-                                        while let Some(next_seg) = iter.peek() {
-                                            match next_seg {
-                                                crate::tokens::Token::Identifier(seg) => {
-                                                    if !path.ends_with('/') {
-                                                        path.push('/');
-                                                    }
-                                                    path.push_str(seg);
-                                                    iter.next(); // consume segment
-                                                }
-                                                crate::tokens::Token::Operator(op3)
-                                                    if op3 == "/" =>
-                                                {
+                                if let Some(crate::tokens::Token::Operator(op2)) = iter.peek()
+                                    && op2 == "/"
+                                {
+                                    path.push('/');
+                                    iter.next(); // consume '/'
+                                    while let Some(next_seg) = iter.peek() {
+                                        match next_seg {
+                                            crate::tokens::Token::Identifier(seg) => {
+                                                if !path.ends_with('/') {
                                                     path.push('/');
-                                                    iter.next(); // consume '/'
                                                 }
-                                                _ => break,
+                                                path.push_str(seg);
+                                                iter.next(); // consume segment
+                                            }
+                                            crate::tokens::Token::Operator(op3) if op3 == "/" => {
+                                                path.push('/');
+                                                iter.next(); // consume '/'
                                             }
+                                            _ => break,
                                         }
-                                        args.push(crate::tokens::Token::ShellDirectory {
-                                            segments: path
-                                                .split('/')
-                                                .map(|s| s.to_string())
-                                                .collect(),
-                                        });
-                                        continue;
                                     }
+                                    args.push(crate::tokens::Token::ShellDirectory {
+                                        segments: path.split('/').map(|s| s.to_string()).collect(),
+                                    });
+                                    continue;
                                 }
                             }
 
@@ -1781,4 +3151,24 @@ impl Interpreter {
         );
         parser.parse_expr()
     }
+
+    /// Generate the ASTs of a script, statements separated by newlines or `;`
+    ///
+    /// `None` when a `}` is left unmatched
+    pub fn interpret_script(&mut self) -> Option<Vec<crate::expressions::Expressions>> {
+        let tokens = self.tokens.clone();
+        let mut parser = AbstractSyntaxTree::new(
+            tokens,
+            self.builtins.clone(),
+            self.env.clone(),
+            self.aliases.clone(),
+            self.vars.clone(),
+            self.args.clone(),
+        );
+        let expressions = parser.parse_statements();
+        match parser.peek() {
+            crate::tokens::Token::Eof => Some(expressions),
+            _ => None,
+        }
+    }
 }
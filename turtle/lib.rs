@@ -3,13 +3,26 @@ pub mod builtins;
 pub mod config;
 pub mod constants;
 pub mod context;
+pub mod dates;
+pub mod destructure;
+pub mod errors;
 pub mod expressions;
+pub mod format;
+pub mod functions;
 pub mod history;
+pub mod http;
 pub mod lang;
+pub mod modules;
+pub mod numbers;
+pub mod paths;
+pub mod patterns;
 pub mod shell;
+pub mod stdlib;
 pub mod style;
+pub mod terminal;
 pub mod tokens;
 pub mod utils;
+pub mod viewer;
 
 // re-export commonly used items for easier access
 pub use crate::builtins::*;
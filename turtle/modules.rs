@@ -0,0 +1,226 @@
+/// Modules: turtle files imported with `import "lib/git.tt" as git` or
+/// `from "lib/k8s.tt" import ctx, ns`
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// a module is evaluated with its own variables, the ones it defines are its exports, except
+/// names starting with `_`. evaluating and caching modules is left to `Context`
+use crate::expressions::Expressions;
+
+/// the extension of turtle files, it can be left out of imports, eg: `import "lib/git"`
+pub const EXTENSION: &str = "tt";
+
+/// environment variable with directories searched for imports, separated like `PATH`
+pub const SEARCH_PATH: &str = "TURTLE_PATH";
+
+/// the directories searched for imports, those of `TURTLE_PATH` and then `execution.modules`
+pub fn search_path(turtle_path: Option<&str>, modules: &[String]) -> Vec<std::path::PathBuf> {
+    let mut dirs: Vec<std::path::PathBuf> = turtle_path
+        .map(|paths| std::env::split_paths(paths).collect())
+        .unwrap_or_default();
+    dirs.extend(modules.iter().map(std::path::PathBuf::from));
+    dirs.into_iter()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| crate::utils::expand_path(&dir.to_string_lossy()).into())
+        .collect()
+}
+
+/// the file of an import
+///
+/// absolute paths and paths starting with `./` or `../` are used as they are, relative to the
+/// directory of the importing file. other paths are looked for in that directory and then the
+/// search path. files are canonical so each module has a single key
+pub fn resolve(
+    path: &str,
+    dir: &std::path::Path,
+    search: &[std::path::PathBuf],
+) -> Result<std::path::PathBuf, String> {
+    let expanded = std::path::PathBuf::from(crate::utils::expand_path(path));
+    let dirs: Vec<&std::path::Path> =
+        if expanded.is_absolute() || path.starts_with("./") || path.starts_with("../") {
+            vec![dir]
+        } else {
+            std::iter::once(dir)
+                .chain(search.iter().map(|dir| dir.as_path()))
+                .collect()
+        };
+    for dir in dirs {
+        let mut file = dir.join(&expanded);
+        if file.extension().is_none() {
+            file.set_extension(EXTENSION);
+        }
+        if file.is_file() {
+            return file
+                .canonicalize()
+                .map_err(|e| format!("import: {}: {}", file.display(), e));
+        }
+    }
+    Err(format!("import: `{}` not found", path))
+}
+
+/// the error of importing a file that is still being imported, eg:
+/// `circular import: a.tt -> b.tt -> a.tt`
+///
+/// `None` when the file isn't on the stack of files being imported
+pub fn cycle(importing: &[std::path::PathBuf], file: &std::path::Path) -> Option<String> {
+    let start = importing.iter().position(|entry| entry == file)?;
+    let files = importing[start..]
+        .iter()
+        .map(|entry| entry.as_path())
+        .chain(std::iter::once(file));
+    Some(format!("circular import: {}", chain(files)))
+}
+
+/// the names of a chain of imported files, eg: `a.tt -> b.tt -> c.tt`
+pub fn chain<'a>(files: impl IntoIterator<Item = &'a std::path::Path>) -> String {
+    files
+        .into_iter()
+        .map(|entry| {
+            entry
+                .file_name()
+                .unwrap_or(entry.as_os_str())
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
+
+/// the namespace of a module, an object of the variables it defines sorted by name
+///
+/// names starting with `_` are private to the module
+pub fn namespace(vars: &std::collections::HashMap<String, Expressions>) -> Expressions {
+    let mut entries: Vec<(String, Expressions)> = vars
+        .iter()
+        .filter(|(name, _)| !name.starts_with('_'))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Expressions::Object(entries)
+}
+
+/// the entries of a namespace named by `from ... import a, b`, in the order they were named
+pub fn select(
+    namespace: &Expressions,
+    names: &[String],
+    path: &str,
+) -> Result<Vec<(String, Expressions)>, String> {
+    let Expressions::Object(entries) = namespace else {
+        return Err(format!("import: {} isn't a module", path));
+    };
+    names
+        .iter()
+        .map(|name| {
+            entries
+                .iter()
+                .find(|(key, _)| key == name)
+                .cloned()
+                .ok_or_else(|| format!("import: `{}` isn't defined in {}", name, path))
+        })
+        .collect()
+}
+
+/// the variable a namespace is imported as, the `as` name or the stem of the file
+pub fn alias(path: &str, alias: Option<&str>) -> String {
+    match alias {
+        Some(alias) => alias.to_string(),
+        None => std::path::Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    #[test]
+    fn test_resolve_imports() {
+        let root = std::env::temp_dir().join(format!("turtle-modules-{}", std::process::id()));
+        let lib = root.join("lib");
+        let shared = root.join("shared");
+        std::fs::create_dir_all(&lib).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(lib.join("git.tt"), "").unwrap();
+        std::fs::write(shared.join("k8s.tt"), "").unwrap();
+        let root = root.canonicalize().unwrap();
+        let search = search_path(Some(&shared.to_string_lossy()), &[]);
+
+        let git = root.join("lib/git.tt");
+        assert_eq!(resolve("lib/git.tt", &root, &search), Ok(git.clone()));
+        assert_eq!(resolve("./lib/git", &root, &search), Ok(git.clone()));
+        assert_eq!(resolve("../lib/git.tt", &lib, &search), Ok(git.clone()));
+        assert_eq!(
+            resolve(&git.to_string_lossy(), &shared, &search),
+            Ok(git.clone())
+        );
+        assert_eq!(
+            resolve("k8s.tt", &root, &search),
+            Ok(root.join("shared/k8s.tt"))
+        );
+        assert!(resolve("./k8s.tt", &root, &search).is_err());
+        assert!(resolve("missing.tt", &root, &search).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_search_path() {
+        let search = search_path(
+            Some("/opt/turtle:/usr/share/turtle"),
+            &["/etc/turtle".into()],
+        );
+        assert_eq!(
+            search,
+            vec![
+                std::path::PathBuf::from("/opt/turtle"),
+                "/usr/share/turtle".into(),
+                "/etc/turtle".into(),
+            ]
+        );
+        assert!(search_path(None, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_cycles_and_namespaces() {
+        let a = std::path::PathBuf::from("/lib/a.tt");
+        let b = std::path::PathBuf::from("/lib/b.tt");
+        assert_eq!(
+            cycle(&[a.clone(), b.clone()], &a),
+            Some("circular import: a.tt -> b.tt -> a.tt".to_string())
+        );
+        assert_eq!(cycle(std::slice::from_ref(&a), &b), None);
+
+        let vars = std::collections::HashMap::from([
+            ("ns".to_string(), s("default")),
+            ("ctx".to_string(), s("kind")),
+            ("_cache".to_string(), Expressions::Null),
+        ]);
+        let module = namespace(&vars);
+        assert_eq!(
+            module,
+            Expressions::Object(vec![
+                ("ctx".to_string(), s("kind")),
+                ("ns".to_string(), s("default")),
+            ])
+        );
+        assert_eq!(
+            select(&module, &["ns".to_string()], "k8s.tt"),
+            Ok(vec![("ns".to_string(), s("default"))])
+        );
+        assert!(select(&module, &["_cache".to_string()], "k8s.tt").is_err());
+        assert_eq!(alias("lib/k8s.tt", None), "k8s");
+        assert_eq!(alias("lib/k8s.tt", Some("kube")), "kube");
+    }
+}
@@ -0,0 +1,362 @@
+/// Integers and the numeric operators shared by integers and floats
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// integers are exact: they start as `i64` and grow into big integers rather than overflow,
+/// eg: `2 ** 64`. floats are `f64`, mixing an integer with a float gives a float.
+use crate::expressions::Expressions;
+use num_integer::Integer as _;
+use num_traits::{Signed, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// largest shift accepted by `<<` and `>>`
+const MAX_SHIFT: usize = 1 << 16;
+
+/// largest exponent accepted by `**` on integers, except for 0, 1 and -1
+const MAX_EXPONENT: u32 = 1 << 16;
+
+/// an exact integer
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Integer {
+    /// an integer that fits in 64 bits
+    Small(i64),
+    /// an integer that grew past 64 bits, only used for values that don't fit in `Small`
+    Big(num_bigint::BigInt),
+}
+
+impl From<i64> for Integer {
+    fn from(value: i64) -> Self {
+        Integer::Small(value)
+    }
+}
+
+impl From<num_bigint::BigInt> for Integer {
+    fn from(value: num_bigint::BigInt) -> Self {
+        match value.to_i64() {
+            Some(small) => Integer::Small(small),
+            None => Integer::Big(value),
+        }
+    }
+}
+
+impl std::fmt::Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Integer::Small(n) => write!(f, "{}", n),
+            Integer::Big(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl Integer {
+    /// parse an integer literal
+    ///
+    /// accepts decimal, `0x` hex, `0o` octal and `0b` binary digits with an optional sign,
+    /// and `_` separators, eg: `1_000_000`, `-0xff`, `0b1010_1010`
+    pub fn parse(literal: &str) -> Option<Self> {
+        let literal = literal.trim();
+        let (negative, literal) = match literal.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, literal.strip_prefix('+').unwrap_or(literal)),
+        };
+        let (radix, digits) = match literal.get(..2) {
+            Some("0x" | "0X") => (16, &literal[2..]),
+            Some("0o" | "0O") => (8, &literal[2..]),
+            Some("0b" | "0B") => (2, &literal[2..]),
+            _ => (10, literal),
+        };
+        let digits: String = digits.chars().filter(|c| *c != '_').collect();
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        let value = num_bigint::BigInt::parse_bytes(digits.as_bytes(), radix)?;
+        Some(Integer::from(if negative { -value } else { value }))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            Integer::Small(n) => Some(*n),
+            Integer::Big(_) => None,
+        }
+    }
+
+    /// the nearest float, big integers lose precision
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Integer::Small(n) => *n as f64,
+            Integer::Big(n) => n.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    pub fn to_big(&self) -> num_bigint::BigInt {
+        match self {
+            Integer::Small(n) => num_bigint::BigInt::from(*n),
+            Integer::Big(n) => n.clone(),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Integer::Small(n) => *n < 0,
+            Integer::Big(n) => n.is_negative(),
+        }
+    }
+
+    /// the digits of the magnitude in `radix`, eg: `ff` for 255 in hex
+    pub fn to_radix(&self, radix: u32, upper: bool) -> String {
+        let digits = self.to_big().abs().to_str_radix(radix);
+        if upper { digits.to_uppercase() } else { digits }
+    }
+
+    pub fn compare(&self, other: &Integer) -> std::cmp::Ordering {
+        match (self, other) {
+            (Integer::Small(a), Integer::Small(b)) => a.cmp(b),
+            (a, b) => a.to_big().cmp(&b.to_big()),
+        }
+    }
+}
+
+/// a numeric operand
+enum Operand {
+    Integer(Integer),
+    Float(f64),
+}
+
+fn operand(value: &Expressions) -> Option<Operand> {
+    match value {
+        Expressions::Integer(n) => Some(Operand::Integer(n.clone())),
+        Expressions::Number(n) => Some(Operand::Float(*n)),
+        _ => None,
+    }
+}
+
+/// apply a binary operator to two numbers
+///
+/// returns `None` when either side isn't a number, so the caller can try other types
+///
+/// - `+`, `-`, `*` and `**` keep integers exact, a negative exponent gives a float
+/// - `/` always divides as floats, `//` divides rounding down
+/// - `%` takes the sign of the divisor, so `-7 % 3` is `2`, like `//`
+/// - `&`, `|`, `^`, `<<` and `>>` need integers
+pub fn binary(
+    left: &Expressions,
+    op: &str,
+    right: &Expressions,
+) -> Option<Result<Expressions, String>> {
+    Some(match (operand(left)?, operand(right)?) {
+        (Operand::Integer(a), Operand::Integer(b)) => integer_binary(&a, op, &b),
+        (Operand::Integer(a), Operand::Float(b)) => float_binary(a.to_f64(), op, b),
+        (Operand::Float(a), Operand::Integer(b)) => float_binary(a, op, b.to_f64()),
+        (Operand::Float(a), Operand::Float(b)) => float_binary(a, op, b),
+    })
+}
+
+/// apply a unary operator to a number: `-`, `+` or `~` (integers only)
+///
+/// returns `None` when the value isn't a number
+pub fn unary(op: &str, value: &Expressions) -> Option<Result<Expressions, String>> {
+    Some(match (op, operand(value)?) {
+        ("+", _) => Ok(value.clone()),
+        ("-", Operand::Float(n)) => Ok(Expressions::Number(-n)),
+        ("-", Operand::Integer(Integer::Small(n))) if n != i64::MIN => {
+            Ok(Expressions::Integer(Integer::Small(-n)))
+        }
+        ("-", Operand::Integer(n)) => Ok(Expressions::Integer(Integer::from(-n.to_big()))),
+        ("~", Operand::Integer(Integer::Small(n))) => Ok(Expressions::Integer(Integer::Small(!n))),
+        ("~", Operand::Integer(n)) => Ok(Expressions::Integer(Integer::from(-n.to_big() - 1))),
+        ("~", Operand::Float(n)) => Err(format!("`~` needs an integer, got {}", n)),
+        (op, _) => Err(format!("unsupported operation for numbers: {}", op)),
+    })
+}
+
+fn integer_binary(a: &Integer, op: &str, b: &Integer) -> Result<Expressions, String> {
+    let zero = matches!(b, Integer::Small(0));
+    if zero && matches!(op, "/" | "//" | "%") {
+        return Err("division by zero".to_string());
+    }
+
+    // most integers are small, try without allocating first
+    if let (Integer::Small(x), Integer::Small(y)) = (a, b) {
+        let (x, y) = (*x, *y);
+        let small = match op {
+            "+" => x.checked_add(y),
+            "-" => x.checked_sub(y),
+            "*" => x.checked_mul(y),
+            "//" if !(x == i64::MIN && y == -1) => Some(num_integer::Integer::div_floor(&x, &y)),
+            "%" if y != -1 => Some(num_integer::Integer::mod_floor(&x, &y)),
+            "%" => Some(0),
+            "**" if y >= 0 => u32::try_from(y).ok().and_then(|y| x.checked_pow(y)),
+            "&" => Some(x & y),
+            "|" => Some(x | y),
+            "^" => Some(x ^ y),
+            _ => None,
+        };
+        if let Some(n) = small {
+            return Ok(Expressions::Integer(Integer::Small(n)));
+        }
+    }
+
+    let (x, y) = (a.to_big(), b.to_big());
+    let shift = || {
+        y.to_usize()
+            .filter(|shift| *shift <= MAX_SHIFT)
+            .ok_or(format!(
+                "shift must be between 0 and {}, got {}",
+                MAX_SHIFT, y
+            ))
+    };
+    let value = match op {
+        "+" => &x + &y,
+        "-" => &x - &y,
+        "*" => &x * &y,
+        "/" => return Ok(Expressions::Number(a.to_f64() / b.to_f64())),
+        "//" => x.div_floor(&y),
+        "%" => x.mod_floor(&y),
+        "**" if y.is_negative() => return Ok(Expressions::Number(a.to_f64().powf(b.to_f64()))),
+        "**" => match y.to_u32().filter(|exponent| *exponent <= MAX_EXPONENT) {
+            Some(exponent) => x.pow(exponent),
+            // 0, 1 and -1 stay small for any exponent
+            None if x.abs() <= num_bigint::BigInt::from(1) => {
+                if x.is_negative() && y.is_even() {
+                    -x
+                } else {
+                    x
+                }
+            }
+            None => {
+                return Err(format!(
+                    "exponent must be at most {}, got {}",
+                    MAX_EXPONENT, y
+                ));
+            }
+        },
+        "&" => &x & &y,
+        "|" => &x | &y,
+        "^" => &x ^ &y,
+        "<<" => &x << shift()?,
+        ">>" => &x >> shift()?,
+        _ => return Err(format!("unsupported operation for integers: {}", op)),
+    };
+    Ok(Expressions::Integer(Integer::from(value)))
+}
+
+fn float_binary(a: f64, op: &str, b: f64) -> Result<Expressions, String> {
+    let value = match op {
+        "+" => a + b,
+        "-" => a - b,
+        "*" => a * b,
+        "/" => a / b,
+        "//" => (a / b).floor(),
+        "%" => a - b * (a / b).floor(),
+        "**" => a.powf(b),
+        "&" | "|" | "^" | "<<" | ">>" => {
+            return Err(format!("`{}` needs integers, got {} and {}", op, a, b));
+        }
+        _ => return Err(format!("unsupported operation for numbers: {}", op)),
+    };
+    Ok(Expressions::Number(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(n: i64) -> Expressions {
+        Expressions::Integer(Integer::Small(n))
+    }
+
+    fn calc(left: Expressions, op: &str, right: Expressions) -> Result<Expressions, String> {
+        binary(&left, op, &right).unwrap()
+    }
+
+    #[test]
+    fn test_parse_integer_literals() {
+        assert_eq!(Integer::parse("42"), Some(Integer::Small(42)));
+        assert_eq!(Integer::parse("1_000_000"), Some(Integer::Small(1_000_000)));
+        assert_eq!(Integer::parse("0xff"), Some(Integer::Small(255)));
+        assert_eq!(Integer::parse("-0o17"), Some(Integer::Small(-15)));
+        assert_eq!(Integer::parse("0b1010_1010"), Some(Integer::Small(170)));
+        assert_eq!(
+            Integer::parse("18446744073709551616").map(|n| n.to_string()),
+            Some("18446744073709551616".to_string())
+        );
+        assert_eq!(Integer::parse("0x"), None);
+        assert_eq!(Integer::parse("12ab"), None);
+        assert_eq!(Integer::parse("1.5"), None);
+    }
+
+    #[test]
+    fn test_integer_arithmetic() {
+        assert_eq!(calc(int(7), "+", int(5)), Ok(int(12)));
+        assert_eq!(calc(int(7), "/", int(2)), Ok(Expressions::Number(3.5)));
+        assert_eq!(calc(int(7), "//", int(2)), Ok(int(3)));
+        assert_eq!(calc(int(-7), "//", int(2)), Ok(int(-4)));
+        assert_eq!(calc(int(-7), "%", int(3)), Ok(int(2)));
+        assert_eq!(calc(int(2), "**", int(10)), Ok(int(1024)));
+        assert_eq!(calc(int(2), "**", int(-1)), Ok(Expressions::Number(0.5)));
+        assert!(calc(int(1), "//", int(0)).is_err());
+        assert!(calc(int(1), "%", int(0)).is_err());
+    }
+
+    #[test]
+    fn test_integers_grow() {
+        let big = calc(int(2), "**", int(64)).unwrap();
+        assert_eq!(
+            big,
+            Expressions::Integer(Integer::parse("18446744073709551616").unwrap())
+        );
+        assert_eq!(calc(int(i64::MAX), "+", int(1)).unwrap(), {
+            Expressions::Integer(Integer::from(num_bigint::BigInt::from(i64::MAX) + 1))
+        });
+        // and shrink back when they fit again
+        assert_eq!(calc(big, "//", int(1 << 32)), Ok(int(1 << 32)));
+        assert_eq!(
+            unary("-", &int(i64::MIN)),
+            Some(Ok(Expressions::Integer(
+                Integer::parse("9223372036854775808").unwrap()
+            )))
+        );
+        assert_eq!(calc(int(-1), "**", int(1 << 40)), Ok(int(1)));
+    }
+
+    #[test]
+    fn test_large_exponents_are_rejected() {
+        assert!(calc(int(2), "**", int(1 << 16)).is_ok());
+        assert_eq!(
+            calc(int(2), "**", int(100_000_000)),
+            Err("exponent must be at most 65536, got 100000000".to_string())
+        );
+        assert_eq!(calc(int(0), "**", int(100_000_000)), Ok(int(0)));
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        assert_eq!(calc(int(0b1100), "&", int(0b1010)), Ok(int(0b1000)));
+        assert_eq!(calc(int(0b1100), "|", int(0b1010)), Ok(int(0b1110)));
+        assert_eq!(calc(int(0b1100), "^", int(0b1010)), Ok(int(0b0110)));
+        assert_eq!(calc(int(1), "<<", int(4)), Ok(int(16)));
+        assert_eq!(calc(int(-16), ">>", int(2)), Ok(int(-4)));
+        assert_eq!(unary("~", &int(5)), Some(Ok(int(-6))));
+        assert!(calc(int(1), "<<", int(-1)).is_err());
+        assert!(calc(Expressions::Number(1.5), "&", int(1)).is_err());
+    }
+
+    #[test]
+    fn test_mixed_arithmetic() {
+        assert_eq!(
+            calc(int(1), "+", Expressions::Number(0.5)),
+            Ok(Expressions::Number(1.5))
+        );
+        assert_eq!(
+            calc(Expressions::Number(-7.0), "%", int(3)),
+            Ok(Expressions::Number(2.0))
+        );
+        assert_eq!(binary(&int(1), "+", &Expressions::String("a".into())), None);
+    }
+}
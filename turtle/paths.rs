@@ -0,0 +1,322 @@
+/// Paths: `./src/main.rs` literals, members like `.name` and `.size`, `/` joining and the
+/// `path`, `ls`, `read`, `write` and `append` functions
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// paths are kept as written, eg: `~/notes.md`, and only expanded when the filesystem is used.
+/// wherever a path is expected a string can be given instead, eg: `read("Cargo.toml")`
+use crate::expressions::Expressions;
+
+/// a path value from text, eg: `./src/main.rs`
+pub fn from_text(text: &str) -> Expressions {
+    Expressions::Path {
+        segments: text.split('/').map(str::to_string).collect(),
+    }
+}
+
+/// the text of a path as written, eg: `~/notes.md`
+pub fn display(segments: &[String]) -> String {
+    match segments {
+        // `/` splits into two empty segments
+        [first, second] if first.is_empty() && second.is_empty() => "/".to_string(),
+        segments => segments.join("/"),
+    }
+}
+
+/// the filesystem path of a value, `~` is expanded to the home directory
+///
+/// objects with a `path`, eg: the entries of `ls()`, are read as their path
+pub fn to_path(value: &Expressions) -> Result<std::path::PathBuf, String> {
+    match value {
+        Expressions::Path { segments } => Ok(crate::utils::expand_path(&display(segments)).into()),
+        Expressions::String(text) => Ok(crate::utils::expand_path(text).into()),
+        Expressions::Object(entries) => match entries.iter().find(|(key, _)| key == "path") {
+            Some((_, path)) => to_path(path),
+            None => Err("expected a path, got an object without a `path`".to_string()),
+        },
+        other => Err(format!("`{}` isn't a path", crate::format::display(other))),
+    }
+}
+
+/// the member of a path, `None` for unknown members
+///
+/// - `name`, `stem` and `ext` are strings, eg: `main.rs`, `main` and `rs`, or `null`
+/// - `parent` is the path of the containing directory
+/// - `exists`, `is_file` and `is_dir` look at the filesystem, symlinks are followed
+/// - `size` in bytes and `mtime`, the last modification as a datetime, are `null` for paths
+///   that don't exist
+pub fn member(segments: &[String], property: &str) -> Option<Expressions> {
+    let text = display(segments);
+    let path = std::path::Path::new(&text);
+    let expanded = std::path::PathBuf::from(crate::utils::expand_path(&text));
+    let string = |value: Option<&std::ffi::OsStr>| match value {
+        Some(value) => Expressions::String(value.to_string_lossy().to_string()),
+        None => Expressions::Null,
+    };
+    let metadata = || std::fs::metadata(&expanded).ok();
+    Some(match property {
+        "name" => string(path.file_name()),
+        "stem" => string(path.file_stem()),
+        "ext" => string(path.extension()),
+        "parent" => match path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => from_text("."),
+            Some(parent) => from_text(&parent.to_string_lossy()),
+            None => Expressions::Null,
+        },
+        "exists" => Expressions::Boolean(expanded.exists()),
+        "is_file" => Expressions::Boolean(expanded.is_file()),
+        "is_dir" => Expressions::Boolean(expanded.is_dir()),
+        "size" => match metadata() {
+            Some(metadata) => Expressions::Integer((metadata.len() as i64).into()),
+            None => Expressions::Null,
+        },
+        "mtime" => match metadata().and_then(|metadata| metadata.modified().ok()) {
+            Some(modified) => Expressions::DateTime(
+                chrono::DateTime::<chrono::Local>::from(modified).fixed_offset(),
+            ),
+            None => Expressions::Null,
+        },
+        _ => return None,
+    })
+}
+
+/// join paths with `/`, eg: `./src / "main.rs"`
+///
+/// joining an absolute path replaces the left side, like `std::path::Path::join`.
+/// returns `None` for other operators or when the left side isn't a path
+pub fn binary(
+    left: &Expressions,
+    op: &str,
+    right: &Expressions,
+) -> Option<Result<Expressions, String>> {
+    let Expressions::Path { segments } = left else {
+        return None;
+    };
+    if op != "/" {
+        return None;
+    }
+    let right = match right {
+        Expressions::Path { segments } => display(segments),
+        Expressions::String(text) => text.clone(),
+        other => {
+            return Some(Err(format!(
+                "can't join a path with {}",
+                crate::format::display(other)
+            )));
+        }
+    };
+    if right.starts_with('/') || right.starts_with('~') {
+        return Some(Ok(from_text(&right)));
+    }
+    let left = display(segments);
+    Some(Ok(from_text(&format!(
+        "{}/{}",
+        left.trim_end_matches('/'),
+        right.trim_start_matches("./")
+    ))))
+}
+
+/// call a path function, `path`, `ls`, `read`, `write` or `append`
+///
+/// returns `None` for other functions, so the caller can try them
+///
+/// - `path(text)` is the path of a string
+/// - `ls()` and `ls(dir)` are the entries of a directory sorted by name, each an object with
+///   the `name`, `path`, `ext`, `type` (`file`, `dir` or `symlink`), `size` and `mtime` of the
+///   entry
+/// - `read(path)` is the text of a file
+/// - `write(path, value)` and `append(path, value)` write a value as `{}` shows it and are the
+///   path, so calls can be chained
+pub fn call(func: &str, args: &[Expressions]) -> Option<Result<Expressions, String>> {
+    let result = match (func, args) {
+        ("path", [Expressions::String(text)]) => Ok(from_text(text)),
+        ("path", [path @ Expressions::Path { .. }]) => Ok(path.clone()),
+        ("ls", []) => ls(&from_text(".")),
+        ("ls", [dir]) => ls(dir),
+        ("read", [path]) => to_path(path).and_then(|file| {
+            std::fs::read_to_string(&file)
+                .map(Expressions::String)
+                .map_err(|e| format!("read: {}: {}", file.display(), e))
+        }),
+        ("write", [path, value]) => to_path(path).and_then(|file| {
+            std::fs::write(&file, crate::format::display(value))
+                .map(|_| path.clone())
+                .map_err(|e| format!("write: {}: {}", file.display(), e))
+        }),
+        ("append", [path, value]) => to_path(path).and_then(|file| {
+            use std::io::Write;
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&file)
+                .and_then(|mut out| out.write_all(crate::format::display(value).as_bytes()))
+                .map(|_| path.clone())
+                .map_err(|e| format!("append: {}: {}", file.display(), e))
+        }),
+        ("path", _) => Err("path: expected text".to_string()),
+        ("ls", _) => Err("ls: expected a directory or nothing".to_string()),
+        ("read", _) => Err("read: expected a path".to_string()),
+        ("write", _) => Err("write: expected a path and a value".to_string()),
+        ("append", _) => Err("append: expected a path and a value".to_string()),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// the entries of a directory, see `call`
+fn ls(dir: &Expressions) -> Result<Expressions, String> {
+    let written = match dir {
+        Expressions::Path { segments } => display(segments),
+        Expressions::String(text) => text.clone(),
+        other => {
+            return Err(format!(
+                "ls: `{}` isn't a path",
+                crate::format::display(other)
+            ));
+        }
+    };
+    let expanded = to_path(dir)?;
+    let entries =
+        std::fs::read_dir(&expanded).map_err(|e| format!("ls: {}: {}", expanded.display(), e))?;
+    let mut names: Vec<(String, std::fs::FileType)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let kind = entry.file_type().ok()?;
+            Some((entry.file_name().to_string_lossy().to_string(), kind))
+        })
+        .collect();
+    names.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let items = names
+        .into_iter()
+        .map(|(name, kind)| {
+            let path = match written.as_str() {
+                "." | "./" => format!("./{}", name),
+                written => format!("{}/{}", written.trim_end_matches('/'), name),
+            };
+            let segments: Vec<String> = path.split('/').map(str::to_string).collect();
+            let field = |property: &str| member(&segments, property).unwrap_or(Expressions::Null);
+            let kind = if kind.is_symlink() {
+                "symlink"
+            } else if kind.is_dir() {
+                "dir"
+            } else {
+                "file"
+            };
+            Expressions::Object(vec![
+                ("name".to_string(), Expressions::String(name)),
+                ("path".to_string(), from_text(&path)),
+                ("ext".to_string(), field("ext")),
+                ("type".to_string(), Expressions::String(kind.to_string())),
+                ("size".to_string(), field("size")),
+                ("mtime".to_string(), field("mtime")),
+            ])
+        })
+        .collect();
+    Ok(Expressions::Array(items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    fn p(value: &str) -> Expressions {
+        from_text(value)
+    }
+
+    fn segments(value: &str) -> Vec<String> {
+        value.split('/').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_path_members() {
+        let main = segments("./src/main.rs");
+        assert_eq!(member(&main, "name"), Some(s("main.rs")));
+        assert_eq!(member(&main, "stem"), Some(s("main")));
+        assert_eq!(member(&main, "ext"), Some(s("rs")));
+        assert_eq!(member(&main, "parent"), Some(p("./src")));
+        assert_eq!(member(&segments("README"), "ext"), Some(Expressions::Null));
+        assert_eq!(member(&segments("README"), "parent"), Some(p(".")));
+        assert_eq!(member(&segments("/"), "name"), Some(Expressions::Null));
+        assert_eq!(member(&main, "owner"), None);
+        assert_eq!(display(&segments("/")), "/");
+    }
+
+    #[test]
+    fn test_join_paths() {
+        assert_eq!(
+            binary(&p("./src"), "/", &s("main.rs")),
+            Some(Ok(p("./src/main.rs")))
+        );
+        assert_eq!(
+            binary(&p("./src/"), "/", &p("./bin")),
+            Some(Ok(p("./src/bin")))
+        );
+        assert_eq!(binary(&p("./src"), "/", &p("/etc")), Some(Ok(p("/etc"))));
+        assert!(matches!(
+            binary(&p("./src"), "/", &Expressions::Null),
+            Some(Err(_))
+        ));
+        assert_eq!(binary(&p("./src"), "+", &s("x")), None);
+        assert_eq!(binary(&s("a"), "/", &p("./b")), None);
+    }
+
+    #[test]
+    fn test_read_write_and_ls() {
+        let dir = std::env::temp_dir().join(format!("turtle-paths-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = p(&dir.to_string_lossy());
+        let file = binary(&dir, "/", &s("notes.txt")).unwrap().unwrap();
+
+        assert_eq!(
+            call("write", &[file.clone(), s("a")]),
+            Some(Ok(file.clone()))
+        );
+        assert_eq!(
+            call("append", &[file.clone(), s("b")]),
+            Some(Ok(file.clone()))
+        );
+        assert_eq!(call("read", std::slice::from_ref(&file)), Some(Ok(s("ab"))));
+
+        let Expressions::Path { segments } = &file else {
+            unreachable!()
+        };
+        assert_eq!(
+            member(segments, "size"),
+            Some(Expressions::Integer(2.into()))
+        );
+        assert_eq!(
+            member(segments, "is_file"),
+            Some(Expressions::Boolean(true))
+        );
+        assert!(matches!(
+            member(segments, "mtime"),
+            Some(Expressions::DateTime(_))
+        ));
+
+        let Some(Ok(Expressions::Array(entries))) = call("ls", std::slice::from_ref(&dir)) else {
+            panic!("ls failed");
+        };
+        let Expressions::Object(entry) = &entries[0] else {
+            panic!("expected an object");
+        };
+        assert_eq!(entry[0], ("name".to_string(), s("notes.txt")));
+        assert_eq!(entry[1], ("path".to_string(), file.clone()));
+        assert_eq!(entry[3], ("type".to_string(), s("file")));
+        assert_eq!(call("read", &[entries[0].clone()]), Some(Ok(s("ab"))));
+
+        std::fs::remove_dir_all(to_path(&dir).unwrap()).unwrap();
+        assert!(matches!(call("read", &[file]), Some(Err(_))));
+        assert_eq!(call("format", &[]), None);
+    }
+}
@@ -0,0 +1,232 @@
+/// Regular expressions: `/pattern/flags` literals, the `=~` and `!~` operators and the
+/// `matches` and `replace` functions
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// patterns use the syntax of the `regex` crate. for `=~`, `!~` and `matches` a string can be
+/// given instead of a regex, it is read as a pattern without flags, eg: `name =~ "^v\d+"`.
+/// `replace` takes strings literally, eg: `replace(version, ".", "-")`, like `split` in
+/// `crate::stdlib`, which splits on the matches of a regex literal
+use crate::expressions::Expressions;
+
+/// flags accepted after a regex literal, eg: `/turtle/i`
+///
+/// - `i` ignores case
+/// - `m` lets `^` and `$` match at line breaks
+/// - `s` lets `.` match newlines
+/// - `x` ignores whitespace and `#` comments in the pattern
+/// - `U` swaps greedy and lazy repetition
+pub const FLAGS: &str = "imsxU";
+
+/// compile a pattern with its flags
+pub fn compile(pattern: &str, flags: Option<&str>) -> Result<regex::Regex, String> {
+    let mut builder = regex::RegexBuilder::new(pattern);
+    for flag in flags.unwrap_or_default().chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            'm' => builder.multi_line(true),
+            's' => builder.dot_matches_new_line(true),
+            'x' => builder.ignore_whitespace(true),
+            'U' => builder.swap_greed(true),
+            other => {
+                return Err(format!(
+                    "unknown regex flag `{}`, expected one of: {}",
+                    other, FLAGS
+                ));
+            }
+        };
+    }
+    builder
+        .build()
+        .map_err(|e| format!("invalid regex /{}/: {}", pattern, e))
+}
+
+/// the regex of a value, strings are read as patterns without flags
+pub fn regex(value: &Expressions) -> Result<regex::Regex, String> {
+    match value {
+        Expressions::RegularExpression { pattern, flags } => compile(pattern, flags.as_deref()),
+        Expressions::String(pattern) => compile(pattern, None),
+        other => Err(format!("`{}` isn't a regex", crate::format::display(other))),
+    }
+}
+
+/// read `/pattern/flags` text, eg: the value of a `where` condition
+pub fn parse_literal(text: &str) -> Option<Expressions> {
+    let (pattern, flags) = text.strip_prefix('/')?.rsplit_once('/')?;
+    if !flags.chars().all(|flag| FLAGS.contains(flag)) {
+        return None;
+    }
+    Some(Expressions::RegularExpression {
+        pattern: pattern.to_string(),
+        flags: (!flags.is_empty()).then(|| flags.to_string()),
+    })
+}
+
+/// whether the text of a value matches a regex, for `=~` and `!~`
+///
+/// values that aren't strings are matched as `{}` writes them, eg: `42 =~ /^\d+$/`
+pub fn is_match(value: &Expressions, pattern: &Expressions) -> Result<bool, String> {
+    Ok(regex(pattern)?.is_match(&crate::format::display(value)))
+}
+
+/// call a regex function, `matches` or `replace`
+///
+/// returns `None` for other functions, so the caller can try them
+///
+/// - `matches(text, regex)` is an array with an item per match, see `captures`
+/// - `replace(text, regex, replacement)` replaces every match, `$1` and `${name}` in the
+///   replacement write capture groups. a string is replaced as it is, without groups
+pub fn call(func: &str, args: &[Expressions]) -> Option<Result<Expressions, String>> {
+    let result = match (func, args) {
+        (
+            "replace",
+            [
+                Expressions::String(text),
+                Expressions::String(part),
+                Expressions::String(replacement),
+            ],
+        ) => Ok(Expressions::String(
+            text.replace(part.as_str(), replacement),
+        )),
+        ("matches", [Expressions::String(text), pattern]) => regex(pattern).map(|regex| {
+            Expressions::Array(
+                regex
+                    .captures_iter(text)
+                    .map(|groups| captures(&regex, &groups))
+                    .collect(),
+            )
+        }),
+        (
+            "replace",
+            [
+                Expressions::String(text),
+                pattern,
+                Expressions::String(replacement),
+            ],
+        ) => regex(pattern).map(|regex| {
+            Expressions::String(regex.replace_all(text, replacement.as_str()).into_owned())
+        }),
+        ("matches", _) => Err("matches: expected text and a regex".to_string()),
+        ("replace", _) => Err("replace: expected text, a regex and a replacement".to_string()),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// the value of a single match
+///
+/// - the matched text when the regex has no groups, eg: `"v1"`
+/// - an array of the groups, eg: `["v", "1"]`
+/// - an object of the groups when any group is named, unnamed groups are keyed by their
+///   number, eg: `{"name": "v", "2": "1"}`
+///
+/// groups that didn't take part in the match are `null`
+fn captures(regex: &regex::Regex, groups: &regex::Captures) -> Expressions {
+    let group = |i: usize| match groups.get(i) {
+        Some(m) => Expressions::String(m.as_str().to_string()),
+        None => Expressions::Null,
+    };
+    if regex.captures_len() == 1 {
+        return group(0);
+    }
+    if regex.capture_names().flatten().next().is_some() {
+        return Expressions::Object(
+            regex
+                .capture_names()
+                .enumerate()
+                .skip(1)
+                .map(|(i, name)| (name.map_or_else(|| i.to_string(), str::to_string), group(i)))
+                .collect(),
+        );
+    }
+    Expressions::Array((1..regex.captures_len()).map(group).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    fn re(pattern: &str, flags: Option<&str>) -> Expressions {
+        Expressions::RegularExpression {
+            pattern: pattern.to_string(),
+            flags: flags.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_regex_flags() {
+        assert!(is_match(&s("Turtle"), &re("^turtle$", Some("i"))).unwrap());
+        assert!(!is_match(&s("Turtle"), &re("^turtle$", None)).unwrap());
+        assert!(is_match(&s("a\nb"), &re("^b$", Some("m"))).unwrap());
+        assert!(is_match(&s("a\nb"), &re("a.b", Some("s"))).unwrap());
+        assert!(compile("a", Some("g")).is_err());
+        assert!(compile("(", None).is_err());
+    }
+
+    #[test]
+    fn test_regex_matches() {
+        let text = s("v1.2 v3.4");
+        assert_eq!(
+            call("matches", &[text.clone(), re(r"v\d", None)]),
+            Some(Ok(Expressions::Array(vec![s("v1"), s("v3")])))
+        );
+        assert_eq!(
+            call("matches", &[text.clone(), re(r"v(\d)\.(\d)", None)]),
+            Some(Ok(Expressions::Array(vec![
+                Expressions::Array(vec![s("1"), s("2")]),
+                Expressions::Array(vec![s("3"), s("4")]),
+            ])))
+        );
+        assert_eq!(
+            call("matches", &[text, re(r"v(?<major>\d)\.(\d)(x)?", None)]),
+            Some(Ok(Expressions::Array(vec![
+                Expressions::Object(vec![
+                    ("major".to_string(), s("1")),
+                    ("2".to_string(), s("2")),
+                    ("3".to_string(), Expressions::Null),
+                ]),
+                Expressions::Object(vec![
+                    ("major".to_string(), s("3")),
+                    ("2".to_string(), s("4")),
+                    ("3".to_string(), Expressions::Null),
+                ]),
+            ])))
+        );
+    }
+
+    #[test]
+    fn test_regex_replace() {
+        assert_eq!(
+            call(
+                "replace",
+                &[s("a-1 b-2"), re(r"(\w)-(\d)", None), s("$2$1")]
+            ),
+            Some(Ok(s("1a 2b")))
+        );
+        // strings are replaced rather than patterns
+        assert_eq!(
+            call("replace", &[s("1.2.3"), s("."), s("$1")]),
+            Some(Ok(s("1$12$13")))
+        );
+        assert_eq!(call("split", &[s("a"), s(",")]), None);
+        assert_eq!(call("format", &[]), None);
+    }
+
+    #[test]
+    fn test_parse_regex_literal() {
+        assert_eq!(parse_literal(r"/\.rs$/i"), Some(re(r"\.rs$", Some("i"))));
+        assert_eq!(parse_literal("/a/b/"), Some(re("a/b", None)));
+        assert_eq!(parse_literal("/a/q"), None);
+        assert_eq!(parse_literal("rs"), None);
+    }
+}
@@ -15,8 +15,6 @@ pub struct Shell {
     debug: bool,
     /// Shell start time
     start: Option<std::time::Instant>,
-    /// Shell uptime
-    uptime: fn(start: std::time::Instant) -> Option<std::time::Duration>,
     /// Shell defaults
     defaults: crate::config::Defaults,
 
@@ -33,7 +31,6 @@ pub struct Shell {
     // replace events with history manager
     history: std::sync::Arc<std::sync::Mutex<crate::history::History>>,
     // events: std::sync::Arc<std::sync::Mutex<Vec<crate::history::Event>>>,
-    env: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
     aliases: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>,
     tokens: Vec<Vec<crate::tokens::Token>>,
     expressions: Vec<crate::expressions::Expressions>,
@@ -50,111 +47,193 @@ pub struct Shell {
 impl Shell {
     /// Handle configuration signals
     fn handle_config_signals(&mut self) {
-        if let Some(receiver) = &self.config_receiver {
-            while let Ok(signal) = receiver.try_recv() {
-                match signal {
-                    crate::config::ConfigSignal::Reloaded(cfg) => {
-                        if self.debug {
-                            println!("🔄 reloading configuration due to file change");
-                        }
-                        let args = self
-                            .args
-                            .as_ref()
-                            .and_then(|a| Some(a.lock().unwrap().clone()));
-                        let new_config = Self::configure(args.as_ref().map(|a| a.clone()));
-                        if let Some(cfg) = &self.config {
-                            let mut cfg_lock = cfg.lock().unwrap();
-                            *cfg_lock = new_config;
-                        }
+        let signals: Vec<crate::config::ConfigSignal> = match &self.config_receiver {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => return,
+        };
+        for signal in signals {
+            match signal {
+                crate::config::ConfigSignal::Reloaded(cfg) => {
+                    if self.debug {
+                        println!("🔄 reloading configuration due to file change");
                     }
-                    crate::config::ConfigSignal::Loaded(cfg) => {
-                        if self.debug {
-                            println!("✅ configuration file loaded");
-                        }
-                        if let Some(c) = &self.config {
-                            let mut c_lock = c.lock().unwrap();
-                            *c_lock = cfg;
-                        }
+                    let changes = self.apply_config(cfg);
+                    if !changes.is_empty() {
+                        let changes: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+                        println!("🔄 config reloaded: {}", changes.join(", "));
                     }
-                    crate::config::ConfigSignal::Error(err_msg) => {
-                        if self.debug {
-                            println!("❌ configuration error: {}", err_msg);
-                        }
+                }
+                crate::config::ConfigSignal::Loaded(cfg) => {
+                    if self.debug {
+                        println!("✅ configuration file loaded");
                     }
-                    _ => {
-                        if self.debug {
-                            println!("⚠️ received unhandled config signal: {:?}", signal);
-                        }
+                    if let Some(c) = &self.config {
+                        let mut c_lock = c.lock().unwrap();
+                        *c_lock = cfg;
                     }
                 }
+                crate::config::ConfigSignal::Error(err_msg) => {
+                    eprintln!("❌ config not reloaded: {}", err_msg);
+                }
             }
         }
     }
 
-    /// Create a new Rustyline editor instance
-    // TODO: investigate using a custom editor config
-    fn create_reader(&self) -> rustyline::DefaultEditor {
-        let config = rustyline::config::Config::builder()
-            .edit_mode(rustyline::config::EditMode::Vi)
-            .build();
-        let rl = rustyline::DefaultEditor::with_config(config);
-        rl.unwrap()
+    /// Apply a reloaded configuration to the running shell
+    ///
+    /// the prompt and editor are re-derived from the config by the main loop
+    fn apply_config(
+        &mut self,
+        new_config: crate::config::Config,
+    ) -> Vec<crate::config::ConfigChange> {
+        match &self.config {
+            Some(config) => {
+                crate::config::apply_config(config, &self.aliases, &self.history, new_config)
+            }
+            None => Vec::new(),
+        }
     }
 
-    /// Get elapsed time since shell start
-    fn elapsed(&self) -> Option<std::time::Duration> {
-        if let Some(start) = self.start {
-            Some(start.elapsed())
-        } else {
-            None
+    /// Evaluate a line of input and print the result
+    ///
+    /// the output format comes from a trailing `| as <format>`, or the
+    /// `execution.format` config. `jsonl` prints a single
+    /// `{code, stdout, stderr, duration}` line per command, with the duration
+    /// in milliseconds. Returns the exit code
+    fn execute(&mut self, input: &str) -> i32 {
+        let (input, format) = crate::expressions::Outputs::split_format(input);
+        let format = format
+            .map(|f| f.to_string())
+            .unwrap_or_else(|| self.context.format());
+        let start = std::time::Instant::now();
+
+        let tokens = self.interpreter.tokenize(input);
+        if self.debug {
+            println!("tokens: {:?}", tokens);
+        }
+        self.tokens.push(tokens.clone());
+        let expr = self.interpreter.interpret();
+
+        if self.debug {
+            println!("expression: {:?}", expr);
+        }
+
+        let valid = expr.is_some();
+        let result = match expr {
+            Some(expr) => {
+                self.expressions.push(expr.clone());
+                self.context.eval(Some(expr))
+            }
+            None => None,
+        };
+        if self.debug {
+            println!("result: {:?}", result);
         }
+
+        if format == "jsonl" {
+            let (code, stdout, stderr) = match &result {
+                Some(crate::context::EvalResults::CommandExpressionResult(cmd)) => {
+                    (cmd.code, cmd.stdout.clone(), cmd.stderr.clone())
+                }
+                Some(result) => (
+                    0,
+                    crate::expressions::Outputs::from_result("text", result)
+                        .map(|o| o.to_string())
+                        .unwrap_or_default(),
+                    String::new(),
+                ),
+                None => (
+                    1,
+                    String::new(),
+                    "invalid command or expression".to_string(),
+                ),
+            };
+            println!(
+                "{}",
+                serde_json::json!({
+                    "code": code,
+                    "stdout": stdout,
+                    "stderr": stderr,
+                    "duration": start.elapsed().as_millis() as u64,
+                })
+            );
+            return code;
+        }
+
+        match result {
+            Some(result) => {
+                self.context.print(&result, &format);
+                result.code()
+            }
+            None if !valid => {
+                let error_prompt = self
+                    .config
+                    .as_ref()
+                    .and_then(|cfg| cfg.lock().unwrap().error_prompt.clone())
+                    .unwrap_or(self.defaults.error_prompt.clone());
+                println!("{} Invalid command or expression", error_prompt);
+                1
+            }
+            None => 1,
+        }
+    }
+
+    /// Current line editor settings
+    fn editor_config(&self) -> crate::config::EditorConfig {
+        self.config
+            .as_ref()
+            .map(|cfg| cfg.lock().unwrap().editor())
+            .unwrap_or_else(|| crate::config::Config::default().editor())
+    }
+
+    /// Create a new Rustyline editor instance from the `editor` config
+    ///
+    /// builtin names and arguments complete with tab
+    fn create_reader(
+        &self,
+    ) -> rustyline::Editor<crate::builtins::Completions, rustyline::history::DefaultHistory> {
+        let editor = self.editor_config();
+        let bell_style = if editor.bell.unwrap_or(false) {
+            rustyline::config::BellStyle::Audible
+        } else {
+            rustyline::config::BellStyle::None
+        };
+        let config = rustyline::config::Config::builder()
+            .edit_mode(editor.mode.unwrap_or(crate::config::EditMode::Vi).into())
+            .bell_style(bell_style)
+            .tab_stop(editor.tab_width.unwrap_or(crate::config::DEFAULT_TAB_WIDTH))
+            .build();
+        let mut rl = rustyline::Editor::with_config(config).unwrap();
+        rl.set_helper(
+            self.context
+                .builtins
+                .as_ref()
+                .map(|builtins| builtins.completions()),
+        );
+        rl
     }
 
     /// Configure the shell
     fn configure(args: Option<crate::config::Arguments>) -> crate::config::Config {
-        let defaults = crate::config::Defaults::default();
-        let config_path = args
-            .as_ref()
-            .and_then(|args| args.config_path.as_ref())
-            .unwrap_or(&defaults.config_path)
-            .clone();
+        Self::configure_layers(args).config
+    }
 
-        let config = crate::config::Config::resolve(config_path.as_str(), &args);
-        config.unwrap()
+    /// Resolve each configuration layer, keeping track of where values came from
+    fn configure_layers(args: Option<crate::config::Arguments>) -> crate::config::LayeredConfig {
+        let config_path = crate::config::user_config_path(&args);
+        crate::config::LayeredConfig::resolve(config_path.as_str(), &args)
     }
 
     /// Create a new Turtle shell instance
     pub fn new(args: crate::config::Arguments) -> Self {
-        // capture user environment
-        let user_environment =
-            std::env::vars().collect::<std::collections::HashMap<String, String>>();
-
         // load defaults
         let defaults = crate::config::Defaults::default();
 
         // optional command line args
         let args = Some(args);
 
-        let env_config = crate::config::Environment::new(args.as_ref().unwrap().clone());
-
-        // Determine history path - from args or defaults
-        // TODO: incorporate TURTLE_HISTORY_PATH env var
-        let history_path = args
-            .as_ref()
-            .and_then(|args| args.history_path.as_ref())
-            .unwrap_or(&defaults.history_path)
-            .clone();
-
-        // Determine config path - from args or defaults
-        // TODO: incorporate TURTLE_CONFIG_PATH env var, or  XDG_CONFIG_HOME convention
-        let config_path = args
-            .as_ref()
-            .and_then(|args| args.config_path.as_ref())
-            .unwrap_or(&defaults.config_path)
-            .clone();
-
         // load config from file or use default config blob
-        let config = Self::configure(args.as_ref().map(|a| a.clone()));
+        let config = Self::configure(args.clone());
 
         // send configuration signal
 
@@ -169,11 +248,11 @@ impl Shell {
 
         let mut _aliases_ = std::collections::HashMap::new();
 
-        if let Some(cfg) = &config {
-            if let Some(turtle_aliases) = &cfg.lock().unwrap().aliases {
-                for (key, value) in turtle_aliases {
-                    _aliases_.insert(key.clone(), value.clone());
-                }
+        if let Some(cfg) = &config
+            && let Some(turtle_aliases) = &cfg.lock().unwrap().aliases
+        {
+            for (key, value) in turtle_aliases {
+                _aliases_.insert(key.clone(), value.clone());
             }
         }
 
@@ -185,18 +264,22 @@ impl Shell {
             crate::expressions::Expressions,
         >::new()));
 
-        let args = Some(std::sync::Arc::new(std::sync::Mutex::new(args.unwrap())));
+        let args = args.map(|args| std::sync::Arc::new(std::sync::Mutex::new(args)));
 
-        let history_path = args
+        // history settings from args, TURTLE_HISTORY_* or the history config section
+        let history_config = config
             .as_ref()
-            .and_then(|args| args.lock().unwrap().history_path.clone())
-            .unwrap_or(defaults.history_path.clone());
+            .map(|cfg| cfg.lock().unwrap().history())
+            .unwrap_or_default();
 
         let mut history = crate::history::History::new(
-            Some(history_path.clone()),
-            Some(defaults.save_interval),
+            history_config.path.clone(),
+            history_config
+                .flush_interval
+                .or(Some(defaults.save_interval)),
             debug,
         );
+        history.configure(history_config);
 
         history.setup();
 
@@ -244,9 +327,6 @@ impl Shell {
         Shell {
             debug,
             start: None,
-            uptime: |start: std::time::Instant| -> Option<std::time::Duration> {
-                Some(start.elapsed())
-            },
             pid: None,
             paused: false,
             running: false,
@@ -255,7 +335,6 @@ impl Shell {
             args,
             thememanager,
             history,
-            env,
             aliases,
             interpreter,
             context,
@@ -287,7 +366,7 @@ impl Shell {
         if self.debug {
             println!("🐢 setup completed in {} milliseconds", elapsed.as_millis());
         }
-        return std::collections::HashMap::from([("total".into(), elapsed.as_millis())]);
+        std::collections::HashMap::from([("total".into(), elapsed.as_millis())])
     }
 
     /// Start the shell main loop
@@ -300,91 +379,92 @@ impl Shell {
         self.setup();
 
         // get default paths and values
-        let default_config_path = self.defaults.config_path.clone();
-        let default_history_path = self.defaults.history_path.clone();
         let default_prompt = self.defaults.prompt.clone();
         let default_theme = self.defaults.theme.clone();
 
         // lock & process args and config
-        let args = self
-            .args
-            .as_ref()
-            .and_then(|a| Some(a.lock().unwrap().clone()));
+        let args = self.args.as_ref().map(|a| a.lock().unwrap().clone());
 
         let c_args = args.clone();
 
-        let config = self
-            .config
-            .as_ref()
-            .and_then(|c| Some(c.lock().unwrap().clone()));
-
-        let c_config = config.clone();
+        let config = self.config.as_ref().map(|c| c.lock().unwrap().clone());
 
         // --version flag
-        if let Some(show_version) = args.as_ref().and_then(|a| Some(a.version)) {
-            if show_version {
-                println!("version: {}", crate::constants::VERSION);
-                std::process::exit(0);
-            }
+        if let Some(show_version) = args.as_ref().map(|a| a.version)
+            && show_version
+        {
+            println!("version: {}", crate::constants::VERSION);
+            std::process::exit(0);
         }
 
         // handle: --display-env flag
         let user_env = crate::config::Environment::new(args.as_ref().unwrap().clone());
-        if let Some(display_env) = args.as_ref().and_then(|a| Some(a.display_env)) {
-            if display_env {
-                for (key, value) in user_env.config.iter() {
-                    {
-                        println!("{}={}", key, value);
-                    }
+        if let Some(display_env) = args.as_ref().map(|a| a.display_env)
+            && display_env
+        {
+            for (key, value) in user_env.config.iter() {
+                {
+                    println!("{}={}", key, value);
                 }
-                std::process::exit(0);
             }
+            std::process::exit(0);
         }
 
         // handle: --display-defaults flag
-        if let Some(display_defaults) = args.as_ref().and_then(|a| Some(a.display_defaults)) {
-            if display_defaults {
-                let defaults = crate::config::Defaults::default();
-                println!("{}", defaults.to_string());
-                std::process::exit(0);
-            }
+        if let Some(display_defaults) = args.as_ref().map(|a| a.display_defaults)
+            && display_defaults
+        {
+            let defaults = crate::config::Defaults::default();
+            println!("{}", defaults);
+            std::process::exit(0);
         }
 
-        // handle: --display-config flag
-        if let Some(display_config) = args.as_ref().and_then(|a| Some(a.display_config)) {
-            if display_config {
-                if let Some(cfg) = &self.config {
-                    let cfg = cfg.lock().unwrap();
-                    let resolved = crate::config::ResolvedConfig::from(cfg.clone());
-                    println!("{}", resolved.to_string());
-                    std::process::exit(0);
-                } else {
-                    eprintln!("❌ no configuration loaded");
-                    std::process::exit(1);
+        // handle: config check subcommand
+        if let Some(crate::config::Commands::Config {
+            action: crate::config::ConfigCommands::Check,
+        }) = args.as_ref().and_then(|a| a.subcommand.clone())
+        {
+            let user_config_path = crate::config::user_config_path(&args);
+            let mut failed = false;
+            for (source, errors) in crate::config::LayeredConfig::check(&user_config_path, &args) {
+                if errors.is_empty() {
+                    println!("✅ {}", source);
+                    continue;
+                }
+                failed = true;
+                println!("❌ {}", source);
+                for error in errors {
+                    println!("   {}", error);
                 }
             }
+            std::process::exit(if failed { 1 } else { 0 });
+        }
+
+        // handle: --display-config flag
+        if let Some(display_config) = args.as_ref().map(|a| a.display_config)
+            && display_config
+        {
+            let layered = Self::configure_layers(args.clone());
+            print!("{}", layered);
+            std::process::exit(0);
         }
 
         // handle: --list-themes flag
         if let Some(list_themes) = self
             .args
             .as_ref()
-            .and_then(|a| Some(a.lock().unwrap().available_themes))
+            .map(|a| a.lock().unwrap().available_themes)
+            && list_themes
         {
-            if list_themes {
-                self.thememanager.list().iter().for_each(|theme_name| {
-                    println!("- {}", theme_name);
-                });
-                std::process::exit(0);
-            }
+            self.thememanager.list().iter().for_each(|theme_name| {
+                println!("- {}", theme_name);
+            });
+            std::process::exit(0);
         }
 
         // handle: --watch-config flag
-        if let Some(watch_config) = args.as_ref().and_then(|a| Some(a.watch_config)) {
-            let config_path = args
-                // .as_ref()
-                .and_then(|args| args.config_path)
-                .unwrap_or(default_config_path.clone());
+        if let Some(watch_config) = args.as_ref().map(|a| a.watch_config) {
+            let config_path = crate::config::user_config_path(&args);
             if watch_config {
                 if let Some(cfg) = &self.config {
                     let cfg = cfg.lock().unwrap();
@@ -412,14 +492,6 @@ impl Shell {
             }
         }
 
-        /*
-            get prompt from the configuration file, or use the default
-        */
-        let user_prompt = config
-            .clone()
-            .and_then(|cfg| cfg.prompt)
-            .unwrap_or(default_prompt.clone());
-
         /*
             get theme from the configuration file, or use the default
         */
@@ -431,36 +503,24 @@ impl Shell {
         let start = crate::utils::now();
         let mut editor = self.create_reader();
 
-        self.thememanager
-            .apply(&mut std::io::stdout(), &user_theme)
-            .ok();
-
-        // get our prompt from the configuration file, or use the default
-        let user_prompt = self
-            .config
+        let command = self
+            .args
             .as_ref()
-            .and_then(|cfg| cfg.lock().unwrap().prompt.clone())
-            .unwrap_or(default_prompt.clone());
+            .and_then(|args| args.lock().unwrap().command.clone());
 
-        let rendered_prompt = user_prompt.clone();
-        let mut turtle_prompt = crate::style::Prompt::new(rendered_prompt.as_str());
+        // commands run with `--command` print only their output
+        if command.is_none() && crate::style::ThemeManager::applies(&self.context.format()) {
+            self.thememanager
+                .apply(&mut std::io::stdout(), &user_theme)
+                .ok();
+        }
 
-        if let Some(command) = self
-            .args
-            .as_ref()
-            .and_then(|args| args.lock().unwrap().command.clone())
-        // .as_str()
-        {
-            let tokens = self.interpreter.tokenize(command.as_str());
+        // editor settings the current reader was created with
+        let mut editor_config = self.editor_config();
 
-            let expr = self.interpreter.interpret();
-            let result = self.context.eval(expr.clone());
-            if let Some(res) = result {
-                if self.debug {
-                    println!("result: {:?}", res);
-                }
-                std::process::exit(0);
-            }
+        if let Some(command) = command {
+            let code = self.execute(command.as_str());
+            std::process::exit(code);
         }
 
         // main shell loop
@@ -468,7 +528,26 @@ impl Shell {
             // handle any config file change signals
             self.handle_config_signals();
 
-            let readline = editor.readline(turtle_prompt.render().as_str());
+            // recreate the reader when editor settings were reloaded
+            let current_editor_config = self.editor_config();
+            if current_editor_config != editor_config {
+                editor = self.create_reader();
+                editor_config = current_editor_config;
+            }
+
+            // get our prompt from the configuration file, or use the default
+            let user_prompt = self
+                .config
+                .as_ref()
+                .and_then(|cfg| cfg.lock().unwrap().prompt.clone())
+                .unwrap_or(default_prompt.clone());
+            let mut turtle_prompt = crate::style::Prompt::new(user_prompt.as_str());
+
+            let readline = match crate::terminal::take_prompt_input() {
+                Some(input) => editor
+                    .readline_with_initial(turtle_prompt.render().as_str(), (input.as_str(), "")),
+                None => editor.readline(turtle_prompt.render().as_str()),
+            };
 
             // get user input
             let input = match readline {
@@ -496,29 +575,7 @@ impl Shell {
                 continue;
             }
 
-            let tokens = self.interpreter.tokenize(input);
-            if self.debug {
-                println!("tokens: {:?}", tokens);
-            }
-            self.tokens.push(tokens.clone());
-            let expr = self.interpreter.interpret();
-
-            if self.debug {
-                println!("expression: {:?}", expr);
-            }
-
-            if expr.is_none() {
-                println!("Invalid command or expression");
-                continue;
-            }
-
-            self.expressions.push(expr.clone().unwrap());
-            let result = self.context.eval(expr.clone());
-            if let Some(res) = result {
-                if self.debug {
-                    println!("result: {:?}", res);
-                }
-            }
+            self.execute(input);
         }
         let elapsed = start.elapsed();
         if self.debug {
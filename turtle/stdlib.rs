@@ -0,0 +1,615 @@
+/// Standard library: functions on strings, arrays, objects and numbers, type checks,
+/// conversions, `print` and `input`
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+///
+/// functions are called by name, eg: `upper(name)`, as methods, eg: `name.upper()`, or in their
+/// namespace, eg: `strings.upper(name)`. values aren't changed in place, eg: `push` is a new
+/// array
+use crate::expressions::Expressions;
+use crate::numbers::Integer;
+
+/// the namespaces of the standard library and their functions
+pub const NAMESPACES: &[(&str, &[&str])] = &[
+    (
+        "strings",
+        &[
+            "len",
+            "upper",
+            "lower",
+            "trim",
+            "split",
+            "join",
+            "contains",
+            "starts_with",
+            "pad",
+        ],
+    ),
+    (
+        "arrays",
+        &["push", "pop", "slice", "reverse", "range", "zip", "flatten"],
+    ),
+    ("objects", &["keys", "values", "entries", "merge", "get"]),
+    (
+        "math",
+        &[
+            "abs", "floor", "ceil", "round", "min", "max", "sqrt", "random",
+        ],
+    ),
+];
+
+/// functions outside of the namespaces
+pub const FUNCTIONS: &[&str] = &[
+    "type_of",
+    "to_string",
+    "to_number",
+    "to_json",
+    "parse_json",
+    "print",
+    "input",
+];
+
+/// whether a name is a namespace of the standard library, eg: `math`
+pub fn is_namespace(name: &str) -> bool {
+    NAMESPACES.iter().any(|(namespace, _)| *namespace == name)
+}
+
+/// call a function of a namespace, eg: `math.sqrt(2)`
+pub fn call_in(namespace: &str, func: &str, args: &[Expressions]) -> Result<Expressions, String> {
+    let functions = NAMESPACES
+        .iter()
+        .find(|(name, _)| *name == namespace)
+        .map(|(_, functions)| *functions)
+        .unwrap_or_default();
+    if !functions.contains(&func) {
+        return Err(format!("{}: no function `{}`", namespace, func));
+    }
+    call(func, args).unwrap_or_else(|| Err(format!("{}: unknown", func)))
+}
+
+/// call a function of the standard library
+///
+/// returns `None` for other functions, so the caller can try them
+///
+/// - strings: `len` (of strings, arrays and objects), `upper`, `lower`, `trim`,
+///   `split(text, separator)` on a string or on the matches of a regex literal, `split(text)`
+///   on whitespace, `join(items, separator)`, `contains` (text or an item of an array),
+///   `starts_with` and `pad(value, width, fill)`, which pads the start and pads the end for a
+///   negative width, like printf
+/// - arrays: `push(items, value)`, `pop(items)` is the array without its last item,
+///   `slice(items, start, end)` counts negative indexes from the end and works on text,
+///   `reverse`, `range(end)`, `range(start, end, step)`, `zip(a, b)` and `flatten`
+/// - objects: `keys`, `values`, `entries`, `merge(a, b, ...)` where later fields win, and
+///   `get(object, key, default)`
+/// - math: `abs`, `floor`, `ceil`, `round(x, digits)`, `min` and `max` of values or an array,
+///   `sqrt`, `random()` between 0 and 1 and `random(start, end)` an integer up to `end`
+/// - `type_of`, `to_string`, `to_number`, `to_json`, `parse_json`, `print(values...)` and
+///   `input(prompt)`, which is `null` at the end of input
+pub fn call(func: &str, args: &[Expressions]) -> Option<Result<Expressions, String>> {
+    let known = FUNCTIONS.contains(&func)
+        || NAMESPACES
+            .iter()
+            .any(|(_, functions)| functions.contains(&func));
+    if !known {
+        return None;
+    }
+    Some(match func {
+        "len" | "upper" | "lower" | "trim" | "split" | "join" | "contains" | "starts_with"
+        | "pad" => call_strings(func, args),
+        "push" | "pop" | "slice" | "reverse" | "range" | "zip" | "flatten" => {
+            call_arrays(func, args)
+        }
+        "keys" | "values" | "entries" | "merge" | "get" => call_objects(func, args),
+        "abs" | "floor" | "ceil" | "round" | "min" | "max" | "sqrt" | "random" => {
+            call_math(func, args)
+        }
+        _ => call_values(func, args),
+    })
+}
+
+/// see `call`
+fn call_strings(func: &str, args: &[Expressions]) -> Result<Expressions, String> {
+    match (func, args) {
+        ("len", [Expressions::String(text)]) => Ok(integer(text.chars().count())),
+        ("len", [Expressions::Array(items)]) => Ok(integer(items.len())),
+        ("len", [Expressions::Object(entries)]) => Ok(integer(entries.len())),
+        ("upper", [Expressions::String(text)]) => Ok(string(text.to_uppercase())),
+        ("lower", [Expressions::String(text)]) => Ok(string(text.to_lowercase())),
+        ("trim", [Expressions::String(text)]) => Ok(string(text.trim())),
+        ("split", [Expressions::String(text)]) => Ok(strings(text.split_whitespace())),
+        ("split", [Expressions::String(text), Expressions::String(separator)]) => {
+            Ok(strings(text.split(separator.as_str())))
+        }
+        (
+            "split",
+            [
+                Expressions::String(text),
+                pattern @ Expressions::RegularExpression { .. },
+            ],
+        ) => crate::patterns::regex(pattern).map(|regex| strings(regex.split(text))),
+        ("join", [Expressions::Array(items), rest @ ..]) if rest.len() <= 1 => {
+            let separator = match rest.first() {
+                Some(Expressions::String(separator)) => separator.as_str(),
+                Some(_) => return Err("join: expected a text separator".to_string()),
+                None => "",
+            };
+            let items: Vec<String> = items.iter().map(crate::format::display).collect();
+            Ok(string(items.join(separator)))
+        }
+        ("contains", [Expressions::String(text), Expressions::String(part)]) => {
+            Ok(Expressions::Boolean(text.contains(part.as_str())))
+        }
+        ("contains", [Expressions::Array(items), value]) => {
+            Ok(Expressions::Boolean(items.contains(value)))
+        }
+        ("starts_with", [Expressions::String(text), Expressions::String(prefix)]) => {
+            Ok(Expressions::Boolean(text.starts_with(prefix.as_str())))
+        }
+        ("pad", [value, width, rest @ ..]) if rest.len() <= 1 => {
+            let width = index(width).ok_or("pad: expected an integer width")?;
+            let fill = match rest.first() {
+                Some(Expressions::String(fill)) if fill.chars().count() == 1 => fill.as_str(),
+                Some(_) => return Err("pad: expected a single character to fill".to_string()),
+                None => " ",
+            };
+            let text = crate::format::display(value);
+            let missing = (width.unsigned_abs() as usize).saturating_sub(text.chars().count());
+            let padding = fill.repeat(missing);
+            Ok(string(match width < 0 {
+                true => text + &padding,
+                false => padding + &text,
+            }))
+        }
+        ("len", _) => Err("len: expected text, an array or an object".to_string()),
+        ("split", _) => Err("split: expected text and a separator or regex".to_string()),
+        ("join", _) => Err("join: expected an array and a separator".to_string()),
+        ("contains", _) => Err("contains: expected text and text, or an array and a value".into()),
+        ("pad", _) => Err("pad: expected a value, a width and a fill".to_string()),
+        (func, _) => Err(format!("{}: expected text", func)),
+    }
+}
+
+/// see `call`
+fn call_arrays(func: &str, args: &[Expressions]) -> Result<Expressions, String> {
+    match (func, args) {
+        ("push", [Expressions::Array(items), values @ ..]) => Ok(Expressions::Array(
+            items.iter().chain(values).cloned().collect(),
+        )),
+        ("pop", [Expressions::Array(items)]) => Ok(Expressions::Array(
+            items[..items.len().saturating_sub(1)].to_vec(),
+        )),
+        ("slice", [value, start, rest @ ..]) if rest.len() <= 1 => {
+            let items: Vec<Expressions> = match value {
+                Expressions::Array(items) => items.clone(),
+                Expressions::String(text) => text.chars().map(string).collect(),
+                _ => return Err("slice: expected an array or text".to_string()),
+            };
+            let at = |position: &Expressions| {
+                let position = index(position).ok_or("slice: expected integer indexes")?;
+                let len = items.len() as i64;
+                Ok::<usize, String>(match position < 0 {
+                    true => (len + position).max(0) as usize,
+                    false => position.min(len) as usize,
+                })
+            };
+            let start = at(start)?;
+            let end = match rest.first() {
+                Some(end) => at(end)?,
+                None => items.len(),
+            };
+            let items = items[start.min(end)..end].to_vec();
+            Ok(match value {
+                Expressions::String(_) => {
+                    string(items.iter().map(crate::format::display).collect::<String>())
+                }
+                _ => Expressions::Array(items),
+            })
+        }
+        ("reverse", [Expressions::Array(items)]) => {
+            Ok(Expressions::Array(items.iter().rev().cloned().collect()))
+        }
+        ("reverse", [Expressions::String(text)]) => {
+            Ok(string(text.chars().rev().collect::<String>()))
+        }
+        ("range", bounds) if (1..=3).contains(&bounds.len()) => {
+            let bounds: Vec<i64> = bounds
+                .iter()
+                .map(index)
+                .collect::<Option<_>>()
+                .ok_or("range: expected integers")?;
+            let (start, end, step) = match bounds.as_slice() {
+                [end] => (0, *end, 1),
+                [start, end] => (*start, *end, 1),
+                [start, end, step] => (*start, *end, *step),
+                _ => unreachable!(),
+            };
+            if step == 0 {
+                return Err("range: the step can't be 0".to_string());
+            }
+            let mut items = Vec::new();
+            let mut n = start;
+            while (step > 0 && n < end) || (step < 0 && n > end) {
+                items.push(Expressions::Integer(n.into()));
+                n += step;
+            }
+            Ok(Expressions::Array(items))
+        }
+        ("zip", [Expressions::Array(a), Expressions::Array(b)]) => Ok(Expressions::Array(
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| Expressions::Array(vec![a.clone(), b.clone()]))
+                .collect(),
+        )),
+        ("flatten", [Expressions::Array(items)]) => Ok(Expressions::Array(
+            items
+                .iter()
+                .flat_map(|item| match item {
+                    Expressions::Array(items) => items.clone(),
+                    item => vec![item.clone()],
+                })
+                .collect(),
+        )),
+        ("slice", _) => Err("slice: expected an array, a start and an end".to_string()),
+        ("reverse", _) => Err("reverse: expected an array or text".to_string()),
+        ("range", _) => Err("range: expected an end, or a start, an end and a step".to_string()),
+        ("zip", _) => Err("zip: expected two arrays".to_string()),
+        (func, _) => Err(format!("{}: expected an array", func)),
+    }
+}
+
+/// see `call`
+fn call_objects(func: &str, args: &[Expressions]) -> Result<Expressions, String> {
+    match (func, args) {
+        ("keys", [Expressions::Object(entries)]) => Ok(Expressions::Array(
+            entries.iter().map(|(key, _)| string(key)).collect(),
+        )),
+        ("values", [Expressions::Object(entries)]) => Ok(Expressions::Array(
+            entries.iter().map(|(_, value)| value.clone()).collect(),
+        )),
+        ("entries", [Expressions::Object(entries)]) => Ok(Expressions::Array(
+            entries
+                .iter()
+                .map(|(key, value)| Expressions::Array(vec![string(key), value.clone()]))
+                .collect(),
+        )),
+        ("merge", objects) if !objects.is_empty() => {
+            let mut merged: Vec<(String, Expressions)> = Vec::new();
+            for object in objects {
+                let Expressions::Object(entries) = object else {
+                    return Err("merge: expected objects".to_string());
+                };
+                for (key, value) in entries {
+                    match merged.iter_mut().find(|(name, _)| name == key) {
+                        Some((_, merged)) => *merged = value.clone(),
+                        None => merged.push((key.clone(), value.clone())),
+                    }
+                }
+            }
+            Ok(Expressions::Object(merged))
+        }
+        (
+            "get",
+            [
+                Expressions::Object(entries),
+                Expressions::String(key),
+                rest @ ..,
+            ],
+        ) if rest.len() <= 1 => Ok(entries
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.clone())
+            .or_else(|| rest.first().cloned())
+            .unwrap_or(Expressions::Null)),
+        ("merge", _) => Err("merge: expected objects".to_string()),
+        ("get", _) => Err("get: expected an object, a key and a default".to_string()),
+        (func, _) => Err(format!("{}: expected an object", func)),
+    }
+}
+
+/// see `call`
+fn call_math(func: &str, args: &[Expressions]) -> Result<Expressions, String> {
+    match (func, args) {
+        ("abs", [Expressions::Integer(n)]) if n.is_negative() => {
+            Ok(Expressions::Integer(Integer::from(-n.to_big())))
+        }
+        ("abs", [n @ Expressions::Integer(_)]) => Ok(n.clone()),
+        ("abs", [Expressions::Number(n)]) => Ok(Expressions::Number(n.abs())),
+        ("floor" | "ceil" | "round", [n @ Expressions::Integer(_)]) => Ok(n.clone()),
+        ("floor", [Expressions::Number(n)]) => whole(n.floor()),
+        ("ceil", [Expressions::Number(n)]) => whole(n.ceil()),
+        ("round", [Expressions::Number(n)]) => whole(n.round()),
+        ("round", [n, digits]) => match (number(n), index(digits)) {
+            (Some(n), Some(digits)) => {
+                let scale = 10f64.powi(digits.clamp(-300, 300) as i32);
+                Ok(Expressions::Number((n * scale).round() / scale))
+            }
+            _ => Err("round: expected a number and a number of digits".to_string()),
+        },
+        ("min" | "max", values) if !values.is_empty() => {
+            let values = match values {
+                [Expressions::Array(items)] if !items.is_empty() => items.as_slice(),
+                [Expressions::Array(_)] => return Err(format!("{}: expected values", func)),
+                values => values,
+            };
+            let pick = values.iter().reduce(|best, value| {
+                let ordering = value.compare(best);
+                match (func, ordering) {
+                    ("min", std::cmp::Ordering::Less) | ("max", std::cmp::Ordering::Greater) => {
+                        value
+                    }
+                    _ => best,
+                }
+            });
+            Ok(pick.cloned().unwrap_or(Expressions::Null))
+        }
+        ("sqrt", [n]) => match number(n) {
+            Some(n) if n >= 0.0 => Ok(Expressions::Number(n.sqrt())),
+            Some(n) => Err(format!("sqrt: {} is negative", n)),
+            None => Err("sqrt: expected a number".to_string()),
+        },
+        ("random", []) => Ok(Expressions::Number(rand::random::<f64>())),
+        ("random", [start, end]) => match (index(start), index(end)) {
+            (Some(start), Some(end)) if start <= end => {
+                Ok(Expressions::Integer(rand::random_range(start..=end).into()))
+            }
+            (Some(_), Some(_)) => Err("random: the start is after the end".to_string()),
+            _ => Err("random: expected integers".to_string()),
+        },
+        ("min" | "max", _) => Err(format!("{}: expected values or an array", func)),
+        ("random", _) => Err("random: expected nothing, or a start and an end".to_string()),
+        (func, _) => Err(format!("{}: expected a number", func)),
+    }
+}
+
+/// see `call`
+fn call_values(func: &str, args: &[Expressions]) -> Result<Expressions, String> {
+    match (func, args) {
+        ("type_of", [value]) => Ok(string(type_of(value))),
+        ("to_string", [value]) => Ok(string(crate::format::display(value))),
+        ("to_number", [n @ (Expressions::Integer(_) | Expressions::Number(_))]) => Ok(n.clone()),
+        ("to_number", [Expressions::String(text)]) => {
+            let text = text.trim();
+            Integer::parse(text)
+                .map(Expressions::Integer)
+                .or_else(|| text.parse::<f64>().ok().map(Expressions::Number))
+                .ok_or_else(|| format!("to_number: can't read `{}` as a number", text))
+        }
+        ("to_json", [value]) => Ok(string(value.to_json().to_string())),
+        ("parse_json", [Expressions::String(text)]) => {
+            Expressions::parse_as("json", text).map_err(|e| format!("parse_json: {}", e))
+        }
+        ("print", values) => {
+            let values: Vec<String> = values.iter().map(crate::format::display).collect();
+            println!("{}", values.join(" "));
+            Ok(Expressions::Null)
+        }
+        ("input", prompt) if prompt.len() <= 1 => {
+            use std::io::Write;
+            if let Some(prompt) = prompt.first() {
+                print!("{}", crate::format::display(prompt));
+                std::io::stdout()
+                    .flush()
+                    .map_err(|e| format!("input: {}", e))?;
+            }
+            let mut line = String::new();
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) => Ok(Expressions::Null),
+                Ok(_) => Ok(string(line.trim_end_matches(['\n', '\r']))),
+                Err(e) => Err(format!("input: {}", e)),
+            }
+        }
+        ("to_number", _) => Err("to_number: expected text or a number".to_string()),
+        ("parse_json", _) => Err("parse_json: expected text".to_string()),
+        ("input", _) => Err("input: expected a prompt or nothing".to_string()),
+        (func, _) => Err(format!("{}: expected one value", func)),
+    }
+}
+
+/// the name of the type of a value, eg: `integer`, `string` or `function`
+pub fn type_of(value: &Expressions) -> &'static str {
+    match value {
+        Expressions::Integer(_) => "integer",
+        Expressions::Number(_) => "number",
+        Expressions::String(_) => "string",
+        Expressions::Boolean(_) => "boolean",
+        Expressions::Null => "null",
+        Expressions::Array(_) => "array",
+        Expressions::Object(_) => "object",
+        Expressions::DateTime(_) => "datetime",
+        Expressions::Duration(_) => "duration",
+        Expressions::Path { .. } => "path",
+        Expressions::RegularExpression { .. } => "regex",
+        Expressions::HttpClient(_) => "http",
+        Expressions::Error(_) => "error",
+        Expressions::Function(_) => "function",
+        _ => "expression",
+    }
+}
+
+fn string(text: impl Into<String>) -> Expressions {
+    Expressions::String(text.into())
+}
+
+fn strings<'a>(parts: impl Iterator<Item = &'a str>) -> Expressions {
+    Expressions::Array(parts.map(string).collect())
+}
+
+fn integer(n: usize) -> Expressions {
+    Expressions::Integer((n as i64).into())
+}
+
+/// the value of a whole float as an integer
+fn whole(n: f64) -> Result<Expressions, String> {
+    if !n.is_finite() {
+        return Err(format!("{} isn't a whole number", n));
+    }
+    Ok(Expressions::Integer(match n.abs() < i64::MAX as f64 {
+        true => (n as i64).into(),
+        false => Integer::from(
+            <num_bigint::BigInt as num_traits::FromPrimitive>::from_f64(n).unwrap_or_default(),
+        ),
+    }))
+}
+
+fn number(value: &Expressions) -> Option<f64> {
+    match value {
+        Expressions::Integer(n) => Some(n.to_f64()),
+        Expressions::Number(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn index(value: &Expressions) -> Option<i64> {
+    match value {
+        Expressions::Integer(n) => n.to_i64(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn i(value: i64) -> Expressions {
+        Expressions::Integer(value.into())
+    }
+
+    fn s(value: &str) -> Expressions {
+        Expressions::String(value.to_string())
+    }
+
+    fn a(items: &[Expressions]) -> Expressions {
+        Expressions::Array(items.to_vec())
+    }
+
+    fn ok(func: &str, args: &[Expressions]) -> Expressions {
+        call(func, args).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(ok("len", &[s("tür")]), i(3));
+        assert_eq!(ok("len", &[a(&[i(1), i(2)])]), i(2));
+        assert_eq!(ok("upper", &[s("pods")]), s("PODS"));
+        assert_eq!(ok("trim", &[s("  pods\n")]), s("pods"));
+        assert_eq!(ok("split", &[s("a  b")]), a(&[s("a"), s("b")]));
+        assert_eq!(ok("join", &[a(&[s("a"), i(1)]), s(", ")]), s("a, 1"));
+        assert_eq!(
+            ok("contains", &[s("kubectl"), s("cub")]),
+            Expressions::Boolean(false)
+        );
+        assert_eq!(
+            ok("contains", &[a(&[i(1)]), i(1)]),
+            Expressions::Boolean(true)
+        );
+        assert_eq!(ok("pad", &[i(7), i(3), s("0")]), s("007"));
+        assert_eq!(ok("pad", &[s("ab"), i(-4)]), s("ab  "));
+        assert_eq!(
+            call_in("strings", "split", &[s("a,b"), s(",")]),
+            Ok(a(&[s("a"), s("b")]))
+        );
+        // strings are separators rather than patterns, regex literals are patterns
+        let regex = Expressions::RegularExpression {
+            pattern: r",\s*".to_string(),
+            flags: None,
+        };
+        assert_eq!(
+            ok("split", &[s("a.b.c"), s(".")]),
+            a(&[s("a"), s("b"), s("c")])
+        );
+        assert_eq!(
+            call_in("strings", "split", &[s("a, b,c"), regex.clone()]),
+            Ok(a(&[s("a"), s("b"), s("c")]))
+        );
+        assert_eq!(
+            ok("split", &[s("a, b,c"), regex]),
+            a(&[s("a"), s("b"), s("c")])
+        );
+        assert!(call("split", &[s("a"), i(1)]).unwrap().is_err());
+        assert!(call_in("strings", "sqrt", &[i(4)]).is_err());
+        assert!(call("upper", &[i(1)]).unwrap().is_err());
+        assert_eq!(call("matches", &[]), None);
+    }
+
+    #[test]
+    fn test_arrays_and_objects() {
+        let items = a(&[i(1), i(2), i(3)]);
+        assert_eq!(
+            ok("push", &[items.clone(), i(4)]),
+            a(&[i(1), i(2), i(3), i(4)])
+        );
+        assert_eq!(ok("pop", std::slice::from_ref(&items)), a(&[i(1), i(2)]));
+        assert_eq!(ok("slice", &[items.clone(), i(-2)]), a(&[i(2), i(3)]));
+        assert_eq!(ok("slice", &[s("turtle"), i(0), i(4)]), s("turt"));
+        assert_eq!(
+            ok("reverse", std::slice::from_ref(&items)),
+            a(&[i(3), i(2), i(1)])
+        );
+        assert_eq!(ok("range", &[i(3)]), a(&[i(0), i(1), i(2)]));
+        assert_eq!(ok("range", &[i(5), i(0), i(-2)]), a(&[i(5), i(3), i(1)]));
+        assert_eq!(
+            ok("zip", &[items.clone(), a(&[s("a")])]),
+            a(&[a(&[i(1), s("a")])])
+        );
+        assert_eq!(
+            ok("flatten", &[a(&[items.clone(), i(4)])]),
+            ok("push", &[items, i(4)])
+        );
+
+        let object = |entries: &[(&str, Expressions)]| {
+            Expressions::Object(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect(),
+            )
+        };
+        let cfg = object(&[("host", s("localhost")), ("port", i(80))]);
+        assert_eq!(
+            ok("keys", std::slice::from_ref(&cfg)),
+            a(&[s("host"), s("port")])
+        );
+        assert_eq!(
+            ok("merge", &[cfg.clone(), object(&[("port", i(8080))])]),
+            object(&[("host", s("localhost")), ("port", i(8080))])
+        );
+        assert_eq!(ok("get", &[cfg.clone(), s("tls"), i(0)]), i(0));
+        assert_eq!(ok("get", &[cfg, s("tls")]), Expressions::Null);
+    }
+
+    #[test]
+    fn test_math_and_values() {
+        assert_eq!(ok("abs", &[i(-3)]), i(3));
+        assert_eq!(ok("floor", &[Expressions::Number(-1.5)]), i(-2));
+        assert_eq!(ok("round", &[Expressions::Number(2.5)]), i(3));
+        assert_eq!(
+            ok("round", &[Expressions::Number(1.23456), i(2)]),
+            Expressions::Number(1.23)
+        );
+        assert_eq!(
+            ok("max", &[i(1), Expressions::Number(2.5)]),
+            Expressions::Number(2.5)
+        );
+        assert_eq!(ok("min", &[a(&[i(3), i(1)])]), i(1));
+        assert_eq!(ok("sqrt", &[i(9)]), Expressions::Number(3.0));
+        assert!(call("sqrt", &[i(-1)]).unwrap().is_err());
+        let n = ok("random", &[i(1), i(3)]);
+        assert!(matches!(n, Expressions::Integer(Integer::Small(1..=3))));
+
+        assert_eq!(ok("type_of", &[Expressions::Null]), s("null"));
+        assert_eq!(ok("to_number", &[s(" 42 ")]), i(42));
+        assert_eq!(ok("to_number", &[s("1.5")]), Expressions::Number(1.5));
+        assert_eq!(ok("to_string", &[i(1)]), s("1"));
+        assert_eq!(ok("to_json", &[a(&[i(1), s("a")])]), s(r#"[1,"a"]"#));
+        assert_eq!(
+            ok("parse_json", &[s(r#"{"a": [1]}"#)]),
+            ok("parse_json", &[s(r#"{"a":[1]}"#)])
+        );
+    }
+}
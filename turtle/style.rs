@@ -218,6 +218,15 @@ impl ThemeManager {
         self.themes.keys().collect()
     }
 
+    /// whether themes are applied for an output format
+    ///
+    /// themes are written as escape codes to stdout, so they are left out of structured
+    /// output and of output that isn't going to a terminal
+    pub fn applies(format: &str) -> bool {
+        use std::io::IsTerminal;
+        std::io::stdout().is_terminal() && !matches!(format, "json" | "jsonl")
+    }
+
     pub fn apply<W: std::io::Write>(
         &self,
         writer: &mut W,
@@ -318,9 +327,9 @@ impl<'a> Prompt<'a> {
         let context = self.context();
         let mut engine = tinytemplate::TinyTemplate::new();
         let template = self.template;
-        engine.add_template("prompt", template);
         engine
-            .render("prompt", &context)
+            .add_template("prompt", template)
+            .and_then(|_| engine.render("prompt", &context))
             .unwrap_or_else(|_| template.to_string())
     }
 }
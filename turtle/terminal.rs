@@ -1,3 +1,20 @@
+/// default pager when `$PAGER` isn't set
+pub static DEFAULT_PAGER: &str = "less -RFX";
+
+/// text to prefill the next prompt with, eg: a row copied in `view`
+static PROMPT_INPUT: once_cell::sync::Lazy<std::sync::Mutex<Option<String>>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
+
+/// prefill the next prompt
+pub fn set_prompt_input(input: String) {
+    *PROMPT_INPUT.lock().unwrap() = Some(input);
+}
+
+/// take the text to prefill the prompt with, if any
+pub fn take_prompt_input() -> Option<String> {
+    PROMPT_INPUT.lock().unwrap().take()
+}
+
 pub struct TerminalManager {
     size: (u16, u16), // (cols, rows)
 }
@@ -11,4 +28,45 @@ impl TerminalManager {
     pub fn get_size(&self) -> (u16, u16) {
         self.size
     }
+
+    /// print output, paging it when it's taller than the terminal
+    ///
+    /// output is piped to `$PAGER`, or `less -RFX`, when stdout is a terminal.
+    /// Falls back to printing when the pager can't be started
+    pub fn page(&self, output: &str) {
+        use std::io::IsTerminal;
+        let (_, rows) = self.size;
+        if !std::io::stdout().is_terminal() || output.lines().count() < rows as usize {
+            println!("{}", output);
+            return;
+        }
+
+        let pager = std::env::var("PAGER").unwrap_or(DEFAULT_PAGER.to_string());
+        let mut words = pager.split_whitespace();
+        let child = words.next().and_then(|program| {
+            std::process::Command::new(program)
+                .args(words)
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .ok()
+        });
+
+        match child {
+            Some(mut child) => {
+                if let Some(mut stdin) = child.stdin.take() {
+                    use std::io::Write;
+                    // the pager may exit before reading everything, eg: `q` in less
+                    let _ = writeln!(stdin, "{}", output);
+                }
+                let _ = child.wait();
+            }
+            None => println!("{}", output),
+        }
+    }
+}
+
+impl Default for TerminalManager {
+    fn default() -> Self {
+        TerminalManager::new()
+    }
 }
@@ -2,18 +2,16 @@
 // use std::sync::{Arc, Mutex};
 
 fn setup_test_config() -> turtle::config::Config {
-    let mut config = turtle::config::Config::default();
-    config
+    turtle::config::Config::default()
 }
 
 fn setup_test_config_file() -> String {
     let config_content = r#"
-        [defaults]
-        history_path = "~/.turtle_history_test"
-        prompt = "turtle-test> "
+        prompt: "turtle-test> "
+        history_size: 10
     "#;
 
-    let config_path = std::env::temp_dir().join("turtle_test_config.toml");
+    let config_path = std::env::temp_dir().join("turtle_test_config.yaml");
     std::fs::write(&config_path, config_content).expect("Failed to write test config file");
 
     config_path.to_string_lossy().to_string()
@@ -41,11 +39,15 @@ fn setup_test_env() -> (
         command: None,
         format: None,
         config_path: None,
+        prompt: None,
+        theme: None,
+        history_size: None,
         history_path: None,
         display_defaults: false,
         display_config: false,
         display_env: false,
         display_prompt: false,
+        subcommand: None,
         skip_aliases: false,
         skip_history: false,
         watch_config: false,
@@ -56,6 +58,7 @@ fn setup_test_env() -> (
         path: None,
         debug: true,
         events: Some(vec![]),
+        config: None,
     }));
 
     let interpreter = turtle::lang::Interpreter::new(
@@ -67,8 +70,9 @@ fn setup_test_env() -> (
         true,
     );
 
+    let config = std::sync::Arc::new(std::sync::Mutex::new(setup_test_config()));
     let mut context = turtle::context::Context::new(
-        None,
+        Some(config),
         None,
         env,
         aliases.clone(),
@@ -92,8 +96,17 @@ fn assert_number_result(result: Option<turtle::context::EvalResults>, expected:
                 n.value
             );
         }
+        Some(turtle::context::EvalResults::IntegerExpressionResult(n)) => {
+            assert_eq!(
+                n.value.to_f64(),
+                expected,
+                "Expected {}, got {}",
+                expected,
+                n.value
+            );
+        }
         other => panic!(
-            "Expected NumberExpressionResult({}), got {:?}",
+            "Expected NumberExpressionResult({}) or IntegerExpressionResult, got {:?}",
             expected, other
         ),
     }
@@ -125,6 +138,19 @@ fn assert_boolean_result(result: Option<turtle::context::EvalResults>, expected:
     }
 }
 
+#[test]
+fn test_user_config_layer() {
+    let path = setup_test_config_file();
+    let layered = turtle::config::LayeredConfig::resolve(&path, &None);
+
+    assert_eq!(layered.config.prompt.as_deref(), Some("turtle-test> "));
+    assert_eq!(layered.config.history_size, Some(10));
+    assert_eq!(
+        layered.sources.get("prompt").map(|source| source.layer),
+        Some(turtle::config::ConfigLayer::User)
+    );
+}
+
 #[test]
 fn test_simple_arithmetic() {
     let (mut interp, mut ctx, _) = setup_test_env();
@@ -379,12 +405,67 @@ fn test_undefined_variable_error() {
     let expr = interp.interpret();
     let result = ctx.eval(expr);
 
-    // Should return error result with message
-    assert!(matches!(
-        result,
-        Some(turtle::context::EvalResults::StringExpressionResult(s))
-        if s.value.contains("not defined")
-    ));
+    // Undefined variables are an error
+    assert!(result.is_none());
+}
+
+#[test]
+fn test_null_and_optional_chaining() {
+    use turtle::expressions::Expressions;
+
+    let (mut interp, mut ctx, vars) = setup_test_env();
+    vars.lock().unwrap().insert(
+        "pod".to_string(),
+        Expressions::from_json(&serde_json::json!({
+            "name": "web",
+            "status": null,
+            "spec": {"replicas": 2}
+        })),
+    );
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_string())
+    };
+
+    assert_eq!(eval(&mut ctx, "null").as_deref(), Some("null"));
+    assert_eq!(eval(&mut ctx, "pod.status").as_deref(), Some("null"));
+    assert_eq!(eval(&mut ctx, "pod?.spec.replicas").as_deref(), Some("2"));
+    assert_eq!(eval(&mut ctx, "pod.status?.phase").as_deref(), Some("null"));
+    assert_eq!(
+        eval(&mut ctx, "pod?.missing.deeper").as_deref(),
+        Some("null")
+    );
+    assert_eq!(eval(&mut ctx, "pod.missing"), None);
+    assert_eq!(
+        eval(&mut ctx, "pod.status?.phase ?? \"Pending\"").as_deref(),
+        Some("Pending")
+    );
+    assert_eq!(
+        eval(&mut ctx, "pod.name ?? \"none\"").as_deref(),
+        Some("web")
+    );
+    assert_eq!(
+        eval(&mut ctx, "is_null(pod.status)").as_deref(),
+        Some("true")
+    );
+    assert_eq!(
+        eval(&mut ctx, "is_null(pod.name)").as_deref(),
+        Some("false")
+    );
+
+    // undefined variables are an error unless the shell is lenient
+    assert_eq!(eval(&mut ctx, "undefined_var ?? 1"), None);
+    ctx.config = Some(std::sync::Arc::new(std::sync::Mutex::new(
+        turtle::config::Config {
+            execution: Some(turtle::config::ExecutionConfig {
+                lenient: Some(true),
+                ..Default::default()
+            }),
+            ..turtle::config::Config::default()
+        },
+    )));
+    assert_eq!(eval(&mut ctx, "undefined_var ?? 1").as_deref(), Some("1"));
 }
 
 #[test]
@@ -397,7 +478,7 @@ fn test_boolean_true() {
 
     assert!(matches!(
         result,
-        Some(turtle::context::EvalResults::BooleanExpressionResult(b)) if b.value == true
+        Some(turtle::context::EvalResults::BooleanExpressionResult(b)) if b.value
     ));
 }
 
@@ -411,7 +492,7 @@ fn test_boolean_false() {
 
     assert!(matches!(
         result,
-        Some(turtle::context::EvalResults::BooleanExpressionResult(b)) if b.value == false
+        Some(turtle::context::EvalResults::BooleanExpressionResult(b)) if !b.value
     ));
 }
 
@@ -419,16 +500,666 @@ fn test_boolean_false() {
 fn test_number_literal() {
     let (mut interp, mut ctx, _) = setup_test_env();
 
-    interp.tokenize("3.14");
+    interp.tokenize("2.5");
     let expr = interp.interpret();
     let result = ctx.eval(expr);
 
     assert!(matches!(
         result,
-        Some(turtle::context::EvalResults::NumberExpressionResult(n)) if (n.value - 3.14).abs() < 0.001
+        Some(turtle::context::EvalResults::NumberExpressionResult(n)) if (n.value - 2.5).abs() < 0.001
     ));
 }
 
+#[test]
+fn test_integer_arithmetic() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_string())
+    };
+
+    assert_eq!(eval("0xff + 0b1 + 1_000").as_deref(), Some("1256"));
+    assert_eq!(eval("7 / 2").as_deref(), Some("3.5"));
+    assert_eq!(eval("-7 // 2").as_deref(), Some("-4"));
+    assert_eq!(eval("-7 % 3").as_deref(), Some("2"));
+    assert_eq!(eval("2 ** 3 ** 2").as_deref(), Some("512"));
+    assert_eq!(eval("2 ** 64").as_deref(), Some("18446744073709551616"));
+    assert_eq!(eval("1 << 4 | 0b11 & ~1").as_deref(), Some("18"));
+    assert_eq!(eval("6 ^ 3").as_deref(), Some("5"));
+    assert_eq!(eval("1 + 0.5").as_deref(), Some("1.5"));
+    assert_eq!(eval("1 // 0"), None);
+}
+
+#[test]
+fn test_regular_expressions() {
+    use turtle::expressions::Expressions;
+
+    let (mut interp, mut ctx, vars) = setup_test_env();
+    vars.lock().unwrap().insert(
+        "version".to_string(),
+        Expressions::String("turtle v1.22".to_string()),
+    );
+    let mut eval = |code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(eval(r"version =~ /V\d+/i"), Some(serde_json::json!(true)));
+    assert_eq!(eval(r"version !~ /^v/"), Some(serde_json::json!(true)));
+    assert_eq!(eval("10 / 2 / 5"), Some(serde_json::json!(1)));
+    assert_eq!(
+        eval(r"matches(version, /v(?<major>\d+)\.(?<minor>\d+)/)"),
+        Some(serde_json::json!([{"major": "1", "minor": "22"}]))
+    );
+    assert_eq!(
+        eval(r#"replace(version, /(\d+)/, "<$1>")"#),
+        Some(serde_json::json!("turtle v<1>.<22>"))
+    );
+    assert_eq!(
+        eval(r"split(version, /[ .]/)"),
+        Some(serde_json::json!(["turtle", "v1", "22"]))
+    );
+    assert_eq!(eval("version =~ /(/"), None);
+}
+
+#[test]
+fn test_datetimes_and_durations() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_string())
+    };
+
+    let start = r#"date("2025-01-01T09:30Z")"#;
+    assert_eq!(eval(start), Some("2025-01-01T09:30:00Z".to_string()));
+    assert_eq!(eval("2h30m"), Some("2h30m".to_string()));
+    assert_eq!(eval("90m + 30s"), Some("1h30m30s".to_string()));
+    assert_eq!(eval("5m * 3"), Some("15m".to_string()));
+    assert_eq!(eval("-5m"), Some("-5m".to_string()));
+    assert_eq!(
+        eval(&format!("{} + 2h30m", start)),
+        Some("2025-01-01T12:00:00Z".to_string())
+    );
+    assert_eq!(
+        eval(&format!(r#"date("2025-01-02 09:30:00 +0000") - {}"#, start)),
+        Some("1d".to_string())
+    );
+    assert_eq!(
+        eval(r#"date("10/Oct/2000:13:55:36 -0700")"#),
+        Some("2000-10-10T13:55:36-07:00".to_string())
+    );
+    assert_eq!(
+        eval(&format!(r#"to_timezone({}, "Asia/Kolkata")"#, start)),
+        Some("2025-01-01T15:00:00+05:30".to_string())
+    );
+    assert_eq!(
+        eval(&format!(r#"format("{{:%d %b %Y, %H:%M}}", {})"#, start)),
+        Some("01 Jan 2025, 09:30".to_string())
+    );
+    assert_eq!(
+        eval(r#"date("1735723800") - date(1735723800)"#),
+        Some("0s".to_string())
+    );
+    assert!(eval("now()").is_some());
+    assert_eq!(eval(r#"date("yesterday")"#), None);
+    assert_eq!(eval(&format!("{} + {}", start, start)), None);
+}
+
+#[test]
+fn test_paths_and_control_flow() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    let dir = std::env::temp_dir().join(format!("turtle-itest-paths-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir = dir.to_string_lossy().to_string();
+
+    assert_eq!(
+        eval(&format!(r#"write({} / "a.rs", "fn main() {{}}")"#, dir)),
+        Some(serde_json::json!(format!("{}/a.rs", dir)))
+    );
+    assert_eq!(
+        eval(&format!(r#"path("{}/b.txt").write("hello").size"#, dir)),
+        Some(serde_json::json!(5))
+    );
+    assert_eq!(
+        eval(&format!("({}/b.txt).stem", dir)),
+        Some(serde_json::json!("b"))
+    );
+    assert_eq!(
+        eval(&format!("ls({}) | where ext == rs | count", dir)),
+        Some(serde_json::json!(1))
+    );
+    assert_eq!(
+        eval(&format!(
+            r#"for f in ls({}) {{ if f.ext == "rs" {{ f.name }} else {{ f.size }} }}"#,
+            dir
+        )),
+        Some(serde_json::json!(["a.rs", 5]))
+    );
+    assert_eq!(
+        eval("if 1 > 2 || 2 >= 2 && \"a\" != \"b\" { \"yes\" }"),
+        Some(serde_json::json!("yes"))
+    );
+    assert_eq!(eval("if 1 == 2 { 1 }"), Some(serde_json::json!(null)));
+    assert_eq!(eval("if 1 { 1 }"), None);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(eval(&format!("read({}/b.txt)", dir)), None);
+}
+
+#[test]
+fn test_modules() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let dir = std::env::temp_dir().join(format!("turtle-itest-modules-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    let loads = dir.join("loads.txt");
+    std::fs::write(
+        dir.join("lib/git.tt"),
+        format!(
+            "let branch = \"main\"\nlet _loads = append(\"{}\", \"x\")\n",
+            loads.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("lib/k8s.tt"),
+        "import \"./git.tt\"\nlet ctx = \"kind\"; let ns = git.branch\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("a.tt"), "import \"./b.tt\"\n").unwrap();
+    std::fs::write(dir.join("b.tt"), "import \"./a.tt\"\n").unwrap();
+    ctx.set_env("TURTLE_PATH".to_string(), dir.to_string_lossy().to_string());
+
+    let mut eval = |code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(r#"import "lib/git.tt" as g"#),
+        Some(serde_json::json!({"branch": "main"}))
+    );
+    assert_eq!(eval("g.branch"), Some(serde_json::json!("main")));
+    assert_eq!(
+        eval(r#"from "lib/k8s" import ctx, ns"#),
+        Some(serde_json::json!({"ctx": "kind", "ns": "main"}))
+    );
+    assert_eq!(
+        eval("ctx + \"/\" + ns"),
+        Some(serde_json::json!("kind/main"))
+    );
+    assert_eq!(eval("branch"), None);
+    // git.tt is evaluated once, for both imports
+    assert_eq!(std::fs::read_to_string(&loads).unwrap(), "x");
+
+    assert_eq!(eval(r#"from "lib/k8s.tt" import context"#), None);
+    assert_eq!(eval(r#"import "a.tt""#), None);
+    assert_eq!(eval(r#"import "missing.tt""#), None);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_errors() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(&mut ctx, r#"try { throw "no pods" } catch e { e.message }"#),
+        Some(serde_json::json!("no pods"))
+    );
+    assert_eq!(
+        eval(&mut ctx, "try { undefined_var } catch e { e }"),
+        Some(serde_json::json!({
+            "message": "Variable 'undefined_var' not defined",
+            "code": null,
+            "stderr": null
+        }))
+    );
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r#"try { throw {message: "denied", code: 403} } catch e { e.code }"#
+        ),
+        Some(serde_json::json!(403))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"try { 1 } catch { "caught" }"#),
+        Some(serde_json::json!(1))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"try { throw 1 } catch { "caught" }"#),
+        Some(serde_json::json!("caught"))
+    );
+    // the error variable is only set in the catch branch
+    assert_eq!(eval(&mut ctx, "e"), None);
+    assert_eq!(eval(&mut ctx, r#"throw "uncaught""#), None);
+
+    // finally always runs, errors without a catch are raised again
+    let file = std::env::temp_dir().join(format!("turtle-itest-errors-{}", std::process::id()));
+    let file = file.to_string_lossy().to_string();
+    assert_eq!(
+        eval(
+            &mut ctx,
+            &format!(
+                r#"try {{ throw "x" }} finally {{ write("{}", "done") }}"#,
+                file
+            )
+        ),
+        None
+    );
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "done");
+    std::fs::remove_file(&file).unwrap();
+
+    // failing commands are only errors in errexit mode
+    let missing = "ls /turtle-itest-missing";
+    assert_eq!(
+        eval(&mut ctx, &format!("try {{ {} }} catch {{ 0 }}", missing)).map(|r| r["code"] != 0),
+        Some(true)
+    );
+    ctx.config = Some(std::sync::Arc::new(std::sync::Mutex::new(
+        turtle::config::Config {
+            execution: Some(turtle::config::ExecutionConfig {
+                errexit: Some(true),
+                ..Default::default()
+            }),
+            ..turtle::config::Config::default()
+        },
+    )));
+    assert_eq!(
+        eval(
+            &mut ctx,
+            &format!(
+                "try {{ {} }} catch e {{ e.code > 0 && e.stderr =~ /turtle-itest-missing/ }}",
+                missing
+            )
+        ),
+        Some(serde_json::json!(true))
+    );
+    assert_eq!(eval(&mut ctx, missing), None);
+    // commands in conditions don't raise
+    assert_eq!(
+        eval(&mut ctx, &format!("if {} {{ 1 }} else {{ 2 }}", missing)),
+        Some(serde_json::json!(2))
+    );
+}
+
+#[test]
+fn test_closures() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    eval(&mut ctx, "let multiplier = 3");
+    eval(&mut ctx, "let triple = |x| x * multiplier");
+    // closures capture their scope where they are defined
+    eval(&mut ctx, "let multiplier = 10");
+    assert_eq!(eval(&mut ctx, "triple(2)"), Some(serde_json::json!(6)));
+    assert_eq!(
+        eval(&mut ctx, "map([1, 2, 3], triple)"),
+        Some(serde_json::json!([3, 6, 9]))
+    );
+    assert_eq!(
+        eval(&mut ctx, "[1, 2, 3, 4].filter(fn(n) { n % 2 == 0 })"),
+        Some(serde_json::json!([2, 4]))
+    );
+    assert_eq!(
+        eval(&mut ctx, "reduce([1, 2, 3], |acc, n| acc + n, 10)"),
+        Some(serde_json::json!(16))
+    );
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r#"sort([{name: "b", age: 2}, {name: "a", age: 1}], |p| p.age).map(|p| p.name)"#
+        ),
+        Some(serde_json::json!(["a", "b"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, "[1, 2].any(|n| n > 1) && ![1, 2].all(|n| n > 1)"),
+        Some(serde_json::json!(true))
+    );
+    assert_eq!(eval(&mut ctx, "(|| 42)"), Some(serde_json::json!("fn ()")));
+    assert_eq!(
+        eval(&mut ctx, "map([1], |x| ({n: x}))"),
+        Some(serde_json::json!([{"n": 1}]))
+    );
+
+    // named functions can call themselves
+    eval(
+        &mut ctx,
+        "fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }",
+    );
+    assert_eq!(eval(&mut ctx, "fact(5)"), Some(serde_json::json!(120)));
+
+    // functions stored in objects are called as methods
+    eval(&mut ctx, "let math = {double: |x| x * 2}");
+    assert_eq!(
+        eval(&mut ctx, "math.double(21)"),
+        Some(serde_json::json!(42))
+    );
+
+    // errors in the functions applied are raised and can be caught
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r#"try { map([1], |x| throw "bad") } catch e { e.message }"#
+        ),
+        Some(serde_json::json!("bad"))
+    );
+    assert_eq!(eval(&mut ctx, "map([1], 2)"), None);
+    assert_eq!(eval(&mut ctx, "triple(1, 2)"), None);
+}
+
+#[test]
+fn test_match() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    eval(
+        &mut ctx,
+        r#"let describe = |v| match v {
+            0 => "zero",
+            "x" | "y" => "letter",
+            [first, ..rest] => f"{first} and {rest}",
+            { name, age } if age >= 18 => f"{name} is an adult",
+            { name } => name,
+            n if n > 10 => "many",
+            _ => "other"
+        }"#,
+    );
+    let describe = |eval: &mut dyn FnMut(&str) -> Option<serde_json::Value>, value: &str| {
+        eval(&format!("describe({})", value))
+    };
+    let mut run = |code: &str| eval(&mut ctx, code);
+    assert_eq!(describe(&mut run, "0"), Some(serde_json::json!("zero")));
+    assert_eq!(describe(&mut run, "0.0"), Some(serde_json::json!("zero")));
+    assert_eq!(
+        describe(&mut run, r#""y""#),
+        Some(serde_json::json!("letter"))
+    );
+    assert_eq!(
+        describe(&mut run, "[1, 2, 3]"),
+        Some(serde_json::json!("1 and [2,3]"))
+    );
+    assert_eq!(
+        describe(&mut run, r#"{name: "ada", age: 36}"#),
+        Some(serde_json::json!("ada is an adult"))
+    );
+    assert_eq!(
+        describe(&mut run, r#"{name: "bo", age: 9}"#),
+        Some(serde_json::json!("bo"))
+    );
+    assert_eq!(describe(&mut run, "42"), Some(serde_json::json!("many")));
+    assert_eq!(describe(&mut run, "5"), Some(serde_json::json!("other")));
+
+    // no arm matching is null, bindings are only set in their arm
+    assert_eq!(
+        run(r#"match "z" { "x" => 1 }"#),
+        Some(serde_json::Value::Null)
+    );
+    assert_eq!(run("match 5 { n => n * 2 }"), Some(serde_json::json!(10)));
+    assert_eq!(run("n"), None);
+
+    // destructuring
+    run("let [a, b] = [1, 2]");
+    assert_eq!(run("a + b"), Some(serde_json::json!(3)));
+    run(r#"let { host, port } = {host: "localhost", port: 8080, user: "admin"}"#);
+    assert_eq!(
+        run(r#"f"{host}:{port}""#),
+        Some(serde_json::json!("localhost:8080"))
+    );
+    assert_eq!(run("let [c, d] = [1]"), None);
+}
+
+#[test]
+fn test_stdlib() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(&mut ctx, r#"len("turtle")"#),
+        Some(serde_json::json!(6))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#""  Pods ".trim().upper()"#),
+        Some(serde_json::json!("PODS"))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"strings.split("a.b", ".").join("-")"#),
+        Some(serde_json::json!("a-b"))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"pad(7, 3, "0")"#),
+        Some(serde_json::json!("007"))
+    );
+    assert_eq!(
+        eval(&mut ctx, "arrays.range(1, 4).reverse().slice(0, -1)"),
+        Some(serde_json::json!([3, 2]))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"zip([1, 2], ["a", "b"]).flatten()"#),
+        Some(serde_json::json!([1, "a", 2, "b"]))
+    );
+    eval(&mut ctx, r#"let cfg = {host: "localhost", port: 80}"#);
+    assert_eq!(
+        eval(&mut ctx, "objects.merge(cfg, {port: 8080}).values()"),
+        Some(serde_json::json!(["localhost", 8080]))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"get(cfg, "user", "admin")"#),
+        Some(serde_json::json!("admin"))
+    );
+    assert_eq!(
+        eval(&mut ctx, "math.max(1, math.sqrt(16), math.floor(2.5))"),
+        Some(serde_json::json!(4))
+    );
+    assert_eq!(
+        eval(&mut ctx, "type_of(to_number(\"42\")) + to_json([1])"),
+        Some(serde_json::json!("integer[1]"))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"parse_json("{\"a\": [1, 2]}").a"#),
+        Some(serde_json::json!([1, 2]))
+    );
+    assert_eq!(eval(&mut ctx, "print(1, 2)"), Some(serde_json::Value::Null));
+
+    // namespaces only have their own functions, and variables hide them
+    assert_eq!(eval(&mut ctx, "math.upper(1)"), None);
+    eval(&mut ctx, r#"let math = {double: |x| x * 2}"#);
+    assert_eq!(eval(&mut ctx, "math.double(2)"), Some(serde_json::json!(4)));
+}
+
+/// a local http server for `test_http_requests`, each path answers with a canned response
+///
+/// - `/pods` is json
+/// - `/echo` writes back the content type, the `x-token` header and the body of the request
+/// - `/flaky` fails with a 503 twice, then answers
+/// - `/slow` answers after a second
+fn serve_http() -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    listener.set_nonblocking(true).unwrap();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            let flaky = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let flaky = flaky.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buffer = [0; 1024];
+                    let (head, body) = loop {
+                        let read = socket.read(&mut buffer).await.unwrap();
+                        request.extend_from_slice(&buffer[..read]);
+                        let text = String::from_utf8_lossy(&request).to_string();
+                        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                            let length = head
+                                .lines()
+                                .find_map(|line| {
+                                    line.to_lowercase()
+                                        .strip_prefix("content-length:")
+                                        .map(|n| n.trim().parse::<usize>().unwrap())
+                                })
+                                .unwrap_or(0);
+                            if body.len() >= length || read == 0 {
+                                break (head.to_string(), body.to_string());
+                            }
+                        }
+                    };
+                    let header = |name: &str| {
+                        head.lines()
+                            .find_map(|line| {
+                                let (key, value) = line.split_once(':')?;
+                                key.eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+                            })
+                            .unwrap_or_default()
+                    };
+                    let route = head.split(' ').take(2).collect::<Vec<_>>().join(" ");
+                    let (status, content_type, response) = match route.as_str() {
+                        "GET /pods" => ("200 OK", "application/json", r#"{"items": [{"name": "api"}]}"#.to_string()),
+                        "POST /echo" | "PUT /echo" => (
+                            "201 Created",
+                            "text/plain",
+                            format!("{}|{}|{}", header("content-type"), header("x-token"), body),
+                        ),
+                        "DELETE /pods/api" => ("204 No Content", "text/plain", String::new()),
+                        "GET /flaky" if flaky.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 => {
+                            ("503 Service Unavailable", "text/plain", "busy".to_string())
+                        }
+                        "GET /flaky" => ("200 OK", "text/plain", "ready".to_string()),
+                        "GET /slow" => {
+                            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                            ("200 OK", "text/plain", "late".to_string())
+                        }
+                        _ => ("404 Not Found", "text/plain", "missing".to_string()),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        status,
+                        content_type,
+                        response.len(),
+                        response
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+    });
+    format!("http://{}", address)
+}
+
+#[test]
+fn test_http_requests() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+    let base = serve_http();
+
+    assert_eq!(
+        eval(&format!(r#"http.get("{}/pods").body.items"#, base)),
+        Some(serde_json::json!([{"name": "api"}]))
+    );
+    assert_eq!(
+        eval(&format!(r#"http("{}").get("/pods").headers"#, base))
+            .map(|headers| headers["content-type"].clone()),
+        Some(serde_json::json!("application/json"))
+    );
+    assert_eq!(
+        eval(&format!(
+            r#"http.post("{}/echo", {{headers: {{"x-token": "t1"}}, body: {{"replicas": 2}}}}).body"#,
+            base
+        )),
+        Some(serde_json::json!(r#"application/json|t1|{"replicas":2}"#))
+    );
+    assert_eq!(
+        eval(&format!(
+            r#"http.put("{}/echo", {{body: "hi"}}).status"#,
+            base
+        )),
+        Some(serde_json::json!(201))
+    );
+    assert_eq!(
+        eval(&format!(r#"http.delete("{}/pods/api").status"#, base)),
+        Some(serde_json::json!(204))
+    );
+    assert_eq!(
+        eval(&format!(r#"http.get("{}/missing").status"#, base)),
+        Some(serde_json::json!(404))
+    );
+
+    // 503s are retried, the first request gives up right away
+    assert_eq!(
+        eval(&format!(r#"http.get("{}/flaky").status"#, base)),
+        Some(serde_json::json!(503))
+    );
+    assert_eq!(
+        eval(&format!(
+            r#"http.get("{}/flaky", {{retries: 2}}).body"#,
+            base
+        )),
+        Some(serde_json::json!("ready"))
+    );
+    assert_eq!(
+        eval(&format!(r#"http.get("{}/slow", {{timeout: 100ms}})"#, base)),
+        None
+    );
+
+    let file = std::env::temp_dir().join(format!("turtle-itest-http-{}.json", std::process::id()));
+    assert_eq!(
+        eval(&format!(
+            r#"http.get("{}/pods", {{output: "{}"}}).body"#,
+            base,
+            file.display()
+        )),
+        Some(serde_json::json!(file.display().to_string()))
+    );
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        r#"{"items": [{"name": "api"}]}"#
+    );
+    std::fs::remove_file(&file).unwrap();
+
+    assert_eq!(
+        eval(&format!(r#"http.get("{}/pods", {{verb: 1}})"#, base)),
+        None
+    );
+    assert_eq!(eval("http.get()"), None);
+}
+
 #[test]
 fn test_string_literal() {
     let (mut interp, mut ctx, _) = setup_test_env();
@@ -455,7 +1186,7 @@ fn test_complex_arithmetic() {
     // Should respect operator precedence: 2 + (3 * 4) = 14
     assert!(matches!(
         result,
-        Some(turtle::context::EvalResults::NumberExpressionResult(n)) if n.value == 14.0
+        Some(turtle::context::EvalResults::IntegerExpressionResult(n)) if n.value.to_i64() == Some(14)
     ));
 }
 
@@ -470,7 +1201,7 @@ fn test_chained_operations() {
     // Should be left-associative: (10 - 3) - 2 = 5
     assert!(matches!(
         result,
-        Some(turtle::context::EvalResults::NumberExpressionResult(n)) if n.value == 5.0
+        Some(turtle::context::EvalResults::IntegerExpressionResult(n)) if n.value.to_i64() == Some(5)
     ));
 }
 
@@ -562,7 +1293,7 @@ fn test_reassignment() {
 
     assert!(matches!(
         result,
-        Some(turtle::context::EvalResults::NumberExpressionResult(n)) if n.value == 2.0
+        Some(turtle::context::EvalResults::IntegerExpressionResult(n)) if n.value.to_i64() == Some(2)
     ));
 }
 
@@ -595,3 +1326,836 @@ fn test_empty_object() {
         _ => panic!("Expected Object expression"),
     }
 }
+
+#[test]
+fn test_parse_as_formats() {
+    use turtle::expressions::Expressions;
+
+    let csv = Expressions::parse_as("csv", "name,size\nfoo,10\nbar,20\n").unwrap();
+    match csv {
+        Expressions::Array(rows) => {
+            assert_eq!(rows.len(), 2);
+            assert_eq!(
+                rows[1],
+                Expressions::Object(vec![
+                    ("name".to_string(), Expressions::String("bar".to_string())),
+                    ("size".to_string(), Expressions::Integer(20.into())),
+                ])
+            );
+        }
+        other => panic!("Expected Array expression, got {:?}", other),
+    }
+
+    let kv = Expressions::parse_as("kv", "# comment\nNAME=turtle\nDEBUG = true\n").unwrap();
+    assert_eq!(
+        kv,
+        Expressions::Object(vec![
+            (
+                "NAME".to_string(),
+                Expressions::String("turtle".to_string())
+            ),
+            ("DEBUG".to_string(), Expressions::Boolean(true)),
+        ])
+    );
+
+    let lines = Expressions::parse_as("lines", "a\nb").unwrap();
+    assert_eq!(
+        lines,
+        Expressions::Array(vec![
+            Expressions::String("a".to_string()),
+            Expressions::String("b".to_string()),
+        ])
+    );
+
+    assert!(Expressions::parse_as("json", "{").is_err());
+    assert!(Expressions::parse_as("xml", "<a/>").is_err());
+}
+
+#[test]
+fn test_detect_format() {
+    use turtle::expressions::Expressions;
+
+    assert_eq!(Expressions::detect_format("{\"a\": 1}"), "json");
+    assert_eq!(Expressions::detect_format("a\tb\n1\t2\n"), "tsv");
+    assert_eq!(Expressions::detect_format("a,b\n1,2\n"), "csv");
+    assert_eq!(Expressions::detect_format("A=1\nB=2\n"), "kv");
+    assert_eq!(Expressions::detect_format("a: 1\nb: [1, 2]\n"), "yaml");
+    assert_eq!(Expressions::detect_format("just some\nplain text"), "lines");
+}
+
+#[test]
+fn test_from_json_member_access() {
+    use turtle::expressions::Expressions;
+
+    let (_, mut ctx, _) = setup_test_env();
+
+    let pods = Expressions::Pipe {
+        input: Box::new(Expressions::String(
+            r#"{"items": [{"metadata": {"name": "web-1"}}]}"#.to_string(),
+        )),
+        stage: "from".to_string(),
+        args: "json".to_string(),
+    };
+    ctx.eval(Some(Expressions::Assignment {
+        name: "pods".to_string(),
+        value: Box::new(pods),
+    }));
+
+    // pods.items[0].metadata.name
+    let name = ["items", "0", "metadata", "name"].iter().fold(
+        Expressions::Identifier("pods".to_string()),
+        |object, property| Expressions::MemberAccess {
+            object: Box::new(object),
+            property: property.to_string(),
+        },
+    );
+    assert_string_result(ctx.eval(Some(name)), "web-1");
+}
+
+#[test]
+fn test_table_render() {
+    let table = turtle::expressions::OutputCsv {
+        headers: vec!["name".to_string(), "size".to_string()],
+        data: vec![
+            vec!["turtle.rs".to_string(), "1200".to_string()],
+            vec!["a-very-long-file-name.rs".to_string(), "35".to_string()],
+        ],
+    };
+
+    let lines = table.render(80, None);
+    assert_eq!(lines[0], "name                      size");
+    assert_eq!(lines[2], "turtle.rs                 1200");
+    assert_eq!(lines[3], "a-very-long-file-name.rs    35");
+
+    // the widest column is truncated to fit
+    let lines = table.render(16, None);
+    assert!(lines.iter().all(|line| line.chars().count() <= 16));
+    assert_eq!(lines[3], "a-very-lo…    35");
+}
+
+/// Helper to set up an environment with a `files` array of objects
+fn setup_files_env() -> (turtle::lang::Interpreter, turtle::context::Context) {
+    let (interp, ctx, vars) = setup_test_env();
+    vars.lock().unwrap().insert(
+        "files".to_string(),
+        turtle::expressions::Expressions::from_json(&serde_json::json!([
+            {"name": "main.rs", "size": 1200, "ext": "rs"},
+            {"name": "README.md", "size": 300, "ext": "md"},
+            {"name": "lang.rs", "size": 5000, "ext": "rs"}
+        ])),
+    );
+    (interp, ctx)
+}
+
+#[test]
+fn test_hyphenated_stage_names() {
+    let (mut interp, mut ctx) = setup_files_env();
+
+    interp.tokenize("files | group-by ext");
+    let expr = interp.interpret();
+    assert_eq!(
+        expr,
+        Some(turtle::expressions::Expressions::Pipe {
+            input: Box::new(turtle::expressions::Expressions::Identifier(
+                "files".to_string()
+            )),
+            stage: "group-by".to_string(),
+            args: "ext".to_string(),
+        })
+    );
+    assert_eq!(
+        ctx.eval(expr).map(|groups| groups.to_json()["md"].clone()),
+        Some(serde_json::json!([{"name": "README.md", "size": 300, "ext": "md"}]))
+    );
+}
+
+#[test]
+fn test_sort_by_and_select() {
+    let (mut interp, mut ctx) = setup_files_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(
+            &mut ctx,
+            "files | sort-by size --reverse | select size name"
+        ),
+        Some(serde_json::json!([
+            {"size": 5000, "name": "lang.rs"},
+            {"size": 1200, "name": "main.rs"},
+            {"size": 300, "name": "README.md"}
+        ]))
+    );
+
+    // directory listings sort by their members
+    let dir = std::env::temp_dir().join(format!("turtle-itest-sort-by-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("large.txt"), "a larger file").unwrap();
+    std::fs::write(dir.join("small.txt"), "small").unwrap();
+    assert_eq!(
+        eval(
+            &mut ctx,
+            &format!("ls({}) | sort-by size | map .name", dir.display())
+        ),
+        Some(serde_json::json!(["small.txt", "large.txt"]))
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pipeline_stages() {
+    let (mut interp, mut ctx, vars) = setup_test_env();
+    vars.lock().unwrap().insert(
+        "text".to_string(),
+        turtle::expressions::Expressions::String(
+            "name,size,ext\nmain.rs,1200,rs\nREADME.md,300,md\nlang.rs,5000,rs\n".to_string(),
+        ),
+    );
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+    assert!(eval(&mut ctx, "let files = text | from csv").is_some());
+
+    assert_eq!(
+        eval(&mut ctx, "files | where size > 1000 | map .name"),
+        Some(serde_json::json!(["main.rs", "lang.rs"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"files | where ext == "md" | map .name"#),
+        Some(serde_json::json!(["README.md"]))
+    );
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r"files | where name =~ /^(main|lang)\.RS$/i | map .name"
+        ),
+        Some(serde_json::json!(["main.rs", "lang.rs"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, r"files | where name !~ /\.rs$/ | map .name"),
+        Some(serde_json::json!(["README.md"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, "files | map .ext | uniq"),
+        Some(serde_json::json!(["rs", "md"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, "files | sort-by size | first 2 | map .name"),
+        Some(serde_json::json!(["README.md", "main.rs"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, "files | last | map .name"),
+        Some(serde_json::json!(["lang.rs"]))
+    );
+    assert_eq!(eval(&mut ctx, "files | count"), Some(serde_json::json!(3)));
+    assert_eq!(
+        eval(&mut ctx, "files | sum size"),
+        Some(serde_json::json!(6500))
+    );
+    assert_eq!(
+        eval(&mut ctx, "files | group-by ext").map(|groups| groups["rs"].clone()),
+        Some(serde_json::json!([
+            {"name": "main.rs", "size": 1200, "ext": "rs"},
+            {"name": "lang.rs", "size": 5000, "ext": "rs"}
+        ]))
+    );
+
+    // stages report errors rather than guessing
+    assert_eq!(eval(&mut ctx, "files | where size"), None);
+    assert_eq!(eval(&mut ctx, "files | unknown"), None);
+}
+
+#[test]
+fn test_format_function_and_f_strings() {
+    use turtle::expressions::Expressions;
+
+    let (_, mut ctx, vars) = setup_test_env();
+    vars.lock().unwrap().insert(
+        "name".to_string(),
+        Expressions::String("turtle".to_string()),
+    );
+
+    let format = |args: Vec<Expressions>| Expressions::FunctionCall {
+        func: "format".to_string(),
+        args,
+    };
+
+    let result = ctx.eval(Some(format(vec![
+        Expressions::String("{:#x} {:>6.2}|".to_string()),
+        Expressions::Integer(255.into()),
+        Expressions::Number(1.23456),
+    ])));
+    assert_string_result(result, "0xff   1.23|");
+
+    // f-strings read placeholders from turtle variables
+    let result = ctx.eval(Some(format(vec![Expressions::String(
+        "hello {name:>10}".to_string(),
+    )])));
+    assert_string_result(result, "hello     turtle");
+
+    assert_eq!(
+        ctx.eval(Some(format(vec![Expressions::String(
+            "{missing}".to_string()
+        )]))),
+        None
+    );
+}
+
+#[test]
+fn test_template_strings() {
+    use turtle::expressions::Expressions;
+
+    let (mut interp, mut ctx, vars) = setup_test_env();
+    vars.lock().unwrap().insert(
+        "name".to_string(),
+        Expressions::String("turtle".to_string()),
+    );
+    vars.lock()
+        .unwrap()
+        .insert("count".to_string(), Expressions::Number(3.0));
+
+    interp.tokenize("`hello ${name}, ${count} shells\\t\\${done}`");
+    let expr = interp.interpret();
+    assert_eq!(
+        expr,
+        Some(Expressions::TemplateString {
+            parts: vec![
+                Expressions::String("hello ".to_string()),
+                Expressions::Identifier("name".to_string()),
+                Expressions::String(", ".to_string()),
+                Expressions::Identifier("count".to_string()),
+                Expressions::String(" shells\t${done}".to_string()),
+            ],
+        })
+    );
+    assert_string_result(ctx.eval(expr), "hello turtle, 3 shells\t${done}");
+}
+
+#[test]
+fn test_builtins() {
+    let (_, mut ctx, _) = setup_test_env();
+    let args = std::sync::Arc::new(std::sync::Mutex::new(turtle::config::Arguments::default()));
+    let mut interp = turtle::lang::Interpreter::new(
+        Some(args),
+        ctx.env.clone(),
+        ctx.aliases.clone(),
+        ctx.vars.clone(),
+        ctx.builtins.as_ref().unwrap().list(),
+        false,
+    );
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    // builtins return values, or null when they print their own output
+    assert_eq!(
+        eval(&mut ctx, r#"eval [1, "a  b"]"#),
+        Some(serde_json::json!([1, "a  b"]))
+    );
+    assert_eq!(eval(&mut ctx, "noop a b"), Some(serde_json::Value::Null));
+    assert_eq!(eval(&mut ctx, "help printf"), Some(serde_json::Value::Null));
+
+    // arguments are checked against the signature, errors are raised
+    assert_eq!(eval(&mut ctx, "keywords extra"), None);
+    assert_eq!(
+        ctx.error.take().map(|e| e.message),
+        Some("keywords: unexpected argument `extra`, see `keywords --help`".to_string())
+    );
+    assert_eq!(eval(&mut ctx, "stats --limit many"), None);
+    assert_eq!(eval(&mut ctx, "eval"), None);
+    assert_eq!(eval(&mut ctx, "eval undefined_var"), None);
+}
+
+/// Helper to set up an interpreter that knows the builtins of its context
+fn setup_builtins_env() -> (turtle::lang::Interpreter, turtle::context::Context) {
+    let (_, ctx, _) = setup_test_env();
+    let args = std::sync::Arc::new(std::sync::Mutex::new(turtle::config::Arguments::default()));
+    let interp = turtle::lang::Interpreter::new(
+        Some(args),
+        ctx.env.clone(),
+        ctx.aliases.clone(),
+        ctx.vars.clone(),
+        ctx.builtins.as_ref().unwrap().list(),
+        false,
+    );
+    (interp, ctx)
+}
+
+#[test]
+fn test_config_set() {
+    let (mut interp, mut ctx) = setup_builtins_env();
+    let config = std::sync::Arc::new(std::sync::Mutex::new(turtle::config::Config::default()));
+    ctx.config = Some(config.clone());
+
+    interp.tokenize("config set theme gruvbox-dark");
+    let expr = interp.interpret();
+    assert_eq!(
+        ctx.eval(expr).map(|result| result.to_json()),
+        Some(serde_json::Value::Null)
+    );
+    assert_eq!(
+        config.lock().unwrap().theme.as_deref(),
+        Some("gruvbox-dark")
+    );
+}
+
+#[test]
+fn test_keywords_in_command_arguments() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+
+    interp.tokenize("echo try match catch throw ok");
+    let expr = interp.interpret();
+    assert_eq!(
+        ctx.eval(expr)
+            .map(|result| result.to_json()["stdout"].clone()),
+        Some(serde_json::json!("try match catch throw ok\n"))
+    );
+}
+
+#[test]
+fn test_assigning_command_results() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    eval(&mut ctx, "let hi = (echo hi)");
+    assert_eq!(
+        eval(&mut ctx, "hi"),
+        Some(serde_json::json!({"code": 0, "stdout": "hi\n", "stderr": ""}))
+    );
+
+    // the command runs once, when it is assigned
+    eval(&mut ctx, "let uuid = (cat /proc/sys/kernel/random/uuid)");
+    let first = eval(&mut ctx, "uuid.stdout");
+    assert!(first.is_some());
+    assert_eq!(eval(&mut ctx, "uuid.stdout"), first);
+}
+
+#[test]
+fn test_assigning_try_results() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    eval(
+        &mut ctx,
+        "let r = try { ls /turtle-itest-missing } catch e { e }",
+    );
+    assert_eq!(
+        eval(&mut ctx, "r.code > 0 && r.stderr =~ /turtle-itest-missing/"),
+        Some(serde_json::json!(true))
+    );
+}
+
+#[test]
+fn test_split_on_strings() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    // separators are split on as they are, even when they are regex metacharacters
+    assert_eq!(
+        eval(&mut ctx, r#"split("a.b.c", ".")"#),
+        Some(serde_json::json!(["a", "b", "c"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#""a|b".split("|")"#),
+        Some(serde_json::json!(["a", "b"]))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"replace("v1.2", ".", "-")"#),
+        Some(serde_json::json!("v1-2"))
+    );
+}
+
+#[test]
+fn test_calling_function_values() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    eval(&mut ctx, "let fs = [fn(x) { x }, fn(x) { x * 2 }]");
+    assert_eq!(eval(&mut ctx, "fs[1](5)"), Some(serde_json::json!(10)));
+    assert_eq!(eval(&mut ctx, "(|x| x + 1)(1)"), Some(serde_json::json!(2)));
+    assert_eq!(eval(&mut ctx, "fs.len()"), Some(serde_json::json!(2)));
+
+    eval(&mut ctx, "let n = [1]");
+    assert_eq!(
+        eval(&mut ctx, "try { n[0](1) } catch e { e.message }"),
+        Some(serde_json::json!("`1` isn't a function"))
+    );
+}
+
+#[test]
+fn test_command_arguments_verbatim() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr)
+            .map(|result| result.to_json()["stdout"].clone())
+    };
+
+    // numbers are passed as they were written
+    assert_eq!(
+        eval(&mut ctx, "echo 0xff 1_000 1.50 1e3 0755"),
+        Some(serde_json::json!("0xff 1_000 1.50 1e3 0755\n"))
+    );
+    // and so are operators
+    assert_eq!(
+        eval(&mut ctx, "echo a/b a == b 5 % 2 $HOME"),
+        Some(serde_json::json!("a/b a == b 5 % 2 $HOME\n"))
+    );
+
+    // outside of commands operators are still operators
+    interp.tokenize("2 == 2");
+    let expr = interp.interpret();
+    assert_boolean_result(ctx.eval(expr), true);
+}
+
+#[test]
+fn test_relative_paths_in_commands() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr)
+            .map(|result| result.to_json()["stdout"].clone())
+    };
+
+    // a directory relative to the working directory, eg: `cat dir/file`
+    let dir = format!("turtle-itest-cat-{}", std::process::id());
+    std::fs::create_dir_all(format!("{}/notes", dir)).unwrap();
+    std::fs::write(format!("{}/notes/todo-1.txt", dir), "hello\n").unwrap();
+
+    let stdout = eval(&mut ctx, &format!("cat {}/notes/todo-1.txt", dir));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(stdout, Some(serde_json::json!("hello\n")));
+
+    assert_eq!(
+        eval(&mut ctx, "echo src/main.rs ../lib/mod.rs"),
+        Some(serde_json::json!("src/main.rs ../lib/mod.rs\n"))
+    );
+}
+
+#[test]
+fn test_output_format_suffix() {
+    assert_eq!(
+        turtle::expressions::Outputs::split_format("ls -la | as json"),
+        ("ls -la", Some("json"))
+    );
+    // pipes in quotes are part of the command
+    assert_eq!(
+        turtle::expressions::Outputs::split_format(r#"echo "a | as json""#),
+        (r#"echo "a | as json""#, None)
+    );
+    assert_eq!(
+        turtle::expressions::Outputs::split_format(r#"echo 'a | b' | as yaml"#),
+        (r#"echo 'a | b'"#, Some("yaml"))
+    );
+}
+
+#[test]
+fn test_builtin_output_formats() {
+    // builtins print as they run, so there's no `null` left to print in any format
+    let printed =
+        turtle::context::EvalResults::BuiltinExpressionResult(turtle::context::BuiltinEvalResult {
+            output: None,
+        });
+    for format in ["json", "jsonl", "yaml", "table", "text"] {
+        assert!(turtle::expressions::Outputs::from_result(format, &printed).is_none());
+    }
+
+    let null = turtle::context::EvalResults::NullExpressionResult;
+    assert_eq!(
+        turtle::expressions::Outputs::from_result("json", &null).map(|output| output.to_string()),
+        Some("null".to_string())
+    );
+}
+
+#[test]
+fn test_table_headers_without_terminal() {
+    // stdout is a pipe here, so table headers are left unstyled
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_turtle"))
+        .args([
+            "--skip-history",
+            "--theme",
+            "gruvbox-dark",
+            "--format",
+            "table",
+            "--command",
+            "echo name,size",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("name  size"), "{:?}", stdout);
+    assert!(!stdout.contains('\x1b'), "{:?}", stdout);
+}
+
+#[test]
+fn test_sort_comparators_and_arity() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(&mut ctx, "sort([3, 1, 2], |a, b| b - a)"),
+        Some(serde_json::json!([3, 2, 1]))
+    );
+    assert_eq!(
+        eval(&mut ctx, "[3, 1, 2].sort(|x| x)"),
+        Some(serde_json::json!([1, 2, 3]))
+    );
+    assert_eq!(
+        eval(
+            &mut ctx,
+            "try { sort([1, 2], |a, b, c| a) } catch e { e.message }"
+        ),
+        Some(serde_json::json!(
+            "sort expects a 1-argument key function or a 2-argument comparator, got fn (a, b, c)"
+        ))
+    );
+
+    // functions are called with an argument for each parameter
+    assert_eq!(
+        eval(&mut ctx, "try { map([1], |a, b| a) } catch e { e.message }"),
+        Some(serde_json::json!("fn (a, b): expected 2 arguments, got 1"))
+    );
+    eval(&mut ctx, "fn add(a, b) { a + b }");
+    assert_eq!(
+        eval(&mut ctx, "try { add(1, 2, 3) } catch e { e.message }"),
+        Some(serde_json::json!(
+            "fn add(a, b): expected 2 arguments, got 3"
+        ))
+    );
+}
+
+#[test]
+fn test_format_string_expressions() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(&mut ctx, r#"f"hello {1 + 2:>5}|""#),
+        Some(serde_json::json!("hello     3|"))
+    );
+    eval(&mut ctx, "let xs = [7, 8]");
+    eval(&mut ctx, "let name = \"turtle\"");
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r#"f"{xs.len():03} {xs[0]} {{xs}} { {a: 1}.a :<3}| {name:*^8}""#
+        ),
+        Some(serde_json::json!("002 7 {xs} 1  | *turtle*"))
+    );
+    eval(&mut ctx, r#"let day = date("2025-01-01")"#);
+    assert_eq!(
+        eval(&mut ctx, r#"f"{day + 1d:%Y/%m/%d}""#),
+        Some(serde_json::json!("2025/01/02"))
+    );
+}
+
+#[test]
+fn test_datetime_format_method() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(&mut ctx, r#"date("2025-01-02").format("%Y/%m/%d")"#),
+        Some(serde_json::json!("2025/01/02"))
+    );
+    // other values are still format strings
+    assert_eq!(
+        eval(&mut ctx, r#""{:>3}".format(7)"#),
+        Some(serde_json::json!("  7"))
+    );
+}
+
+#[test]
+fn test_failed_imports() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let dir = std::env::temp_dir().join(format!("turtle-itest-cycle-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.tt"), "import \"b.tt\" as b\n").unwrap();
+    std::fs::write(dir.join("b.tt"), "import \"c.tt\" as c\n").unwrap();
+    std::fs::write(dir.join("c.tt"), "import \"a.tt\" as a\n").unwrap();
+    std::fs::write(dir.join("d.tt"), "import \"e.tt\" as e\n").unwrap();
+    std::fs::write(dir.join("e.tt"), "let x = y + 1\n").unwrap();
+    ctx.set_env("TURTLE_PATH".to_string(), dir.to_string_lossy().to_string());
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r#"try { import "a.tt" as a } catch e { e.message }"#
+        ),
+        Some(serde_json::json!(
+            "circular import: a.tt -> b.tt -> c.tt -> a.tt"
+        ))
+    );
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r#"try { import "d.tt" as d } catch e { e.message }"#
+        ),
+        Some(serde_json::json!("d.tt -> e.tt: Variable 'y' not defined"))
+    );
+
+    // the error is printed once, not again by each import on the way
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_turtle"))
+        .args(["--skip-history", "--command", r#"import "a.tt" as a"#])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(
+        stderr.trim(),
+        "Error: circular import: a.tt -> b.tt -> c.tt -> a.tt"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_config_arguments() {
+    let (mut interp, mut ctx) = setup_builtins_env();
+    let config = std::sync::Arc::new(std::sync::Mutex::new(turtle::config::Config::default()));
+    ctx.config = Some(config.clone());
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(&mut ctx, r#"config set history.ignore ["^ls$", "^cd"]"#),
+        Some(serde_json::Value::Null)
+    );
+    assert_eq!(
+        config.lock().unwrap().get("history.ignore"),
+        Some(serde_json::json!(["^ls$", "^cd"]))
+    );
+    assert_eq!(eval(&mut ctx, "config path"), Some(serde_json::Value::Null));
+    assert_eq!(
+        eval(&mut ctx, "try { config set theme } catch e { e.message }"),
+        Some(serde_json::json!(
+            "config: wrong arguments for set, see `config --help`"
+        ))
+    );
+    assert_eq!(
+        eval(&mut ctx, "try { config edit now } catch e { e.message }"),
+        Some(serde_json::json!(
+            "config: wrong arguments for edit, see `config --help`"
+        ))
+    );
+}
+
+#[test]
+fn test_split_entry_points() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    // a string separator is literal and a regex literal is a pattern, however split is called
+    for code in [
+        r#"split("a.b.c", ".")"#,
+        r#"strings.split("a.b.c", ".")"#,
+        r#""a.b.c".split(".")"#,
+        r#"split("a1b22c", /\d+/)"#,
+        r#"strings.split("a1b22c", /\d+/)"#,
+        r#""a1b22c".split(/\d+/)"#,
+    ] {
+        assert_eq!(
+            eval(&mut ctx, code),
+            Some(serde_json::json!(["a", "b", "c"])),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn test_match_object_scrutinee() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(
+            &mut ctx,
+            r#"match {name: "a"} { {name: n} => n, _ => "none" }"#
+        ),
+        Some(serde_json::json!("a"))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"match {name: "a"}.name { "a" => 1, _ => 2 }"#),
+        Some(serde_json::json!(1))
+    );
+    // a `{` after an operand is still the block
+    assert_eq!(
+        eval(&mut ctx, r#"if 3 > 2 { "big" } else { "small" }"#),
+        Some(serde_json::json!("big"))
+    );
+}
+
+#[test]
+fn test_destructuring_result() {
+    let (mut interp, mut ctx, _) = setup_test_env();
+    let mut eval = |ctx: &mut turtle::context::Context, code: &str| {
+        interp.tokenize(code);
+        let expr = interp.interpret();
+        ctx.eval(expr).map(|result| result.to_json())
+    };
+
+    assert_eq!(
+        eval(&mut ctx, "let [a, b] = [1, 2]"),
+        Some(serde_json::json!({"a": 1, "b": 2}))
+    );
+    assert_eq!(
+        eval(&mut ctx, r#"let {host, port: p} = {host: "h", port: 80}"#),
+        Some(serde_json::json!({"host": "h", "p": 80}))
+    );
+}
@@ -12,13 +12,30 @@ use serde::{Deserialize, Serialize};
 pub enum Token {
     /// used in function definitions to define their return types, eg: `func foo() -> String { ... }`
     Arrow,
+    /// separates the pattern of a match arm from its value, eg: `0 => "none"`
+    FatArrow,
 
     /// string literals, eg: `"hello"`, `'world'`
     String(String),
-    /// numeric literals, eg: `42`, `3.14`
-    Number(f64),
+    /// format string literals, eg: `f"hello {name:>10}"`
+    FormatString(String),
+    /// template string literals, eg: `` `hello ${name}` ``, made of `String` text and `CodeBlock` expressions
+    TemplateString(Vec<Token>),
+    /// floating point literals and their lexeme, eg: `3.14`, `1e3`, `1_000.5`
+    Number(f64, String),
+    /// integer literals and their lexeme, eg: `42`, `0xff`, `0o17`, `0b1010`, `1_000_000`
+    Integer(crate::numbers::Integer, String),
     /// a literal `True` or `False`
     Boolean(bool),
+    /// a literal `null`, the absence of a value
+    Null,
+    /// duration literals as written, eg: `5m`, `2h30m`, `500ms`
+    Duration(String),
+    /// regular expression literals, eg: `/^v\d+/i`
+    RegularExpression {
+        pattern: String,
+        flags: Option<String>,
+    },
 
     /// a closing brace '}' typically used to complete code blocks or object literals
     BraceClose,
@@ -82,22 +99,34 @@ pub enum Token {
     Tab,     // tab character
     Newline, // newline character
     // Whitespace, // spaces, tabs, newlines
-    // Operator(String), // +, -, *, **, /, %, ==, !=, <, >, <=, >=, &&, ||, !
+    Operator(String), // operators without a token of their own, eg: `-`
     // TODO: implement specific operators
-    ExponentiationOperator,     // ** or ^
+    ExponentiationOperator,     // **
     AdditionOperator,           // +
     SubtractionOperator,        // -
     MultiplicationOperator,     // *
     DivisionOperator,           // /
+    FloorDivisionOperator,      // //
     ModulusOperator,            // %
+    BitwiseAndOperator,         // &
+    BitwiseXorOperator,         // ^
+    BitwiseNotOperator,         // ~
+    ShiftLeftOperator,          // <<
+    ShiftRightOperator,         // >>
+    AssignmentOperator,         // =
     EqualOperator,              // ==
     NotEqualOperator,           // !=
+    RegexMatchOperator,         // =~
+    RegexNotMatchOperator,      // !~
     LessThanOperator,           // <
     GreaterThanOperator,        // >
     LessThanOrEqualOperator,    // <=
     GreaterThanOrEqualOperator, // >=
     LogicalAndOperator,         // &&
     LogicalOrOperator,          // ||
+    PipeOperator,               // |
+    OptionalChainOperator,      // ?.
+    NullCoalescingOperator,     // ??
     NotOperator,                // !
     DollarOperator,             // $
     Semicolon,                  // ;
@@ -124,11 +153,15 @@ mod tests {
             command: None,
             format: None,
             config_path: None,
+            prompt: None,
+            theme: None,
+            history_size: None,
             history_path: None,
             display_defaults: false,
             display_config: false,
             display_env: false,
             display_prompt: false,
+            subcommand: None,
             skip_aliases: false,
             skip_history: false,
             watch_config: false,
@@ -139,9 +172,9 @@ mod tests {
         let tokens = interp.tokenize_primitives("1+1");
 
         let expected = vec![
-            Token::Number(1.0),
+            Token::Integer(1.into(), "1".into()),
             Token::AdditionOperator,
-            Token::Number(1.0),
+            Token::Integer(1.into(), "1".into()),
             Token::Eof,
         ];
 
@@ -165,11 +198,15 @@ mod tests {
             command: None,
             format: None,
             config_path: None,
+            prompt: None,
+            theme: None,
+            history_size: None,
             history_path: None,
             display_defaults: false,
             display_config: false,
             display_env: false,
             display_prompt: false,
+            subcommand: None,
             skip_aliases: false,
             skip_history: false,
             watch_config: false,
@@ -182,11 +219,11 @@ mod tests {
         let tokens = interp.tokenize_primitives("1 + 1");
 
         let expected = vec![
-            Token::Number(1.0),
+            Token::Integer(1.into(), "1".into()),
             Token::Space,
             Token::AdditionOperator,
             Token::Space,
-            Token::Number(1.0),
+            Token::Integer(1.into(), "1".into()),
             Token::Eof,
         ];
 
@@ -210,11 +247,15 @@ mod tests {
             command: None,
             format: None,
             config_path: None,
+            prompt: None,
+            theme: None,
+            history_size: None,
             history_path: None,
             display_defaults: false,
             display_config: false,
             display_env: false,
             display_prompt: false,
+            subcommand: None,
             skip_aliases: false,
             skip_history: false,
             watch_config: false,
@@ -253,11 +294,15 @@ mod tests {
             command: None,
             format: None,
             config_path: None,
+            prompt: None,
+            theme: None,
+            history_size: None,
             history_path: None,
             display_defaults: false,
             display_config: false,
             display_env: false,
             display_prompt: false,
+            subcommand: None,
             skip_aliases: false,
             skip_history: false,
             watch_config: false,
@@ -267,11 +312,387 @@ mod tests {
             crate::lang::Interpreter::new(Some(args.clone()), env, aliases, vars, builtins, false);
         let tokens = interp.tokenize_primitives("True && False");
         let expected = vec![
-            Token::Keyword("True".to_string()),
+            Token::Boolean(true),
             Token::Space,
             Token::LogicalAndOperator,
             Token::Space,
-            Token::Keyword("False".to_string()),
+            Token::Boolean(false),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_pipe_operator() {
+        let env = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let aliases = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let vars = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let builtins: Vec<String> = vec![];
+        let args = std::sync::Arc::new(std::sync::Mutex::new(crate::config::Arguments {
+            version: false,
+            debug: false,
+            debug_expressions: false,
+            debug_tokenization: false,
+            debug_context: false,
+            available_themes: false,
+            command: None,
+            format: None,
+            config_path: None,
+            prompt: None,
+            theme: None,
+            history_size: None,
+            history_path: None,
+            display_defaults: false,
+            display_config: false,
+            display_env: false,
+            display_prompt: false,
+            subcommand: None,
+            skip_aliases: false,
+            skip_history: false,
+            watch_config: false,
+        }));
+
+        let mut interp =
+            crate::lang::Interpreter::new(Some(args.clone()), env, aliases, vars, builtins, false);
+        let tokens = interp.tokenize_primitives("(pods) | from json");
+        let expected = vec![
+            Token::ParenOpen,
+            Token::Identifier("pods".to_string()),
+            Token::ParenClose,
+            Token::Space,
+            Token::PipeOperator,
+            Token::Space,
+            Token::Identifier("from".to_string()),
+            Token::Space,
+            Token::Identifier("json".to_string()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_format_string() {
+        let env = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let aliases = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let vars = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let builtins: Vec<String> = vec![];
+        let args = std::sync::Arc::new(std::sync::Mutex::new(crate::config::Arguments {
+            version: false,
+            debug: false,
+            debug_expressions: false,
+            debug_tokenization: false,
+            debug_context: false,
+            available_themes: false,
+            command: None,
+            format: None,
+            config_path: None,
+            prompt: None,
+            theme: None,
+            history_size: None,
+            history_path: None,
+            display_defaults: false,
+            display_config: false,
+            display_env: false,
+            display_prompt: false,
+            subcommand: None,
+            skip_aliases: false,
+            skip_history: false,
+            watch_config: false,
+        }));
+
+        let mut interp =
+            crate::lang::Interpreter::new(Some(args.clone()), env, aliases, vars, builtins, false);
+        let tokens = interp.tokenize_primitives(r#"f"hello {name:>10}" f"#);
+        let expected = vec![
+            Token::FormatString("hello {name:>10}".to_string()),
+            Token::Space,
+            Token::Identifier("f".to_string()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    /// an interpreter with no builtins, for tokenizer tests
+    fn interpreter() -> crate::lang::Interpreter {
+        let env = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let aliases = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let vars = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+        let args = std::sync::Arc::new(std::sync::Mutex::new(crate::config::Arguments {
+            version: false,
+            debug: false,
+            debug_expressions: false,
+            debug_tokenization: false,
+            debug_context: false,
+            available_themes: false,
+            command: None,
+            format: None,
+            config_path: None,
+            prompt: None,
+            theme: None,
+            history_size: None,
+            history_path: None,
+            display_defaults: false,
+            display_config: false,
+            display_env: false,
+            display_prompt: false,
+            subcommand: None,
+            skip_aliases: false,
+            skip_history: false,
+            watch_config: false,
+        }));
+        crate::lang::Interpreter::new(Some(args), env, aliases, vars, vec![], false)
+    }
+
+    #[test]
+    fn tokenize_string_escapes() {
+        let tokens = interpreter().tokenize_primitives(r#""a\tb\n\"c\" \u{1F422} \d""#);
+        let expected = vec![Token::String("a\tb\n\"c\" 🐢 \\d".to_string()), Token::Eof];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_raw_strings() {
+        let tokens = interpreter().tokenize_primitives(r##"r"C:\new" r#"say "hi""#"##);
+        let expected = vec![
+            Token::String("C:\\new".to_string()),
+            Token::Space,
+            Token::String("say \"hi\"".to_string()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_multiline_strings() {
+        let input = "\"\"\"\n    first\n      second\\tline\n    \"\"\"";
+        let tokens = interpreter().tokenize_primitives(input);
+        let expected = vec![
+            Token::String("first\n  second\tline".to_string()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_template_strings() {
+        let tokens = interpreter().tokenize_primitives("`hi ${name}, \\${x} ${ {a: 1}.a }`");
+        let expected = vec![
+            Token::TemplateString(vec![
+                Token::String("hi ".to_string()),
+                Token::CodeBlock(vec![Token::Identifier("name".to_string())]),
+                Token::String(", ${x} ".to_string()),
+                Token::CodeBlock(vec![
+                    Token::BraceOpen,
+                    Token::Identifier("a".to_string()),
+                    Token::Colon,
+                    Token::Space,
+                    Token::Integer(1.into(), "1".into()),
+                    Token::BraceClose,
+                    Token::Identifier(".a".to_string()),
+                ]),
+            ]),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_number_literals() {
+        let tokens = interpreter().tokenize_primitives("0xff 0o17 0b101 1_000 2.5 1e3 3 ** 2");
+        let expected = vec![
+            Token::Integer(255.into(), "0xff".into()),
+            Token::Space,
+            Token::Integer(15.into(), "0o17".into()),
+            Token::Space,
+            Token::Integer(5.into(), "0b101".into()),
+            Token::Space,
+            Token::Integer(1000.into(), "1_000".into()),
+            Token::Space,
+            Token::Number(2.5, "2.5".into()),
+            Token::Space,
+            Token::Number(1000.0, "1e3".into()),
+            Token::Space,
+            Token::Integer(3.into(), "3".into()),
+            Token::Space,
+            Token::ExponentiationOperator,
+            Token::Space,
+            Token::Integer(2.into(), "2".into()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_duration_literals() {
+        let tokens = interpreter().tokenize_primitives("2h30m+500ms 5min 3d6");
+        let expected = vec![
+            Token::Duration("2h30m".to_string()),
+            Token::AdditionOperator,
+            Token::Duration("500ms".to_string()),
+            Token::Space,
+            Token::Integer(5.into(), "5".into()),
+            Token::Identifier("min".to_string()),
+            Token::Space,
+            Token::Integer(3.into(), "3".into()),
+            Token::Identifier("d6".to_string()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_integer_operators() {
+        let tokens = interpreter().tokenize_primitives("7//2%3&1^~x<<2>>1");
+        let expected = vec![
+            Token::Integer(7.into(), "7".into()),
+            Token::FloorDivisionOperator,
+            Token::Integer(2.into(), "2".into()),
+            Token::ModulusOperator,
+            Token::Integer(3.into(), "3".into()),
+            Token::BitwiseAndOperator,
+            Token::Integer(1.into(), "1".into()),
+            Token::BitwiseXorOperator,
+            Token::BitwiseNotOperator,
+            Token::Identifier("x".to_string()),
+            Token::ShiftLeftOperator,
+            Token::Integer(2.into(), "2".into()),
+            Token::ShiftRightOperator,
+            Token::Integer(1.into(), "1".into()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_null_and_optional_chaining() {
+        let tokens = interpreter().tokenize_primitives("a?.b.c ?? null");
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::OptionalChainOperator,
+            Token::Identifier("b.c".to_string()),
+            Token::Space,
+            Token::NullCoalescingOperator,
+            Token::Space,
+            Token::Null,
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_regex_literals() {
+        let regex = |pattern: &str, flags: Option<&str>| Token::RegularExpression {
+            pattern: pattern.to_string(),
+            flags: flags.map(str::to_string),
+        };
+        let tokens = interpreter().tokenize_primitives(r"name =~ /^v\d+\/x/i");
+        let expected = vec![
+            Token::Identifier("name".to_string()),
+            Token::Space,
+            Token::RegexMatchOperator,
+            Token::Space,
+            regex(r"^v\d+/x", Some("i")),
+            Token::Eof,
+        ];
+        assert_eq!(tokens, expected);
+
+        // division and paths aren't regexes
+        let tokens = interpreter().tokenize_primitives("a / b / c");
+        assert!(
+            !tokens
+                .iter()
+                .any(|t| matches!(t, Token::RegularExpression { .. }))
+        );
+        let tokens = interpreter().tokenize_primitives("/usr/bin/env");
+        assert!(
+            !tokens
+                .iter()
+                .any(|t| matches!(t, Token::RegularExpression { .. }))
+        );
+
+        let tokens = interpreter().tokenize_primitives("split(x, /,/) !~ 1");
+        assert!(tokens.contains(&regex(",", None)));
+        assert!(tokens.contains(&Token::RegexNotMatchOperator));
+    }
+
+    #[test]
+    fn tokenize_path_literals() {
+        let dir = |path: &str| Token::ShellDirectory {
+            segments: path.split('/').map(str::to_string).collect(),
+        };
+        let tokens = interpreter().tokenize_primitives("ls(./src) ~/notes.md /etc a/b");
+        let expected = vec![
+            Token::Identifier("ls".to_string()),
+            Token::ParenOpen,
+            dir("./src"),
+            Token::ParenClose,
+            Token::Space,
+            Token::ShellFile {
+                name: "notes.md".to_string(),
+                path: "~/notes.md".to_string(),
+                extension: Some("md".to_string()),
+            },
+            Token::Space,
+            dir("/etc"),
+            Token::Space,
+            Token::Identifier("a".to_string()),
+            Token::DivisionOperator,
+            Token::Identifier("b".to_string()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_assignment_operator() {
+        let tokens = interpreter().tokenize_primitives("let x = 1 == 1");
+        let expected = vec![
+            Token::Keyword("Let".to_string()),
+            Token::Space,
+            Token::Identifier("x".to_string()),
+            Token::Space,
+            Token::AssignmentOperator,
+            Token::Space,
+            Token::Integer(1.into(), "1".into()),
+            Token::Space,
+            Token::EqualOperator,
+            Token::Space,
+            Token::Integer(1.into(), "1".into()),
+            Token::Eof,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokenize_match_arms() {
+        let tokens = interpreter().tokenize_primitives("match x { _ => 1 }");
+        let expected = vec![
+            Token::Keyword("Match".to_string()),
+            Token::Space,
+            Token::Identifier("x".to_string()),
+            Token::Space,
+            Token::BraceOpen,
+            Token::Space,
+            Token::Identifier("_".to_string()),
+            Token::Space,
+            Token::FatArrow,
+            Token::Space,
+            Token::Integer(1.into(), "1".into()),
+            Token::Space,
+            Token::BraceClose,
             Token::Eof,
         ];
 
@@ -28,7 +28,7 @@ pub fn now() -> std::time::Instant {
 #[test]
 fn test_this_instant() {
     let instant = now();
-    assert!(instant.elapsed().as_secs() >= 0);
+    assert!(instant <= now());
 }
 
 /// Get the elapsed time
@@ -46,15 +46,14 @@ fn test_elapsed_millis() {
 
 /// Expands path modifiers (~, etc.) in a given path string
 pub fn expand_path(path: &str) -> String {
-    if path.starts_with("~/") {
+    if let Some(without_tilde) = path.strip_prefix("~/") {
         if let Some(home) = dirs::home_dir() {
-            let without_tilde = &path[2..]; // Remove "~/"
             return home.join(without_tilde).to_string_lossy().to_string();
         }
-    } else if path == "~" {
-        if let Some(home) = dirs::home_dir() {
-            return home.to_string_lossy().to_string();
-        }
+    } else if path == "~"
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.to_string_lossy().to_string();
     }
     path.to_string()
 }
@@ -131,7 +130,7 @@ pub fn build_user_environment() -> std::collections::HashMap<String, String> {
     if let Some(home_dir) = dirs::home_dir() {
         details.insert("HOME".to_string(), home_dir.to_string_lossy().to_string());
     }
-    return details;
+    details
 }
 
 #[test]
@@ -142,3 +141,179 @@ fn test_build_user_environment() {
     assert_ne!(env.get("USER").unwrap(), "testuser");
     assert_ne!(env.get("HOME").unwrap(), "/home/testuser");
 }
+
+/// Compute the edit distance between two strings
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[test]
+fn test_levenshtein() {
+    assert_eq!(levenshtein("theme", "theme"), 0);
+    assert_eq!(levenshtein("them", "theme"), 1);
+    assert_eq!(levenshtein("promtp", "prompt"), 2);
+    assert_eq!(levenshtein("", "abc"), 3);
+}
+
+/// Find the candidate closest to `name`, if any is within a couple of edits
+pub fn closest_match<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[test]
+fn test_closest_match() {
+    let candidates = vec!["prompt", "aliases", "theme"];
+    assert_eq!(closest_match("promt", candidates.clone()), Some("prompt"));
+    assert_eq!(closest_match("colors", candidates), None);
+}
+
+/// Split builtin arguments on whitespace, keeping double quoted arguments together
+///
+/// quotes are removed and `\"` writes a literal quote, eg: `"a b" c` => `["a b", "c"]`
+pub fn split_args(args: &str) -> Vec<String> {
+    split_args_at(args)
+        .into_iter()
+        .map(|(_, arg)| arg)
+        .collect()
+}
+
+/// Split builtin arguments like `split_args`, with the byte offset each argument starts at
+pub fn split_args_at(args: &str) -> Vec<(usize, String)> {
+    let mut split = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut start = None;
+    let mut chars = args.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                if let Some((_, next)) = chars.next() {
+                    if next != '"' {
+                        current.push('\\');
+                    }
+                    current.push(next);
+                }
+            }
+            '"' => {
+                quoted = !quoted;
+                start.get_or_insert(i);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if let Some(start) = start.take() {
+                    split.push((start, std::mem::take(&mut current)));
+                }
+            }
+            c => {
+                current.push(c);
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(start) = start {
+        split.push((start, current));
+    }
+    split
+}
+
+#[test]
+fn test_split_args() {
+    assert_eq!(
+        split_args(r#""Hello, {}!" World"#),
+        vec!["Hello, {}!", "World"]
+    );
+    assert_eq!(split_args(r#"  "" "a \"b\"" c"#), vec!["", "a \"b\"", "c"]);
+    assert!(split_args("   ").is_empty());
+    assert_eq!(
+        split_args_at(r#"a  "b c" d"#),
+        vec![
+            (0, "a".to_string()),
+            (3, "b c".to_string()),
+            (9, "d".to_string())
+        ]
+    );
+}
+
+/// Wait for a child process to exit, killing it if it runs longer than `timeout`
+///
+/// stdout and stderr are read on separate threads so a chatty child can't block on a full pipe
+pub fn wait_with_timeout(
+    mut child: std::process::Child,
+    timeout: Option<std::time::Duration>,
+) -> std::io::Result<std::process::Output> {
+    use std::io::Read;
+    let stdout = child.stdout.take().map(|mut out| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            out.read_to_end(&mut buffer).ok();
+            buffer
+        })
+    });
+    let stderr = child.stderr.take().map(|mut err| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            err.read_to_end(&mut buffer).ok();
+            buffer
+        })
+    });
+
+    let start = std::time::Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            child.kill().ok();
+            child.wait().ok();
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!(
+                    "timed out after {} seconds",
+                    timeout.unwrap_or_default().as_secs()
+                ),
+            ));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    };
+
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.and_then(|t| t.join().ok()).unwrap_or_default(),
+        stderr: stderr.and_then(|t| t.join().ok()).unwrap_or_default(),
+    })
+}
+
+#[test]
+fn test_wait_with_timeout() {
+    let child = std::process::Command::new("echo")
+        .arg("turtle")
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let output = wait_with_timeout(child, None).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "turtle\n");
+
+    let child = std::process::Command::new("sleep")
+        .arg("5")
+        .spawn()
+        .unwrap();
+    let result = wait_with_timeout(child, Some(std::time::Duration::from_millis(50)));
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+}
@@ -0,0 +1,478 @@
+/// Full screen viewer for command output, tables and history
+///
+/// Copyright (c) 2025 Aaron P. Samuel
+///
+/// Licensed under the MIT License <LICENSE-MIT or http://opensource.org/licenses/MIT>
+///
+/// **SPDX-License-Identifier**: MIT
+///
+/// See LICENSE for details.
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// widest a column is drawn, longer cells are truncated
+const MAX_COLUMN_WIDTH: usize = 48;
+
+/// key bindings shown in the status line
+const HELP: &str = "↑↓ scroll  ←→ columns  / search  n/N next/prev  c columns  ⏎ copy  q quit";
+
+/// text being typed into the status line
+#[derive(Debug, Clone, PartialEq)]
+enum Input {
+    /// `/` search term
+    Search(String),
+    /// `c` space separated column names
+    Columns(String),
+}
+
+/// result of handling a key press
+#[derive(Debug, Clone, PartialEq)]
+pub enum Control {
+    Continue,
+    Quit,
+    /// copy a row back into the prompt
+    Copy(String),
+}
+
+/// Browse a table with scrolling, search and column filtering
+///
+/// ```text
+/// let viewer = Viewer::from_text("ls -la", &output);
+/// if let Some(row) = viewer.run()? { ... }
+/// ```
+pub struct Viewer {
+    title: String,
+    table: crate::expressions::OutputCsv,
+    /// column copied by `⏎`, all visible columns when None
+    copy_column: Option<usize>,
+    /// indexes of the visible columns
+    columns: Vec<usize>,
+    /// first visible column when scrolled right
+    column_offset: usize,
+    selected: usize,
+    search: String,
+    input: Option<Input>,
+    message: Option<String>,
+    header: ratatui::style::Style,
+    state: ratatui::widgets::TableState,
+    /// rows that fit on screen, for page up and down
+    page: usize,
+}
+
+impl Viewer {
+    pub fn new(title: &str, table: crate::expressions::OutputCsv) -> Self {
+        let columns = (0..table.headers.len()).collect();
+        Viewer {
+            title: title.to_string(),
+            table,
+            copy_column: None,
+            columns,
+            column_offset: 0,
+            selected: 0,
+            search: String::new(),
+            input: None,
+            message: None,
+            header: ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+            state: ratatui::widgets::TableState::default(),
+            page: 10,
+        }
+    }
+
+    /// view text, as a table when it's structured, eg: json or csv, otherwise line by line
+    pub fn from_text(title: &str, text: &str) -> Self {
+        let table = match crate::expressions::Expressions::detect_format(text) {
+            "lines" => None,
+            format => crate::expressions::Expressions::parse_as(format, text)
+                .ok()
+                .and_then(|value| crate::expressions::OutputCsv::from_json(&value.to_json())),
+        };
+        match table {
+            Some(table) => Viewer::new(title, table),
+            None => {
+                let mut viewer = Viewer::new(
+                    title,
+                    crate::expressions::OutputCsv {
+                        headers: vec!["output".to_string()],
+                        data: text.lines().map(|line| vec![line.to_string()]).collect(),
+                    },
+                );
+                viewer.copy_column = Some(0);
+                viewer
+            }
+        }
+    }
+
+    /// view a turtle value, eg: an array of objects
+    pub fn from_value(title: &str, value: &crate::expressions::Expressions) -> Option<Self> {
+        match value {
+            crate::expressions::Expressions::String(text) => Some(Viewer::from_text(title, text)),
+            value => crate::expressions::OutputCsv::from_json(&value.to_json())
+                .map(|table| Viewer::new(title, table)),
+        }
+    }
+
+    /// view the commands in the history, copying a row copies its command
+    pub fn from_history(history: &crate::history::History) -> Self {
+        let events = history.events.clone().unwrap_or_default();
+        let data = events
+            .iter()
+            .filter_map(|event| match event {
+                crate::history::Event::CommandRequest(request) => Some(request),
+                _ => None,
+            })
+            .map(|request| {
+                let response = events.iter().find_map(|event| match event {
+                    crate::history::Event::CommandResponse(response)
+                        if response.id == request.id =>
+                    {
+                        Some(response)
+                    }
+                    _ => None,
+                });
+                let time = chrono::DateTime::from_timestamp(request.timestamp as i64, 0)
+                    .map(|t| {
+                        t.with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M:%S")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                vec![
+                    time,
+                    request.line(),
+                    response.map(|r| r.code.to_string()).unwrap_or_default(),
+                    response
+                        .and_then(|r| r.output.lines().next())
+                        .unwrap_or_default()
+                        .to_string(),
+                ]
+            })
+            .collect();
+
+        let mut viewer = Viewer::new(
+            "history",
+            crate::expressions::OutputCsv {
+                headers: vec![
+                    "time".to_string(),
+                    "command".to_string(),
+                    "code".to_string(),
+                    "output".to_string(),
+                ],
+                data,
+            },
+        );
+        viewer.copy_column = Some(1);
+        viewer
+    }
+
+    /// colour the header from a theme
+    pub fn with_theme(mut self, theme: Option<&crate::style::Theme>) -> Self {
+        let color = |color: crossterm::style::Color| match color {
+            crossterm::style::Color::Rgb { r, g, b } => ratatui::style::Color::Rgb(r, g, b),
+            _ => ratatui::style::Color::Reset,
+        };
+        if let Some(theme) = theme {
+            self.header = self
+                .header
+                .fg(color(theme.foreground))
+                .bg(color(theme.selection));
+        }
+        self
+    }
+
+    /// show the viewer until it's closed
+    ///
+    /// returns the row to copy into the prompt, if one was chosen
+    pub fn run(mut self) -> std::io::Result<Option<String>> {
+        let mut terminal = ratatui::try_init()?;
+        let result = loop {
+            if let Err(e) = terminal.draw(|frame| self.draw(frame)) {
+                break Err(e);
+            }
+            match ratatui::crossterm::event::read() {
+                Ok(ratatui::crossterm::event::Event::Key(key))
+                    if key.kind == KeyEventKind::Press =>
+                {
+                    match self.handle_key(key) {
+                        Control::Continue => {}
+                        Control::Quit => break Ok(None),
+                        Control::Copy(row) => break Ok(Some(row)),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => break Err(e),
+            }
+        };
+        ratatui::restore();
+        result
+    }
+
+    /// rows with any cell containing the search term
+    fn matches(&self, row: &[String]) -> bool {
+        let search = self.search.to_lowercase();
+        !search.is_empty() && row.iter().any(|cell| cell.to_lowercase().contains(&search))
+    }
+
+    /// move the selection to the first row matching the search, starting
+    /// `skip` rows after, or before, the selected row and wrapping around
+    fn find(&mut self, skip: usize, forward: bool) {
+        let count = self.table.data.len();
+        if self.search.is_empty() || count == 0 {
+            return;
+        }
+        let found = (skip..count + skip)
+            .map(|step| {
+                if forward {
+                    (self.selected + step) % count
+                } else {
+                    (self.selected + count - step % count) % count
+                }
+            })
+            .find(|row| self.matches(&self.table.data[*row]));
+        match found {
+            Some(row) => {
+                self.selected = row;
+                self.message = None;
+            }
+            None => self.message = Some(format!("no match for `{}`", self.search)),
+        }
+    }
+
+    /// show only the named columns, or every column when none are given
+    fn filter_columns(&mut self, names: &str) {
+        let names: Vec<String> = names.split_whitespace().map(|n| n.to_lowercase()).collect();
+        self.column_offset = 0;
+        if names.is_empty() {
+            self.columns = (0..self.table.headers.len()).collect();
+            return;
+        }
+        let columns: Vec<usize> = names
+            .iter()
+            .filter_map(|name| {
+                self.table
+                    .headers
+                    .iter()
+                    .position(|header| header.to_lowercase() == *name)
+            })
+            .collect();
+        if columns.is_empty() {
+            self.message = Some(format!("no columns named `{}`", names.join(" ")));
+        } else {
+            self.columns = columns;
+        }
+    }
+
+    /// text copied into the prompt for the selected row
+    fn row_text(&self) -> Option<String> {
+        let row = self.table.data.get(self.selected)?;
+        Some(match self.copy_column {
+            Some(column) => row.get(column).cloned().unwrap_or_default(),
+            None => self
+                .columns
+                .iter()
+                .filter_map(|column| row.get(*column).cloned())
+                .collect::<Vec<String>>()
+                .join(" "),
+        })
+    }
+
+    /// update the viewer for a key press
+    pub fn handle_key(&mut self, key: KeyEvent) -> Control {
+        if let Some(input) = self.input.as_mut() {
+            let text = match input {
+                Input::Search(text) | Input::Columns(text) => text,
+            };
+            match key.code {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Enter => match self.input.take() {
+                    Some(Input::Search(text)) => {
+                        self.search = text;
+                        self.find(0, true);
+                    }
+                    Some(Input::Columns(names)) => self.filter_columns(&names),
+                    None => {}
+                },
+                _ => {}
+            }
+            return Control::Continue;
+        }
+
+        let last = self.table.data.len().saturating_sub(1);
+        let page = self.page.max(1);
+        self.message = None;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Control::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Control::Quit;
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => {
+                self.selected = (self.selected + page).min(last)
+            }
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(page),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected = last,
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.column_offset =
+                    (self.column_offset + 1).min(self.columns.len().saturating_sub(1))
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.column_offset = self.column_offset.saturating_sub(1)
+            }
+            KeyCode::Char('/') => self.input = Some(Input::Search(String::new())),
+            KeyCode::Char('c') => self.input = Some(Input::Columns(String::new())),
+            KeyCode::Char('n') => self.find(1, true),
+            KeyCode::Char('N') => self.find(1, false),
+            KeyCode::Enter | KeyCode::Char('y') => {
+                if let Some(row) = self.row_text() {
+                    return Control::Copy(row);
+                }
+            }
+            _ => {}
+        }
+        Control::Continue
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame) {
+        use ratatui::style::Stylize;
+
+        let [body, status] = ratatui::layout::Layout::vertical([
+            ratatui::layout::Constraint::Min(1),
+            ratatui::layout::Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        // borders and the header row
+        self.page = (body.height as usize).saturating_sub(3);
+
+        let columns: Vec<usize> = self
+            .columns
+            .iter()
+            .skip(self.column_offset)
+            .copied()
+            .collect();
+        let widths: Vec<ratatui::layout::Constraint> = columns
+            .iter()
+            .map(|column| {
+                let width = self
+                    .table
+                    .data
+                    .iter()
+                    .filter_map(|row| row.get(*column))
+                    .chain(self.table.headers.get(*column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .min(MAX_COLUMN_WIDTH);
+                ratatui::layout::Constraint::Length(width as u16)
+            })
+            .collect();
+
+        let header = ratatui::widgets::Row::new(
+            columns
+                .iter()
+                .map(|column| self.table.headers[*column].clone()),
+        )
+        .style(self.header);
+        let rows = self.table.data.iter().map(|row| {
+            let cells = columns
+                .iter()
+                .map(|column| row.get(*column).cloned().unwrap_or_default());
+            let row_widget = ratatui::widgets::Row::new(cells);
+            if self.matches(row) {
+                row_widget.yellow()
+            } else {
+                row_widget
+            }
+        });
+
+        let table = ratatui::widgets::Table::new(rows, widths)
+            .header(header)
+            .column_spacing(2)
+            .row_highlight_style(ratatui::style::Style::default().reversed())
+            .block(
+                ratatui::widgets::Block::bordered()
+                    .title(format!(" {} ", self.title))
+                    .title_bottom(format!(
+                        " {}/{} ",
+                        (self.selected + 1).min(self.table.data.len()),
+                        self.table.data.len()
+                    )),
+            );
+        self.state.select(Some(self.selected));
+        frame.render_stateful_widget(table, body, &mut self.state);
+
+        let line = match (&self.input, &self.message) {
+            (Some(Input::Search(text)), _) => format!("/{}", text),
+            (Some(Input::Columns(text)), _) => format!("columns: {}", text),
+            (None, Some(message)) => message.clone(),
+            (None, None) => HELP.to_string(),
+        };
+        frame.render_widget(ratatui::widgets::Paragraph::new(line), status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn viewer() -> Viewer {
+        Viewer::from_text(
+            "files",
+            "name,size\nturtle.rs,1200\nlang.rs,35\nshell.rs,80\n",
+        )
+    }
+
+    #[test]
+    fn test_viewer_search() {
+        let mut viewer = viewer();
+        for code in [KeyCode::Char('/'), KeyCode::Char('s'), KeyCode::Char('h')] {
+            viewer.handle_key(key(code));
+        }
+        viewer.handle_key(key(KeyCode::Enter));
+        assert_eq!(viewer.selected, 2);
+
+        // wraps around to the only match
+        viewer.handle_key(key(KeyCode::Char('n')));
+        assert_eq!(viewer.selected, 2);
+        assert_eq!(
+            viewer.handle_key(key(KeyCode::Enter)),
+            Control::Copy("shell.rs 80".to_string())
+        );
+    }
+
+    #[test]
+    fn test_viewer_columns() {
+        let mut viewer = viewer();
+        viewer.handle_key(key(KeyCode::Char('c')));
+        for c in "size".chars() {
+            viewer.handle_key(key(KeyCode::Char(c)));
+        }
+        viewer.handle_key(key(KeyCode::Enter));
+        assert_eq!(viewer.columns, vec![1]);
+
+        viewer.handle_key(key(KeyCode::Down));
+        assert_eq!(
+            viewer.handle_key(key(KeyCode::Enter)),
+            Control::Copy("35".to_string())
+        );
+        assert_eq!(viewer.handle_key(key(KeyCode::Char('q'))), Control::Quit);
+    }
+
+    #[test]
+    fn test_viewer_lines() {
+        let mut viewer = Viewer::from_text("echo", "hello world\nsecond line");
+        assert_eq!(viewer.table.headers, vec!["output".to_string()]);
+        viewer.handle_key(key(KeyCode::End));
+        assert_eq!(
+            viewer.handle_key(key(KeyCode::Char('y'))),
+            Control::Copy("second line".to_string())
+        );
+    }
+}